@@ -0,0 +1,110 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! The cost of every atom in isolation, in a basket prepared in advance
+//! with all the attributes the atom reads already dataized: first through
+//! `Emu::delegate()`, which finds the atom and calls it, and then by
+//! calling the function of the atom directly. The difference is what the
+//! dispatch costs. Run it like this, with the number of calls optional:
+//!
+//! ```text
+//! cargo bench --bench atoms -- 1000000
+//! ```
+
+extern crate phie;
+
+use phie::atom::{registry, Meta};
+use phie::basket::{Basket, Bk, Kid};
+use phie::data::{of_float, Data, Type};
+use phie::emu::{Emu, ROOT_BK};
+use phie::loc::Loc;
+use phie::object::Ob;
+use phie::perf::Perf;
+use std::env;
+use std::hint::black_box;
+use std::str::FromStr;
+use std::time::Instant;
+
+/// The basket the atom is called in.
+const BK: Bk = Bk::new(1);
+
+/// How many calls of each atom are made, unless told otherwise.
+const CALLS: usize = 100_000;
+
+/// Data of the type the atom expects, which it won't fail on.
+fn sample(t: Option<Type>) -> Data {
+    match t {
+        Some(Type::Float) => of_float(1.5),
+        Some(Type::Bool) => 1,
+        _ => 7,
+    }
+}
+
+/// An `Emu` with the atom in ν1 and a basket of it, with everything
+/// it reads already dataized, and enough data on the input tape for
+/// `io-read` to be called this many times.
+fn context(meta: &Meta, calls: usize) -> Emu {
+    let mut emu = Emu::from_str(&format!(
+        "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ λ ↦ {} ⟧",
+        meta.name
+    ))
+    .unwrap();
+    let mut bsk = Basket::start(Ob::new(1), ROOT_BK);
+    for (loc, t) in meta.args {
+        bsk.put(loc.clone(), Kid::Dtzd(sample(*t)));
+    }
+    bsk.put(Loc::Phi, Kid::Rqtd);
+    emu.inject(BK, bsk);
+    emu.set_input_tape(std::iter::repeat_n(7, calls));
+    emu
+}
+
+/// Nanoseconds per call of the closure, made this many times.
+fn measure(calls: usize, mut f: impl FnMut()) -> f64 {
+    let start = Instant::now();
+    for _ in 0..calls {
+        f();
+    }
+    start.elapsed().as_nanos() as f64 / calls as f64
+}
+
+fn main() {
+    let calls = env::args()
+        .skip(1)
+        .find_map(|a| a.parse().ok())
+        .unwrap_or(CALLS);
+    println!(
+        "{:<14} {:>10} {:>10} {:>10}",
+        "Atom", "Delegate", "Call", "Dispatch"
+    );
+    for meta in registry() {
+        if meta.name.starts_with("rat-") {
+            // Fractions are stored in the Emu, which would grow with every call.
+            continue;
+        }
+        let mut emu = context(&meta, calls);
+        let mut perf = Perf::new();
+        let delegate = measure(calls, || {
+            emu.baskets[BK.index()].put(Loc::Phi, Kid::Rqtd);
+            emu.delegate(&mut perf, BK).unwrap();
+            black_box(&emu.baskets[BK.index()]);
+        });
+        assert_eq!(
+            calls,
+            perf.total_atoms(),
+            "The atom '{}' was not called",
+            meta.name
+        );
+        let mut emu = context(&meta, calls);
+        let call = measure(calls, || {
+            black_box((meta.func)(&mut emu, BK));
+        });
+        println!(
+            "{:<14} {:>8.1}ns {:>8.1}ns {:>8.1}ns",
+            meta.name,
+            delegate,
+            call,
+            delegate - call
+        );
+    }
+}
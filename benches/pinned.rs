@@ -0,0 +1,56 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! The cost of dataizing a tiny program by a new `Emu` every time,
+//! against `Program::dataize_pinned()`, which recycles the `Emu` pinned
+//! to the thread. The difference is what making an `Emu` costs. Run it
+//! like this, with the number of calls optional:
+//!
+//! ```text
+//! cargo bench --bench pinned -- 100000
+//! ```
+
+extern crate phie;
+
+use phie::emu::Emu;
+use phie::program::Program;
+use std::env;
+use std::hint::black_box;
+use std::str::FromStr;
+use std::time::Instant;
+
+/// How many dataizations of each kind are made, unless told otherwise.
+const CALLS: usize = 100_000;
+
+/// Nanoseconds per call of the closure, made this many times.
+fn measure(calls: usize, mut f: impl FnMut()) -> f64 {
+    let start = Instant::now();
+    for _ in 0..calls {
+        f();
+    }
+    start.elapsed().as_nanos() as f64 / calls as f64
+}
+
+fn main() {
+    let calls = env::args()
+        .skip(1)
+        .find_map(|a| a.parse().ok())
+        .unwrap_or(CALLS);
+    let prog = Program::from_str(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+        ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧
+        ",
+    )
+    .unwrap();
+    let fresh = measure(calls, || {
+        black_box(Emu::from(&prog).try_dataize().unwrap());
+    });
+    let pinned = measure(calls, || {
+        black_box(prog.dataize_pinned().unwrap());
+    });
+    println!("{:<8} {:>8.1}ns", "Fresh", fresh);
+    println!("{:<8} {:>8.1}ns", "Pinned", pinned);
+    println!("{:<8} {:>8.1}ns", "Saved", fresh - pinned);
+}
@@ -2,46 +2,485 @@
 // SPDX-License-Identifier: MIT
 
 use crate::basket::Bk;
-use crate::data::Data;
-use crate::emu::Emu;
+use crate::data::{float, of_float, of_soft_float, soft_float, Calc, Data, Datum, Type};
+use crate::emu::{Emu, FloatMode, Opt};
 use crate::loc::Loc;
+#[cfg(feature = "bigint")]
+use num_bigint::BigInt;
+#[cfg(feature = "rational")]
+use num_rational::BigRational;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::Mutex;
 
-pub type Atom = fn(&mut Emu, Bk) -> Option<Data>;
+pub type Atom<D = Data> = fn(&mut Emu<D>, Bk) -> Option<D>;
 
-pub fn int_add(emu: &mut Emu, bk: Bk) -> Option<Data> {
-    Some(emu.read(bk, Loc::Rho)? + emu.read(bk, Loc::Attr(0))?)
+/// The position of an atom in `registry()`, which an object gets when
+/// it's parsed, so that its atom is found without looking up its name.
+pub type AtomId = usize;
+
+/// What is known about an atom before it's called.
+pub struct Meta<D: Datum = Data> {
+    pub name: &'static str,
+    pub func: Atom<D>,
+    /// The attributes it reads, with the types it expects there, if any.
+    pub args: &'static [(Loc, Option<Type>)],
+    /// The type of its results, unless it's the type of an argument.
+    pub returns: Option<Type>,
+    /// Its result depends on its arguments only, so it may be cached.
+    pub pure: bool,
+}
+
+impl<D: Datum> Clone for Meta<D> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<D: Datum> Copy for Meta<D> {}
+
+const INT: &[(Loc, Option<Type>)] = &[(Loc::Rho, Some(Type::Int))];
+const INTS: &[(Loc, Option<Type>)] =
+    &[(Loc::Rho, Some(Type::Int)), (Loc::Attr(0), Some(Type::Int))];
+const FLOAT: &[(Loc, Option<Type>)] = &[(Loc::Rho, Some(Type::Float))];
+const FLOATS: &[(Loc, Option<Type>)] = &[
+    (Loc::Rho, Some(Type::Float)),
+    (Loc::Attr(0), Some(Type::Float)),
+];
+const BOOL: &[(Loc, Option<Type>)] = &[(Loc::Rho, Some(Type::Bool))];
+const BRANCHES: &[(Loc, Option<Type>)] = &[
+    (Loc::Rho, Some(Type::Bool)),
+    (Loc::Attr(0), None),
+    (Loc::Attr(1), None),
+];
+const ANY: &[(Loc, Option<Type>)] = &[(Loc::Rho, None)];
+#[cfg(feature = "rational")]
+const FRACTIONS: &[(Loc, Option<Type>)] = &[(Loc::Rho, None), (Loc::Attr(0), None)];
+
+/// All atoms a program may refer to by their names in `λ ↦` pairs,
+/// made once for each `Datum`, see `Datum::atoms()`.
+pub fn registry<D: Datum>() -> Vec<Meta<D>> {
+    let atom = |name, func, args, returns| Meta {
+        name,
+        func,
+        args,
+        returns,
+        pure: true,
+    };
+    let effect = |name, func, args, returns| Meta {
+        name,
+        func,
+        args,
+        returns,
+        pure: false,
+    };
+    vec![
+        atom("int-times", int_times as Atom<D>, INTS, Some(Type::Int)),
+        atom("int-div", int_div, INTS, Some(Type::Int)),
+        atom("int-sub", int_sub, INTS, Some(Type::Int)),
+        atom("int-add", int_add, INTS, Some(Type::Int)),
+        atom("int-neg", int_neg, INT, Some(Type::Int)),
+        atom("int-less", int_less, INTS, Some(Type::Bool)),
+        atom("bool-if", bool_if, BRANCHES, None),
+        atom("int-to-bool", int_to_bool, INT, Some(Type::Bool)),
+        atom("bool-to-int", bool_to_int, BOOL, Some(Type::Int)),
+        atom("float-add", float_add, FLOATS, Some(Type::Float)),
+        atom("float-sub", float_sub, FLOATS, Some(Type::Float)),
+        atom("float-mul", float_mul, FLOATS, Some(Type::Float)),
+        atom("float-div", float_div, FLOATS, Some(Type::Float)),
+        atom("float-less", float_less, FLOATS, Some(Type::Bool)),
+        atom("int-to-float", int_to_float, INT, Some(Type::Float)),
+        atom("float-to-int", float_to_int, FLOAT, Some(Type::Int)),
+        effect("io-write", io_write, ANY, None),
+        effect("io-read", io_read, &[], Some(Type::Int)),
+        #[cfg(feature = "rational")]
+        atom("rat-add", rat_add, FRACTIONS, None),
+        #[cfg(feature = "rational")]
+        atom("rat-mul", rat_mul, FRACTIONS, None),
+        #[cfg(feature = "rational")]
+        atom("rat-cmp", rat_cmp, FRACTIONS, Some(Type::Int)),
+    ]
+}
+
+/// The atoms of one `Datum`, see `Datum::atoms()`: the ones of
+/// `registry()`, made when they are first needed, and the ones added
+/// by `register()`. A list of them is never changed: adding an atom
+/// makes a new list, while the old one is leaked, since references to
+/// it may still be around.
+pub struct Registry<D: Datum> {
+    list: AtomicPtr<Vec<Meta<D>>>,
+    lock: Mutex<()>,
+}
+
+impl<D: Datum> Registry<D> {
+    pub const fn new() -> Registry<D> {
+        Registry {
+            list: AtomicPtr::new(ptr::null_mut()),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// All atoms added so far.
+    pub fn all(&self) -> &'static [Meta<D>] {
+        let list = self.list.load(Ordering::Acquire);
+        if list.is_null() {
+            let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+            return self.locked();
+        }
+        // SAFETY: the pointer is of a leaked list, which is never
+        // changed or freed.
+        unsafe { &*list }
+    }
+
+    /// Add the atom, unless there is one of the same name, and return
+    /// its id.
+    pub fn add(&self, meta: Meta<D>) -> Result<AtomId, String> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut list = self.locked().to_vec();
+        if list.iter().any(|m| m.name == meta.name) {
+            return Err(format!("The atom '{}' is already there", meta.name));
+        }
+        list.push(meta);
+        self.list
+            .store(Box::into_raw(Box::new(list)), Ordering::Release);
+        Ok(self.locked().len() - 1)
+    }
+
+    /// All atoms, made of `registry()` if there are none yet, while
+    /// the lock is held.
+    fn locked(&self) -> &'static [Meta<D>] {
+        if self.list.load(Ordering::Acquire).is_null() {
+            self.list
+                .store(Box::into_raw(Box::new(registry::<D>())), Ordering::Release);
+        }
+        // SAFETY: the pointer is of a leaked list, which is never
+        // changed or freed.
+        unsafe { &*self.list.load(Ordering::Acquire) }
+    }
+}
+
+impl<D: Datum> Default for Registry<D> {
+    fn default() -> Self {
+        Registry::new()
+    }
+}
+
+/// Add the atom to the ones of `D`, so that programs may refer to it
+/// by its name, and return its id, see `Object::atomic()`. The atoms
+/// of `registry()` have the same ids for all data, while the ids of
+/// the added ones are only known to the data they are added to:
+///
+/// ```
+/// use phie::atom::{register, Meta};
+/// use phie::basket::Bk;
+/// use phie::data::Data;
+/// use phie::emu::Emu;
+/// fn answer(_: &mut Emu, _: Bk) -> Option<Data> {
+///     Some(42)
+/// }
+/// register(Meta {
+///     name: "answer",
+///     func: answer,
+///     args: &[],
+///     returns: None,
+///     pure: true,
+/// })
+/// .unwrap();
+/// let mut emu: Emu = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ λ ↦ answer ⟧".parse().unwrap();
+/// assert_eq!(42, emu.dataize().0);
+/// ```
+pub fn register<D: Datum>(meta: Meta<D>) -> Result<AtomId, String> {
+    D::registered().add(meta)
+}
+
+/// The atom with this name, if there is one.
+pub fn meta(name: &str) -> Option<Meta> {
+    Data::atoms().iter().find(|m| m.name == name).copied()
+}
+
+/// The id of the atom with this name, if there is one.
+pub fn id(name: &str) -> Option<AtomId> {
+    Data::atoms().iter().position(|m| m.name == name)
+}
+
+/// The atom with this id, see `id()`.
+pub fn by_id(id: AtomId) -> &'static Meta {
+    &Data::atoms()[id]
 }
 
-pub fn int_times(emu: &mut Emu, bk: Bk) -> Option<Data> {
-    Some(emu.read(bk, Loc::Rho)? * emu.read(bk, Loc::Attr(0))?)
+/// The type of the values the atom with this name returns, if it's
+/// always the same one: `bool-if` returns one of its branches.
+pub fn returns(name: &str) -> Option<Type> {
+    meta(name)?.returns
 }
 
-pub fn int_neg(emu: &mut Emu, bk: Bk) -> Option<Data> {
-    Some(-emu.read(bk, Loc::Rho)?)
+/// The type the atom with this name expects at this attribute, if
+/// it expects any: the branches of `bool-if` may be of any type.
+pub fn expects(name: &str, loc: &Loc) -> Option<Type> {
+    meta(name)?.args.iter().find(|(l, _)| l == loc)?.1
 }
 
-pub fn int_sub(emu: &mut Emu, bk: Bk) -> Option<Data> {
-    Some(emu.read(bk, Loc::Rho)? - emu.read(bk, Loc::Attr(0))?)
+/// Is the atom with this name known to depend on its arguments only?
+pub fn is_pure(name: &str) -> bool {
+    meta(name).is_some_and(|m| m.pure)
 }
 
-pub fn int_div(emu: &mut Emu, bk: Bk) -> Option<Data> {
-    Some(emu.read(bk, Loc::Rho)? / emu.read(bk, Loc::Attr(0))?)
+/// The result of the integer atom in the arithmetic of the `Emu`,
+/// unless it overflows and the arithmetic is checked.
+fn calc<D: Datum>(emu: &Emu<D>, bk: Bk, name: &str, op: Calc, a: D, b: D) -> D {
+    a.calc(op, b, emu.arithmetic()).unwrap_or_else(|| {
+        panic!(
+            "The atom '{}' overflowed with {} and {} in β{}, see the arithmetic option",
+            name, a, b, bk
+        )
+    })
 }
 
-pub fn int_less(emu: &mut Emu, bk: Bk) -> Option<Data> {
-    Some((emu.read(bk, Loc::Rho)? < emu.read(bk, Loc::Attr(0))?) as Data)
+pub fn int_add<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = (emu.read(bk, Loc::Rho)?, emu.read(bk, Loc::Attr(0))?);
+    Some(calc(emu, bk, "int-add", Calc::Add, a, b))
 }
 
-pub fn bool_if(emu: &mut Emu, bk: Bk) -> Option<Data> {
+pub fn int_times<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = (emu.read(bk, Loc::Rho)?, emu.read(bk, Loc::Attr(0))?);
+    Some(calc(emu, bk, "int-times", Calc::Mul, a, b))
+}
+
+pub fn int_neg<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let a = emu.read(bk, Loc::Rho)?;
+    Some(calc(emu, bk, "int-neg", Calc::Sub, D::default(), a))
+}
+
+pub fn int_sub<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = (emu.read(bk, Loc::Rho)?, emu.read(bk, Loc::Attr(0))?);
+    Some(calc(emu, bk, "int-sub", Calc::Sub, a, b))
+}
+
+pub fn int_div<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = (emu.read(bk, Loc::Rho)?, emu.read(bk, Loc::Attr(0))?);
+    Some(D::of(a.data() / b.data()))
+}
+
+pub fn int_less<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    Some(D::of(
+        (emu.read(bk, Loc::Rho)? < emu.read(bk, Loc::Attr(0))?) as Data,
+    ))
+}
+
+pub fn bool_if<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
     let term = emu.read(bk, Loc::Rho)?;
-    emu.read(bk, Loc::Attr(if term == 1 { 0 } else { 1 }))
+    emu.read(bk, Loc::Attr(if emu.truth(term) { 0 } else { 1 }))
+}
+
+/// Any integer other than zero is TRUE.
+pub fn int_to_bool<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    Some(D::of((emu.read(bk, Loc::Rho)?.data() != 0) as Data))
+}
+
+/// Put the data of `ρ` on the output tape of the `Emu` and return it,
+/// see `Emu::output_tape()`.
+pub fn io_write<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let d = emu.read(bk, Loc::Rho)?;
+    emu.leak(bk, d.data());
+    emu.tape_out.push(d.data());
+    Some(d)
+}
+
+/// Take the next data from the input tape of the `Emu`, see
+/// `Emu::set_input_tape()`.
+pub fn io_read<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    match emu.tape_in.pop_front() {
+        Some(d) => Some(D::of(d)),
+        None => panic!("The input tape is empty, 'io-read' can't read in β{}", bk),
+    }
+}
+
+/// TRUE is one, FALSE is zero.
+pub fn bool_to_int<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let term = emu.read(bk, Loc::Rho)?;
+    Some(D::of(emu.truth(term) as Data))
+}
+
+/// The float kept in the data, see `Opt::DeterministicFloats`.
+fn unfloat<D: Datum>(emu: &Emu<D>, d: D) -> f32 {
+    if emu.opts.contains(&Opt::DeterministicFloats) {
+        soft_float(d.data())
+    } else {
+        float(d.data())
+    }
+}
+
+/// The data that keep the float, see `Opt::DeterministicFloats`.
+fn enfloat<D: Datum>(emu: &Emu<D>, f: f32) -> D {
+    D::of(if emu.opts.contains(&Opt::DeterministicFloats) {
+        of_soft_float(f)
+    } else {
+        of_float(f)
+    })
+}
+
+/// The float made by the atom, kept in the data, unless it's NaN or
+/// an infinity, once kept, and `FloatMode::Strict` is set.
+fn floated<D: Datum>(emu: &Emu<D>, bk: Bk, name: &str, f: f32) -> D {
+    let d = enfloat(emu, f);
+    assert!(
+        unfloat(emu, d).is_finite() || emu.float_mode() != FloatMode::Strict,
+        "The atom '{}' made {} in β{}, see the float-mode option",
+        name,
+        f,
+        bk
+    );
+    d
+}
+
+fn float_args<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<(f32, f32)> {
+    let rho = emu.read(bk, Loc::Rho)?;
+    let arg = emu.read(bk, Loc::Attr(0))?;
+    Some((unfloat(emu, rho), unfloat(emu, arg)))
+}
+
+pub fn float_add<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = float_args(emu, bk)?;
+    Some(floated(emu, bk, "float-add", a + b))
+}
+
+pub fn float_sub<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = float_args(emu, bk)?;
+    Some(floated(emu, bk, "float-sub", a - b))
+}
+
+pub fn float_mul<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = float_args(emu, bk)?;
+    Some(floated(emu, bk, "float-mul", a * b))
+}
+
+pub fn float_div<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = float_args(emu, bk)?;
+    Some(floated(emu, bk, "float-div", a / b))
+}
+
+pub fn float_less<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = float_args(emu, bk)?;
+    Some(D::of((a < b) as Data))
+}
+
+pub fn int_to_float<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let d = emu.read(bk, Loc::Rho)?;
+    Some(floated(emu, bk, "int-to-float", d.data() as f32))
+}
+
+/// Round the float to an integer, as `Opt::FloatMode` says, saturating
+/// at the bounds of `Data`; NaN becomes zero, unless it's strict.
+pub fn float_to_int<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let d = emu.read(bk, Loc::Rho)?;
+    let f = unfloat(emu, d);
+    floated::<D>(emu, bk, "float-to-int", f);
+    Some(D::of(match emu.float_mode() {
+        FloatMode::Truncate => f.trunc(),
+        _ => f.round(),
+    } as Data))
+}
+
+/// The atom to use instead of the one with this name, when the
+/// data are big integers: their baskets keep handles, not values.
+#[cfg(feature = "bigint")]
+pub fn big_atom<D: Datum>(name: &str) -> Option<Atom<D>> {
+    match name {
+        "int-times" => Some(big_int_times),
+        "int-div" => Some(big_int_div),
+        "int-sub" => Some(big_int_sub),
+        "int-add" => Some(big_int_add),
+        "int-neg" => Some(big_int_neg),
+        "int-less" => Some(big_int_less),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "bigint")]
+fn big_args<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<(BigInt, BigInt)> {
+    let rho = emu.read(bk, Loc::Rho)?;
+    let arg = emu.read(bk, Loc::Attr(0))?;
+    Some((emu.big(rho).clone(), emu.big(arg).clone()))
+}
+
+#[cfg(feature = "bigint")]
+pub fn big_int_add<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = big_args(emu, bk)?;
+    Some(emu.store(a + b))
+}
+
+#[cfg(feature = "bigint")]
+pub fn big_int_times<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = big_args(emu, bk)?;
+    Some(emu.store(a * b))
+}
+
+#[cfg(feature = "bigint")]
+pub fn big_int_neg<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let rho = emu.read(bk, Loc::Rho)?;
+    let v = -emu.big(rho).clone();
+    Some(emu.store(v))
+}
+
+#[cfg(feature = "bigint")]
+pub fn big_int_sub<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = big_args(emu, bk)?;
+    Some(emu.store(a - b))
+}
+
+#[cfg(feature = "bigint")]
+pub fn big_int_div<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = big_args(emu, bk)?;
+    Some(emu.store(a / b))
+}
+
+#[cfg(feature = "bigint")]
+pub fn big_int_less<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = big_args(emu, bk)?;
+    Some(emu.store(BigInt::from((a < b) as Data)))
+}
+
+#[cfg(feature = "rational")]
+fn rat_args<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<(BigRational, BigRational)> {
+    assert!(
+        emu.big_mode(),
+        "Fractions can only be dataized in the big mode, see Emu::dataize_value()"
+    );
+    let rho = emu.read(bk, Loc::Rho)?;
+    let arg = emu.read(bk, Loc::Attr(0))?;
+    Some((emu.ratio(rho), emu.ratio(arg)))
+}
+
+#[cfg(feature = "rational")]
+pub fn rat_add<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = rat_args(emu, bk)?;
+    Some(emu.store(a + b))
+}
+
+#[cfg(feature = "rational")]
+pub fn rat_mul<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = rat_args(emu, bk)?;
+    Some(emu.store(a * b))
+}
+
+/// Compare two fractions: -1 if the first one is smaller, 1 if it's
+/// bigger, and 0 if they are equal.
+#[cfg(feature = "rational")]
+pub fn rat_cmp<D: Datum>(emu: &mut Emu<D>, bk: Bk) -> Option<D> {
+    let (a, b) = rat_args(emu, bk)?;
+    Some(emu.store(BigInt::from(a.cmp(&b) as Data)))
 }
 
 #[cfg(test)]
 use crate::assert_dataized_eq;
-
 #[cfg(test)]
-use crate::emu::Opt;
+use crate::emu::Arithmetic;
+#[cfg(test)]
+use crate::object::{Ob, Object};
+#[cfg(test)]
+use crate::program::Program;
+#[cfg(test)]
+use std::str::FromStr;
 
 #[test]
 pub fn bool_if_works() {
@@ -149,3 +588,229 @@ pub fn int_less_works() {
     "
     );
 }
+
+#[test]
+pub fn float_div_works() {
+    assert_dataized_eq!(
+        of_float(0.75),
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2 ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x3FC00000 ⟧
+        ν2(𝜋) ↦ ⟦ λ ↦ float-div, ρ ↦ ν1, 𝛼0 ↦ ν3 ⟧
+        ν3(𝜋) ↦ ⟦ Δ ↦ 0x40000000 ⟧
+    "
+    );
+}
+
+#[test]
+pub fn divides_ints_as_floats() {
+    let program = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1 ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ float-to-int, ρ ↦ ν2 ⟧
+        ν2(𝜋) ↦ ⟦ λ ↦ float-div, ρ ↦ ν3, 𝛼0 ↦ ν4 ⟧
+        ν3(𝜋) ↦ ⟦ λ ↦ int-to-float, ρ ↦ ν5 ⟧
+        ν4(𝜋) ↦ ⟦ λ ↦ int-to-float, ρ ↦ ν6 ⟧
+        ν5(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+        ν6(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧
+    ";
+    assert_dataized_eq!(4, program);
+    let mut emu: Emu = program.parse().unwrap();
+    emu.opt(Opt::FloatMode(FloatMode::Truncate));
+    assert_eq!(3, emu.dataize().0);
+}
+
+#[test]
+pub fn fails_on_nan_when_strict() {
+    let program = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1 ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ float-div, ρ ↦ ν2, 𝛼0 ↦ ν2 ⟧
+        ν2(𝜋) ↦ ⟦ Δ ↦ 0x0000 ⟧
+    ";
+    let mut emu: Emu = program.parse().unwrap();
+    assert!(float(emu.dataize().0).is_nan());
+    let mut emu: Emu = program.parse().unwrap();
+    emu.opt(Opt::FloatMode(FloatMode::Strict));
+    let err = emu.try_dataize().err().unwrap().to_string();
+    assert!(err.contains("The atom 'float-div' made NaN"), "{}", err);
+}
+
+#[test]
+pub fn keeps_large_floats_when_strict() {
+    let program = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1 ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ int-to-float, ρ ↦ ν2 ⟧
+        ν2(𝜋) ↦ ⟦ Δ ↦ 0x11170 ⟧
+    ";
+    let mut emu: Emu = program.parse().unwrap();
+    emu.opt(Opt::FloatMode(FloatMode::Strict));
+    assert_eq!(70000.0, float(emu.try_dataize().unwrap().0));
+}
+
+#[test]
+pub fn makes_same_floats_everywhere() {
+    let program = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1 ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ float-add, ρ ↦ ν2, 𝛼0 ↦ ν3 ⟧
+        ν2(𝜋) ↦ ⟦ λ ↦ float-div, ρ ↦ ν4, 𝛼0 ↦ ν4 ⟧
+        ν3(𝜋) ↦ ⟦ λ ↦ int-to-float, ρ ↦ ν5 ⟧
+        ν4(𝜋) ↦ ⟦ Δ ↦ 0x0000 ⟧
+        ν5(𝜋) ↦ ⟦ Δ ↦ 0x0003 ⟧
+    ";
+    let mut emu: Emu = program.parse().unwrap();
+    emu.opt(Opt::DeterministicFloats);
+    assert_eq!(crate::data::CANONICAL_NAN, emu.dataize().0);
+    for f in [0.1f32, -2.5, 65504.0, 1e-7, 1e9] {
+        assert_eq!(of_float(f), of_soft_float(f), "{}", f);
+        assert_eq!(float(of_float(f)), soft_float(of_float(f)), "{}", f);
+    }
+}
+
+#[test]
+pub fn overflows_as_arithmetic_says() {
+    let mut prog = Program::from_str(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1 ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν2, 𝛼0 ↦ ν2 ⟧
+        ν2(𝜋) ↦ ⟦ Δ ↦ 0x7FFF ⟧
+        ",
+    )
+    .unwrap();
+    assert_eq!(-2, Emu::<i16>::of(&prog).dataize().0);
+    prog.opt(Opt::Arithmetic(Arithmetic::Saturating));
+    assert_eq!(i16::MAX, Emu::<i16>::of(&prog).dataize().0);
+    prog.opt(Opt::Arithmetic(Arithmetic::Checked));
+    let err = Emu::<i16>::of(&prog)
+        .try_dataize()
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(err.contains("The atom 'int-add' overflowed"), "{}", err);
+    assert_eq!(0xFFFE, Emu::<i64>::of(&prog).dataize().0);
+    prog.opt(Opt::Semantics(1));
+    let err = Emu::<i16>::of(&prog)
+        .try_dataize()
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(err.contains("needs semantics 2"), "{}", err);
+}
+
+#[test]
+pub fn converts_ints_to_bools() {
+    assert_dataized_eq!(
+        1,
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1 ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ bool-to-int, ρ ↦ ν2 ⟧
+        ν2(𝜋) ↦ ⟦ λ ↦ int-to-bool, ρ ↦ ν3 ⟧
+        ν3(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+    "
+    );
+}
+
+#[test]
+pub fn takes_only_bools_when_strict() {
+    let program = |cond: &str| {
+        format!(
+            "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1 ⟧
+            ν1(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ {}, 𝛼0 ↦ ν3, 𝛼1 ↦ ν4 ⟧
+            ν2(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧
+            ν3(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+            ν4(𝜋) ↦ ⟦ Δ ↦ 0x0000 ⟧
+            ν5(𝜋) ↦ ⟦ λ ↦ int-to-bool, ρ ↦ ν2 ⟧
+        ",
+            cond
+        )
+    };
+    let mut emu: Emu = program("ν5").parse().unwrap();
+    emu.opt(Opt::StrictTypes);
+    assert_eq!(42, emu.dataize().0);
+    let mut emu: Emu = program("ν2").parse().unwrap();
+    emu.opt(Opt::StrictTypes);
+    let err = emu.try_dataize().err().unwrap().to_string();
+    assert!(
+        err.contains("The atom 'bool-if' of ν1 expects bool at ρ, but got int"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+pub fn checks_types_of_all_atoms_when_strict() {
+    let mut emu: Emu = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1 ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν2, 𝛼0 ↦ ν3 ⟧
+        ν2(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧
+        ν3(𝜋) ↦ ⟦ λ ↦ int-to-float, ρ ↦ ν2 ⟧
+    "
+    .parse()
+    .unwrap();
+    emu.opt(Opt::StrictTypes);
+    let err = emu.try_dataize().err().unwrap();
+    assert!(
+        err.to_string()
+            .starts_with("The atom 'int-add' of ν1 expects int at 𝛼0, but got float"),
+        "{}",
+        err
+    );
+    assert!(
+        matches!(&err, crate::error::Error::TypeMismatch { stack, .. } if stack.len() == 2),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+pub fn knows_all_atoms() {
+    let all = registry::<Data>();
+    assert!(all.iter().all(|m| m.pure != m.name.starts_with("io-")));
+    assert!(!is_pure("io-read"));
+    assert_eq!(Some(Type::Bool), returns("int-less"));
+    assert_eq!(None, expects("bool-if", &Loc::Attr(1)));
+    assert_eq!(Some(Type::Float), expects("float-to-int", &Loc::Rho));
+    assert!(meta("int-add").is_some_and(|m| m.args.len() == 2));
+    assert!(meta("str-concat").is_none());
+}
+
+#[test]
+pub fn finds_atoms_by_id() {
+    for (i, m) in registry::<Data>().iter().enumerate() {
+        assert_eq!(Some(i), id(m.name));
+        assert_eq!(m.name, by_id(i).name);
+    }
+    assert!(id("str-concat").is_none());
+    let obj = Object::from_str("⟦ λ ↦ int-sub ⟧").unwrap();
+    assert_eq!(id("int-sub"), obj.lambda);
+    assert_eq!("⟦λ↦int-sub⟧", obj.to_string());
+}
+
+#[cfg(test)]
+fn int_twice(emu: &mut Emu, bk: Bk) -> Option<Data> {
+    Some(emu.read(bk, Loc::Rho)? * 2)
+}
+
+#[test]
+pub fn registers_custom_atoms() {
+    let twice = Meta {
+        name: "int-twice",
+        func: int_twice,
+        args: INT,
+        returns: Some(Type::Int),
+        pure: true,
+    };
+    let at = register(twice).unwrap();
+    assert_eq!(Some(at), id("int-twice"));
+    assert!(register(twice).is_err());
+    let mut prog = Program::from_str("ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν2(𝜋) ↦ ⟦ Δ ↦ 0x0015 ⟧").unwrap();
+    let mut obj = Object::atomic(at);
+    obj.push(Loc::Rho, "ν2".parse().unwrap(), false);
+    prog.insert(Ob::new(1), obj);
+    assert_eq!(42, Emu::from(&prog).dataize().0);
+    let err = Emu::<i32>::of(&prog)
+        .try_dataize()
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(err.contains("not known to this data"), "{}", err);
+}
@@ -0,0 +1,227 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Numbers of the dataization of each program of a corpus, saved to
+//! a file by `phie bench --baseline <file> --record`, to check later
+//! that a change of the emulator doesn't make it slower.
+//!
+//! The file is a JSON object with one entry per program:
+//!
+//! ```text
+//! {"sum.phi": {"cycles": 12, "baskets": 4, "micros": 310, "program": "5f0c6e1a9b3d2e47"}}
+//! ```
+//!
+//! Only cycles are compared, since they don't depend on the machine,
+//! while baskets and time are there for information. The program
+//! is the `Program::fingerprint()`, which tells whether the program
+//! itself was changed since the baseline:
+//!
+//! ```
+//! use phie::baseline::{Baseline, Measure};
+//! let mut was = Baseline::default();
+//! was.put("sum.phi", Measure { cycles: 100, baskets: 4, micros: 310, program: None });
+//! let mut now = Baseline::default();
+//! now.put("sum.phi", Measure { cycles: 120, baskets: 4, micros: 290, program: None });
+//! assert_eq!(vec!["sum.phi"], now.regressions(&was, 10.0));
+//! assert!(now.regressions(&was, 25.0).is_empty());
+//! ```
+
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measure {
+    pub cycles: usize,
+    /// The peak number of live baskets.
+    pub baskets: usize,
+    pub micros: u128,
+    /// The fingerprint of the program, if it's known.
+    pub program: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Baseline {
+    measures: BTreeMap<String, Measure>,
+}
+
+impl Baseline {
+    pub fn put(&mut self, name: &str, measure: Measure) {
+        self.measures.insert(name.to_string(), measure);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Measure> {
+        self.measures.get(name)
+    }
+
+    /// All programs with their numbers, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Measure)> {
+        self.measures.iter()
+    }
+
+    /// The change of cycles of the program since the other baseline,
+    /// in percent, if it's there.
+    pub fn change(&self, was: &Baseline, name: &str) -> Option<f64> {
+        let now = self.get(name)?.cycles as f64;
+        let before = was.get(name)?.cycles as f64;
+        Some(if before == 0.0 {
+            if now == 0.0 {
+                0.0
+            } else {
+                f64::INFINITY
+            }
+        } else {
+            (now - before) * 100.0 / before
+        })
+    }
+
+    /// The program is not the same as it was in the other baseline.
+    pub fn edited(&self, was: &Baseline, name: &str) -> bool {
+        matches!(
+            (self.get(name).and_then(|m| m.program), was.get(name).and_then(|m| m.program)),
+            (Some(now), Some(before)) if now != before
+        )
+    }
+
+    /// The programs that make more cycles than in the other baseline,
+    /// by more than this many percent.
+    pub fn regressions(&self, was: &Baseline, threshold: f64) -> Vec<&str> {
+        self.measures
+            .keys()
+            .filter(|name| self.change(was, name).is_some_and(|c| c > threshold))
+            .map(|name| name.as_str())
+            .collect()
+    }
+}
+
+impl fmt::Display for Baseline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let map: serde_json::Map<String, Value> = self
+            .measures
+            .iter()
+            .map(|(name, m)| {
+                let mut json =
+                    json!({"cycles": m.cycles, "baskets": m.baskets, "micros": m.micros});
+                if let Some(p) = m.program {
+                    json["program"] = json!(format!("{:016x}", p));
+                }
+                (name.clone(), json)
+            })
+            .collect();
+        write!(f, "{:#}", Value::Object(map))
+    }
+}
+
+impl FromStr for Baseline {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let json: Value =
+            serde_json::from_str(s).map_err(|e| format!("Can't parse the baseline: {}", e))?;
+        let map = json
+            .as_object()
+            .ok_or_else(|| "The baseline must be a JSON object".to_string())?;
+        let mut baseline = Baseline::default();
+        for (name, m) in map {
+            let num = |key: &str| {
+                m[key].as_u64().ok_or_else(|| {
+                    format!(
+                        "The '{}' of '{}' in the baseline is not a number",
+                        key, name
+                    )
+                })
+            };
+            baseline.put(
+                name,
+                Measure {
+                    cycles: num("cycles")? as usize,
+                    baskets: num("baskets")? as usize,
+                    micros: num("micros")? as u128,
+                    program: match &m["program"] {
+                        Value::Null => None,
+                        p => Some(
+                            p.as_str()
+                                .and_then(|p| u64::from_str_radix(p, 16).ok())
+                                .ok_or_else(|| {
+                                    format!(
+                                        "The 'program' of '{}' in the baseline is not a fingerprint",
+                                        name
+                                    )
+                                })?,
+                        ),
+                    },
+                },
+            );
+        }
+        Ok(baseline)
+    }
+}
+
+#[test]
+fn prints_and_parses_itself() {
+    let mut b = Baseline::default();
+    b.put(
+        "a.phi",
+        Measure {
+            cycles: 7,
+            baskets: 3,
+            micros: 15,
+            program: Some(0xff01),
+        },
+    );
+    assert_eq!(b, Baseline::from_str(&b.to_string()).unwrap());
+}
+
+#[test]
+fn fails_on_broken_baseline() {
+    assert!(Baseline::from_str("[]").is_err());
+    assert!(Baseline::from_str(r#"{"a.phi": {"cycles": "x"}}"#).is_err());
+}
+
+#[test]
+fn ignores_new_and_faster_programs() {
+    let mut was = Baseline::default();
+    let m = Measure {
+        cycles: 10,
+        baskets: 1,
+        micros: 1,
+        program: None,
+    };
+    was.put("a.phi", m);
+    let mut now = Baseline::default();
+    now.put("a.phi", Measure { cycles: 5, ..m });
+    now.put("b.phi", m);
+    assert_eq!(Some(-50.0), now.change(&was, "a.phi"));
+    assert!(now.regressions(&was, 0.0).is_empty());
+}
+
+#[test]
+fn notices_edited_programs() {
+    let m = Measure {
+        cycles: 10,
+        baskets: 1,
+        micros: 1,
+        program: Some(1),
+    };
+    let mut was = Baseline::default();
+    was.put("a.phi", m);
+    was.put("b.phi", m);
+    was.put("c.phi", Measure { program: None, ..m });
+    let mut now = Baseline::default();
+    now.put("a.phi", m);
+    now.put(
+        "b.phi",
+        Measure {
+            program: Some(2),
+            ..m
+        },
+    );
+    now.put("c.phi", m);
+    assert!(!now.edited(&was, "a.phi"));
+    assert!(now.edited(&was, "b.phi"));
+    assert!(!now.edited(&was, "c.phi"));
+    assert!(Baseline::from_str(
+        r#"{"a.phi": {"cycles": 1, "baskets": 1, "micros": 1, "program": "xyz"}}"#
+    )
+    .is_err());
+}
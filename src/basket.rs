@@ -1,75 +1,236 @@
 // SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
 // SPDX-License-Identifier: MIT
 
-use crate::data::Data;
+use crate::data::{Data, Datum};
+use crate::emu::ROOT_OB;
+use crate::kids::Kids;
 use crate::loc::Loc;
-use crate::object::Ob;
+use crate::object::{Ob, Object};
+use crate::program::Program;
+use crate::syntax;
 use itertools::Itertools;
 use regex::Regex;
 use rstest::rstest;
-use std::collections::HashMap;
+use serde::{Serialize, Serializer};
 use std::fmt;
+use std::num::NonZeroU32;
 use std::str::FromStr;
 
-pub type Bk = isize;
+/// The number of a basket, like `β3`. It's kept one more than it is in
+/// a `NonZeroU32`, like `Ob` is, while `Bk::NONE` is the ψ of empty
+/// baskets, which is printed as `-1`:
+///
+/// ```
+/// use phie::basket::Bk;
+/// assert_eq!("3", Bk::new(3).to_string());
+/// assert_eq!("-1", Bk::NONE.to_string());
+/// assert_eq!(4, std::mem::size_of::<Option<Bk>>());
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Bk(NonZeroU32);
 
-pub enum Kid {
+impl Bk {
+    /// No basket at all.
+    pub const NONE: Bk = Bk(NonZeroU32::MAX);
+
+    /// The basket with this number, which must be less than `u32::MAX - 1`.
+    pub const fn new(n: usize) -> Bk {
+        assert!(
+            n < u32::MAX as usize - 1,
+            "The number of the basket is too big"
+        );
+        match NonZeroU32::new(n as u32 + 1) {
+            Some(v) => Bk(v),
+            None => panic!("The number of the basket is too big"),
+        }
+    }
+
+    /// The number of the basket, to find it among others.
+    pub const fn index(self) -> usize {
+        self.0.get() as usize - 1
+    }
+
+    /// It's `Bk::NONE`.
+    pub fn is_none(self) -> bool {
+        self == Bk::NONE
+    }
+}
+
+impl fmt::Display for Bk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_none() {
+            f.write_str("-1")
+        } else {
+            write!(f, "{}", self.index())
+        }
+    }
+}
+
+impl From<Bk> for i64 {
+    fn from(bk: Bk) -> i64 {
+        if bk.is_none() {
+            -1
+        } else {
+            bk.index() as i64
+        }
+    }
+}
+
+impl Serialize for Bk {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(i64::from(*self))
+    }
+}
+
+impl FromStr for Bk {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "-1" {
+            return Ok(Bk::NONE);
+        }
+        match s.parse::<usize>() {
+            Ok(n) if n < u32::MAX as usize - 1 => Ok(Bk::new(n)),
+            _ => Err(format!("Can't parse the number of a basket '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Debug for Bk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// The state of an attribute in a basket: it's empty until requested,
+/// then it needs an object in a basket, then waits for the attribute
+/// of another basket, and finally is dataized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Kid<D: Datum = Data> {
     Empt,
     Rqtd,
     Need(Ob, Bk),
     Wait(Bk, Loc),
-    Dtzd(Data),
+    Dtzd(D),
 }
 
-pub struct Basket {
+impl<D: Datum> Kid<D> {
+    /// No transition is going on with it: it's either empty or
+    /// dataized already.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Kid::Empt | Kid::Dtzd(_))
+    }
+
+    /// The data, if it's dataized.
+    pub fn data(&self) -> Option<D> {
+        match self {
+            Kid::Dtzd(d) => Some(*d),
+            _ => None,
+        }
+    }
+
+    /// The basket and its attribute it waits for.
+    pub fn waiting_on(&self) -> Option<(Bk, &Loc)> {
+        match self {
+            Kid::Wait(bk, loc) => Some((*bk, loc)),
+            _ => None,
+        }
+    }
+
+    /// The object it needs and the basket of its ξ.
+    pub fn needed(&self) -> Option<(Ob, Bk)> {
+        match self {
+            Kid::Need(ob, bk) => Some((*ob, *bk)),
+            _ => None,
+        }
+    }
+}
+
+pub struct Basket<D: Datum = Data> {
     pub ob: Ob,
     pub psi: Bk,
-    pub kids: HashMap<Loc, Kid>,
+    pub kids: Kids<D>,
 }
 
-impl Basket {
-    pub fn empty() -> Basket {
+impl<D: Datum> Basket<D> {
+    pub fn empty() -> Basket<D> {
         Basket {
-            ob: 0,
-            psi: -1,
-            kids: HashMap::new(),
+            ob: ROOT_OB,
+            psi: Bk::NONE,
+            kids: Kids::new(),
         }
     }
 
-    pub fn start(ob: Ob, psi: Bk) -> Basket {
+    pub fn start(ob: Ob, psi: Bk) -> Basket<D> {
         Basket {
             ob,
             psi,
-            kids: HashMap::new(),
+            kids: Kids::new(),
         }
     }
 
+    /// Make it empty, keeping the room its kids took, so that the next
+    /// basket put in its place doesn't allocate it again.
+    pub fn clear(&mut self) {
+        self.ob = ROOT_OB;
+        self.psi = Bk::NONE;
+        self.kids.clear();
+    }
+
     pub fn is_empty(&self) -> bool {
-        self.psi < 0
+        self.psi.is_none()
     }
 
-    pub fn put(&mut self, loc: Loc, kid: Kid) {
+    pub fn put(&mut self, loc: Loc, kid: Kid<D>) {
         self.kids.insert(loc, kid);
     }
 }
 
-impl fmt::Display for Basket {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut parts = vec![];
-        parts.push(format!("ν{}", self.ob));
+impl<D: Datum> Basket<D> {
+    /// Print it with the definition of its object taken from the program,
+    /// like `[ν5 ⟦λ↦int-add, ρ↦ν1(𝜋)⟧, ξ:β7, 𝜑→?]`:
+    ///
+    /// ```
+    /// use phie::basket::Basket;
+    /// use phie::emu::{ROOT_BK, ROOT_OB};
+    /// use phie::program::Program;
+    /// let prog: Program = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+    /// let bsk = <Basket>::start(ROOT_OB, ROOT_BK);
+    /// assert_eq!("[ν0 ⟦! Δ↦0x002A⟧, ξ:β0]", bsk.display_with(&prog));
+    /// ```
+    pub fn display_with(&self, prog: &Program) -> String {
+        self.display_of(prog.get(self.ob))
+    }
+
+    /// Print it with this definition of its object, if it's known.
+    pub fn display_of(&self, obj: Option<&Object>) -> String {
+        self.render(
+            obj.map(|o| format!("{} {}", o.label(self.ob), o))
+                .unwrap_or_else(|| format!("ν{}", self.ob)),
+        )
+    }
+
+    fn render(&self, head: String) -> String {
+        let mut parts = vec![head];
         parts.push(format!("ξ:β{}", self.psi));
         parts.extend(
             self.kids
                 .iter()
+                .sorted_by(|a, b| a.0.cmp(b.0))
                 .map(|(i, d)| format!("{}{}", i, d))
-                .sorted()
                 .collect::<Vec<String>>(),
         );
-        write!(f, "[{}]", parts.iter().join(", "))
+        format!("[{}]", parts.iter().join(", "))
+    }
+}
+
+impl<D: Datum> fmt::Display for Basket<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.render(format!("ν{}", self.ob)))
     }
 }
 
-impl fmt::Display for Kid {
+impl<D: Datum> fmt::Display for Kid<D> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.write_str(&match self {
             Kid::Empt => "→∅".to_string(),
@@ -81,11 +242,11 @@ impl fmt::Display for Kid {
     }
 }
 
-impl FromStr for Basket {
+impl<D: Datum> FromStr for Basket<D> {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let re =
-            Regex::new("\\[(.*)]").map_err(|e| format!("Invalid basket regex pattern: {}", e))?;
+        let re = Regex::new(syntax::BASKET)
+            .map_err(|e| format!("Invalid basket regex pattern: {}", e))?;
         let mut bsk = Basket::empty();
         let caps = re
             .captures(s)
@@ -113,8 +274,8 @@ impl FromStr for Basket {
         bsk.psi = psi_str
             .parse()
             .map_err(|e| format!("Can't parse the psi part '{}': {}", psi_str, e))?;
-        let pre = Regex::new("^(.*)(⇶0x|⇉β|→\\(ν|→∅|→\\?)(.*?)\\)?$")
-            .map_err(|e| format!("Invalid kid pattern regex: {}", e))?;
+        let pre =
+            Regex::new(syntax::KID).map_err(|e| format!("Invalid kid pattern regex: {}", e))?;
         for p in parts.iter().skip(2) {
             let caps = pre
                 .captures(p)
@@ -130,7 +291,7 @@ impl FromStr for Basket {
                         .get(3)
                         .ok_or_else(|| format!("Missing data value in '{}'", p))?
                         .as_str();
-                    let parsed_data = Data::from_str_radix(data, 16)
+                    let parsed_data = D::from_hex(data)
                         .map_err(|e| format!("Can't parse data '{}': {}", data, e))?;
                     Kid::Dtzd(parsed_data)
                 }
@@ -183,9 +344,12 @@ impl FromStr for Basket {
     }
 }
 
+#[cfg(test)]
+use crate::emu::ROOT_BK;
+
 #[test]
 fn makes_simple_basket() {
-    let mut basket = Basket::start(0, 0);
+    let mut basket = <Basket>::start(ROOT_OB, ROOT_BK);
     basket.put(Loc::Delta, Kid::Dtzd(42));
     if let Kid::Dtzd(d) = basket.kids.get(&Loc::Delta).unwrap() {
         assert_eq!(42, *d);
@@ -194,42 +358,49 @@ fn makes_simple_basket() {
 
 #[test]
 fn checks_if_empty() {
-    let empty = Basket::empty();
+    let empty = <Basket>::empty();
     assert!(empty.is_empty());
-    let not_empty = Basket::start(0, 0);
+    let not_empty = <Basket>::start(ROOT_OB, ROOT_BK);
     assert!(!not_empty.is_empty());
 }
 
 #[test]
 fn prints_itself() {
-    let mut basket = Basket::start(5, 7);
+    let mut basket = <Basket>::start(Ob::new(5), Bk::new(7));
     basket.put(Loc::Delta, Kid::Dtzd(42));
-    basket.put(Loc::Rho, Kid::Wait(42, Loc::Phi));
-    basket.put(Loc::Attr(1), Kid::Need(7, 12));
+    basket.put(Loc::Rho, Kid::Wait(Bk::new(42), Loc::Phi));
+    basket.put(Loc::Attr(1), Kid::Need(Ob::new(7), Bk::new(12)));
     assert_eq!(
-        "[ν5, ξ:β7, Δ⇶0x002A, ρ⇉β42.𝜑, 𝛼1→(ν7;β12)]",
+        "[ν5, ξ:β7, ρ⇉β42.𝜑, Δ⇶0x002A, 𝛼1→(ν7;β12)]",
         basket.to_string()
     );
 }
 
 #[test]
 fn parses_itself() {
-    let txt = "[ν5, ξ:β18, Δ⇶0x1F21, ρ⇉β4.𝜑, 𝛼12→?, 𝛼1→?, 𝛼3→(ν5;β5), 𝜑→∅]";
-    let basket = Basket::from_str(txt).unwrap();
+    let txt = "[ν5, ξ:β18, ρ⇉β4.𝜑, 𝜑→∅, Δ⇶0x1F21, 𝛼1→?, 𝛼3→(ν5;β5), 𝛼12→?]";
+    let basket = <Basket>::from_str(txt).unwrap();
     assert_eq!(txt, basket.to_string());
 }
 
 #[test]
 fn fails_on_invalid_basket_format() {
-    let result = Basket::from_str("invalid");
+    let result = <Basket>::from_str("invalid");
     assert!(result.is_err());
     let err = result.err().unwrap();
     assert!(err.contains("Can't parse the basket"));
 }
 
+#[test]
+fn parses_negative_data() {
+    let bsk = <Basket>::from_str("[ν5, ξ:β7, 𝜑⇶0xFFFFFFFFFFFFFFF9]").unwrap();
+    assert_eq!(Some(-7), bsk.kids.get(&Loc::Phi).and_then(Kid::data));
+    assert_eq!("[ν5, ξ:β7, 𝜑⇶0xFFFFFFFFFFFFFFF9]", bsk.to_string());
+}
+
 #[test]
 fn fails_on_invalid_data_hex() {
-    let result = Basket::from_str("[ν5, ξ:β7, Δ⇶0xZZZZ]");
+    let result = <Basket>::from_str("[ν5, ξ:β7, Δ⇶0xZZZZ]");
     assert!(result.is_err());
     let err = result.err().unwrap();
     assert!(err.contains("Can't parse data"));
@@ -237,43 +408,43 @@ fn fails_on_invalid_data_hex() {
 
 #[test]
 fn fails_on_invalid_wait_format() {
-    let result = Basket::from_str("[ν5, ξ:β7, ρ⇉βinvalid]");
+    let result = <Basket>::from_str("[ν5, ξ:β7, ρ⇉βinvalid]");
     assert!(result.is_err());
 }
 
 #[test]
 fn fails_on_invalid_need_format() {
-    let result = Basket::from_str("[ν5, ξ:β7, 𝛼1→(νinvalid)]");
+    let result = <Basket>::from_str("[ν5, ξ:β7, 𝛼1→(νinvalid)]");
     assert!(result.is_err());
 }
 
 #[test]
 fn fails_on_unknown_kid_type() {
-    let result = Basket::from_str("[ν5, ξ:β7, 𝛼1→☠]");
+    let result = <Basket>::from_str("[ν5, ξ:β7, 𝛼1→☠]");
     assert!(result.is_err());
 }
 
 #[test]
 fn fails_on_invalid_need_obj_number() {
-    let result = Basket::from_str("[ν5, ξ:β7, 𝛼1→(νinvalid_obj;β5)]");
+    let result = <Basket>::from_str("[ν5, ξ:β7, 𝛼1→(νinvalid_obj;β5)]");
     assert!(result.is_err());
 }
 
 #[test]
 fn fails_on_invalid_need_psi_number() {
-    let result = Basket::from_str("[ν5, ξ:β7, 𝛼1→(ν5;βinvalid_psi)]");
+    let result = <Basket>::from_str("[ν5, ξ:β7, 𝛼1→(ν5;βinvalid_psi)]");
     assert!(result.is_err());
 }
 
 #[test]
 fn fails_on_invalid_location_in_kid() {
-    let result = Basket::from_str("[ν5, ξ:β7, invalid_loc→?]");
+    let result = <Basket>::from_str("[ν5, ξ:β7, invalid_loc→?]");
     assert!(result.is_err());
 }
 
 #[test]
 fn fails_on_invalid_ob_number() {
-    let result = Basket::from_str("[νinvalid, ξ:β7]");
+    let result = <Basket>::from_str("[νinvalid, ξ:β7]");
     assert!(result.is_err());
     let err = result.err().unwrap();
     assert!(err.contains("Can't parse the v part"));
@@ -281,7 +452,7 @@ fn fails_on_invalid_ob_number() {
 
 #[test]
 fn fails_on_invalid_psi_number() {
-    let result = Basket::from_str("[ν5, ξ:βinvalid]");
+    let result = <Basket>::from_str("[ν5, ξ:βinvalid]");
     assert!(result.is_err());
     let err = result.err().unwrap();
     assert!(err.contains("Can't parse the psi part"));
@@ -289,7 +460,7 @@ fn fails_on_invalid_psi_number() {
 
 #[test]
 fn fails_on_missing_psi_part() {
-    let result = Basket::from_str("[ν5]");
+    let result = <Basket>::from_str("[ν5]");
     assert!(result.is_err());
     let err = result.err().unwrap();
     assert!(err.contains("Missing psi part"));
@@ -297,7 +468,7 @@ fn fails_on_missing_psi_part() {
 
 #[test]
 fn fails_on_invalid_wait_number() {
-    let result = Basket::from_str("[ν5, ξ:β7, ρ⇉βnotnum.𝜑]");
+    let result = <Basket>::from_str("[ν5, ξ:β7, ρ⇉βnotnum.𝜑]");
     assert!(result.is_err());
     let err = result.err().unwrap();
     assert!(err.contains("Can't parse wait number"));
@@ -305,29 +476,51 @@ fn fails_on_invalid_wait_number() {
 
 #[test]
 fn fails_on_invalid_wait_loc() {
-    let result = Basket::from_str("[ν5, ξ:β7, ρ⇉β5.invalid]");
+    let result = <Basket>::from_str("[ν5, ξ:β7, ρ⇉β5.invalid]");
     assert!(result.is_err());
     let err = result.err().unwrap();
     assert!(err.contains("Can't parse wait loc"));
 }
 
 #[rstest]
-#[case("[ν5, ξ:β7, Δ⇶0x002A, ρ⇉β42.𝜑]")]
-#[case("[ν5, ξ:β18, Δ⇶0x1F21, ρ⇉β4.𝜑, 𝛼12→?, 𝛼1→?, 𝛼3→(ν5;β5), 𝜑→∅]")]
+#[case("[ν5, ξ:β7, ρ⇉β42.𝜑, Δ⇶0x002A]")]
+#[case("[ν5, ξ:β18, ρ⇉β4.𝜑, 𝜑→∅, Δ⇶0x1F21, 𝛼1→?, 𝛼3→(ν5;β5), 𝛼12→?]")]
 fn parses_text(#[case] txt: &str) {
-    let basket = Basket::from_str(txt).unwrap();
+    let basket = <Basket>::from_str(txt).unwrap();
     assert_eq!(txt, basket.to_string());
 }
 
 #[test]
 fn parses_wait_kid() {
     let txt = "[ν0, ξ:β0, ρ⇉β1.Δ]";
-    let basket = Basket::from_str(txt).unwrap();
-    match basket.kids.get(&Loc::Rho).unwrap() {
-        Kid::Wait(bk, loc) => {
-            assert_eq!(*bk, 1);
-            assert_eq!(*loc, Loc::Delta);
-        }
-        _ => panic!("Expected Wait kid"),
-    }
+    let basket = <Basket>::from_str(txt).unwrap();
+    assert_eq!(
+        Some((Bk::new(1), &Loc::Delta)),
+        basket.kids.get(&Loc::Rho).unwrap().waiting_on()
+    );
+}
+
+#[test]
+fn inspects_kids() {
+    assert!(<Kid>::Empt.is_terminal());
+    assert!(Kid::Dtzd(7).is_terminal());
+    assert!(!<Kid>::Rqtd.is_terminal());
+    assert_eq!(Some(7), Kid::Dtzd(7).data());
+    assert_eq!(None, <Kid>::Rqtd.data());
+    assert_eq!(
+        Some((Ob::new(3), Bk::new(4))),
+        <Kid>::Need(Ob::new(3), Bk::new(4)).needed()
+    );
+    assert_eq!(None, <Kid>::Wait(Bk::new(3), Loc::Phi).needed());
+    let wait = <Kid>::Wait(Bk::new(3), Loc::Phi);
+    assert_eq!(wait, wait.clone());
+}
+
+#[test]
+fn prints_itself_with_unknown_object() {
+    let prog = Program::new();
+    assert_eq!(
+        "[ν3, ξ:β1]",
+        <Basket>::start(Ob::new(3), Bk::new(1)).display_with(&prog)
+    );
 }
@@ -0,0 +1,19 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+extern crate phie;
+
+use phie::examples::{run, ACKERMANN};
+use std::env;
+
+pub fn main() {
+    env_logger::init();
+    let args: Vec<String> = env::args().collect();
+    match run(&ACKERMANN, &args) {
+        Ok(output) => println!("{}", output),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -4,71 +4,222 @@
 
 extern crate phie;
 
-use phie::data::Data;
-use phie::emu::{Emu, Opt};
+use phie::data::{Data, Typed};
+use phie::emu::Emu;
+use phie::error::Error;
+use phie::examples::{configure, dataize};
+use phie::program::{Expectation, Program};
 use std::env;
 use std::fs;
+use std::panic;
+use std::path::Path;
 use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Cycles, hits and atoms of a run, to compare with the next one.
+type Stats = (usize, usize, usize);
+
+/// Run the program in the file and, if it has a `# expect` trailer,
+/// check its outcome against it. It's `None` if the program failed
+/// just as its `# expect-error` says.
+pub fn run_emulator(filename: &str) -> Result<Option<Data>, Error> {
+    run_typed(filename).map(|t| t.map(|t| t.data()))
+}
+
+/// Run the program in the file, just like `run_emulator()`, with
+/// `Typed` data, which tells the type of the result by the atom that
+/// made it, see `Meta::returns`.
+pub fn run_typed(filename: &str) -> Result<Option<Typed>, Error> {
+    let path = Path::new(filename);
+    let text = Program::read(path)?;
+    let expected = Expectation::find(&text).map_err(|e| Error::Parse(path.to_path_buf(), e))?;
+    let prog = Program::from_str(&text).map_err(|e| Error::Parse(path.to_path_buf(), e))?;
+    let mut emu = Emu::<Typed>::of(&prog);
+    emu.file = Some(path.to_path_buf());
+    configure(&mut emu).map_err(Error::Usage)?;
+    let outcome = emu.try_dataize().map(|(t, _)| t);
+    if let Some(x) = expected {
+        x.check(&outcome.clone().map(|t| t.data()).map_err(String::from))
+            .map_err(Error::Mismatch)?;
+        if let Expectation::Error(_) = x {
+            return Ok(None);
+        }
+    }
+    outcome.map(Some)
+}
+
+/// Print the numbers of this run, together with their changes
+/// since the previous one, if there was any.
+pub fn perf_delta(prev: Option<Stats>, cur: Stats) -> String {
+    let names = ["cycles", "hits", "atoms"];
+    let now = [cur.0, cur.1, cur.2];
+    let before = prev.map(|p| [p.0, p.1, p.2]);
+    names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| match before {
+            Some(b) => format!("{} {} ({:+})", name, now[i], now[i] as i64 - b[i] as i64),
+            None => format!("{} {}", name, now[i]),
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Run the file once, catching a panic of the emulator, and return
+/// the report together with the numbers of this run.
+pub fn rerun(filename: &str, prev: Option<Stats>) -> (String, Option<Stats>) {
+    let emu = match Emu::from_file(Path::new(filename)) {
+        Ok(e) => e,
+        Err(e) => return (e.to_string(), prev),
+    };
+    match panic::catch_unwind(panic::AssertUnwindSafe(|| dataize(emu))) {
+        Ok(Err(e)) => (e, prev),
+        Ok(Ok((result, perf))) => {
+            let cur = (perf.cycles, perf.total_hits(), perf.total_atoms());
+            (
+                format!("Executor result: {}\n{}", result, perf_delta(prev, cur)),
+                Some(cur),
+            )
+        }
+        Err(_) => ("Dataization failed, see the error above".to_string(), prev),
+    }
+}
 
-fn emulate(phi_code: &str) -> Result<Data, String> {
-    let mut emu: Emu =
-        Emu::from_str(phi_code).map_err(|e| format!("Failed to parse phi code: {}", e))?;
-    emu.opt(Opt::LogSnapshots);
-    emu.opt(Opt::StopWhenTooManyCycles);
-    emu.opt(Opt::StopWhenStuck);
-    Ok(emu.dataize().0)
+/// Run the file again every time it's modified, until it disappears.
+pub fn watch(filename: &str) -> Result<String, String> {
+    let mut seen: Option<SystemTime> = None;
+    let mut prev = None;
+    loop {
+        let modified = fs::metadata(filename)
+            .and_then(|m| m.modified())
+            .map_err(|e| format!("Can't watch file '{}': {}", filename, e))?;
+        if seen != Some(modified) {
+            seen = Some(modified);
+            let (report, stats) = rerun(filename, prev);
+            println!("{}", report);
+            prev = stats;
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
 }
 
-pub fn run_emulator(filename: &str) -> Result<i16, String> {
-    let binding = fs::read_to_string(filename)
-        .map_err(|e| format!("Failed to read file '{}': {}", filename, e))?;
-    let phi_code: &str = binding.as_str();
-    emulate(phi_code)
+pub fn execute_program(args: &[String]) -> Result<Option<Data>, Error> {
+    execute_typed(args).map(|t| t.map(|t| t.data()))
 }
 
-pub fn execute_program(args: &[String]) -> Result<i16, String> {
+/// Run the file and check its result against the expected one, which
+/// is either a plain number, compared with the data only, or a typed
+/// value after `--expect`, like `--expect bool:true`. It's `None` if
+/// the program failed just as its `# expect-error` says.
+pub fn execute_typed(args: &[String]) -> Result<Option<Typed>, Error> {
     if args.len() < 2 {
-        return Err("Insufficient arguments".to_string());
+        return Err(Error::Usage("Insufficient arguments".to_string()));
     }
     let filename: &str = &args[1];
-    let result: i16 = run_emulator(filename)?;
-    if args.len() >= 3 {
-        let correct = args[2]
-            .parse::<i16>()
-            .map_err(|e| format!("Invalid expected value argument '{}': {}", args[2], e))?;
+    let typed = match run_typed(filename)? {
+        Some(t) => t,
+        None if args.len() >= 3 => {
+            return Err(Error::Mismatch(format!(
+                "Expected {}, but failed as the file expects",
+                args[args.len() - 1]
+            )))
+        }
+        None => return Ok(None),
+    };
+    let result: Data = typed.data();
+    if args.get(2).map(|a| a.as_str()) == Some("--expect") {
+        let arg = args
+            .get(3)
+            .ok_or_else(|| Error::Usage("Missing value after --expect".to_string()))?;
+        let correct = Typed::from_str(arg).map_err(|e| {
+            Error::Usage(format!("Invalid expected value argument '{}': {}", arg, e))
+        })?;
+        if typed != correct {
+            return Err(Error::Mismatch(format!(
+                "Result {} does not match expected {}",
+                typed, correct
+            )));
+        }
+    } else if args.len() >= 3 {
+        let correct = args[2].parse::<Data>().map_err(|e| {
+            Error::Usage(format!(
+                "Invalid expected value argument '{}': {}",
+                args[2], e
+            ))
+        })?;
         if result != correct {
-            return Err(format!(
+            return Err(Error::Mismatch(format!(
                 "Result {} does not match expected {}",
                 result, correct
-            ));
+            )));
         }
     }
-    Ok(result)
+    Ok(Some(typed))
 }
 
-pub fn validate_and_execute(args: &[String]) -> Result<i16, String> {
-    if args.len() < 2 {
-        return Err(format!(
-            "Usage: {} <filename> [expected_result]",
-            args.first().unwrap_or(&"custom_executor".to_string())
-        ));
-    }
+pub fn validate_and_execute(args: &[String]) -> Result<Option<Data>, Error> {
+    usage(args)?;
     execute_program(args)
 }
 
-pub fn run(args: &[String]) -> Result<String, String> {
-    let result = validate_and_execute(args)?;
-    Ok(format!("Executor result: {}", result))
+fn usage(args: &[String]) -> Result<(), Error> {
+    if args.len() < 2 {
+        return Err(Error::Usage(format!(
+            "Usage: {} [--quiet] [--json] <filename> [expected_result | --expect <type>:<value>]",
+            args.first().unwrap_or(&"custom_executor".to_string())
+        )));
+    }
+    Ok(())
+}
+
+pub fn run(args: &[String]) -> Result<String, Error> {
+    if args.get(1).map(|a| a.as_str()) == Some("--watch") {
+        let filename = args.get(2).ok_or_else(|| {
+            Error::Usage(format!(
+                "Usage: {} --watch <filename>",
+                args.first().unwrap_or(&"custom_executor".to_string())
+            ))
+        })?;
+        return watch(filename).map_err(Error::Failed);
+    }
+    let json = args.iter().any(|a| a == "--json");
+    let args: Vec<String> = args.iter().filter(|a| *a != "--json").cloned().collect();
+    usage(&args)?;
+    let result = execute_typed(&args)?;
+    match result {
+        Some(r) if json => Ok(r.to_json().to_string()),
+        Some(r) => Ok(format!("Executor result: {}", r.data())),
+        None if json => Ok(r#"{"type":"error","expected":true}"#.to_string()),
+        None => Ok("Executor result: expected error".to_string()),
+    }
 }
 
+/// Exit codes: 0 on success, 2 if the result is not the expected one,
+/// 3 if the program can't be parsed, 4 if it gets stuck, 5 if it makes
+/// too many cycles, 6 if it calls an atom it's not allowed to, 7 if an atom
+/// gets data of a wrong type with `+opt strict-types`, 8 if a locator walks
+/// out of the graph of objects, 9 if it needs an attribute nobody has,
+/// 10 if it takes too many hops to resolve, and 1 otherwise. With `--quiet` nothing is printed,
+/// with `--json` the result is printed like `{"type":"int","value":84}`.
 pub fn main() {
     env_logger::init();
-    let args: Vec<String> = env::args().collect();
+    let quiet = env::args().any(|a| a == "--quiet");
+    let args: Vec<String> = env::args().filter(|a| a != "--quiet").collect();
+    if args.get(1).map(|a| a.as_str()) != Some("--watch") {
+        panic::set_hook(Box::new(|_| {}));
+    }
     match run(&args) {
-        Ok(output) => println!("{}", output),
+        Ok(output) => {
+            if !quiet {
+                println!("{}", output);
+            }
+        }
         Err(e) => {
-            eprintln!("{}", e);
-            std::process::exit(1);
+            if !quiet {
+                eprintln!("{}", e);
+            }
+            std::process::exit(e.code());
         }
     }
 }
@@ -82,7 +233,7 @@ fn test_execute_program_with_valid_args() {
     ];
     let result = execute_program(&args);
     assert!(result.is_ok());
-    assert_eq!(result.unwrap(), 84);
+    assert_eq!(result.unwrap(), Some(84));
 }
 
 #[test]
@@ -95,7 +246,7 @@ fn test_execute_program_with_invalid_args() {
 #[test]
 fn executes_file_example() {
     assert_eq!(
-        84,
+        Some(84),
         run_emulator("tests/resources/written_test_example").unwrap()
     );
 }
@@ -103,7 +254,7 @@ fn executes_file_example() {
 #[test]
 fn executes_fibonacci_file() {
     assert_eq!(
-        21,
+        Some(21),
         run_emulator("tests/resources/written_fibonacci_test").unwrap()
     );
 }
@@ -111,7 +262,7 @@ fn executes_fibonacci_file() {
 #[test]
 fn executes_sum_file() {
     assert_eq!(
-        84,
+        Some(84),
         run_emulator("tests/resources/written_sum_test").unwrap()
     );
 }
@@ -122,7 +273,7 @@ fn test_emulate_basic() {
         ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
         ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
     ";
-    assert_eq!(42, emulate(phi_code).unwrap());
+    assert_eq!(42, dataize(Emu::from_str(phi_code).unwrap()).unwrap().0);
 }
 
 #[test]
@@ -133,19 +284,19 @@ fn test_execute_program_single_arg() {
     ];
     let result = execute_program(&args);
     assert!(result.is_ok());
-    assert_eq!(result.unwrap(), 84);
+    assert_eq!(result.unwrap(), Some(84));
 }
 
 #[test]
 fn test_run_emulator_sum() {
     let result = run_emulator("tests/resources/written_sum_test");
-    assert_eq!(result.unwrap(), 84);
+    assert_eq!(result.unwrap(), Some(84));
 }
 
 #[test]
 fn test_emulate_simple_data() {
     let phi_code = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧";
-    assert_eq!(1, emulate(phi_code).unwrap());
+    assert_eq!(1, dataize(Emu::from_str(phi_code).unwrap()).unwrap().0);
 }
 
 #[test]
@@ -156,13 +307,13 @@ fn test_emulate_with_lambda() {
         ν2(𝜋) ↦ ⟦ Δ ↦ 0x0005 ⟧
         ν3(𝜋) ↦ ⟦ Δ ↦ 0x0003 ⟧
     ";
-    assert_eq!(8, emulate(phi_code).unwrap());
+    assert_eq!(8, dataize(Emu::from_str(phi_code).unwrap()).unwrap().0);
 }
 
 #[test]
 fn test_run_emulator_fibonacci() {
     let result = run_emulator("tests/resources/written_fibonacci_test");
-    assert_eq!(21, result.unwrap());
+    assert_eq!(Some(21), result.unwrap());
 }
 
 #[test]
@@ -173,7 +324,7 @@ fn validates_and_executes_with_valid_args() {
     ];
     let result = validate_and_execute(&args);
     assert!(result.is_ok());
-    assert_eq!(result.unwrap(), 84);
+    assert_eq!(result.unwrap(), Some(84));
 }
 
 #[test]
@@ -185,7 +336,7 @@ fn validates_and_executes_with_expected_result() {
     ];
     let result = validate_and_execute(&args);
     assert!(result.is_ok());
-    assert_eq!(result.unwrap(), 84);
+    assert_eq!(result.unwrap(), Some(84));
 }
 
 #[test]
@@ -193,7 +344,7 @@ fn fails_validation_with_insufficient_args() {
     let args = vec!["custom_executor".to_string()];
     let result = validate_and_execute(&args);
     assert!(result.is_err());
-    assert!(result.unwrap_err().contains("Usage"));
+    assert!(result.unwrap_err().to_string().contains("Usage"));
 }
 
 #[test]
@@ -201,7 +352,7 @@ fn validates_with_empty_args() {
     let args: Vec<String> = vec![];
     let result = validate_and_execute(&args);
     assert!(result.is_err());
-    assert!(result.unwrap_err().contains("Usage"));
+    assert!(result.unwrap_err().to_string().contains("Usage"));
 }
 
 #[test]
@@ -221,7 +372,7 @@ fn test_run_with_no_args() {
     let args: Vec<String> = vec![];
     let result = run(&args);
     assert!(result.is_err());
-    assert!(result.unwrap_err().contains("Usage"));
+    assert!(result.unwrap_err().to_string().contains("Usage"));
 }
 
 #[test]
@@ -229,22 +380,22 @@ fn test_run_with_single_arg() {
     let args = vec!["custom_executor".to_string()];
     let result = run(&args);
     assert!(result.is_err());
-    assert!(result.unwrap_err().contains("Usage"));
+    assert!(result.unwrap_err().to_string().contains("Usage"));
 }
 
 #[test]
 fn test_emulate_with_invalid_phi_code() {
-    let result = emulate("invalid phi code");
-    assert!(result.is_err());
-    let err = result.err().unwrap();
-    assert!(err.contains("Failed to parse phi code"));
+    let file = std::env::temp_dir().join("phie-invalid-code-test.phi");
+    fs::write(&file, "invalid phi code").unwrap();
+    let err = run_emulator(file.to_str().unwrap()).unwrap_err();
+    assert_eq!(3, err.code());
 }
 
 #[test]
 fn test_run_emulator_with_nonexistent_file() {
     let result = run_emulator("nonexistent_file.txt");
     assert!(result.is_err());
-    let err = result.err().unwrap();
+    let err = result.err().unwrap().to_string();
     assert!(err.contains("Failed to read file"));
 }
 
@@ -257,7 +408,7 @@ fn test_execute_program_with_invalid_expected_value() {
     ];
     let result = execute_program(&args);
     assert!(result.is_err());
-    let err = result.err().unwrap();
+    let err = result.err().unwrap().to_string();
     assert!(err.contains("Invalid expected value argument"));
 }
 
@@ -270,7 +421,7 @@ fn test_execute_program_with_wrong_expected_value() {
     ];
     let result = execute_program(&args);
     assert!(result.is_err());
-    let err = result.err().unwrap();
+    let err = result.err().unwrap().to_string();
     assert!(err.contains("does not match expected"));
 }
 
@@ -279,7 +430,7 @@ fn test_execute_program_insufficient_args() {
     let args = vec!["program".to_string()];
     let result = execute_program(&args);
     assert!(result.is_err());
-    let err = result.err().unwrap();
+    let err = result.err().unwrap().to_string();
     assert!(err.contains("Insufficient arguments"));
 }
 
@@ -291,7 +442,7 @@ fn test_emulate_with_multiple_operations() {
         ν2(𝜋) ↦ ⟦ Δ ↦ 0x0006 ⟧
         ν3(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
     ";
-    assert_eq!(42, emulate(phi_code).unwrap());
+    assert_eq!(42, dataize(Emu::from_str(phi_code).unwrap()).unwrap().0);
 }
 
 #[test]
@@ -311,7 +462,7 @@ fn test_validate_and_execute_with_one_arg() {
     let args = vec!["custom_executor".to_string()];
     let result = validate_and_execute(&args);
     assert!(result.is_err());
-    assert!(result.unwrap_err().contains("custom_executor"));
+    assert!(result.unwrap_err().to_string().contains("custom_executor"));
 }
 
 #[test]
@@ -323,14 +474,14 @@ fn test_execute_program_with_correct_expected() {
     ];
     let result = execute_program(&args);
     assert!(result.is_ok());
-    assert_eq!(result.unwrap(), 21);
+    assert_eq!(result.unwrap(), Some(21));
 }
 
 #[test]
 fn test_run_emulator_fibonacci_file() {
     let result = run_emulator("tests/resources/written_fibonacci_test");
     assert!(result.is_ok());
-    assert_eq!(result.unwrap(), 21);
+    assert_eq!(result.unwrap(), Some(21));
 }
 
 #[test]
@@ -340,7 +491,7 @@ fn test_emulate_negative_number() {
         ν1(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν2(𝜋) ⟧
         ν2(𝜋) ↦ ⟦ Δ ↦ 0x0005 ⟧
     ";
-    assert_eq!(-5, emulate(phi_code).unwrap());
+    assert_eq!(-5, dataize(Emu::from_str(phi_code).unwrap()).unwrap().0);
 }
 
 #[test]
@@ -351,7 +502,7 @@ fn test_emulate_division() {
         ν2(𝜋) ↦ ⟦ Δ ↦ 0x0014 ⟧
         ν3(𝜋) ↦ ⟦ Δ ↦ 0x0004 ⟧
     ";
-    assert_eq!(5, emulate(phi_code).unwrap());
+    assert_eq!(5, dataize(Emu::from_str(phi_code).unwrap()).unwrap().0);
 }
 
 #[test]
@@ -373,19 +524,19 @@ fn test_emulate_subtraction() {
         ν2(𝜋) ↦ ⟦ Δ ↦ 0x000A ⟧
         ν3(𝜋) ↦ ⟦ Δ ↦ 0x0003 ⟧
     ";
-    assert_eq!(7, emulate(phi_code).unwrap());
+    assert_eq!(7, dataize(Emu::from_str(phi_code).unwrap()).unwrap().0);
 }
 
 #[test]
 fn test_emulate_zero_value() {
     let phi_code = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x0000 ⟧";
-    assert_eq!(0, emulate(phi_code).unwrap());
+    assert_eq!(0, dataize(Emu::from_str(phi_code).unwrap()).unwrap().0);
 }
 
 #[test]
 fn test_emulate_large_value() {
     let phi_code = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x00FF ⟧";
-    assert_eq!(255, emulate(phi_code).unwrap());
+    assert_eq!(255, dataize(Emu::from_str(phi_code).unwrap()).unwrap().0);
 }
 
 #[test]
@@ -397,7 +548,7 @@ fn test_run_with_expected_mismatch() {
     ];
     let result = run(&args);
     assert!(result.is_err());
-    let err = result.err().unwrap();
+    let err = result.err().unwrap().to_string();
     assert!(err.contains("does not match expected"));
 }
 
@@ -409,5 +560,124 @@ fn test_validate_and_execute_sum_file() {
     ];
     let result = validate_and_execute(&args);
     assert!(result.is_ok());
-    assert_eq!(result.unwrap(), 84);
+    assert_eq!(result.unwrap(), Some(84));
+}
+
+#[test]
+fn prints_perf_delta() {
+    assert_eq!("cycles 5, hits 10, atoms 2", perf_delta(None, (5, 10, 2)));
+    assert_eq!(
+        "cycles 5 (+1), hits 10 (-3), atoms 2 (+0)",
+        perf_delta(Some((4, 13, 2)), (5, 10, 2))
+    );
+}
+
+#[test]
+fn reruns_file_with_delta() {
+    let (report, stats) = rerun("tests/resources/written_sum_test", None);
+    assert!(report.contains("Executor result: 84"));
+    let (again, _) = rerun("tests/resources/written_sum_test", stats);
+    assert!(
+        again.contains("cycles") && again.contains("(+0)"),
+        "{}",
+        again
+    );
+}
+
+#[test]
+fn fails_to_watch_absent_file() {
+    let args = vec![
+        "custom_executor".to_string(),
+        "--watch".to_string(),
+        "/tmp/phie-absent-watch.phi".to_string(),
+    ];
+    assert!(run(&args)
+        .unwrap_err()
+        .to_string()
+        .contains("Can't watch file"));
+}
+
+#[test]
+fn fails_to_watch_without_file() {
+    let args = vec!["custom_executor".to_string(), "--watch".to_string()];
+    assert!(run(&args).unwrap_err().to_string().contains("Usage"));
+}
+
+#[test]
+fn checks_expected_result_in_file() {
+    let file = std::env::temp_dir().join("phie-expect-test.phi");
+    fs::write(&file, "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧\n# expect: 42").unwrap();
+    assert_eq!(Some(42), run_emulator(file.to_str().unwrap()).unwrap());
+    fs::write(&file, "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧\n# expect: 7").unwrap();
+    assert!(run_emulator(file.to_str().unwrap())
+        .unwrap_err()
+        .to_string()
+        .contains("does not match expected 7"));
+}
+
+#[test]
+fn prints_typed_result_as_json() {
+    let args = vec![
+        "custom_executor".to_string(),
+        "--json".to_string(),
+        "tests/resources/written_sum_test".to_string(),
+    ];
+    assert_eq!(r#"{"type":"int","value":84}"#, run(&args).unwrap());
+}
+
+#[test]
+fn checks_typed_expected_value() {
+    let file = std::env::temp_dir().join("phie-typed-expect-test.phi");
+    fs::write(
+        &file,
+        "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ λ ↦ int-less, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧\nν2(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧\nν3(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧",
+    )
+    .unwrap();
+    let mut args = vec![
+        "custom_executor".to_string(),
+        file.to_str().unwrap().to_string(),
+        "--expect".to_string(),
+        "bool:true".to_string(),
+    ];
+    assert_eq!(Some(Typed::Bool(true)), execute_typed(&args).unwrap());
+    args[3] = "int:1".to_string();
+    assert!(execute_typed(&args)
+        .unwrap_err()
+        .to_string()
+        .contains("Result bool:true does not match expected int:1"));
+}
+
+#[test]
+fn renders_type_mismatch_with_call_stack() {
+    let file = std::env::temp_dir().join("phie-type-mismatch-test.phi");
+    fs::write(
+        &file,
+        "+opt strict-types\nν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν2(𝜋), 𝛼1 ↦ ν2(𝜋) ⟧\nν2(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧",
+    )
+    .unwrap();
+    let err = run_emulator(file.to_str().unwrap()).unwrap_err();
+    assert_eq!(7, err.code());
+    assert!(
+        err.to_string()
+            .starts_with("The atom 'bool-if' of ν1 expects bool at ρ, but got int\n  in β"),
+        "{}",
+        err
+    );
+    assert!(err.to_string().ends_with(" ν0"), "{}", err);
+}
+
+#[test]
+fn passes_when_failing_as_expected() {
+    let file = std::env::temp_dir().join("phie-expect-error-test.phi");
+    fs::write(
+        &file,
+        "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν0(𝜋) ⟧\n# expect-error: empty baskets",
+    )
+    .unwrap();
+    assert_eq!(None, run_emulator(file.to_str().unwrap()).unwrap());
+    let args = vec![
+        "custom_executor".to_string(),
+        file.to_str().unwrap().to_string(),
+    ];
+    assert_eq!("Executor result: expected error", run(&args).unwrap());
 }
@@ -4,69 +4,25 @@
 extern crate phie;
 
 use phie::data::Data;
-use phie::emu::{Emu, Opt};
+use phie::examples::{self, FIBONACCI};
 use std::env;
 
 pub fn fibo(x: Data) -> Result<Data, String> {
-    let mut emu: Emu = format!(
-        "
-        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
-        ν1(𝜋) ↦ ⟦ Δ ↦ 0x{:04X} ⟧
-        ν2(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν1(𝜋) ⟧
-        ν3(𝜋) ↦ ⟦ 𝜑 ↦ ν13(𝜋) ⟧
-        ν5(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧
-        ν6(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ 𝜋.𝜋.𝛼0, 𝛼0 ↦ ν5(𝜋) ⟧
-        ν7(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧
-        ν8(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ 𝜋.𝜋.𝛼0, 𝛼0 ↦ ν7(𝜋) ⟧
-        ν9(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν8(𝜋) ⟧
-        ν10(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν6(𝜋) ⟧
-        ν11(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν9(𝜋), 𝛼0 ↦ ν10(𝜋) ⟧
-        ν12(𝜋) ↦ ⟦ λ ↦ int-less, ρ ↦ 𝜋.𝛼0, 𝛼0 ↦ ν5(𝜋) ⟧
-        ν13(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ ν12(𝜋), 𝛼0 ↦ ν7(𝜋), 𝛼1 ↦ ν11(𝜋) ⟧
-        ",
-        x
-    )
-    .parse()
-    .map_err(|e| format!("Failed to parse Fibonacci emulator: {}", e))?;
-    emu.opt(Opt::LogSnapshots);
-    emu.opt(Opt::StopWhenTooManyCycles);
-    emu.opt(Opt::StopWhenStuck);
-    Ok(emu.dataize().0)
+    Ok(examples::emulate(&FIBONACCI, x)?.0)
 }
 
 pub fn parse_fibonacci_args(args: &[String]) -> Result<(Data, i32), String> {
-    if args.len() < 3 {
-        return Err(format!(
-            "Usage: {} <input> <cycles>",
-            args.first().unwrap_or(&"fibonacci".to_string())
-        ));
-    }
-    let input = args[1]
-        .parse()
-        .map_err(|e| format!("Invalid input argument '{}': {}", args[1], e))?;
-    let cycles = args[2]
-        .parse()
-        .map_err(|e| format!("Invalid cycles argument '{}': {}", args[2], e))?;
-    Ok((input, cycles))
+    let a = examples::parse_args(FIBONACCI.name, args)?;
+    Ok((a.input, a.repetitions))
 }
 
 pub fn run_fibonacci_cycles(input: Data, cycles: i32) -> Result<(Data, Data), String> {
-    let mut total = 0;
-    let mut f = 0;
-    for _ in 0..cycles {
-        f = fibo(input)?;
-        total += f;
-    }
+    let (f, total, _) = examples::repeat(&FIBONACCI, input, cycles)?;
     Ok((f, total))
 }
 
 pub fn run(args: &[String]) -> Result<String, String> {
-    let (input, cycles) = parse_fibonacci_args(args)?;
-    let (f, total) = run_fibonacci_cycles(input, cycles)?;
-    Ok(format!(
-        "{}-th Fibonacci number is {}\nSum of results is {}",
-        input, f, total
-    ))
+    examples::run(&FIBONACCI, args)
 }
 
 pub fn main() {
@@ -144,7 +100,7 @@ fn fails_to_parse_invalid_cycles() {
     ];
     let result = parse_fibonacci_args(&args);
     assert!(result.is_err());
-    assert!(result.unwrap_err().contains("Invalid cycles argument"));
+    assert!(result.unwrap_err().contains("Invalid repetitions argument"));
 }
 
 #[test]
@@ -231,7 +187,7 @@ fn test_run_with_invalid_cycles_format() {
     ];
     let result = run(&args);
     assert!(result.is_err());
-    assert!(result.unwrap_err().contains("Invalid cycles argument"));
+    assert!(result.unwrap_err().contains("Invalid repetitions argument"));
 }
 
 #[test]
@@ -0,0 +1,44 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Debug adapter for phie programs, talking DAP over stdio.
+//!
+//! # Usage
+//!
+//! Configure your editor to start it as the debug adapter for `.phie` files:
+//!
+//! ```bash
+//! phie-dap
+//! ```
+
+extern crate phie;
+
+use std::io::{stdin, stdout, BufReader};
+use std::process::exit;
+
+use phie::dap::Session;
+use phie::lsp::{read_message, write_message};
+
+fn main() {
+    env_logger::init();
+    let mut input = BufReader::new(stdin());
+    let mut output = stdout();
+    let mut session = Session::new();
+    while !session.done {
+        match read_message(&mut input) {
+            Ok(Some(msg)) => {
+                for out in session.handle(&msg) {
+                    if let Err(e) = write_message(&mut output, &out) {
+                        eprintln!("{}", e);
+                        exit(1);
+                    }
+                }
+            }
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        }
+    }
+}
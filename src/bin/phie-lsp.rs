@@ -0,0 +1,43 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Language server for phie program files, talking LSP over stdio.
+//!
+//! # Usage
+//!
+//! Configure your editor to start it for `.phie` files:
+//!
+//! ```bash
+//! phie-lsp
+//! ```
+
+extern crate phie;
+
+use std::io::{stdin, stdout, BufReader};
+use std::process::exit;
+
+use phie::lsp::{read_message, write_message, Server};
+
+fn main() {
+    env_logger::init();
+    let mut input = BufReader::new(stdin());
+    let mut output = stdout();
+    let mut server = Server::new();
+    while !server.done {
+        match read_message(&mut input) {
+            Ok(Some(msg)) => {
+                for out in server.handle(&msg) {
+                    if let Err(e) = write_message(&mut output, &out) {
+                        eprintln!("{}", e);
+                        exit(1);
+                    }
+                }
+            }
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        }
+    }
+}
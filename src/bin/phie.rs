@@ -9,8 +9,53 @@
 //!
 //! ```bash
 //! phie program.phie
+//! phie program.phie --opt 'max-cycles 100000' --opt stop-when-stuck
+//! phie program.phie --events jsonl
+//! phie program.phie --opt explain
+//! phie program.phie --memo memo.json
+//! phie check program.phie --format sarif
+//! phie check program.phie --types --ranges
+//! phie run-all programs/
+//! phie corpus programs/ --report md
+//! phie stats program.phie
+//! phie fingerprint a.phie b.phie
+//! phie diff a.phie b.phie
+//! phie reduce crash-0123456789abcdef-42.json
+//! phie inspect crash-0123456789abcdef-42.json
+//! phie --help-syntax
+//! phie conformance --report md
+//! phie bench program.phie --compare default parallel no-gc
+//! phie bench programs/ --baseline baseline.json --threshold 5
+//! phie serve program.phie --port 7878
+//! phie sweep program.phie --arg x=0..100
 //! ```
 //!
+//! Built with the `heap` feature, it also prints the bytes allocated
+//! by parsing and emulation to stderr.
+//!
+//! With `--events jsonl` every event of the emulator is printed as
+//! a JSON line while it runs, see `phie::stream` for the schema,
+//! and the result goes last, as usual.
+//!
+//! With `--memo memo.json` the results of pure atoms are memoized and
+//! kept in the file from one run of the same program to the next,
+//! together with the number of kids the baskets of each object held.
+//!
+//! With the `serve` feature, `phie serve` lets a debugger in the
+//! browser step through the program over WebSocket, see `phie::serve`.
+//!
+//! With `reduce` it prints a smaller program that fails the same way
+//! as the one in a crash dump, see `phie::reduce`.
+//!
+//! With `inspect` it reads queries, like `kids(state=wait)`, and answers
+//! them with the baskets saved in a crash dump, see `phie::inspect`.
+//!
+//! With `--help-syntax` it prints the grammar of programs, see `phie::syntax`.
+//!
+//! Options are taken from the `+opt` lines of the program, then from
+//! `PHIE_*` environment variables, like `PHIE_MAX_CYCLES=100000`, and
+//! then from `--opt` arguments, each replacing the previous ones.
+//!
 //! The program file should contain phie calculus expressions in the format:
 //! ```text
 //! ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
@@ -24,10 +69,19 @@ use env_logger::init as logger_init;
 
 use phie::cli::run;
 
+#[cfg(feature = "heap")]
+#[global_allocator]
+static HEAP: phie::heap::Counting = phie::heap::Counting;
+
 fn main() {
     logger_init();
     let args: Vec<String> = args().collect();
-    match run(&args) {
+    let result = run(&args);
+    #[cfg(feature = "heap")]
+    for (module, bytes) in phie::heap::ledger() {
+        eprintln!("Allocated by {}: {} bytes", module, bytes);
+    }
+    match result {
         Ok(output) => println!("{}", output),
         Err(e) => {
             eprintln!("{}", e);
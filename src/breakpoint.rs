@@ -0,0 +1,197 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Conditions on the kids of baskets, which stop the debugger when a
+//! kid is dataized, like `break when ν9.𝛼0 dataizes to 0`. A condition
+//! is parsed once and given to `Emu::break_when()`, which checks it
+//! every time a kid gets its data, while there are no conditions
+//! nothing is checked at all:
+//!
+//! ```
+//! use phie::emu::Emu;
+//! use phie::perf::Perf;
+//! let mut emu: Emu = "
+//!   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+//!   ν1(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν2(𝜋) ⟧
+//!   ν2(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+//! ".parse().unwrap();
+//! emu.break_when("break when ν1.ρ dataizes to 7".parse().unwrap());
+//! let mut perf = Perf::new();
+//! let hit = loop {
+//!     if let Some(b) = emu.take_break() {
+//!         break b;
+//!     }
+//!     assert!(emu.step(&mut perf).unwrap().is_none());
+//! };
+//! assert_eq!((1, 7), (hit.ob.index(), hit.data));
+//! ```
+//!
+//! The grammar is `[break when] [νN][.loc] dataizes [to|above|below D]`,
+//! where a missing object means any object, a missing loc means `𝜑`,
+//! and the data is either decimal or hex, like `0x002A`.
+
+use crate::basket::Bk;
+use crate::data::Data;
+use crate::loc::{index, Loc};
+use crate::object::Ob;
+use rstest::rstest;
+use std::fmt;
+use std::str::FromStr;
+
+/// What the data of a kid must be, to stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Test {
+    Any,
+    Equal(Data),
+    Above(Data),
+    Below(Data),
+}
+
+/// A condition of a breakpoint, see the grammar above.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    /// The object of the basket, or any, if not set.
+    pub ob: Option<Ob>,
+    pub loc: Loc,
+    pub test: Test,
+}
+
+/// A condition that held, found by `Emu::take_break()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Break {
+    /// The position of the condition, in the order they were given.
+    pub condition: usize,
+    pub bk: Bk,
+    pub ob: Ob,
+    pub loc: Loc,
+    pub data: Data,
+}
+
+impl Condition {
+    /// Does it hold when this kid of a basket of the object gets the data?
+    pub fn holds(&self, ob: Ob, loc: &Loc, d: Data) -> bool {
+        self.ob.is_none_or(|o| o == ob)
+            && self.loc == *loc
+            && match self.test {
+                Test::Any => true,
+                Test::Equal(x) => d == x,
+                Test::Above(x) => d > x,
+                Test::Below(x) => d < x,
+            }
+    }
+
+    /// The same condition, on this object, unless it names its own.
+    pub fn on(mut self, ob: Ob) -> Condition {
+        self.ob.get_or_insert(ob);
+        self
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim();
+        let text = text.strip_prefix("break when ").unwrap_or(text);
+        let mut words = text.split_whitespace().peekable();
+        let (ob, loc) = match words.next_if(|w| *w != "dataizes") {
+            None => (None, Loc::Phi),
+            Some(subject) => {
+                let (ob, loc) = match subject.strip_prefix('ν') {
+                    Some(rest) => match rest.split_once('.') {
+                        Some((num, loc)) => (Some(num), Some(loc)),
+                        None => (Some(rest), None),
+                    },
+                    None => (None, Some(subject.strip_prefix('.').unwrap_or(subject))),
+                };
+                (
+                    ob.map(|n| index(n, Ob::LIMIT, "ν").map(Ob::new))
+                        .transpose()?,
+                    loc.map_or(Ok(Loc::Phi), Loc::from_str)?,
+                )
+            }
+        };
+        if words.next() != Some("dataizes") {
+            return Err(format!("There is no 'dataizes' in the condition '{}'", s));
+        }
+        let test = match (words.next(), words.next()) {
+            (None, _) => Test::Any,
+            (Some(op), Some(d)) => {
+                let d = data(d)?;
+                match op {
+                    "to" => Test::Equal(d),
+                    "above" => Test::Above(d),
+                    "below" => Test::Below(d),
+                    _ => return Err(format!("Unknown test '{}', try to, above or below", op)),
+                }
+            }
+            (Some(op), None) => return Err(format!("There is no data after '{}'", op)),
+        };
+        if let Some(w) = words.next() {
+            return Err(format!("Unexpected '{}' in the condition '{}'", w, s));
+        }
+        Ok(Condition { ob, loc, test })
+    }
+}
+
+/// The data of a condition, decimal or hex.
+fn data(s: &str) -> Result<Data, String> {
+    match s.strip_prefix("0x") {
+        Some(hex) => Data::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .map_err(|e| format!("Invalid data '{}': {}", s, e))
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let subject = match (self.ob, &self.loc) {
+            (Some(ob), Loc::Phi) => format!("ν{} ", ob),
+            (Some(ob), loc) => format!("ν{}.{} ", ob, loc),
+            (None, Loc::Phi) => String::new(),
+            (None, loc) => format!("{} ", loc),
+        };
+        let test = match self.test {
+            Test::Any => String::new(),
+            Test::Equal(d) => format!(" to {}", d),
+            Test::Above(d) => format!(" above {}", d),
+            Test::Below(d) => format!(" below {}", d),
+        };
+        write!(f, "{}dataizes{}", subject, test)
+    }
+}
+
+#[rstest]
+#[case("ν9.𝛼0 dataizes to 0", "ν9.𝛼0 dataizes to 0")]
+#[case("break when ν9.𝛼0 dataizes to 0x002A", "ν9.𝛼0 dataizes to 42")]
+#[case("ν3 dataizes", "ν3 dataizes")]
+#[case("ρ dataizes below -1", "ρ dataizes below -1")]
+#[case(".𝛼1 dataizes above 7", "𝛼1 dataizes above 7")]
+#[case("dataizes", "dataizes")]
+pub fn parses_and_prints(#[case] txt: &str, #[case] expected: &str) {
+    let cond: Condition = txt.parse().unwrap();
+    assert_eq!(expected, cond.to_string());
+    assert_eq!(cond, expected.parse().unwrap());
+}
+
+#[rstest]
+#[case("ν9.𝛼0 is 0")]
+#[case("ν9.𝛼0 dataizes to")]
+#[case("ν9.𝛼0 dataizes near 5")]
+#[case("ν9.𝛼0 dataizes to x")]
+#[case("ν9.zz dataizes")]
+#[case("ν9 dataizes to 1 now")]
+pub fn refuses_broken_conditions(#[case] txt: &str) {
+    assert!(txt.parse::<Condition>().is_err());
+}
+
+#[test]
+fn holds_for_matching_kids() {
+    let cond: Condition = "ν9.𝛼0 dataizes to 0".parse().unwrap();
+    assert!(cond.holds(Ob::new(9), &Loc::Attr(0), 0));
+    assert!(!cond.holds(Ob::new(9), &Loc::Attr(0), 1));
+    assert!(!cond.holds(Ob::new(8), &Loc::Attr(0), 0));
+    assert!(!cond.holds(Ob::new(9), &Loc::Rho, 0));
+    let any: Condition = "dataizes above 3".parse().unwrap();
+    assert!(any.clone().holds(Ob::new(5), &Loc::Phi, 4));
+    assert!(!any.on(Ob::new(2)).holds(Ob::new(5), &Loc::Phi, 4));
+}
@@ -0,0 +1,776 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Static verification of program text, without running it.
+//!
+//! The diagnostics can be printed for humans, or in JSON and SARIF,
+//! so that editors and CI systems may annotate the source files:
+//!
+//! ```
+//! use phie::check::{report, verify, Format};
+//! let diags = verify("ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν7(𝜋) ⟧");
+//! assert_eq!(1, diags.len());
+//! assert!(report("a.phi", &diags, Format::Json).contains("\"rule\":\"undefined\""));
+//! ```
+
+use crate::atom::{expects, returns};
+use crate::data::{Data, Type};
+use crate::emu::{Opt, ROOT_OB};
+use crate::loc::Loc;
+use crate::locator::Locator;
+use crate::object::{Ob, Object};
+use crate::program::Program;
+use serde_json::{json, Value as Json};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        })
+    }
+}
+
+/// A problem found in the text, at the given line (starting from one).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub level: Level,
+    pub rule: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn new(line: usize, level: Level, rule: &str, message: String) -> Diagnostic {
+        Diagnostic {
+            line,
+            level,
+            rule: rule.to_string(),
+            message,
+        }
+    }
+}
+
+/// The way diagnostics are printed by `report`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+    Sarif,
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "sarif" => Ok(Format::Sarif),
+            _ => Err(format!("Unknown format '{}', try text, json or sarif", s)),
+        }
+    }
+}
+
+/// Find all problems in the text of a program. Comments and lines with `+include`
+/// directives are skipped, since there is no file to resolve them against.
+/// Lines with `+opt` are only checked to be known options, and the ones
+/// with `+input` to refer to objects that are defined. Locators that
+/// surely walk out of the graph, see `Error::LocatorEscaped`, are warned
+/// about: 𝜋 of ν0, and ρ of an object that is reached from Φ and has
+/// neither ρ nor 𝜑.
+pub fn verify(text: &str) -> Vec<Diagnostic> {
+    let mut diags = vec![];
+    let mut defined: HashMap<Ob, usize> = HashMap::new();
+    let mut refs: Vec<(usize, Ob)> = vec![];
+    let mut objects: HashMap<Ob, Object> = HashMap::new();
+    let mut walks: Vec<(usize, Ob, Locator)> = vec![];
+    for (pos, line) in text.split('\n').enumerate() {
+        let line = line.trim();
+        let num = pos + 1;
+        if line.is_empty() || line.starts_with("+include") || line.starts_with('#') {
+            continue;
+        }
+        if let Some(opt) = line.strip_prefix("+opt") {
+            if let Err(e) = Opt::of_program(opt) {
+                diags.push(Diagnostic::new(num, Level::Error, "option", e));
+            }
+            continue;
+        }
+        if let Some(input) = line.strip_prefix("+input") {
+            match Program::parse_input(input.trim()) {
+                Ok((_, ob)) => refs.push((num, ob)),
+                Err(e) => diags.push(Diagnostic::new(num, Level::Error, "input", e)),
+            }
+            continue;
+        }
+        match Program::parse_line(line) {
+            Err(e) => diags.push(Diagnostic::new(num, Level::Error, "syntax", e)),
+            Ok((ob, obj)) => {
+                if let Some(first) = defined.get(&ob) {
+                    diags.push(Diagnostic::new(
+                        num,
+                        Level::Error,
+                        "duplicate",
+                        format!("The object ν{} is already defined at line {}", ob, first),
+                    ));
+                } else {
+                    defined.insert(ob, num);
+                    objects.insert(ob, obj.clone());
+                }
+                for (locator, _) in obj.attrs.values() {
+                    match locator.loc(0) {
+                        Some(Loc::Obj(r)) => refs.push((num, *r)),
+                        Some(Loc::Pi) if ob == ROOT_OB => diags.push(Diagnostic::new(
+                            num,
+                            Level::Warning,
+                            "escape",
+                            format!(
+                                "The locator {} of ν{} escapes the graph at ν{}, which has no 𝜋",
+                                locator, ob, ob
+                            ),
+                        )),
+                        Some(Loc::Root) => walks.push((num, ob, locator.clone())),
+                        _ => {}
+                    }
+                }
+                if let Some(m) = obj.atom() {
+                    for (loc, _) in m.args {
+                        if !obj.attrs.contains_key(loc) {
+                            diags.push(Diagnostic::new(
+                                num,
+                                Level::Error,
+                                "arity",
+                                format!(
+                                    "The atom '{}' of ν{} needs {}, which is missing",
+                                    m.name, ob, loc
+                                ),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    for (num, ob, locator) in walks {
+        if let Some(at) = escapes(&objects, &locator) {
+            diags.push(Diagnostic::new(
+                num,
+                Level::Warning,
+                "escape",
+                format!(
+                    "The locator {} of ν{} escapes the graph at ν{}, which has no ρ",
+                    locator, ob, at
+                ),
+            ));
+        }
+    }
+    let mut used = HashSet::new();
+    for (num, r) in refs {
+        used.insert(r);
+        if !defined.contains_key(&r) {
+            diags.push(Diagnostic::new(
+                num,
+                Level::Error,
+                "undefined",
+                format!("The object ν{} is referenced, but not defined", r),
+            ));
+        }
+    }
+    if !defined.is_empty() && !defined.contains_key(&ROOT_OB) {
+        diags.push(Diagnostic::new(
+            1,
+            Level::Error,
+            "no-root",
+            "There is no ν0 object to start dataization from".to_string(),
+        ));
+    }
+    for (ob, num) in defined.iter() {
+        if *ob != ROOT_OB && !used.contains(ob) {
+            diags.push(Diagnostic::new(
+                *num,
+                Level::Warning,
+                "unused",
+                format!("The object ν{} is never referenced", ob),
+            ));
+        }
+    }
+    diags.sort_by(|a, b| a.line.cmp(&b.line).then(a.rule.cmp(&b.rule)));
+    diags
+}
+
+/// Walk the locator, which starts at Φ, through the objects as long as
+/// they are known, returning the one it asks for ρ, while it has neither
+/// ρ nor 𝜑 to find it in.
+fn escapes(objects: &HashMap<Ob, Object>, locator: &Locator) -> Option<Ob> {
+    let mut ob = ROOT_OB;
+    let mut locs = locator.locs()[1..].iter();
+    let mut loc = locs.next();
+    for _ in 0..objects.len() + locator.locs().len() {
+        let obj = objects.get(&ob)?;
+        let next = loc?;
+        let target = match (obj.attrs.get(next), obj.attrs.get(&Loc::Phi)) {
+            (Some((p, _)), _) => {
+                loc = locs.next();
+                p
+            }
+            (None, Some((p, _))) => p,
+            (None, None) if *next == Loc::Rho => return Some(ob),
+            (None, None) => return None,
+        };
+        match target.locs() {
+            [Loc::Obj(o)] => ob = *o,
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Find the atoms that will likely get data of a wrong type, see
+/// `Opt::StrictTypes`, without running the program. The type flowing
+/// into an atom is known only if its attribute refers straight to an
+/// object, which has a Δ, an atom with a known result, or a 𝜑 like that;
+/// everything else is unknown and never reported.
+///
+/// ```
+/// use phie::check::types;
+/// let diags = types("ν0(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋), 𝛼1 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧");
+/// assert_eq!("The atom 'bool-if' of ν0 expects bool at ρ, but ν1 gives int", diags[0].message);
+/// ```
+pub fn types(text: &str) -> Vec<Diagnostic> {
+    let mut objects: HashMap<Ob, (usize, Object)> = HashMap::new();
+    for (pos, line) in text.split('\n').enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('+') || line.starts_with('#') {
+            continue;
+        }
+        if let Ok((ob, obj)) = Program::parse_line(line) {
+            objects.entry(ob).or_insert((pos + 1, obj));
+        }
+    }
+    let mut known = HashMap::new();
+    let mut diags = vec![];
+    for (ob, (num, obj)) in objects.iter() {
+        let Some(name) = obj.atom().map(|m| m.name) else {
+            continue;
+        };
+        for (loc, _) in obj.attrs.iter() {
+            let Some(expected) = expects(name, loc) else {
+                continue;
+            };
+            let Some(target) = referred(obj, loc) else {
+                continue;
+            };
+            if let Some(found) = infer(target, &objects, &mut known, &mut HashSet::new()) {
+                if found != expected {
+                    diags.push(Diagnostic::new(
+                        *num,
+                        Level::Warning,
+                        "type",
+                        format!(
+                            "The atom '{}' of ν{} expects {} at {}, but ν{} gives {}",
+                            name, ob, expected, loc, target, found
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+    diags.sort_by(|a, b| a.line.cmp(&b.line).then(a.message.cmp(&b.message)));
+    diags
+}
+
+/// The object the attribute refers to straight, like `ν3(𝜋)`.
+fn referred(obj: &Object, loc: &Loc) -> Option<Ob> {
+    match obj.attrs.get(loc)?.0.locs() {
+        [Loc::Obj(r)] => Some(*r),
+        _ => None,
+    }
+}
+
+/// The type of the data of the object, if it can be known statically.
+fn infer(
+    ob: Ob,
+    objects: &HashMap<Ob, (usize, Object)>,
+    known: &mut HashMap<Ob, Option<Type>>,
+    seen: &mut HashSet<Ob>,
+) -> Option<Type> {
+    if let Some(t) = known.get(&ob) {
+        return *t;
+    }
+    if !seen.insert(ob) {
+        return None;
+    }
+    let (_, obj) = objects.get(&ob)?;
+    let mut follow = |loc: &Loc| referred(obj, loc).and_then(|r| infer(r, objects, known, seen));
+    let t = if obj.delta.is_some() {
+        Some(Type::Int)
+    } else if let Some(name) = obj.atom().map(|m| m.name) {
+        returns(name).or_else(|| {
+            let (a, b) = (follow(&Loc::Attr(0)), follow(&Loc::Attr(1)));
+            if a == b {
+                a
+            } else {
+                None
+            }
+        })
+    } else {
+        follow(&Loc::Phi)
+    };
+    known.insert(ob, t);
+    t
+}
+
+/// The integers the data of an object may be, from `lo` to `hi`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    pub lo: Data,
+    pub hi: Data,
+}
+
+impl Range {
+    pub fn of(d: Data) -> Range {
+        Range { lo: d, hi: d }
+    }
+
+    /// It's just one integer.
+    pub fn single(&self) -> Option<Data> {
+        (self.lo == self.hi).then_some(self.lo)
+    }
+
+    fn contains(&self, d: Data) -> bool {
+        self.lo <= d && d <= self.hi
+    }
+
+    fn union(&self, other: &Range) -> Range {
+        Range {
+            lo: self.lo.min(other.lo),
+            hi: self.hi.max(other.hi),
+        }
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.single() {
+            Some(d) => write!(f, "{}", d),
+            None => write!(f, "{}..={}", self.lo, self.hi),
+        }
+    }
+}
+
+/// What the integer atom surely does with the ranges of its arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Its result is in the range.
+    Within(Range),
+    /// It overflows, whatever the arithmetic, see `Opt::Arithmetic`.
+    Overflow,
+    /// It divides by zero.
+    DivZero,
+    /// Its result is not known statically.
+    Unknown,
+}
+
+/// The ranges of the data of objects, found without running the program,
+/// by the atoms they are made with, see `Flow`. A range is known only if
+/// every attribute on the way refers straight to an object, like `ν3(𝜋)`,
+/// which has a Δ, an integer atom or a 𝜑 like that; the objects of `+input`
+/// lines may get any data:
+///
+/// ```
+/// use phie::check::{Range, Ranges};
+/// use phie::object::Ob;
+/// use phie::program::Program;
+/// let prog: Program = "
+///   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+///   ν1(𝜋) ↦ ⟦ λ ↦ int-times, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧
+///   ν2(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+/// ".parse().unwrap();
+/// let mut ranges = Ranges::new(|ob| prog.get(ob), prog.inputs().values().copied().collect());
+/// assert_eq!(Some(Range::of(49)), ranges.of(Ob::new(0)));
+/// ```
+pub struct Ranges<'a, F: Fn(Ob) -> Option<&'a Object>> {
+    find: F,
+    inputs: HashSet<Ob>,
+    known: HashMap<Ob, Option<Range>>,
+}
+
+impl<'a, F: Fn(Ob) -> Option<&'a Object>> Ranges<'a, F> {
+    pub fn new(find: F, inputs: HashSet<Ob>) -> Self {
+        Ranges {
+            find,
+            inputs,
+            known: HashMap::new(),
+        }
+    }
+
+    /// The range of the data of the object, if it can be known statically.
+    pub fn of(&mut self, ob: Ob) -> Option<Range> {
+        if let Some(r) = self.known.get(&ob) {
+            return *r;
+        }
+        self.known.insert(ob, None);
+        let r = match self.flow(ob) {
+            Some(Flow::Within(r)) => Some(r),
+            _ => None,
+        };
+        self.known.insert(ob, r);
+        r
+    }
+
+    /// What the atom of the object does, unless it has no integer atom.
+    pub fn flow(&mut self, ob: Ob) -> Option<Flow> {
+        let obj = (self.find)(ob)?;
+        #[cfg(feature = "rational")]
+        if obj.denominator.is_some() {
+            return Some(Flow::Unknown);
+        }
+        if let Some(d) = obj.delta {
+            if self.inputs.contains(&ob) {
+                return Some(Flow::Unknown);
+            }
+            return Some(Flow::Within(Range::of(d)));
+        }
+        let Some(name) = obj.atom().map(|m| m.name) else {
+            return Some(
+                referred(obj, &Loc::Phi)
+                    .and_then(|r| self.of(r))
+                    .map_or(Flow::Unknown, Flow::Within),
+            );
+        };
+        let mut arg = |loc: Loc| referred(obj, &loc).and_then(|r| self.of(r));
+        if name == "bool-if" {
+            let term = arg(Loc::Rho);
+            let (yes, no) = (arg(Loc::Attr(0)), arg(Loc::Attr(1)));
+            let r = match term {
+                Some(t) if t.single() == Some(1) => yes,
+                Some(t) if !t.contains(1) => no,
+                _ => yes.zip(no).map(|(a, b)| a.union(&b)),
+            };
+            return Some(r.map_or(Flow::Unknown, Flow::Within));
+        }
+        let (a, b) = (arg(Loc::Rho), arg(Loc::Attr(0)));
+        let wide = |r: Range| (r.lo as i128, r.hi as i128);
+        let exact = match (name, a, b) {
+            ("int-less", _, _) | ("int-to-bool", _, _) | ("bool-to-int", _, _) => {
+                return Some(Flow::Within(match (name, a, b) {
+                    ("int-less", Some(a), Some(b)) if a.hi < b.lo => Range::of(1),
+                    ("int-less", Some(a), Some(b)) if a.lo >= b.hi => Range::of(0),
+                    ("int-to-bool", Some(a), _) if !a.contains(0) => Range::of(1),
+                    ("bool-to-int", Some(a), _) if !a.contains(1) => Range::of(0),
+                    _ => Range { lo: 0, hi: 1 },
+                }))
+            }
+            ("int-div", _, Some(b)) if b.single() == Some(0) => return Some(Flow::DivZero),
+            ("int-add", Some(a), Some(b)) => (wide(a).0 + wide(b).0, wide(a).1 + wide(b).1),
+            ("int-sub", Some(a), Some(b)) => (wide(a).0 - wide(b).1, wide(a).1 - wide(b).0),
+            ("int-neg", Some(a), _) => (-wide(a).1, -wide(a).0),
+            ("int-times", Some(a), Some(b)) | ("int-div", Some(a), Some(b)) => {
+                if name == "int-div" && b.contains(0) {
+                    return Some(Flow::Unknown);
+                }
+                let (a, b) = (wide(a), wide(b));
+                let corners = [(a.0, b.0), (a.0, b.1), (a.1, b.0), (a.1, b.1)].map(|(x, y)| {
+                    if name == "int-div" {
+                        x / y
+                    } else {
+                        x * y
+                    }
+                });
+                (
+                    *corners.iter().min().expect("There are four corners"),
+                    *corners.iter().max().expect("There are four corners"),
+                )
+            }
+            _ => return Some(Flow::Unknown),
+        };
+        Some(
+            if exact.1 < Data::MIN as i128 || exact.0 > Data::MAX as i128 {
+                Flow::Overflow
+            } else if exact.0 < Data::MIN as i128 || exact.1 > Data::MAX as i128 {
+                Flow::Unknown
+            } else {
+                Flow::Within(Range {
+                    lo: exact.0 as Data,
+                    hi: exact.1 as Data,
+                })
+            },
+        )
+    }
+}
+
+/// Find the integer atoms that surely overflow or divide by zero, see
+/// `Ranges`, without running the program.
+///
+/// ```
+/// use phie::check::ranges;
+/// let diags = ranges("ν0(𝜋) ↦ ⟦ λ ↦ int-div, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧\nν2(𝜋) ↦ ⟦ Δ ↦ 0x0000 ⟧");
+/// assert_eq!("The atom 'int-div' of ν0 surely divides by zero", diags[0].message);
+/// ```
+pub fn ranges(text: &str) -> Vec<Diagnostic> {
+    let mut objects: HashMap<Ob, (usize, Object)> = HashMap::new();
+    let mut inputs = HashSet::new();
+    for (pos, line) in text.split('\n').enumerate() {
+        let line = line.trim();
+        if let Some(input) = line.strip_prefix("+input") {
+            if let Ok((_, ob)) = Program::parse_input(input.trim()) {
+                inputs.insert(ob);
+            }
+            continue;
+        }
+        if line.is_empty() || line.starts_with('+') || line.starts_with('#') {
+            continue;
+        }
+        if let Ok((ob, obj)) = Program::parse_line(line) {
+            objects.entry(ob).or_insert((pos + 1, obj));
+        }
+    }
+    let mut ranges = Ranges::new(|ob| objects.get(&ob).map(|(_, obj)| obj), inputs);
+    let mut diags = vec![];
+    for (ob, (num, obj)) in objects.iter() {
+        let Some(name) = obj.atom().map(|m| m.name) else {
+            continue;
+        };
+        let (rule, what) = match ranges.flow(*ob) {
+            Some(Flow::Overflow) => ("overflow", "overflows"),
+            Some(Flow::DivZero) => ("division", "divides by zero"),
+            _ => continue,
+        };
+        diags.push(Diagnostic::new(
+            *num,
+            Level::Warning,
+            rule,
+            format!("The atom '{}' of ν{} surely {}", name, ob, what),
+        ));
+    }
+    diags.sort_by(|a, b| a.line.cmp(&b.line).then(a.message.cmp(&b.message)));
+    diags
+}
+
+/// Print the diagnostics found in the file.
+pub fn report(file: &str, diags: &[Diagnostic], format: Format) -> String {
+    match format {
+        Format::Text => diags
+            .iter()
+            .map(|d| {
+                format!(
+                    "{}:{}: {}: {} [{}]",
+                    file, d.line, d.level, d.message, d.rule
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n"),
+        Format::Json => json!({
+            "file": file,
+            "diagnostics": diags
+                .iter()
+                .map(|d| json!({
+                    "line": d.line,
+                    "level": d.level.to_string(),
+                    "rule": d.rule,
+                    "message": d.message
+                }))
+                .collect::<Vec<Json>>()
+        })
+        .to_string(),
+        Format::Sarif => json!({
+            "version": "2.1.0",
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "runs": [{
+                "tool": {"driver": {
+                    "name": "phie",
+                    "informationUri": "https://github.com/objectionary/phie"
+                }},
+                "results": diags
+                    .iter()
+                    .map(|d| json!({
+                        "ruleId": d.rule,
+                        "level": d.level.to_string(),
+                        "message": {"text": d.message},
+                        "locations": [{"physicalLocation": {
+                            "artifactLocation": {"uri": file},
+                            "region": {"startLine": d.line}
+                        }}]
+                    }))
+                    .collect::<Vec<Json>>()
+            }]
+        })
+        .to_string(),
+    }
+}
+
+#[test]
+fn finds_nothing_in_correct_program() {
+    let diags = verify(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+        ",
+    );
+    assert!(diags.is_empty(), "{:?}", diags);
+}
+
+#[test]
+fn checks_options() {
+    let diags = verify("+opt stop-when-stuck\n+opt max-cycles many\nν0(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧");
+    assert_eq!(1, diags.len(), "{:?}", diags);
+    assert_eq!("option", diags[0].rule);
+    assert_eq!(2, diags[0].line);
+}
+
+#[test]
+fn finds_all_kinds_of_problems() {
+    let diags =
+        verify("ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν7(𝜋) ⟧\nbroken\nν1(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧\nν2(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧");
+    let rules: Vec<&str> = diags.iter().map(|d| d.rule.as_str()).collect();
+    assert_eq!(
+        vec![
+            "no-root",
+            "undefined",
+            "unused",
+            "syntax",
+            "duplicate",
+            "unused"
+        ],
+        rules
+    );
+    assert_eq!(2, diags[3].line);
+}
+
+#[test]
+fn warns_about_escaping_locators() {
+    let diags = verify(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋), 𝛼0 ↦ ν3(𝜋), 𝛼1 ↦ 𝜋.𝛼0 ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ Φ.𝛼0.ρ, 𝛼0 ↦ Φ.ρ ⟧
+        ν3(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧
+        ",
+    );
+    let messages: Vec<&str> = diags
+        .iter()
+        .filter(|d| d.rule == "escape")
+        .map(|d| d.message.as_str())
+        .collect();
+    assert_eq!(
+        vec![
+            "The locator 𝜋.𝛼0 of ν0 escapes the graph at ν0, which has no 𝜋",
+            "The locator Φ.𝛼0.ρ of ν1 escapes the graph at ν3, which has no ρ",
+        ],
+        messages
+    );
+    assert!(
+        diags.iter().all(|d| d.level == Level::Warning),
+        "{:?}",
+        diags
+    );
+}
+
+#[test]
+fn prints_text_report() {
+    let diags = verify("ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν7(𝜋) ⟧");
+    assert_eq!(
+        "a.phi:1: error: The object ν7 is referenced, but not defined [undefined]",
+        report("a.phi", &diags, Format::Text)
+    );
+}
+
+#[test]
+fn prints_sarif_report() {
+    let diags = verify("oops \"quoted\"");
+    let sarif = report("a.phi", &diags, Format::Sarif);
+    assert!(sarif.contains("\"version\":\"2.1.0\""));
+    assert!(sarif.contains("\"ruleId\":\"syntax\""));
+    assert!(sarif.contains("\\\"quoted\\\""));
+    assert!(sarif.contains("\"startLine\":1"));
+}
+
+#[test]
+fn parses_format() {
+    assert_eq!(Format::Sarif, Format::from_str("sarif").unwrap());
+    assert!(Format::from_str("xml").is_err());
+}
+
+#[test]
+fn infers_types_through_phi() {
+    let diags = types(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧
+        ν2(𝜋) ↦ ⟦ 𝜑 ↦ ν4(𝜋) ⟧
+        ν3(𝜋) ↦ ⟦ λ ↦ int-to-float, ρ ↦ ν4(𝜋) ⟧
+        ν4(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+        ν5(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ ν6(𝜋), 𝛼0 ↦ ν4(𝜋), 𝛼1 ↦ ν3(𝜋) ⟧
+        ν6(𝜋) ↦ ⟦ λ ↦ int-less, ρ ↦ ν5(𝜋), 𝛼0 ↦ ν4(𝜋) ⟧
+        ",
+    );
+    assert_eq!(1, diags.len(), "{:?}", diags);
+    assert_eq!(3, diags[0].line);
+    assert_eq!(
+        "The atom 'int-add' of ν1 expects int at 𝛼0, but ν3 gives float",
+        diags[0].message
+    );
+}
+
+#[test]
+fn finds_missing_arguments_of_atoms() {
+    let diags = verify("ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν0(𝜋) ⟧");
+    assert_eq!(1, diags.len(), "{:?}", diags);
+    assert_eq!("arity", diags[0].rule);
+    assert_eq!(
+        "The atom 'int-add' of ν1 needs 𝛼0, which is missing",
+        diags[0].message
+    );
+}
+
+#[test]
+fn checks_inputs() {
+    let diags = verify("+input x ν1\n+input y\nν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧\nν2(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧");
+    let rules: Vec<&str> = diags.iter().map(|d| d.rule.as_str()).collect();
+    assert_eq!(vec!["undefined", "input"], rules);
+}
+
+#[test]
+fn finds_sure_overflows_and_divisions_by_zero() {
+    let diags = ranges(
+        "
+        +input x ν5
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ int-times, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧
+        ν2(𝜋) ↦ ⟦ Δ ↦ 0x7FFFFFFFFFFF ⟧
+        ν3(𝜋) ↦ ⟦ λ ↦ int-div, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν4(𝜋) ⟧
+        ν4(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ ν6(𝜋), 𝛼0 ↦ ν6(𝜋) ⟧
+        ν5(𝜋) ↦ ⟦ Δ ↦ 0x0000 ⟧
+        ν6(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ ν7(𝜋), 𝛼0 ↦ ν2(𝜋), 𝛼1 ↦ ν2(𝜋) ⟧
+        ν7(𝜋) ↦ ⟦ λ ↦ int-less, ρ ↦ ν5(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧
+        ν8(𝜋) ↦ ⟦ λ ↦ int-div, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν5(𝜋) ⟧
+        ",
+    );
+    let found: Vec<(usize, &str)> = diags.iter().map(|d| (d.line, d.rule.as_str())).collect();
+    assert_eq!(vec![(4, "overflow"), (6, "division")], found, "{:?}", diags);
+}
+
+#[test]
+fn narrows_ranges_through_branches() {
+    let prog: Program = "+input x ν4\n\
+        ν0(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν2(𝜋), 𝛼1 ↦ ν3(𝜋) ⟧\n\
+        ν1(𝜋) ↦ ⟦ λ ↦ int-less, ρ ↦ ν4(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧\n\
+        ν2(𝜋) ↦ ⟦ Δ ↦ 0x0003 ⟧\n\
+        ν3(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν2(𝜋) ⟧\n\
+        ν4(𝜋) ↦ ⟦ Δ ↦ 0x0000 ⟧"
+        .parse()
+        .unwrap();
+    let mut ranges = Ranges::new(|ob| prog.get(ob), prog.inputs().values().copied().collect());
+    assert_eq!(Some(Range { lo: 0, hi: 1 }), ranges.of(Ob::new(1)));
+    assert_eq!(Some(Range { lo: -3, hi: 3 }), ranges.of(Ob::new(0)));
+    assert_eq!(None, ranges.of(Ob::new(4)));
+    assert_eq!("-3..=3", ranges.of(Ob::new(0)).unwrap().to_string());
+}
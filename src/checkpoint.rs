@@ -0,0 +1,130 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Snapshots of all baskets of an `Emu`, taken every N cycles when
+//! `Opt::CheckpointEvery(N)` is set, and kept in a ring of limited size.
+//!
+//! Only live baskets get into a checkpoint, in their text form, so that
+//! it's small and can be saved to a file and read back later, to resume
+//! a long dataization after a crash:
+//!
+//! ```
+//! use phie::checkpoint::Checkpoint;
+//! use phie::emu::{Emu, Opt};
+//! use std::str::FromStr;
+//! let text = "
+//!   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+//!   ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+//!   ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧
+//! ";
+//! let mut emu: Emu = text.parse().unwrap();
+//! emu.opt(Opt::CheckpointEvery(1));
+//! emu.dataize();
+//! let saved = emu.checkpoints().next().unwrap().to_string();
+//! let mut again: Emu = text.parse().unwrap();
+//! again.restore(&Checkpoint::from_str(&saved).unwrap());
+//! assert_eq!(84, again.dataize().0);
+//! ```
+
+use crate::basket::{Basket, Bk};
+use crate::data::{Data, Datum};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The number of cycles made before it was taken.
+    pub cycle: usize,
+    /// Live baskets, one per line, like `β3 [ν2, ξ:β1, 𝜑→?]`.
+    baskets: String,
+}
+
+impl Checkpoint {
+    /// Take the live baskets.
+    pub fn take<D: Datum>(cycle: usize, baskets: &[Basket<D>]) -> Checkpoint {
+        Checkpoint {
+            cycle,
+            baskets: baskets
+                .iter()
+                .enumerate()
+                .filter(|(_, bsk)| !bsk.is_empty())
+                .map(|(bk, bsk)| format!("β{} {}", bk, bsk))
+                .collect::<Vec<String>>()
+                .join("\n"),
+        }
+    }
+
+    /// The baskets, with their numbers.
+    pub fn baskets<D: Datum>(&self) -> Result<Vec<(Bk, Basket<D>)>, String> {
+        self.baskets
+            .lines()
+            .map(|line| {
+                let (bk, bsk) = line
+                    .strip_prefix('β')
+                    .and_then(|rest| rest.split_once(' '))
+                    .ok_or_else(|| format!("Can't parse the checkpoint line '{}'", line))?;
+                let bk: Bk = bk
+                    .parse()
+                    .map_err(|e| format!("Can't parse basket number '{}': {}", bk, e))?;
+                Ok((bk, Basket::from_str(bsk)?))
+            })
+            .collect()
+    }
+
+    /// How many bytes it takes.
+    pub fn size(&self) -> usize {
+        self.baskets.len()
+    }
+}
+
+impl fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}\n{}", self.cycle, self.baskets)
+    }
+}
+
+impl FromStr for Checkpoint {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (head, baskets) = s.trim().split_once('\n').unwrap_or((s.trim(), ""));
+        let cycle = head
+            .strip_prefix('#')
+            .ok_or_else(|| format!("The checkpoint must start with '#', not '{}'", head))?
+            .parse()
+            .map_err(|e| format!("Can't parse the cycle of checkpoint '{}': {}", head, e))?;
+        let cp = Checkpoint {
+            cycle,
+            baskets: baskets.trim().to_string(),
+        };
+        cp.baskets::<Data>()?;
+        Ok(cp)
+    }
+}
+
+#[cfg(test)]
+use crate::basket::Kid;
+#[cfg(test)]
+use crate::emu::ROOT_BK;
+#[cfg(test)]
+use crate::loc::Loc;
+#[cfg(test)]
+use crate::object::Ob;
+
+#[test]
+fn keeps_only_live_baskets() {
+    let mut baskets: Vec<Basket> = vec![
+        Basket::empty(),
+        Basket::start(Ob::new(3), ROOT_BK),
+        Basket::empty(),
+    ];
+    baskets[1].put(Loc::Phi, Kid::Rqtd);
+    let cp = Checkpoint::take(7, &baskets);
+    assert_eq!("#7\nβ1 [ν3, ξ:β0, 𝜑→?]", cp.to_string());
+    assert_eq!(cp, Checkpoint::from_str(&cp.to_string()).unwrap());
+}
+
+#[test]
+fn fails_on_broken_text() {
+    assert!(Checkpoint::from_str("7\nβ1 [ν3, ξ:β0]").is_err());
+    assert!(Checkpoint::from_str("#7\nβx [ν3, ξ:β0]").is_err());
+}
@@ -18,10 +18,23 @@
 //! }
 //! ```
 
-use std::{fs, path::Path};
+use itertools::Itertools;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
+use crate::baseline::{Baseline, Measure};
+use crate::check::{ranges, report, types, verify, Format, Level};
+use crate::conformance::CASES;
+use crate::crash::Dump;
 use crate::data::Data;
-use crate::emu::{Emu, Opt};
+use crate::emu::{Bindings, Emu, Opt};
+use crate::inspect::State;
+use crate::program::{Expectation, Program};
+use crate::scheduler::Schedule;
+use crate::stream::Stream;
 
 /// Parses command line arguments and extracts the file path.
 ///
@@ -48,7 +61,7 @@ use crate::emu::{Emu, Opt};
 pub fn parse_args(args: &[String]) -> Result<String, String> {
     if args.len() < 2 {
         return Err(format!(
-            "Usage: {} <file.phie>",
+            "Usage: {} <file.phie> [--opt <option>]... [--events jsonl] [--memo <file>]",
             args.first().map(|s| s.as_str()).unwrap_or("phie")
         ));
     }
@@ -106,14 +119,98 @@ pub fn read_phie_file(file_path: &str) -> Result<String, String> {
 /// assert_eq!(result, 42);
 /// ```
 pub fn execute_phie(content: &str) -> Result<Data, String> {
+    execute_phie_with(content, &[])
+}
+
+/// Executes a phie program, just like `execute_phie()`, setting
+/// these options after the ones of the program and the environment.
+pub fn execute_phie_with(content: &str, opts: &[Opt]) -> Result<Data, String> {
+    Ok(prepare(content, opts)?.dataize().0)
+}
+
+/// The emulator of the program, ready to run with these options.
+fn prepare(content: &str, opts: &[Opt]) -> Result<Emu, String> {
     let mut emu: Emu = content
         .parse()
         .map_err(|e| format!("Failed to parse phie program: {}", e))?;
-    emu.opt(Opt::StopWhenTooManyCycles);
-    emu.opt(Opt::StopWhenStuck);
-    Ok(emu.dataize().0)
+    emu.opt_default(Opt::StopWhenTooManyCycles);
+    emu.opt_default(Opt::StopWhenStuck);
+    emu.tune(opts)?;
+    Ok(emu)
+}
+
+/// The format of events given on the command line, as `--events jsonl`,
+/// together with the rest of the arguments. Only `jsonl` is known,
+/// see `stream`.
+///
+/// # Examples
+///
+/// ```
+/// use phie::cli::cli_events;
+///
+/// let args: Vec<String> = ["phie", "a.phie", "--events", "jsonl"]
+///     .iter()
+///     .map(|s| s.to_string())
+///     .collect();
+/// let (rest, jsonl) = cli_events(&args).unwrap();
+/// assert_eq!(vec!["phie", "a.phie"], rest);
+/// assert!(jsonl);
+/// ```
+pub fn cli_events(args: &[String]) -> Result<(Vec<String>, bool), String> {
+    match args.iter().position(|a| a == "--events") {
+        None => Ok((args.to_vec(), false)),
+        Some(i) => match args.get(i + 1).map(|f| f.as_str()) {
+            Some("jsonl") => Ok((
+                args.iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i && *j != i + 1)
+                    .map(|(_, a)| a.clone())
+                    .collect(),
+                true,
+            )),
+            Some(f) => Err(format!("Unknown format of events '{}', use 'jsonl'", f)),
+            None => Err("The --events option needs a format".to_string()),
+        },
+    }
 }
 
+/// The options given on the command line, as `--opt 'max-cycles 100'`,
+/// together with the rest of the arguments.
+///
+/// # Examples
+///
+/// ```
+/// use phie::cli::cli_opts;
+/// use phie::emu::Opt;
+///
+/// let args: Vec<String> = ["phie", "--opt", "paranoid", "a.phie"]
+///     .iter()
+///     .map(|s| s.to_string())
+///     .collect();
+/// let (rest, opts) = cli_opts(&args).unwrap();
+/// assert_eq!(vec!["phie", "a.phie"], rest);
+/// assert_eq!(vec![Opt::Paranoid], opts);
+/// ```
+pub fn cli_opts(args: &[String]) -> Result<(Vec<String>, Vec<Opt>), String> {
+    let mut rest = vec![];
+    let mut opts = vec![];
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--opt" {
+            let opt = iter
+                .next()
+                .ok_or_else(|| "The --opt option needs a value".to_string())?;
+            opts.push(Opt::from_str(opt)?);
+        } else {
+            rest.push(arg.clone());
+        }
+    }
+    Ok((rest, opts))
+}
+
+/// The frames of the spinner printed with `--opt 'progress N'`.
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
 /// Main execution pipeline for the CLI.
 ///
 /// Orchestrates argument parsing, file reading, and program execution.
@@ -139,12 +236,903 @@ pub fn execute_phie(content: &str) -> Result<Data, String> {
 /// }
 /// ```
 pub fn run(args: &[String]) -> Result<String, String> {
-    let file_path = parse_args(args)?;
+    match args.get(1).map(|a| a.as_str()) {
+        Some("check") => return check(&args[2..]),
+        Some("run-all") | Some("corpus") => return run_all(&args[2..]),
+        Some("stats") => return stats(&args[2..]),
+        Some("fingerprint") => return fingerprint(&args[2..]),
+        Some("diff") => return diff(&args[2..]),
+        Some("reduce") => return reduce(&args[2..]),
+        Some("inspect") => return inspect(&args[2..]),
+        Some("conformance") => return conformance(&args[2..]),
+        Some("--help-syntax") => return Ok(crate::syntax::grammar()),
+        Some("bench") => return bench(&args[2..]),
+        Some("serve") => return serve(&args[2..]),
+        Some("sweep") => return sweep(&args[2..]),
+        _ => {}
+    }
+    let (args, opts) = cli_opts(args)?;
+    let (args, jsonl) = cli_events(&args)?;
+    let (args, memo) = cli_memo(&args)?;
+    let file_path = parse_args(&args)?;
     let content = read_phie_file(&file_path)?;
-    let result = execute_phie(&content)?;
+    let mut emu = prepare(&content, &opts)?;
+    if jsonl {
+        emu.stream(Stream::stdout());
+    }
+    let progress = emu.opts.iter().any(|o| matches!(o, Opt::Progress(_)));
+    if progress {
+        let mut turn = 0;
+        emu.on_progress(move |p| {
+            turn += 1;
+            eprint!("\r{} {}", SPINNER[turn % SPINNER.len()], p);
+        });
+    }
+    if let Some(path) = &memo {
+        emu.opt(Opt::Memoize);
+        if let Ok(text) = fs::read_to_string(path) {
+            if let Err(e) = emu.import_memo(&text) {
+                eprintln!("The memo in '{}' is ignored: {}", path, e);
+            }
+        }
+    }
+    let result = emu.dataize().0;
+    if progress {
+        eprintln!();
+    }
+    for sentence in emu.explanation() {
+        eprintln!("{}", sentence);
+    }
+    if let Some(path) = &memo {
+        fs::write(path, emu.export_memo())
+            .map_err(|e| format!("Failed to write the memo to '{}': {}", path, e))?;
+    }
     Ok(format!("{}", result))
 }
 
+/// The file given on the command line as `--memo memo.json`, together
+/// with the rest of the arguments. The memoized results of atoms are
+/// imported from it before the run, if it's there and was made for
+/// the same program, and exported to it after the run.
+fn cli_memo(args: &[String]) -> Result<(Vec<String>, Option<String>), String> {
+    match args.iter().position(|a| a == "--memo") {
+        None => Ok((args.to_vec(), None)),
+        Some(i) => {
+            let path = args
+                .get(i + 1)
+                .ok_or_else(|| "The --memo option needs a file".to_string())?;
+            let mut rest = args.to_vec();
+            rest.drain(i..i + 2);
+            Ok((rest, Some(path.clone())))
+        }
+    }
+}
+
+/// Verifies a program file without running it.
+///
+/// Expects the file path, optionally followed by `--format text|json|sarif`
+/// and `--types`, which also reports atoms that will likely get data of
+/// a wrong type, see `check::types()`, and `--ranges`, which reports
+/// integer atoms that surely overflow or divide by zero, see
+/// `check::ranges()`. The report is returned as an error if any problem is an error,
+/// not just a warning.
+///
+/// # Examples
+///
+/// ```no_run
+/// use phie::cli::check;
+///
+/// let args = vec!["program.phie".to_string(), "--format".to_string(), "sarif".to_string()];
+/// println!("{}", check(&args).unwrap());
+/// ```
+pub fn check(args: &[String]) -> Result<String, String> {
+    let file_path = args.first().ok_or_else(|| {
+        "Usage: phie check <file.phie> [--format text|json|sarif] [--types] [--ranges]".to_string()
+    })?;
+    let mut format = Format::Text;
+    let mut typed = false;
+    let mut ranged = false;
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = rest
+                    .next()
+                    .ok_or_else(|| "The --format option needs a value".to_string())?
+                    .parse()?
+            }
+            "--types" => typed = true,
+            "--ranges" => ranged = true,
+            other => return Err(format!("Unknown option '{}'", other)),
+        }
+    }
+    let content = read_phie_file(file_path)?;
+    let mut diags = verify(&content);
+    if typed {
+        diags.extend(types(&content));
+    }
+    if ranged {
+        diags.extend(ranges(&content));
+    }
+    if typed || ranged {
+        diags.sort_by(|a, b| a.line.cmp(&b.line).then(a.rule.cmp(&b.rule)));
+    }
+    let out = report(file_path, &diags, format);
+    if diags.iter().any(|d| d.level == Level::Error) {
+        Err(out)
+    } else {
+        Ok(out)
+    }
+}
+
+/// Prints the numbers of a program file, without running it: objects
+/// of each kind, attributes, the longest locator and the depth of the graph.
+///
+/// # Examples
+///
+/// ```no_run
+/// use phie::cli::stats;
+///
+/// println!("{}", stats(&["program.phie".to_string()]).unwrap());
+/// ```
+pub fn stats(args: &[String]) -> Result<String, String> {
+    let file_path = args
+        .first()
+        .ok_or_else(|| "Usage: phie stats <file.phie>".to_string())?;
+    let prog = Program::from_file(Path::new(file_path))?;
+    Ok(prog.stats().to_string())
+}
+
+/// Prints the `Program::fingerprint()` of each program file, followed
+/// by its path, one file per line, so that identical programs, which
+/// differ only in formatting or the order of lines, may be found.
+///
+/// # Examples
+///
+/// ```no_run
+/// use phie::cli::fingerprint;
+///
+/// println!("{}", fingerprint(&["a.phie".to_string(), "b.phie".to_string()]).unwrap());
+/// ```
+pub fn fingerprint(args: &[String]) -> Result<String, String> {
+    if args.is_empty() {
+        return Err("Usage: phie fingerprint <file.phie>...".to_string());
+    }
+    let lines = args
+        .iter()
+        .map(|file| {
+            let prog = Program::from_file(Path::new(file))?;
+            Ok(format!("{:016x}  {}", prog.fingerprint(), file))
+        })
+        .collect::<Result<Vec<String>, String>>()?;
+    Ok(lines.join("\n"))
+}
+
+/// Prints the objects added, removed and changed in the second program
+/// file, compared with the first one, both canonicalized, see `diff::diff()`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use phie::cli::diff;
+///
+/// println!("{}", diff(&["a.phie".to_string(), "b.phie".to_string()]).unwrap());
+/// ```
+pub fn diff(args: &[String]) -> Result<String, String> {
+    let [was, now] = args else {
+        return Err("Usage: phie diff <a.phie> <b.phie>".to_string());
+    };
+    let changes = crate::diff::diff(
+        Program::from_file(Path::new(was))?,
+        Program::from_file(Path::new(now))?,
+    );
+    if changes.is_empty() {
+        return Ok("The programs are the same".to_string());
+    }
+    Ok(changes
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+/// Prints a smaller program, which fails the same way as the one in
+/// the crash dump, written with `Opt::CrashDumps`, see `reduce::reduce()`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use phie::cli::reduce;
+///
+/// println!("{}", reduce(&["crash-00ab-42.json".to_string()]).unwrap());
+/// ```
+pub fn reduce(args: &[String]) -> Result<String, String> {
+    let [dump] = args else {
+        return Err("Usage: phie reduce <crash.json>".to_string());
+    };
+    Ok(crate::reduce::reduce(&Dump::read(Path::new(dump))?)?.to_string())
+}
+
+/// Reads queries from stdin, one per line, and prints what they find
+/// in the baskets saved in a crash dump or a checkpoint, until `quit`,
+/// see `inspect::State::query()`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use phie::cli::inspect;
+///
+/// inspect(&["crash-00ab-42.json".to_string()]).unwrap();
+/// ```
+pub fn inspect(args: &[String]) -> Result<String, String> {
+    let [file] = args else {
+        return Err("Usage: phie inspect <crash.json>".to_string());
+    };
+    let state = State::read(Path::new(file))?;
+    eprintln!(
+        "{} baskets after {} cycles, type 'help' for queries",
+        state.query("baskets()")?.lines().count(),
+        state.cycle
+    );
+    converse(&state, io::stdin().lock(), &mut io::stdout())?;
+    Ok(String::new())
+}
+
+/// Answer the queries read from the input, until it ends or `quit` comes.
+fn converse(state: &State, input: impl BufRead, out: &mut impl Write) -> Result<(), String> {
+    let fail = |e: io::Error| format!("Can't talk to the terminal: {}", e);
+    write!(out, "> ").and_then(|_| out.flush()).map_err(fail)?;
+    for line in input.lines() {
+        let line = line.map_err(fail)?;
+        let answer = match line.trim() {
+            "" => String::new(),
+            "quit" | "exit" => break,
+            "help" => crate::inspect::HELP.to_string(),
+            q => state.query(q).unwrap_or_else(|e| e),
+        };
+        if !answer.is_empty() {
+            writeln!(out, "{}", answer).map_err(fail)?;
+        }
+        write!(out, "> ").and_then(|_| out.flush()).map_err(fail)?;
+    }
+    Ok(())
+}
+
+/// Runs the program step by step for a debugger connecting over
+/// WebSocket, see `serve`, listening on `--port`, 7878 by default.
+/// Only available with the `serve` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// use phie::cli::serve;
+///
+/// serve(&["program.phie".to_string(), "--port".to_string(), "9000".to_string()]).unwrap();
+/// ```
+pub fn serve(args: &[String]) -> Result<String, String> {
+    let (args, opts) = cli_opts(args)?;
+    let file_path = args
+        .first()
+        .ok_or_else(|| "Usage: phie serve <file.phie> [--port <port>]".to_string())?;
+    let port: u16 = match args.iter().position(|a| a == "--port") {
+        None => 7878,
+        Some(i) => args
+            .get(i + 1)
+            .ok_or_else(|| "The --port option needs a value".to_string())?
+            .parse()
+            .map_err(|e| format!("Invalid port: {}", e))?,
+    };
+    let content = read_phie_file(file_path)?;
+    prepare(&content, &opts)?;
+    #[cfg(feature = "serve")]
+    {
+        let listener = std::net::TcpListener::bind(("127.0.0.1", port))
+            .map_err(|e| format!("Can't listen on port {}: {}", port, e))?;
+        eprintln!("Listening on ws://127.0.0.1:{}", port);
+        crate::serve::serve(listener, || prepare(&content, &opts).unwrap())?;
+        Ok(String::new())
+    }
+    #[cfg(not(feature = "serve"))]
+    Err(format!(
+        "Can't serve on port {}, phie is built without the 'serve' feature",
+        port
+    ))
+}
+
+/// Dataizes a program file once for each combination of the values of its
+/// inputs, see `Emu::sweep()`, printing a line for each of them.
+///
+/// Expects the file path, followed by `--arg <name>=<values>` for each
+/// input, where the values are a number, like `5`, or a range, like `0..100`
+/// or `0..=99`, and optionally by `--opt <option>`. The lines are returned
+/// as an error if any of the dataizations fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// use phie::cli::sweep;
+///
+/// let args: Vec<String> = ["fibonacci.phi", "--arg", "x=0..10"]
+///     .iter()
+///     .map(|s| s.to_string())
+///     .collect();
+/// println!("{}", sweep(&args).unwrap());
+/// ```
+pub fn sweep(args: &[String]) -> Result<String, String> {
+    let (args, opts) = cli_opts(args)?;
+    let file_path = args.first().ok_or_else(|| {
+        "Usage: phie sweep <file.phie> --arg <name>=<from>..<to>... [--opt <option>]...".to_string()
+    })?;
+    let mut ranges = vec![];
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--arg" => ranges
+                .push(arg_range(rest.next().ok_or_else(|| {
+                    "The --arg option needs a value".to_string()
+                })?)?),
+            other => return Err(format!("Unknown option '{}'", other)),
+        }
+    }
+    if ranges.is_empty() {
+        return Err("At least one --arg is needed, like --arg x=0..100".to_string());
+    }
+    let content = read_phie_file(file_path)?;
+    let mut emu = prepare(&content, &opts)?;
+    let inputs: Vec<Bindings> = ranges
+        .iter()
+        .map(|(name, values)| {
+            values
+                .iter()
+                .map(|d| (name.clone(), *d))
+                .collect::<Vec<_>>()
+        })
+        .multi_cartesian_product()
+        .collect();
+    let results = emu.sweep(inputs.iter().cloned());
+    let mut failed = false;
+    let lines: Vec<String> = inputs
+        .iter()
+        .zip(results)
+        .map(|(bindings, result)| {
+            let args = bindings
+                .iter()
+                .map(|(name, d)| format!("{}={}", name, d))
+                .join(" ");
+            match result {
+                Ok(d) => format!("{}: {}", args, d),
+                Err(e) => {
+                    failed = true;
+                    format!(
+                        "{}: {}",
+                        args,
+                        e.to_string().lines().next().unwrap_or_default()
+                    )
+                }
+            }
+        })
+        .collect();
+    let out = lines.join("\n");
+    if failed {
+        Err(out)
+    } else {
+        Ok(out)
+    }
+}
+
+/// The name and the values of an input, like `x=0..100`, `x=0..=99` or `x=5`.
+fn arg_range(arg: &str) -> Result<(String, Vec<Data>), String> {
+    let (name, values) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("Can't parse argument '{}', try x=0..100", arg))?;
+    let num = |s: &str| {
+        s.trim()
+            .parse::<Data>()
+            .map_err(|e| format!("Invalid value '{}' of '{}': {}", s, name, e))
+    };
+    let values = if let Some((from, to)) = values.split_once("..=") {
+        (num(from)?..=num(to)?).collect()
+    } else if let Some((from, to)) = values.split_once("..") {
+        (num(from)?..num(to)?).collect()
+    } else {
+        vec![num(values)?]
+    };
+    Ok((name.to_string(), values))
+}
+
+/// Executes every `.phi` file in a directory and prints a table
+/// with the result, cycles and time of each of them. With `--report md`
+/// the table is in Markdown, with the expected outcome of each program
+/// too, see `program::Expectation`, to be pasted into pull requests.
+/// The directory is called a corpus by `phie corpus`, which is the same.
+///
+/// The table is returned as an error if any of the programs fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// use phie::cli::run_all;
+///
+/// println!("{}", run_all(&["programs/".to_string()]).unwrap());
+/// ```
+pub fn run_all(args: &[String]) -> Result<String, String> {
+    let dir = args
+        .first()
+        .ok_or_else(|| "Usage: phie run-all <dir> [--report text|md]".to_string())?;
+    let md = cli_report(&args[1..])?;
+    let files = programs(Path::new(dir))?;
+    let mut rows = vec![];
+    let mut failed = 0;
+    for file in files.iter() {
+        let time = Instant::now();
+        let text = Program::read(file).map_err(|e| e.to_string());
+        let expected = text
+            .as_ref()
+            .map_err(|e| e.clone())
+            .and_then(|t| Expectation::find(t));
+        let run = text
+            .and_then(|t| Emu::from_str(&t))
+            .and_then(|mut emu| {
+                emu.opt_default(Opt::StopWhenTooManyCycles);
+                emu.opt_default(Opt::StopWhenStuck);
+                emu.tune(&[])?;
+                emu.try_dataize().map_err(String::from)
+            })
+            .map_err(|e| e.lines().next().unwrap_or_default().to_string());
+        let cycles = run
+            .as_ref()
+            .map(|(_, perf)| perf.cycles.to_string())
+            .unwrap_or_else(|_| "-".to_string());
+        let wanted = match &expected {
+            Ok(Some(x)) => x.to_string(),
+            _ => "-".to_string(),
+        };
+        let outcome = match expected {
+            Err(e) => Err(e),
+            Ok(None) => run.map(|(d, _)| d.to_string()),
+            Ok(Some(x)) => {
+                let result = run.map(|(d, _)| d);
+                x.check(&result).map(|_| match result {
+                    Ok(d) => d.to_string(),
+                    Err(e) => format!("expected error: {}", e),
+                })
+            }
+        };
+        let elapsed = format!("{:?}", time.elapsed());
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        rows.push(match outcome {
+            Ok(r) => [name.to_string(), wanted, r, cycles, elapsed],
+            Err(e) => {
+                failed += 1;
+                [
+                    name.to_string(),
+                    wanted,
+                    format!("FAILED: {}", e),
+                    "-".to_string(),
+                    elapsed,
+                ]
+            }
+        });
+    }
+    let mut lines = if md {
+        markdown(&["Program", "Expected", "Got", "Cycles", "Time"], &rows)
+    } else {
+        let rows: Vec<[String; 4]> = rows
+            .into_iter()
+            .map(|[name, _, got, cycles, time]| [name, got, cycles, time])
+            .collect();
+        table(&["File", "Result", "Cycles", "Time"], &rows)
+    };
+    lines.push(format!("{} programs, {} failed", files.len(), failed));
+    let out = lines.join("\n");
+    if failed > 0 {
+        Err(out)
+    } else {
+        Ok(out)
+    }
+}
+
+/// Runs all cases of `conformance::CASES` and prints a table with the
+/// outcome and cycles of each of them, and what it shows. With
+/// `--report md` the table is in Markdown, with the expected outcome
+/// and the time of each case instead, like `run_all()` prints it.
+///
+/// The table is returned as an error if any of the cases fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// use phie::cli::conformance;
+///
+/// println!("{}", conformance(&[]).unwrap());
+/// ```
+pub fn conformance(args: &[String]) -> Result<String, String> {
+    let md = cli_report(args)?;
+    let mut rows = vec![];
+    let mut failed = 0;
+    for case in CASES {
+        let time = Instant::now();
+        let outcome = case.run();
+        let elapsed = format!("{:?}", time.elapsed());
+        let wanted = match Expectation::find(case.program) {
+            Ok(Some(x)) => x.to_string(),
+            _ => "-".to_string(),
+        };
+        rows.push(match outcome {
+            Ok((outcome, perf)) => [
+                case.name.to_string(),
+                wanted,
+                outcome,
+                perf.map_or_else(|| "-".to_string(), |p| p.cycles.to_string()),
+                elapsed,
+                case.doc.to_string(),
+            ],
+            Err(e) => {
+                failed += 1;
+                [
+                    case.name.to_string(),
+                    wanted,
+                    format!("FAILED: {}", e),
+                    "-".to_string(),
+                    elapsed,
+                    case.doc.to_string(),
+                ]
+            }
+        });
+    }
+    let mut lines = if md {
+        let rows: Vec<[String; 5]> = rows
+            .into_iter()
+            .map(|[name, wanted, got, cycles, time, _]| [name, wanted, got, cycles, time])
+            .collect();
+        markdown(&["Program", "Expected", "Got", "Cycles", "Time"], &rows)
+    } else {
+        let rows: Vec<[String; 4]> = rows
+            .into_iter()
+            .map(|[name, _, got, cycles, _, doc]| [name, got, cycles, doc])
+            .collect();
+        table(&["Case", "Outcome", "Cycles", "What it shows"], &rows)
+    };
+    lines.push(format!("{} cases, {} failed", CASES.len(), failed));
+    let out = lines.join("\n");
+    if failed > 0 {
+        Err(out)
+    } else {
+        Ok(out)
+    }
+}
+
+/// Is the report asked to be in Markdown, with `--report md`, instead
+/// of the plain text, which is `--report text`?
+fn cli_report(args: &[String]) -> Result<bool, String> {
+    let mut md = false;
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--report" => {
+                md = match rest.next().map(|r| r.as_str()) {
+                    Some("md") => true,
+                    Some("text") => false,
+                    Some(other) => {
+                        return Err(format!("Unknown report '{}', try text or md", other))
+                    }
+                    None => return Err("The --report option needs a value".to_string()),
+                }
+            }
+            other => return Err(format!("Unknown option '{}'", other)),
+        }
+    }
+    Ok(md)
+}
+
+/// All `.phi` files of the directory, sorted.
+fn programs(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory '{}': {}", dir.display(), e))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|x| x == "phi"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// The options behind a name of a configuration, as `phie bench`
+/// understands it. Names may be joined with `+`, like `speculate+lifo`.
+///
+/// Finished baskets are deleted by default, so `gc` is the same as
+/// `default`, while `no-gc` keeps them. `parallel` is another name for
+/// `speculate`, which dataizes both branches of `bool-if` at once.
+///
+/// # Examples
+///
+/// ```
+/// use phie::cli::options;
+/// use phie::emu::Opt;
+///
+/// assert_eq!(vec![Opt::DontDelete], options("no-gc").unwrap());
+/// assert!(options("turbo").is_err());
+/// ```
+pub fn options(name: &str) -> Result<Vec<Opt>, String> {
+    let mut opts = vec![];
+    for part in name.split('+') {
+        match part.trim() {
+            "default" | "gc" => {}
+            "no-gc" => opts.push(Opt::DontDelete),
+            "speculate" | "parallel" => opts.push(Opt::Speculate),
+            "fifo" => opts.push(Opt::Schedule(Schedule::Fifo)),
+            "lifo" => opts.push(Opt::Schedule(Schedule::Lifo)),
+            "deepest" => opts.push(Opt::Schedule(Schedule::Deepest)),
+            "paranoid" => opts.push(Opt::Paranoid),
+            other => {
+                return Err(format!(
+                    "Unknown configuration '{}', try default, gc, no-gc, speculate, parallel, fifo, lifo, deepest or paranoid",
+                    other
+                ))
+            }
+        }
+    }
+    Ok(opts)
+}
+
+/// Dataizes a program file once for each configuration of options
+/// and prints a table with the result, time, cycles and peak baskets
+/// of each of them.
+///
+/// Expects the file path, optionally followed by `--compare` and
+/// the names of configurations, see `options()`; without them only
+/// `default` is measured. The table is returned as an error if any
+/// of the configurations fails.
+///
+/// With `--baseline <file>` instead, the numbers of the program, or of
+/// all `.phi` programs of the directory, are compared with the ones in
+/// the file, see `baseline()`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use phie::cli::bench;
+///
+/// let args: Vec<String> = ["program.phie", "--compare", "default", "no-gc"]
+///     .iter()
+///     .map(|s| s.to_string())
+///     .collect();
+/// println!("{}", bench(&args).unwrap());
+/// ```
+pub fn bench(args: &[String]) -> Result<String, String> {
+    if args.iter().any(|a| a == "--baseline") {
+        return baseline(args);
+    }
+    let usage = "Usage: phie bench <file.phie> [--compare <config>...]";
+    let file_path = args.first().ok_or_else(|| usage.to_string())?;
+    let names: Vec<String> = match args.get(1).map(|a| a.as_str()) {
+        Some("--compare") if args.len() > 2 => args[2..].to_vec(),
+        Some(_) => return Err(usage.to_string()),
+        None => vec!["default".to_string()],
+    };
+    let configs = names
+        .iter()
+        .map(|n| options(n).map(|opts| (n, opts)))
+        .collect::<Result<Vec<_>, String>>()?;
+    let text = Program::read(Path::new(file_path)).map_err(|e| e.to_string())?;
+    let mut rows = vec![];
+    let mut failed = 0;
+    for (name, opts) in configs {
+        let mut emu = Emu::from_str(&text)?;
+        emu.opt_default(Opt::StopWhenTooManyCycles);
+        emu.opt_default(Opt::StopWhenStuck);
+        emu.tune(&opts)?;
+        let time = Instant::now();
+        let run = emu.try_dataize();
+        let elapsed = format!("{:?}", time.elapsed());
+        rows.push(match run {
+            Ok((d, perf)) => [
+                name.clone(),
+                d.to_string(),
+                elapsed,
+                perf.cycles.to_string(),
+                perf.peak.to_string(),
+            ],
+            Err(e) => {
+                failed += 1;
+                [
+                    name.clone(),
+                    format!(
+                        "FAILED: {}",
+                        e.to_string().lines().next().unwrap_or_default()
+                    ),
+                    elapsed,
+                    "-".to_string(),
+                    "-".to_string(),
+                ]
+            }
+        });
+    }
+    let out = table(&["Config", "Result", "Time", "Cycles", "Peak"], &rows).join("\n");
+    if failed > 0 {
+        Err(out)
+    } else {
+        Ok(out)
+    }
+}
+
+/// Dataizes a program, or all `.phi` programs of a directory, with
+/// default options and compares their cycles with the ones in the
+/// baseline file, see `crate::baseline`.
+///
+/// Expects the path, followed by `--baseline <file>` and optionally
+/// `--threshold <percent>`, 5 by default, `--warn` and `--record`.
+/// With `--record` the numbers are saved to the file instead. The table
+/// is returned as an error if any program fails or makes more cycles
+/// than in the baseline by more than the threshold, unless `--warn` is
+/// there, which only reports regressions.
+///
+/// # Examples
+///
+/// ```no_run
+/// use phie::cli::bench;
+///
+/// let args: Vec<String> = ["programs/", "--baseline", "baseline.json", "--threshold", "10"]
+///     .iter()
+///     .map(|s| s.to_string())
+///     .collect();
+/// println!("{}", bench(&args).unwrap());
+/// ```
+pub fn baseline(args: &[String]) -> Result<String, String> {
+    let usage = "Usage: phie bench <file.phie|dir> --baseline <file> [--record] [--warn] [--threshold <percent>]";
+    let value = |flag: &str| {
+        args.iter()
+            .position(|a| a == flag)
+            .map(|i| args.get(i + 1).ok_or_else(|| usage.to_string()))
+            .transpose()
+    };
+    let file = value("--baseline")?.ok_or_else(|| usage.to_string())?;
+    let threshold: f64 = match value("--threshold")? {
+        None => 5.0,
+        Some(t) => t
+            .parse()
+            .map_err(|e| format!("Invalid threshold '{}': {}", t, e))?,
+    };
+    let path = Path::new(args.first().ok_or_else(|| usage.to_string())?);
+    let files = if path.is_dir() {
+        programs(path)?
+    } else {
+        vec![path.to_path_buf()]
+    };
+    let mut now = Baseline::default();
+    let mut failed = vec![];
+    for file in files.iter() {
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        let mut emu = Emu::from_file(file).map_err(|e| e.to_string())?;
+        emu.opt_default(Opt::StopWhenTooManyCycles);
+        emu.opt_default(Opt::StopWhenStuck);
+        emu.tune(&[])?;
+        let program = emu.fingerprint();
+        let time = Instant::now();
+        match emu.try_dataize() {
+            Ok((_, perf)) => now.put(
+                &name,
+                Measure {
+                    cycles: perf.cycles,
+                    baskets: perf.peak,
+                    micros: time.elapsed().as_micros(),
+                    program: Some(program),
+                },
+            ),
+            Err(e) => failed.push(format!(
+                "{} FAILED: {}",
+                name,
+                e.to_string().lines().next().unwrap_or_default()
+            )),
+        }
+    }
+    if args.iter().any(|a| a == "--record") {
+        fs::write(file, format!("{}\n", now))
+            .map_err(|e| format!("Can't save the baseline to '{}': {}", file, e))?;
+        let mut lines = failed.clone();
+        lines.push(format!(
+            "{} programs saved to '{}', {} failed",
+            now.iter().count(),
+            file,
+            failed.len()
+        ));
+        return if failed.is_empty() {
+            Ok(lines.join("\n"))
+        } else {
+            Err(lines.join("\n"))
+        };
+    }
+    let was = Baseline::from_str(
+        &fs::read_to_string(file)
+            .map_err(|e| format!("Can't read the baseline '{}': {}", file, e))?,
+    )?;
+    let rows: Vec<[String; 6]> = now
+        .iter()
+        .map(|(name, m)| {
+            [
+                name.clone(),
+                m.cycles.to_string(),
+                was.get(name)
+                    .map(|w| w.cycles.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                now.change(&was, name)
+                    .map(|c| format!("{:+.1}%", c))
+                    .unwrap_or_else(|| "new".to_string()),
+                m.baskets.to_string(),
+                format!("{:?}", Duration::from_micros(m.micros as u64)),
+            ]
+        })
+        .collect();
+    let regressed = now.regressions(&was, threshold);
+    let mut lines = table(
+        &["File", "Cycles", "Was", "Change", "Baskets", "Time"],
+        &rows,
+    );
+    lines.extend(
+        now.iter()
+            .filter(|(name, _)| now.edited(&was, name))
+            .map(|(name, _)| format!("{} was edited since the baseline", name)),
+    );
+    lines.extend(failed.iter().cloned());
+    lines.push(format!(
+        "{} programs, {} failed, {} regressed by more than {}%: {}",
+        rows.len() + failed.len(),
+        failed.len(),
+        regressed.len(),
+        threshold,
+        regressed.join(", ")
+    ));
+    let out = lines.join("\n").trim_end_matches([':', ' ']).to_string();
+    if !failed.is_empty() || (!regressed.is_empty() && !args.iter().any(|a| a == "--warn")) {
+        Err(out)
+    } else {
+        Ok(out)
+    }
+}
+
+/// Render the rows under the header, with columns aligned.
+fn table<const N: usize>(header: &[&str; N], rows: &[[String; N]]) -> Vec<String> {
+    let widths: Vec<usize> = (0..N)
+        .map(|i| {
+            rows.iter()
+                .map(|r| r[i].chars().count())
+                .chain([header[i].len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let line = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!("{:<w$}", c, w = widths[i]))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    let mut lines = vec![line(header.to_vec())];
+    lines.extend(
+        rows.iter()
+            .map(|r| line(r.iter().map(|c| c.as_str()).collect())),
+    );
+    lines
+}
+
+/// Render the rows under the header as a table of Markdown, with
+/// the pipes in the cells escaped.
+fn markdown<const N: usize>(header: &[&str; N], rows: &[[String; N]]) -> Vec<String> {
+    let line = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+    let mut lines = vec![
+        line(header.iter().map(|h| h.to_string()).collect()),
+        line(vec!["---".to_string(); N]),
+    ];
+    lines.extend(
+        rows.iter()
+            .map(|r| line(r.iter().map(|c| c.replace('|', "\\|")).collect())),
+    );
+    lines
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -256,6 +1244,427 @@ mod tests {
         assert_eq!(result.unwrap(), 0);
     }
 
+    #[test]
+    fn checks_file_in_json() {
+        let file = "/tmp/phie_check_json.phie";
+        fs::write(file, "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧").unwrap();
+        let args = vec![
+            "phie".to_string(),
+            "check".to_string(),
+            file.to_string(),
+            "--format".to_string(),
+            "json".to_string(),
+        ];
+        let result = run(&args);
+        fs::remove_file(file).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.unwrap()).unwrap();
+        assert_eq!(
+            serde_json::json!({"file": "/tmp/phie_check_json.phie", "diagnostics": []}),
+            json
+        );
+    }
+
+    #[test]
+    fn fails_check_on_errors() {
+        let file = "/tmp/phie_check_errors.phie";
+        fs::write(file, "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧").unwrap();
+        let result = check(&[file.to_string()]);
+        fs::remove_file(file).unwrap();
+        assert!(result.unwrap_err().contains("[undefined]"));
+    }
+
+    #[test]
+    fn checks_types_when_asked() {
+        let file = "/tmp/phie_check_types.phie";
+        fs::write(
+            file,
+            "ν0(𝜋) ↦ ⟦ λ ↦ float-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧",
+        )
+        .unwrap();
+        let plain = check(&[file.to_string()]);
+        let typed = check(&[file.to_string(), "--types".to_string()]);
+        fs::remove_file(file).unwrap();
+        assert_eq!("", plain.unwrap());
+        assert!(typed
+            .unwrap()
+            .contains("expects float at 𝛼0, but ν1 gives int [type]"));
+    }
+
+    #[test]
+    fn checks_ranges_when_asked() {
+        let file = "/tmp/phie_check_ranges.phie";
+        fs::write(
+            file,
+            "ν0(𝜋) ↦ ⟦ λ ↦ int-div, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧\n\
+            ν2(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧",
+        )
+        .unwrap();
+        let plain = check(&[file.to_string()]);
+        let ranged = check(&[file.to_string(), "--ranges".to_string()]);
+        fs::remove_file(file).unwrap();
+        assert_eq!("", plain.unwrap());
+        assert!(ranged
+            .unwrap()
+            .contains("The atom 'int-div' of ν0 surely divides by zero [division]"));
+    }
+
+    #[test]
+    fn sweeps_through_ranges_of_inputs() {
+        let file = "/tmp/phie_sweep.phie";
+        fs::write(
+            file,
+            "+input x ν1\n+input y ν2\nν0(𝜋) ↦ ⟦ 𝜑 ↦ ν3(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x0000 ⟧\n\
+             ν2(𝜋) ↦ ⟦ Δ ↦ 0x0000 ⟧\nν3(𝜋) ↦ ⟦ λ ↦ int-times, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧",
+        )
+        .unwrap();
+        let args: Vec<String> = [file, "--arg", "x=1..3", "--arg", "y=..=7", "--arg", "y=5"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(sweep(&args).unwrap_err().contains("Invalid value"));
+        let args: Vec<String> = [file, "--arg", "x=1..3", "--arg", "y=4..=5"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let out = sweep(&args);
+        fs::remove_file(file).unwrap();
+        assert_eq!(
+            "x=1 y=4: 4\nx=1 y=5: 5\nx=2 y=4: 8\nx=2 y=5: 10",
+            out.unwrap()
+        );
+    }
+
+    #[test]
+    fn keeps_memo_between_runs() {
+        let file = "/tmp/phie_memo_program.phie";
+        let memo = "/tmp/phie_memo.json";
+        let _ = fs::remove_file(memo);
+        fs::write(
+            file,
+            "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧\nν2(𝜋) ↦ ⟦ Δ ↦ 0x0015 ⟧",
+        )
+        .unwrap();
+        let args: Vec<String> = ["phie", file, "--memo", memo]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!("42", run(&args).unwrap());
+        let saved = fs::read_to_string(memo).unwrap();
+        assert!(saved.contains("[\"int-add\",[21,21],42]"), "{}", saved);
+        assert_eq!("42", run(&args).unwrap());
+        fs::write(file, "ν0(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧").unwrap();
+        assert_eq!("7", run(&args).unwrap());
+        assert!(!fs::read_to_string(memo).unwrap().contains("int-add"));
+        fs::remove_file(file).unwrap();
+        fs::remove_file(memo).unwrap();
+    }
+
+    #[test]
+    fn fails_check_on_unknown_format() {
+        let args = vec![
+            "x.phie".to_string(),
+            "--format".to_string(),
+            "xml".to_string(),
+        ];
+        assert!(check(&args).unwrap_err().contains("Unknown format"));
+    }
+
+    #[test]
+    fn runs_all_programs_in_directory() {
+        let dir = std::env::temp_dir().join("phie-run-all-ok");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.phi"), "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧").unwrap();
+        fs::write(dir.join("skipped.txt"), "whatever").unwrap();
+        let table = run_all(&[dir.to_string_lossy().to_string()]).unwrap();
+        assert!(table.starts_with("File"), "{}", table);
+        assert!(table.contains("a.phi  42"), "{}", table);
+        assert!(!table.contains("skipped"));
+        assert!(table.ends_with("1 programs, 0 failed"));
+    }
+
+    #[test]
+    fn reports_corpus_in_markdown() {
+        let dir = std::env::temp_dir().join("phie-corpus-md");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.phi"), "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧\n# expect: 42").unwrap();
+        fs::write(dir.join("b.phi"), "ν0(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧").unwrap();
+        let args = vec![
+            "phie".to_string(),
+            "corpus".to_string(),
+            dir.to_string_lossy().to_string(),
+            "--report".to_string(),
+            "md".to_string(),
+        ];
+        let md = run(&args).unwrap();
+        let lines: Vec<&str> = md.lines().collect();
+        assert_eq!("| Program | Expected | Got | Cycles | Time |", lines[0]);
+        assert_eq!("| --- | --- | --- | --- | --- |", lines[1]);
+        assert!(lines[2].starts_with("| a.phi | 42 | 42 | 1 | "), "{}", md);
+        assert!(lines[3].starts_with("| b.phi | - | 7 | "), "{}", md);
+        assert_eq!("2 programs, 0 failed", lines[4]);
+        let wrong = run_all(&[
+            dir.to_string_lossy().to_string(),
+            "--report".to_string(),
+            "html".to_string(),
+        ]);
+        assert!(wrong.unwrap_err().contains("Unknown report 'html'"));
+    }
+
+    #[test]
+    fn reports_conformance_in_markdown() {
+        let md = conformance(&["--report".to_string(), "md".to_string()]).unwrap();
+        assert!(md.starts_with("| Program | Expected | Got |"), "{}", md);
+        assert!(md.contains("| error: "), "{}", md);
+        assert!(md.ends_with(" failed"));
+    }
+
+    #[test]
+    fn fails_when_any_program_fails() {
+        let dir = std::env::temp_dir().join("phie-run-all-broken");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.phi"), "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧").unwrap();
+        fs::write(dir.join("b.phi"), "broken").unwrap();
+        let table = run_all(&[dir.to_string_lossy().to_string()]).unwrap_err();
+        assert!(table.contains("FAILED"), "{}", table);
+        assert!(table.ends_with("2 programs, 1 failed"));
+    }
+
+    #[test]
+    fn checks_expectations_of_all_programs() {
+        let dir = std::env::temp_dir().join("phie-run-all-expect");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.phi"), "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧\n# expect: 42").unwrap();
+        fs::write(dir.join("b.phi"), "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧\n# expect: 7").unwrap();
+        fs::write(
+            dir.join("c.phi"),
+            "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν0(𝜋) ⟧\n# expect-error: empty baskets",
+        )
+        .unwrap();
+        let table = run_all(&[dir.to_string_lossy().to_string()]).unwrap_err();
+        assert!(table.contains("does not match expected 7"), "{}", table);
+        assert!(
+            table.contains("expected error: No more empty baskets"),
+            "{}",
+            table
+        );
+        assert!(table.ends_with("3 programs, 1 failed"), "{}", table);
+    }
+
+    #[test]
+    fn prints_stats_of_program() {
+        let args = vec![
+            "phie".to_string(),
+            "stats".to_string(),
+            "tests/resources/written_sum_test".to_string(),
+        ];
+        let out = run(&args).unwrap();
+        assert!(out.starts_with("Objects: "), "{}", out);
+        assert!(out.contains("int-add: 1"), "{}", out);
+    }
+
+    #[test]
+    fn prints_same_fingerprints_of_same_programs() {
+        let files = [
+            "/tmp/phie_cli_fingerprint_a.phie",
+            "/tmp/phie_cli_fingerprint_b.phie",
+        ];
+        fs::write(files[0], "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧").unwrap();
+        fs::write(
+            files[1],
+            "+opt max-cycles 9\nν1(𝜋) ↦ ⟦Δ↦0x002A⟧\n\nν0(𝜋) ↦ ⟦𝜑↦ν1(𝜋)⟧",
+        )
+        .unwrap();
+        let args: Vec<String> = ["phie", "fingerprint", files[0], files[1]]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let out = run(&args);
+        for file in files {
+            fs::remove_file(file).unwrap();
+        }
+        let out = out.unwrap();
+        let prints: Vec<&str> = out.lines().map(|l| &l[..16]).collect();
+        assert_eq!(2, prints.len(), "{}", out);
+        assert_eq!(prints[0], prints[1], "{}", out);
+    }
+
+    #[test]
+    fn prints_diff_of_programs() {
+        let files = ["/tmp/phie_cli_diff_a.phie", "/tmp/phie_cli_diff_b.phie"];
+        fs::write(files[0], "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧").unwrap();
+        fs::write(files[1], "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν9(𝜋) ⟧\nν9(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧").unwrap();
+        let args: Vec<String> = ["phie", "diff", files[0], files[1]]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let out = run(&args);
+        let same: Vec<String> = ["phie", "diff", files[0], files[0]]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let same = run(&same);
+        for file in files {
+            fs::remove_file(file).unwrap();
+        }
+        assert_eq!(
+            "ν1(𝜋) ↦ ⟦! Δ↦0x002A⟧ → ⟦! Δ↦0x0007⟧\n  - Δ↦0x002A\n  + Δ↦0x0007",
+            out.unwrap()
+        );
+        assert_eq!("The programs are the same", same.unwrap());
+    }
+
+    #[test]
+    fn prints_grammar() {
+        let out = run(&["phie".to_string(), "--help-syntax".to_string()]).unwrap();
+        assert!(out.starts_with("(* "), "{}", out);
+        assert!(out.contains("\nbasket ::= "), "{}", out);
+    }
+
+    #[test]
+    fn prints_conformance_table() {
+        let out = run(&["phie".to_string(), "conformance".to_string()]).unwrap();
+        assert!(out.contains("xi-binds-caller"), "{}", out);
+        assert!(out.ends_with(" cases, 0 failed"), "{}", out);
+    }
+
+    #[test]
+    fn overrides_options_of_file() {
+        let file = "/tmp/phie_cli_opts.phie";
+        fs::write(
+            file,
+            "+opt max-cycles 0\nν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧",
+        )
+        .unwrap();
+        let args: Vec<String> = ["phie", file, "--opt", "max-cycles 100"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let result = run(&args);
+        fs::remove_file(file).unwrap();
+        assert_eq!("42", result.unwrap());
+    }
+
+    #[test]
+    fn fails_on_unknown_cli_option() {
+        let args: Vec<String> = ["phie", "a.phie", "--opt", "warp-speed"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(run(&args).unwrap_err().contains("Unknown option"));
+    }
+
+    #[test]
+    fn fails_on_unknown_format_of_events() {
+        let args: Vec<String> = ["phie", "a.phie", "--events", "xml"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(run(&args).unwrap_err().contains("Unknown format of events"));
+    }
+
+    #[cfg(not(feature = "serve"))]
+    #[test]
+    fn fails_to_serve_without_feature() {
+        let args: Vec<String> = ["tests/resources/written_sum_test", "--port", "9000"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(serve(&args).unwrap_err().contains("'serve' feature"));
+    }
+
+    #[test]
+    fn compares_configurations() {
+        let args: Vec<String> = [
+            "phie",
+            "bench",
+            "tests/resources/written_sum_test",
+            "--compare",
+            "default",
+            "parallel",
+            "no-gc+lifo",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let out = run(&args).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(4, lines.len(), "{}", out);
+        assert!(lines[0].starts_with("Config"), "{}", out);
+        assert!(lines[3].starts_with("no-gc+lifo"), "{}", out);
+        assert!(
+            lines.iter().skip(1).all(|l| !l.contains("FAILED")),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn records_and_compares_baseline() {
+        let file = "/tmp/phie_cli_baseline.json";
+        let args = |extra: &[&str]| -> Vec<String> {
+            ["tests/resources/written_sum_test", "--baseline", file]
+                .iter()
+                .chain(extra)
+                .map(|s| s.to_string())
+                .collect()
+        };
+        assert!(bench(&args(&["--record"]))
+            .unwrap()
+            .contains("1 programs saved"));
+        let out = bench(&args(&[])).unwrap();
+        assert!(out.contains("+0.0%"), "{}", out);
+        fs::write(
+            file,
+            r#"{"written_sum_test": {"cycles": 1, "baskets": 1, "micros": 1}}"#,
+        )
+        .unwrap();
+        let out = bench(&args(&["--threshold", "10"])).unwrap_err();
+        assert!(
+            out.contains("1 regressed by more than 10%: written_sum_test"),
+            "{}",
+            out
+        );
+        assert!(bench(&args(&["--warn"])).is_ok());
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn fails_to_bench_unknown_configuration() {
+        let args: Vec<String> = ["tests/resources/written_sum_test", "--compare", "turbo"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(bench(&args).unwrap_err().contains("Unknown configuration"));
+    }
+
+    #[test]
+    fn fails_to_run_all_in_absent_directory() {
+        let result = run_all(&["/tmp/phie-absent-dir".to_string()]);
+        assert!(result.unwrap_err().contains("Failed to read directory"));
+    }
+
+    #[test]
+    fn fails_to_reduce_absent_dump() {
+        let result = reduce(&["/tmp/phie-absent-crash.json".to_string()]);
+        assert!(result.unwrap_err().contains("Can't read the crash dump"));
+    }
+
+    #[test]
+    fn answers_queries_until_quit() {
+        let state = State::of_checkpoint(&"#3\nβ0 [ν0, ξ:β0, 𝜑→?]".parse().unwrap());
+        let mut out = vec![];
+        converse(
+            &state,
+            "baskets(ob=0)\nfly()\nquit\nbaskets()\n".as_bytes(),
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(
+            "> β0 [ν0, ξ:β0, 𝜑→?]\n> Unknown query 'fly', try 'help'\n> ",
+            String::from_utf8(out).unwrap()
+        );
+    }
+
     #[test]
     fn executes_single_byte_value() {
         let program = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧";
@@ -0,0 +1,107 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! A short way to print baskets, available with the `compact` feature,
+//! for snapshots of many baskets: one arrow for every kind of kid, data
+//! in decimal, empty kids left out and no definitions of objects:
+//!
+//! ```
+//! use phie::emu::{Emu, Opt};
+//! let mut emu: Emu = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+//! assert_eq!("β0 ν0 ξ0 𝜑→?", emu.compact_snapshot());
+//! emu.opt(Opt::CompactSnapshots);
+//! assert_eq!(emu.compact_snapshot(), emu.snapshot());
+//! ```
+//!
+//! Crash dumps take the compact snapshot instead of the full one when
+//! the full one is longer than `LIMIT`. Compact snapshots can't be
+//! parsed back, the checkpoints of dumps are always full.
+
+use crate::basket::{Basket, Kid};
+use crate::data::Datum;
+use crate::emu::Emu;
+
+/// How many bytes a full snapshot may take in a file, before it's
+/// replaced with the compact one, see `fitting()`.
+pub const LIMIT: usize = 64 * 1024;
+
+impl<D: Datum> Kid<D> {
+    /// The kid after the arrow, like `42`, `?`, `ν7:β12` or `β3.𝜑`,
+    /// or nothing if it's empty.
+    pub fn compact(&self) -> Option<String> {
+        match self {
+            Kid::Empt => None,
+            Kid::Rqtd => Some("?".to_string()),
+            Kid::Need(ob, bk) => Some(format!("ν{}:β{}", ob, bk)),
+            Kid::Wait(bk, loc) => Some(format!("β{}.{}", bk, loc)),
+            Kid::Dtzd(d) => Some(d.data().to_string()),
+        }
+    }
+}
+
+impl<D: Datum> Basket<D> {
+    /// The object, the ξ and the kids that are not empty, like
+    /// `ν5 ξ7 ρ→β42.𝜑 Δ→42`.
+    pub fn compact(&self) -> String {
+        let mut kids: Vec<_> = self.kids.iter().collect();
+        kids.sort_by(|a, b| a.0.cmp(b.0));
+        let mut parts = vec![format!("ν{}", self.ob), format!("ξ{}", self.psi)];
+        parts.extend(
+            kids.into_iter()
+                .filter_map(|(loc, kid)| kid.compact().map(|k| format!("{}→{}", loc, k))),
+        );
+        parts.join(" ")
+    }
+}
+
+impl<D: Datum> Emu<D> {
+    /// Live baskets, one per line, like `snapshot()`, but compact.
+    pub fn compact_snapshot(&self) -> String {
+        self.baskets
+            .iter()
+            .enumerate()
+            .filter(|(_, bsk)| !bsk.is_empty())
+            .map(|(bk, bsk)| format!("β{} {}", bk, bsk.compact()))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+/// The full snapshot of the `Emu`, unless it's longer than the limit
+/// of bytes, then the compact one.
+pub fn fitting<D: Datum>(emu: &Emu<D>, limit: usize) -> String {
+    let full = emu.snapshot();
+    if full.len() > limit {
+        emu.compact_snapshot()
+    } else {
+        full
+    }
+}
+
+#[cfg(test)]
+use crate::basket::Bk;
+#[cfg(test)]
+use crate::loc::Loc;
+#[cfg(test)]
+use crate::object::Ob;
+
+#[test]
+fn prints_every_kid_shortly() {
+    let mut bsk = <Basket>::start(Ob::new(5), Bk::new(7));
+    bsk.put(Loc::Delta, Kid::Dtzd(-42));
+    bsk.put(Loc::Rho, Kid::Wait(Bk::new(42), Loc::Phi));
+    bsk.put(Loc::Attr(0), Kid::Empt);
+    bsk.put(Loc::Attr(1), Kid::Need(Ob::new(7), Bk::new(12)));
+    bsk.put(Loc::Phi, Kid::Rqtd);
+    assert_eq!("ν5 ξ7 ρ→β42.𝜑 𝜑→? Δ→-42 𝛼1→ν7:β12", bsk.compact());
+}
+
+#[test]
+fn keeps_short_snapshots_full() {
+    let mut emu: Emu = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧"
+        .parse()
+        .unwrap();
+    emu.dataize();
+    assert_eq!(emu.snapshot(), fitting(&emu, LIMIT));
+    assert_eq!("β0 ν0 ξ0 𝜑→42\nβ1 ν1 ξ0 𝜑→42", fitting(&emu, 8));
+}
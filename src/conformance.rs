@@ -0,0 +1,265 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Small programs that pin down how the emulator binds ξ, 𝜋 and σ, each
+//! with its expected outcome in a `# expect:` or `# expect-error:` line,
+//! see `program::Expectation`. They are run by `phie conformance` and
+//! by the tests, and may be read as the documentation of the semantics.
+//!
+//! An attribute written as `νN(ξ)` makes a basket for νN whose ψ is the
+//! basket of the object that refers to it, while `νN(𝜋)` gives it the
+//! same ψ that basket has. So, inside νN, `𝜋` means the caller in the
+//! first case and the caller's own scope in the second one. Inside νN,
+//! `σ` is its home, the object that declares it with an attribute like
+//! `𝜑 ↦ νN`, no matter how the basket was made:
+//!
+//! ```
+//! use phie::conformance::CASES;
+//! for case in CASES {
+//!     assert!(case.run().is_ok(), "{}", case.name);
+//! }
+//! ```
+
+use crate::data::Data;
+use crate::emu::{Emu, Opt};
+use crate::perf::Perf;
+use crate::program::Expectation;
+use std::str::FromStr;
+
+pub struct Case {
+    pub name: &'static str,
+    /// What the case shows, in one line.
+    pub doc: &'static str,
+    pub program: &'static str,
+}
+
+pub const CASES: &[Case] = &[
+    Case {
+        name: "xi-binds-caller",
+        doc: "𝜋 of an object made with (ξ) is the basket that made it",
+        program: "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν2(𝜋) ⟧
+            ν2(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+            ν3(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ 𝜋.𝛼0 ⟧
+            # expect: -7
+        ",
+    },
+    Case {
+        name: "pi-keeps-scope",
+        doc: "𝜋 of an object made with (𝜋) is the ψ of the basket that made it",
+        program: "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν2(𝜋) ⟧
+            ν2(𝜋) ↦ ⟦ Δ ↦ 0x0005 ⟧
+            ν3(𝜋) ↦ ⟦ 𝜑 ↦ ν5(𝜋), 𝛼0 ↦ ν4(𝜋) ⟧
+            ν4(𝜋) ↦ ⟦ Δ ↦ 0x0009 ⟧
+            ν5(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ 𝜋.𝛼0 ⟧
+            # expect: -5
+        ",
+    },
+    Case {
+        name: "xi-shadows-scope",
+        doc: "The same as above, but with (ξ) the nearer 𝛼0 shadows the outer one",
+        program: "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν2(𝜋) ⟧
+            ν2(𝜋) ↦ ⟦ Δ ↦ 0x0005 ⟧
+            ν3(𝜋) ↦ ⟦ 𝜑 ↦ ν5(ξ), 𝛼0 ↦ ν4(𝜋) ⟧
+            ν4(𝜋) ↦ ⟦ Δ ↦ 0x0009 ⟧
+            ν5(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ 𝜋.𝛼0 ⟧
+            # expect: -9
+        ",
+    },
+    Case {
+        name: "nested-psi",
+        doc: "𝜋.𝜋 goes two baskets up, past the nearer 𝛼0",
+        program: "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν2(𝜋) ⟧
+            ν2(𝜋) ↦ ⟦ Δ ↦ 0x0005 ⟧
+            ν3(𝜋) ↦ ⟦ 𝜑 ↦ ν5(ξ), 𝛼0 ↦ ν4(𝜋) ⟧
+            ν4(𝜋) ↦ ⟦ Δ ↦ 0x0009 ⟧
+            ν5(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ 𝜋.𝛼0, 𝛼0 ↦ 𝜋.𝜋.𝛼0 ⟧
+            # expect: 4
+        ",
+    },
+    Case {
+        name: "data-of-decoratee",
+        doc: "An object without Δ is dataized through the object its 𝜑 refers to",
+        program: "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼1 ↦ ν4(𝜋) ⟧
+            ν2(𝜋) ↦ ⟦ Δ ↦ 0x0006 ⟧
+            ν3(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ 𝜋.𝛼1 ⟧
+            ν4(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+            # expect: -6
+        ",
+    },
+    Case {
+        name: "attribute-of-decoratee",
+        doc: "An attribute absent in an object is taken from the object its 𝜑 refers to",
+        program: "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼1 ↦ ν4(𝜋) ⟧
+            ν2(𝜋) ↦ ⟦ Δ ↦ 0x0006 ⟧
+            ν3(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ 𝜋.𝛼1.𝛼0 ⟧
+            ν4(𝜋) ↦ ⟦ 𝜑 ↦ ν5(𝜋) ⟧
+            ν5(𝜋) ↦ ⟦ 𝛼0 ↦ ν2(𝜋) ⟧
+            # expect: -6
+        ",
+    },
+    Case {
+        name: "sigma-is-home",
+        doc: "σ of an object made with (𝜋) is still its home, unlike 𝜋",
+        program: "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν2(𝜋) ⟧
+            ν2(𝜋) ↦ ⟦ Δ ↦ 0x0005 ⟧
+            ν3(𝜋) ↦ ⟦ 𝜑 ↦ ν5(𝜋), 𝛼0 ↦ ν4(𝜋) ⟧
+            ν4(𝜋) ↦ ⟦ Δ ↦ 0x0009 ⟧
+            ν5(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ σ.𝛼0 ⟧
+            # expect: -9
+        ",
+    },
+    Case {
+        name: "sigma-of-sigma",
+        doc: "σ.σ goes up the decoration chain, from home to home",
+        program: "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν2(𝜋) ⟧
+            ν2(𝜋) ↦ ⟦ Δ ↦ 0x0005 ⟧
+            ν3(𝜋) ↦ ⟦ 𝜑 ↦ ν5(𝜋), 𝛼0 ↦ ν4(𝜋) ⟧
+            ν4(𝜋) ↦ ⟦ Δ ↦ 0x0009 ⟧
+            ν5(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ σ.σ.𝛼0 ⟧
+            # expect: -5
+        ",
+    },
+    Case {
+        name: "sigma-of-decoratee",
+        doc: "An attribute of σ is taken through the 𝜑 of what it refers to",
+        program: "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼1 ↦ ν4(𝜋) ⟧
+            ν2(𝜋) ↦ ⟦ Δ ↦ 0x0006 ⟧
+            ν3(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ σ.𝛼1.𝛼0 ⟧
+            ν4(𝜋) ↦ ⟦ 𝜑 ↦ ν5(𝜋) ⟧
+            ν5(𝜋) ↦ ⟦ 𝛼0 ↦ ν2(𝜋) ⟧
+            # expect: -6
+        ",
+    },
+    Case {
+        name: "sigma-of-static-object",
+        doc: "σ of an object reached through Φ is its home, taken statically",
+        program: "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν3(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧
+            ν2(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+            ν3(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ Φ.𝜑.𝛼0.σ.𝛼0 ⟧
+            # expect: -7
+        ",
+    },
+    Case {
+        name: "root-has-no-sigma",
+        doc: "σ of ν0, which nothing declares, walks out of the graph",
+        program: "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ σ.σ.𝛼0 ⟧
+            # expect-error: escaped the graph
+        ",
+    },
+    Case {
+        name: "missing-attribute",
+        doc: "An attribute nobody along the 𝜑 chain has is not found",
+        program: "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼1 ↦ ν4(𝜋) ⟧
+            ν2(𝜋) ↦ ⟦ Δ ↦ 0x0006 ⟧
+            ν3(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ 𝜋.𝛼1.𝛼0 ⟧
+            ν4(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+            # expect-error: The attribute 𝛼0 of ν4 is not found
+        ",
+    },
+    Case {
+        name: "rho-of-atom",
+        doc: "ρ of an atom is found like any other attribute, through the scope",
+        program: "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν4(ξ), ρ ↦ ν2(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧
+            ν2(𝜋) ↦ ⟦ Δ ↦ 0x000A ⟧
+            ν3(𝜋) ↦ ⟦ Δ ↦ 0x0003 ⟧
+            ν4(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ 𝜋.ρ, 𝛼0 ↦ 𝜋.𝛼0 ⟧
+            # expect: 7
+        ",
+    },
+    Case {
+        name: "root-has-no-psi",
+        doc: "𝜋 of an object whose ψ is the root basket walks out of the graph",
+        program: "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ 𝜋.𝛼0 ⟧
+            # expect-error: escaped the graph
+        ",
+    },
+    Case {
+        name: "xi-recursion",
+        doc: "Each call made with (ξ) gets its own 𝜋, so recursion works",
+        program: "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ Δ ↦ 0x0004 ⟧
+            ν2(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν1(𝜋) ⟧
+            ν3(𝜋) ↦ ⟦ 𝜑 ↦ ν9(𝜋) ⟧
+            ν4(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧
+            ν5(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧
+            ν6(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ 𝜋.𝜋.𝛼0, 𝛼0 ↦ ν5(𝜋) ⟧
+            ν7(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν6(𝜋) ⟧
+            ν8(𝜋) ↦ ⟦ λ ↦ int-times, ρ ↦ 𝜋.𝛼0, 𝛼0 ↦ ν7(𝜋) ⟧
+            ν9(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ ν10(𝜋), 𝛼0 ↦ ν5(𝜋), 𝛼1 ↦ ν8(𝜋) ⟧
+            ν10(𝜋) ↦ ⟦ λ ↦ int-less, ρ ↦ 𝜋.𝛼0, 𝛼0 ↦ ν4(𝜋) ⟧
+            # expect: 24
+        ",
+    },
+];
+
+impl Case {
+    /// Dataize the program and check the outcome against the expected
+    /// one, returning what it was: the data or the error.
+    pub fn run(&self) -> Result<(String, Option<Perf>), String> {
+        let expected = Expectation::find(self.program)?
+            .ok_or_else(|| format!("The case '{}' doesn't expect anything", self.name))?;
+        let run = Emu::from_str(self.program).and_then(|mut emu| {
+            emu.opt(Opt::StopWhenTooManyCycles);
+            emu.opt(Opt::StopWhenStuck);
+            emu.try_dataize().map_err(String::from)
+        });
+        let result: Result<Data, String> = run
+            .as_ref()
+            .map(|(d, _)| *d)
+            .map_err(|e| e.lines().next().unwrap_or_default().to_string());
+        expected.check(&result)?;
+        Ok(match run {
+            Ok((d, perf)) => (d.to_string(), Some(perf)),
+            Err(e) => (
+                format!("error: {}", e.lines().next().unwrap_or_default()),
+                None,
+            ),
+        })
+    }
+}
+
+#[test]
+fn passes_all_cases() {
+    for case in CASES {
+        if let Err(e) = case.run() {
+            panic!("{}: {}", case.name, e);
+        }
+    }
+}
+
+#[test]
+fn has_unique_names() {
+    let mut names: Vec<&str> = CASES.iter().map(|c| c.name).collect();
+    names.sort();
+    names.dedup();
+    assert_eq!(CASES.len(), names.len());
+}
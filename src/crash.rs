@@ -0,0 +1,214 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! What the `Emu` knew when its dataization panicked, written to a file
+//! in the directory set by `Opt::CrashDumps` before the panic goes on:
+//! the program with its options, the latest transitions and the live
+//! baskets, which is enough to reproduce the failure elsewhere:
+//!
+//! ```
+//! use phie::crash::Dump;
+//! use phie::emu::{Emu, Opt};
+//! let dir = std::env::temp_dir().join("phie-crash-doc");
+//! let mut emu: Emu = "
+//!   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+//!   ν1(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν1(ξ) ⟧
+//! ".parse().unwrap();
+//! emu.opt(Opt::MaxCycles(10));
+//! emu.opt(Opt::CrashDumps(dir.clone()));
+//! assert!(emu.try_dataize().is_err());
+//! let path = dir.join(format!("crash-{:016x}-11.json", emu.fingerprint()));
+//! let dump = Dump::read(&path).unwrap();
+//! assert!(dump.message.starts_with("Too many cycles"));
+//! assert_eq!(11, dump.cycle);
+//! ```
+//!
+//! The dump carries the version of the encodings of its baskets, see
+//! `syntax::FORMAT`, and a dump in another format is not read.
+
+use crate::checkpoint::Checkpoint;
+use crate::data::Datum;
+use crate::emu::Emu;
+use crate::syntax::FORMAT;
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// How many of the latest transitions a dump keeps.
+pub const RECENT: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dump {
+    /// The message of the panic.
+    pub message: String,
+    /// The fingerprint of the program, see `Emu::fingerprint()`.
+    pub fingerprint: u64,
+    /// The number of cycles made before the panic.
+    pub cycle: usize,
+    /// The options of the `Emu`, as they are written after `+opt`.
+    pub opts: Vec<String>,
+    /// The objects and inputs of the program, in its text format.
+    pub program: String,
+    /// The latest transitions, the oldest first.
+    pub events: Vec<String>,
+    /// The live baskets, see `Emu::snapshot()`.
+    pub snapshot: String,
+    /// The same baskets, without objects, which may be read back.
+    pub checkpoint: Checkpoint,
+}
+
+impl Dump {
+    /// Take what the `Emu` knows right after the panic.
+    pub fn of<D: Datum>(emu: &Emu<D>, message: &str, cycle: usize) -> Dump {
+        let mut opts: Vec<String> = emu.opts.iter().map(|o| o.to_string()).collect();
+        opts.sort();
+        let mut inputs: Vec<String> = emu
+            .inputs()
+            .iter()
+            .map(|(name, ob)| format!("+input {} ν{}", name, ob))
+            .collect();
+        inputs.sort();
+        Dump {
+            message: message.to_string(),
+            fingerprint: emu.fingerprint(),
+            cycle,
+            opts,
+            program: inputs
+                .into_iter()
+                .chain(
+                    emu.objects
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, obj)| !obj.is_empty())
+                        .map(|(ob, obj)| format!("ν{}(𝜋) ↦ {}", ob, obj)),
+                )
+                .collect::<Vec<String>>()
+                .join("\n"),
+            events: emu.recent().map(|e| e.to_string()).collect(),
+            #[cfg(feature = "compact")]
+            snapshot: crate::compact::fitting(emu, crate::compact::LIMIT),
+            #[cfg(not(feature = "compact"))]
+            snapshot: emu.snapshot(),
+            checkpoint: Checkpoint::take(cycle, &emu.baskets),
+        }
+    }
+
+    /// The name of its file, unique for the program and the cycle.
+    pub fn file_name(&self) -> String {
+        format!("crash-{:016x}-{}.json", self.fingerprint, self.cycle)
+    }
+
+    /// Write it to its file in the directory, making the directory
+    /// if necessary, and return the path of the file.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf, String> {
+        let path = dir.join(self.file_name());
+        fs::create_dir_all(dir)
+            .and_then(|_| fs::write(&path, self.to_json()))
+            .map_err(|e| format!("Can't write the crash dump '{}': {}", path.display(), e))?;
+        Ok(path)
+    }
+
+    /// Read it from the file written by `write()`.
+    pub fn read(path: &Path) -> Result<Dump, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Can't read the crash dump '{}': {}", path.display(), e))?;
+        Dump::from_json(&text)
+    }
+
+    pub fn to_json(&self) -> String {
+        json!({
+            "format": FORMAT,
+            "message": self.message,
+            "program": format!("{:016x}", self.fingerprint),
+            "cycle": self.cycle,
+            "opts": self.opts,
+            "objects": self.program,
+            "events": self.events,
+            "snapshot": self.snapshot,
+            "checkpoint": self.checkpoint.to_string(),
+        })
+        .to_string()
+    }
+
+    pub fn from_json(text: &str) -> Result<Dump, String> {
+        let json: serde_json::Value =
+            serde_json::from_str(text).map_err(|e| format!("Can't parse the crash dump: {}", e))?;
+        if let Some(format) = json["format"].as_u64().filter(|f| *f != FORMAT as u64) {
+            return Err(format!(
+                "The crash dump is in the format {}, while we read the format {}",
+                format, FORMAT
+            ));
+        }
+        let text = |key: &str| -> Result<String, String> {
+            json[key]
+                .as_str()
+                .map(|s| s.to_string())
+                .ok_or_else(|| format!("There is no '{}' in the crash dump", key))
+        };
+        let list = |key: &str| -> Result<Vec<String>, String> {
+            serde_json::from_value(json[key].clone())
+                .map_err(|e| format!("Can't parse '{}' in the crash dump: {}", key, e))
+        };
+        Ok(Dump {
+            message: text("message")?,
+            fingerprint: u64::from_str_radix(&text("program")?, 16)
+                .map_err(|e| format!("Can't parse the fingerprint in the crash dump: {}", e))?,
+            cycle: json["cycle"]
+                .as_u64()
+                .ok_or("There is no 'cycle' in the crash dump")? as usize,
+            opts: list("opts")?,
+            program: text("objects")?,
+            events: list("events")?,
+            snapshot: text("snapshot")?,
+            checkpoint: Checkpoint::from_str(&text("checkpoint")?)?,
+        })
+    }
+}
+
+#[cfg(test)]
+use crate::emu::Opt;
+
+#[test]
+fn dumps_and_reads_itself() {
+    let dir = std::env::temp_dir().join(format!("phie-crash-{}", std::process::id()));
+    let mut emu: Emu = "+input x ν1\nν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧\n\
+        ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧\nν3(𝜋) ↦ ⟦ 𝜑 ↦ ν3(𝜋) ⟧"
+        .parse()
+        .unwrap();
+    emu.opt(Opt::StopWhenStuck);
+    emu.opt(Opt::CrashDumps(dir.clone()));
+    let err = emu.try_dataize().err().unwrap().to_string();
+    let files: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|f| f.unwrap().path())
+        .collect();
+    assert_eq!(1, files.len());
+    let dump = Dump::read(&files[0]).unwrap();
+    assert_eq!(err, dump.message);
+    assert!(
+        dump.program.starts_with("+input x ν1\nν0(𝜋) ↦ "),
+        "{}",
+        dump.program
+    );
+    assert!(!dump.events.is_empty());
+    assert!(dump.events.len() <= RECENT);
+    assert!(dump.snapshot.contains("β0"));
+    assert!(dump.opts.contains(&"stop-when-stuck".to_string()));
+    assert_eq!(dump, Dump::from_json(&dump.to_json()).unwrap());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn refuses_dumps_of_other_format() {
+    let emu: Emu = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+    let mut json: serde_json::Value =
+        serde_json::from_str(&Dump::of(&emu, "Oops", 1).to_json()).unwrap();
+    assert_eq!(FORMAT as u64, json["format"].as_u64().unwrap());
+    json["format"] = (FORMAT + 1).into();
+    assert!(Dump::from_json(&json.to_string())
+        .unwrap_err()
+        .contains("in the format"));
+    json.as_object_mut().unwrap().remove("format");
+    assert!(Dump::from_json(&json.to_string()).is_ok());
+}
@@ -0,0 +1,560 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Debug Adapter Protocol support, on top of `Emu::step`.
+//!
+//! One cycle of dataization is one step. A breakpoint is set on the
+//! line where an object is defined and stops the program as soon as a
+//! new basket of this object is created, or, if it has a condition,
+//! like `𝛼0 dataizes to 0`, as soon as the condition holds for a kid of
+//! a basket of this object, see `breakpoint`. A condition typed into the
+//! debug console, like `break when ν9.𝛼0 dataizes to 0`, holds until the
+//! session ends. With the `scripting` feature, the `script` argument
+//! of `launch` is a file with a script, see `script`, which is run on
+//! every breakpoint and may let the program go on. The "call stack" is the list
+//! of live baskets, deepest first, and the variables of each frame are
+//! the kids of its basket. The `phie-dap` binary moves messages between
+//! the `Session` and stdio, framed just like in `lsp`.
+//!
+//! ```
+//! use phie::dap::Session;
+//! use serde_json::json;
+//! let mut session = Session::new();
+//! let out = session.handle(&json!({"seq": 1, "type": "request", "command": "initialize"}));
+//! assert_eq!(true, out[0]["success"]);
+//! ```
+
+use crate::basket::Bk;
+use crate::breakpoint::{Break, Condition};
+use crate::data::Data;
+use crate::emu::Emu;
+use crate::lsp::definition;
+use crate::object::Ob;
+use crate::perf::Perf;
+use crate::scheduler::{Deepest, Scheduler};
+#[cfg(feature = "scripting")]
+use crate::script::{Script, Verdict};
+use itertools::Itertools;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The maximum number of cycles `continue` makes before giving up.
+const MAX_CYCLES: usize = 65536;
+
+pub struct Session {
+    emu: Option<Emu>,
+    perf: Perf,
+    source: String,
+    lines: HashMap<Ob, usize>,
+    breakpoints: HashSet<Ob>,
+    /// The conditions of breakpoints set on lines, see `set_breakpoints()`.
+    conditions: Vec<Condition>,
+    /// The conditions typed into the debug console, see `evaluate()`.
+    watches: Vec<Condition>,
+    /// The script to run on every breakpoint, see `script`.
+    #[cfg(feature = "scripting")]
+    script: Option<Script>,
+    stop_on_entry: bool,
+    seq: i64,
+    pub done: bool,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Session {
+    pub fn new() -> Session {
+        Session {
+            emu: None,
+            perf: Perf::new(),
+            source: String::new(),
+            lines: HashMap::new(),
+            breakpoints: HashSet::new(),
+            conditions: vec![],
+            watches: vec![],
+            #[cfg(feature = "scripting")]
+            script: None,
+            stop_on_entry: false,
+            seq: 0,
+            done: false,
+        }
+    }
+
+    /// Handle one request and return the messages to send back.
+    pub fn handle(&mut self, msg: &Value) -> Vec<Value> {
+        let command = msg["command"].as_str().unwrap_or("").to_string();
+        let args = &msg["arguments"];
+        let mut out = vec![];
+        let body = match command.as_str() {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsConditionalBreakpoints": true
+            })),
+            "launch" => self.launch(args).map(|_| {
+                out.push(self.event("initialized", json!({})));
+                json!({})
+            }),
+            "setBreakpoints" => Ok(self.set_breakpoints(args)),
+            "evaluate" => self.evaluate(args),
+            "configurationDone" => Ok(json!({})),
+            "threads" => Ok(json!({"threads": [{"id": 1, "name": "dataization"}]})),
+            "stackTrace" => Ok(self.stack_trace()),
+            "scopes" => Ok(json!({"scopes": [{
+                "name": "Kids",
+                "variablesReference": args["frameId"],
+                "expensive": false
+            }]})),
+            "variables" => Ok(self.variables(args["variablesReference"].as_i64().unwrap_or(0))),
+            "next" | "stepIn" | "stepOut" | "continue" | "pause" => {
+                if self.emu.is_some() {
+                    Ok(json!({"allThreadsContinued": true}))
+                } else {
+                    Err("The program is not launched".to_string())
+                }
+            }
+            "disconnect" => {
+                self.done = true;
+                Ok(json!({}))
+            }
+            _ => Err(format!("Unknown command '{}'", command)),
+        };
+        let mut all = vec![self.response(msg, &command, body)];
+        all.append(&mut out);
+        match command.as_str() {
+            "configurationDone" if self.emu.is_some() => {
+                if self.stop_on_entry {
+                    all.push(self.stopped("entry"));
+                } else {
+                    all.append(&mut self.run(false));
+                }
+            }
+            "next" | "stepIn" | "stepOut" if self.emu.is_some() => all.append(&mut self.run(true)),
+            "continue" if self.emu.is_some() => all.append(&mut self.run(false)),
+            "pause" if self.emu.is_some() => all.push(self.stopped("pause")),
+            _ => {}
+        }
+        all
+    }
+
+    fn launch(&mut self, args: &Value) -> Result<(), String> {
+        let program = args["program"]
+            .as_str()
+            .ok_or_else(|| "The 'program' argument is missing".to_string())?;
+        let emu = Emu::from_file(Path::new(program))?;
+        let text = fs::read_to_string(program)
+            .map_err(|e| format!("Failed to read file '{}': {}", program, e))?;
+        self.lines = emu
+            .objects
+            .iter()
+            .enumerate()
+            .filter(|(_, obj)| !obj.is_empty())
+            .map(|(ob, _)| Ob::new(ob))
+            .filter_map(|ob| definition(&text, ob).map(|(num, _)| (ob, num)))
+            .collect();
+        #[cfg(feature = "scripting")]
+        {
+            self.script = match args["script"].as_str() {
+                Some(path) => Some(Script::compile(
+                    &fs::read_to_string(path)
+                        .map_err(|e| format!("Failed to read script '{}': {}", path, e))?,
+                )?),
+                None => None,
+            };
+        }
+        self.source = program.to_string();
+        self.stop_on_entry = args["stopOnEntry"].as_bool().unwrap_or(false);
+        self.perf = Perf::new();
+        self.emu = Some(emu);
+        self.arm();
+        Ok(())
+    }
+
+    fn set_breakpoints(&mut self, args: &Value) -> Value {
+        self.breakpoints.clear();
+        self.conditions.clear();
+        let mut verified = vec![];
+        for bp in args["breakpoints"].as_array().cloned().unwrap_or_default() {
+            let line = bp["line"].as_u64().unwrap_or(0) as usize;
+            let ob = self
+                .lines
+                .iter()
+                .find(|(_, num)| **num + 1 == line)
+                .map(|(ob, _)| *ob);
+            let cond = bp["condition"]
+                .as_str()
+                .map(Condition::from_str)
+                .transpose();
+            let mut res = json!({"verified": false, "line": line});
+            match (ob, cond) {
+                (Some(o), Ok(None)) => {
+                    self.breakpoints.insert(o);
+                    res["verified"] = Value::Bool(true);
+                }
+                (Some(o), Ok(Some(c))) => {
+                    self.conditions.push(c.on(o));
+                    res["verified"] = Value::Bool(true);
+                }
+                (_, Err(e)) => res["message"] = Value::String(e),
+                (None, _) => {}
+            }
+            verified.push(res);
+        }
+        self.arm();
+        json!({"breakpoints": verified})
+    }
+
+    /// Take a condition typed into the debug console, like
+    /// `break when ν9.𝛼0 dataizes to 0`.
+    fn evaluate(&mut self, args: &Value) -> Result<Value, String> {
+        let expr = args["expression"].as_str().unwrap_or("");
+        if !expr.trim().starts_with("break when ") {
+            return Err(format!("Can't evaluate '{}', only 'break when ...'", expr));
+        }
+        let cond = Condition::from_str(expr)?;
+        let result = format!("Will break when {}", cond);
+        self.watches.push(cond);
+        self.arm();
+        Ok(json!({"result": result, "variablesReference": 0}))
+    }
+
+    /// Give all conditions to the `Emu`, if it's launched.
+    fn arm(&mut self) {
+        if let Some(emu) = self.emu.as_mut() {
+            emu.clear_breaks();
+            for cond in self.conditions.iter().chain(self.watches.iter()) {
+                emu.break_when(cond.clone());
+            }
+        }
+    }
+
+    fn stack_trace(&self) -> Value {
+        let Some(emu) = &self.emu else {
+            return json!({"stackFrames": [], "totalFrames": 0});
+        };
+        let frames: Vec<Value> = Deepest
+            .order(&emu.psis())
+            .into_iter()
+            .filter(|bk| !emu.basket(*bk).is_empty())
+            .map(|bk| {
+                let ob = emu.basket(bk).ob;
+                json!({
+                    "id": bk.index() + 1,
+                    "name": format!("β{} ν{}", bk, ob),
+                    "source": {"path": self.source},
+                    "line": self.lines.get(&ob).map(|n| n + 1).unwrap_or(0),
+                    "column": 1
+                })
+            })
+            .collect();
+        json!({"totalFrames": frames.len(), "stackFrames": frames})
+    }
+
+    fn variables(&self, reference: i64) -> Value {
+        let vars: Vec<Value> = match &self.emu {
+            Some(emu) if reference > 0 && ((reference - 1) as usize) < emu.baskets.len() => {
+                let bsk = emu.basket(Bk::new((reference - 1) as usize));
+                let mut vars = vec![json!({
+                    "name": "ξ",
+                    "value": format!("β{}", bsk.psi),
+                    "variablesReference": 0
+                })];
+                let kids: Vec<(String, String)> = bsk
+                    .kids
+                    .iter()
+                    .sorted_by(|a, b| a.0.cmp(b.0))
+                    .map(|(loc, kid)| (loc.to_string(), kid.to_string()))
+                    .collect();
+                vars.extend(kids.into_iter().map(
+                    |(name, value)| json!({"name": name, "value": value, "variablesReference": 0}),
+                ));
+                vars
+            }
+            _ => vec![],
+        };
+        json!({"variables": vars})
+    }
+
+    /// Make cycles until the result is ready, a breakpoint is hit,
+    /// or just one cycle, if it's a single step.
+    fn run(&mut self, single: bool) -> Vec<Value> {
+        let mut cycles = 0;
+        let mut out = vec![];
+        loop {
+            let Some(emu) = self.emu.as_mut() else {
+                return out;
+            };
+            let before: HashSet<(Bk, Ob)> = live(emu);
+            let hits = self.perf.total_hits();
+            match emu.step(&mut self.perf) {
+                Ok(Some(d)) => {
+                    out.append(&mut self.finish(Ok(d)));
+                    return out;
+                }
+                Err(e) => {
+                    out.append(&mut self.finish(Err(e.to_string())));
+                    return out;
+                }
+                Ok(None) => {}
+            }
+            if hits == self.perf.total_hits() {
+                let err = format!("We are stuck, no hits after {}", self.perf.total_hits());
+                out.append(&mut self.finish(Err(err)));
+                return out;
+            }
+            let hit = emu.take_break();
+            let born = live(emu)
+                .difference(&before)
+                .filter(|(_, ob)| self.breakpoints.contains(ob))
+                .map(|(bk, _)| *bk)
+                .min();
+            if let Some(bk) = hit.as_ref().map(|b| b.bk).or(born) {
+                if self.judge(bk, hit.as_ref(), &mut out) {
+                    out.push(self.stopped("breakpoint"));
+                    return out;
+                }
+            }
+            if single {
+                out.push(self.stopped("step"));
+                return out;
+            }
+            cycles += 1;
+            if cycles > MAX_CYCLES {
+                out.append(&mut self.finish(Err(format!("Too many cycles ({})", cycles))));
+                return out;
+            }
+        }
+    }
+
+    /// Run the script on the breakpoint, if there is one, sending
+    /// what it logs, and tell whether to stop.
+    #[cfg(feature = "scripting")]
+    fn judge(&mut self, bk: Bk, hit: Option<&Break>, out: &mut Vec<Value>) -> bool {
+        let (Some(script), Some(emu)) = (self.script.as_mut(), self.emu.as_ref()) else {
+            return true;
+        };
+        let verdict = script.run(emu, self.perf.cycles, bk, hit);
+        for line in script.logs() {
+            out.push(self.event(
+                "output",
+                json!({"category": "console", "output": format!("{}\n", line)}),
+            ));
+        }
+        match verdict {
+            Ok(v) => v == Verdict::Stop,
+            Err(e) => {
+                out.push(self.event(
+                    "output",
+                    json!({"category": "stderr", "output": format!("{}\n", e)}),
+                ));
+                true
+            }
+        }
+    }
+
+    /// Without the `scripting` feature, every breakpoint stops.
+    #[cfg(not(feature = "scripting"))]
+    fn judge(&mut self, _: Bk, _: Option<&Break>, _: &mut Vec<Value>) -> bool {
+        true
+    }
+
+    fn finish(&mut self, result: Result<Data, String>) -> Vec<Value> {
+        self.emu = None;
+        let (category, output, code) = match result {
+            Ok(d) => ("stdout", format!("{}\n", d), 0),
+            Err(e) => ("stderr", format!("{}\n", e), 1),
+        };
+        vec![
+            self.event("output", json!({"category": category, "output": output})),
+            self.event("terminated", json!({})),
+            self.event("exited", json!({"exitCode": code})),
+        ]
+    }
+
+    fn stopped(&mut self, reason: &str) -> Value {
+        self.event(
+            "stopped",
+            json!({"reason": reason, "threadId": 1, "allThreadsStopped": true}),
+        )
+    }
+
+    fn event(&mut self, name: &str, body: Value) -> Value {
+        self.seq += 1;
+        json!({"seq": self.seq, "type": "event", "event": name, "body": body})
+    }
+
+    fn response(&mut self, req: &Value, command: &str, body: Result<Value, String>) -> Value {
+        self.seq += 1;
+        let mut res = json!({
+            "seq": self.seq,
+            "type": "response",
+            "request_seq": req["seq"],
+            "command": command,
+            "success": body.is_ok()
+        });
+        match body {
+            Ok(b) => res["body"] = b,
+            Err(e) => res["message"] = Value::String(e),
+        }
+        res
+    }
+}
+
+/// All live baskets together with their objects.
+fn live(emu: &Emu) -> HashSet<(Bk, Ob)> {
+    emu.baskets
+        .iter()
+        .enumerate()
+        .filter(|(_, bsk)| !bsk.is_empty())
+        .map(|(bk, bsk)| (Bk::new(bk), bsk.ob))
+        .collect()
+}
+
+#[cfg(test)]
+fn request(session: &mut Session, command: &str, arguments: Value) -> Vec<Value> {
+    session.handle(&json!({
+        "seq": 1,
+        "type": "request",
+        "command": command,
+        "arguments": arguments
+    }))
+}
+
+#[cfg(test)]
+fn launched(stop_on_entry: bool) -> Session {
+    let mut session = Session::new();
+    request(&mut session, "initialize", json!({}));
+    let out = request(
+        &mut session,
+        "launch",
+        json!({"program": "tests/resources/written_sum_test", "stopOnEntry": stop_on_entry}),
+    );
+    assert_eq!(true, out[0]["success"], "{:?}", out);
+    assert_eq!("initialized", out[1]["event"]);
+    session
+}
+
+#[test]
+fn runs_to_the_end() {
+    let mut session = launched(false);
+    let out = request(&mut session, "configurationDone", json!({}));
+    assert_eq!("84\n", out[1]["body"]["output"]);
+    assert_eq!("terminated", out[2]["event"]);
+}
+
+#[test]
+fn steps_and_inspects() {
+    let mut session = launched(true);
+    let out = request(&mut session, "configurationDone", json!({}));
+    assert_eq!("entry", out[1]["body"]["reason"]);
+    let out = request(&mut session, "next", json!({"threadId": 1}));
+    assert_eq!("step", out[1]["body"]["reason"]);
+    let out = request(&mut session, "stackTrace", json!({"threadId": 1}));
+    let frames = out[0]["body"]["stackFrames"].as_array().unwrap();
+    assert!(!frames.is_empty());
+    let out = request(&mut session, "variables", json!({"variablesReference": 1}));
+    let vars = out[0]["body"]["variables"].as_array().unwrap();
+    assert!(vars.iter().any(|v| v["name"] == "𝜑"), "{:?}", vars);
+}
+
+#[test]
+fn stops_at_breakpoint() {
+    let mut session = launched(false);
+    let out = request(
+        &mut session,
+        "setBreakpoints",
+        json!({"source": {"path": "x"}, "breakpoints": [{"line": 2}, {"line": 99}]}),
+    );
+    assert_eq!(true, out[0]["body"]["breakpoints"][0]["verified"]);
+    assert_eq!(false, out[0]["body"]["breakpoints"][1]["verified"]);
+    let out = request(&mut session, "configurationDone", json!({}));
+    assert_eq!("breakpoint", out[1]["body"]["reason"], "{:?}", out);
+    let out = request(&mut session, "continue", json!({"threadId": 1}));
+    assert!(out.iter().any(|m| m["event"] == "terminated"), "{:?}", out);
+}
+
+#[test]
+fn stops_when_condition_holds() {
+    let mut session = launched(false);
+    let out = request(
+        &mut session,
+        "setBreakpoints",
+        json!({"source": {"path": "x"}, "breakpoints": [
+            {"line": 3, "condition": "dataizes to 84"},
+            {"line": 3, "condition": "dataizes sometimes"}
+        ]}),
+    );
+    assert_eq!(true, out[0]["body"]["breakpoints"][0]["verified"]);
+    assert_eq!(false, out[0]["body"]["breakpoints"][1]["verified"]);
+    let out = request(&mut session, "configurationDone", json!({}));
+    assert_eq!("breakpoint", out[1]["body"]["reason"], "{:?}", out);
+    let out = request(&mut session, "continue", json!({"threadId": 1}));
+    assert_eq!("84\n", out[1]["body"]["output"], "{:?}", out);
+}
+
+#[test]
+fn breaks_when_told_in_console() {
+    let mut session = launched(false);
+    let out = request(
+        &mut session,
+        "evaluate",
+        json!({"expression": "break when ν3.𝛼0 dataizes to 0x002A"}),
+    );
+    assert_eq!(
+        "Will break when ν3.𝛼0 dataizes to 42", out[0]["body"]["result"],
+        "{:?}",
+        out
+    );
+    let out = request(&mut session, "evaluate", json!({"expression": "2 + 2"}));
+    assert_eq!(false, out[0]["success"]);
+    let out = request(&mut session, "configurationDone", json!({}));
+    assert_eq!("breakpoint", out[1]["body"]["reason"], "{:?}", out);
+}
+
+#[test]
+fn fails_to_launch_absent_file() {
+    let mut session = Session::new();
+    let out = request(
+        &mut session,
+        "launch",
+        json!({"program": "/tmp/absent.phie"}),
+    );
+    assert_eq!(false, out[0]["success"]);
+}
+
+#[cfg(feature = "scripting")]
+#[test]
+fn lets_script_decide_on_breakpoints() {
+    let script = std::env::temp_dir().join(format!("phie-dap-{}.rhai", std::process::id()));
+    fs::write(&script, "log(`ν${ob}.${loc} is ${data}`); data > 80").unwrap();
+    let mut session = Session::new();
+    request(
+        &mut session,
+        "launch",
+        json!({"program": "tests/resources/written_sum_test", "script": script}),
+    );
+    request(
+        &mut session,
+        "evaluate",
+        json!({"expression": "break when 𝜑 dataizes"}),
+    );
+    let out = request(&mut session, "configurationDone", json!({}));
+    let logs: Vec<&Value> = out
+        .iter()
+        .filter(|m| m["body"]["category"] == "console")
+        .map(|m| &m["body"]["output"])
+        .collect();
+    assert_eq!(vec!["ν1.𝜑 is 42\n", "ν2.𝜑 is 84\n"], logs, "{:?}", out);
+    assert_eq!(
+        "breakpoint",
+        out.last().unwrap()["body"]["reason"],
+        "{:?}",
+        out
+    );
+    fs::remove_file(script).unwrap();
+}
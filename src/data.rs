@@ -1,4 +1,488 @@
 // SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
 // SPDX-License-Identifier: MIT
 
-pub type Data = i16;
+use crate::atom::{Meta, Registry};
+use crate::emu::Arithmetic;
+use serde_json::{json, Value as Json};
+use std::fmt::{Debug, Display, UpperHex};
+use std::hash::Hash;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+/// The data of programs, which an `Emu` keeps in its baskets, unless
+/// it's made for another `Datum`.
+pub type Data = i64;
+
+/// The integers an `Emu` keeps in its baskets: `Data` by default, or
+/// a narrower one, which overflows sooner, or `Typed` data, which keeps
+/// the types atoms give, or `Big` integers, which never overflow, all
+/// chosen at compile time; floats need 32 bits at least:
+///
+/// ```
+/// use phie::emu::Emu;
+/// use phie::program::Program;
+/// let prog: Program = "
+///   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+///   ν1(𝜋) ↦ ⟦ λ ↦ int-times, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧
+///   ν2(𝜋) ↦ ⟦ Δ ↦ 0x0100 ⟧
+/// ".parse().unwrap();
+/// assert_eq!(65536, Emu::<i64>::of(&prog).dataize().0);
+/// assert_eq!(65536, Emu::<i32>::of(&prog).dataize().0);
+/// ```
+pub trait Datum:
+    Copy + Eq + Ord + Hash + Default + Debug + Display + UpperHex + Send + Sync + 'static
+{
+    /// The `Emu` keeps the values of this data among its big values,
+    /// while baskets keep their handles, see `Opt::BigData`.
+    const BIG: bool = false;
+
+    /// All atoms, working with this data, in the order of `registry()`,
+    /// and then the ones added by `atom::register()`.
+    fn atoms() -> &'static [Meta<Self>] {
+        Self::registered().all()
+    }
+
+    /// The atoms of this data, see `atom::Registry`.
+    fn registered() -> &'static Registry<Self>;
+
+    /// The data of the program, cut to this width.
+    fn of(d: Data) -> Self;
+
+    /// The data of the program it stands for.
+    fn data(self) -> Data;
+
+    /// Parse the hexadecimal digits, as they are printed in baskets.
+    fn from_hex(s: &str) -> Result<Self, ParseIntError>;
+
+    /// The result of the operation in this arithmetic, or nothing if
+    /// it overflows and the arithmetic is checked.
+    fn calc(self, op: Calc, other: Self, arith: Arithmetic) -> Option<Self>;
+
+    /// The result of an atom that returns this type, see `Meta::returns`;
+    /// only `Typed` data keeps it.
+    fn typed(self, _t: Type) -> Self {
+        self
+    }
+}
+
+/// The operations of integer atoms that may overflow, see `Datum::calc()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Calc {
+    Add,
+    Sub,
+    Mul,
+}
+
+macro_rules! datum {
+    ($($t:ty => $u:ty),*) => {$(
+        impl Datum for $t {
+            fn registered() -> &'static Registry<$t> {
+                static ATOMS: Registry<$t> = Registry::new();
+                &ATOMS
+            }
+
+            fn of(d: Data) -> Self {
+                d as $t
+            }
+
+            fn data(self) -> Data {
+                self as Data
+            }
+
+            fn from_hex(s: &str) -> Result<Self, ParseIntError> {
+                <$u>::from_str_radix(s, 16).map(|u| u as $t)
+            }
+
+            fn calc(self, op: Calc, other: Self, arith: Arithmetic) -> Option<Self> {
+                match (arith, op) {
+                    (Arithmetic::Wrapping, Calc::Add) => Some(self.wrapping_add(other)),
+                    (Arithmetic::Wrapping, Calc::Sub) => Some(self.wrapping_sub(other)),
+                    (Arithmetic::Wrapping, Calc::Mul) => Some(self.wrapping_mul(other)),
+                    (Arithmetic::Saturating, Calc::Add) => Some(self.saturating_add(other)),
+                    (Arithmetic::Saturating, Calc::Sub) => Some(self.saturating_sub(other)),
+                    (Arithmetic::Saturating, Calc::Mul) => Some(self.saturating_mul(other)),
+                    (Arithmetic::Checked, Calc::Add) => self.checked_add(other),
+                    (Arithmetic::Checked, Calc::Sub) => self.checked_sub(other),
+                    (Arithmetic::Checked, Calc::Mul) => self.checked_mul(other),
+                }
+            }
+        }
+    )*};
+}
+
+datum!(i16 => u16, i32 => u32, i64 => u64);
+
+/// The float kept in the data, as the bits of a single-precision number.
+pub fn float(d: Data) -> f32 {
+    f32::from_bits(d as u32)
+}
+
+/// The data that keep this float, as its bits.
+pub fn of_float(f: f32) -> Data {
+    f.to_bits() as Data
+}
+
+/// The bits of the only NaN made with `Opt::DeterministicFloats`.
+pub const CANONICAL_NAN: Data = 0x7FC0_0000;
+
+/// The float kept in the data, the same way on every platform, see
+/// `Opt::DeterministicFloats`.
+pub fn soft_float(d: Data) -> f32 {
+    float(d)
+}
+
+/// The data that keep this float, with any NaN made the `CANONICAL_NAN`,
+/// whatever sign and payload the platform gave it.
+pub fn of_soft_float(f: f32) -> Data {
+    if f.is_nan() {
+        CANONICAL_NAN
+    } else {
+        of_float(f)
+    }
+}
+
+/// The type of a dataized value: `Data` doesn't carry it, but the atom
+/// that made the value tells it, see `atom::returns()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Type {
+    #[default]
+    Int,
+    Bool,
+    Float,
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Type::Int => "int",
+            Type::Bool => "bool",
+            Type::Float => "float",
+        })
+    }
+}
+
+impl FromStr for Type {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "int" => Ok(Type::Int),
+            "bool" => Ok(Type::Bool),
+            "float" => Ok(Type::Float),
+            _ => Err(format!("Unknown type '{}', try int, bool or float", s)),
+        }
+    }
+}
+
+/// A dataized value together with its type, written like `bool:true`,
+/// `float:1.5` or `int:84`, the way executors print and expect results:
+///
+/// ```
+/// use phie::data::Typed;
+/// use std::str::FromStr;
+/// let t = Typed::from_str("bool:true").unwrap();
+/// assert_eq!(r#"{"type":"bool","value":true}"#, t.to_json().to_string());
+/// assert_eq!(Typed::Int(84), Typed::from_str("84").unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Typed {
+    Int(Data),
+    Bool(bool),
+    /// The data of a float, see `float()`.
+    Float(Data),
+}
+
+impl Typed {
+    /// The data seen as a value of this type.
+    pub fn of(d: Data, t: Type) -> Typed {
+        match t {
+            Type::Int => Typed::Int(d),
+            Type::Bool => Typed::Bool(d == 1),
+            Type::Float => Typed::Float(d),
+        }
+    }
+
+    /// The name of its type, as in `bool:true`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Typed::Int(_) => "int",
+            Typed::Bool(_) => "bool",
+            Typed::Float(_) => "float",
+        }
+    }
+
+    /// The data behind it.
+    pub fn data(&self) -> Data {
+        match self {
+            Typed::Int(d) | Typed::Float(d) => *d,
+            Typed::Bool(b) => *b as Data,
+        }
+    }
+
+    /// Like `{"type": "int", "value": 84}`.
+    pub fn to_json(&self) -> Json {
+        let value = match self {
+            Typed::Int(d) => json!(d),
+            Typed::Bool(b) => json!(b),
+            Typed::Float(d) => json!(float(*d)),
+        };
+        json!({"type": self.kind(), "value": value})
+    }
+}
+
+impl std::fmt::Display for Typed {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Typed::Int(d) => write!(f, "int:{}", d),
+            Typed::Bool(b) => write!(f, "bool:{}", b),
+            Typed::Float(d) => write!(f, "float:{}", float(*d)),
+        }
+    }
+}
+
+/// An integer, unless an atom says it's of another type.
+impl Default for Typed {
+    fn default() -> Self {
+        Typed::Int(0)
+    }
+}
+
+/// The data behind it, as it's printed in baskets.
+impl UpperHex for Typed {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        UpperHex::fmt(&Typed::data(self), f)
+    }
+}
+
+impl PartialOrd for Typed {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// By the values: integers and booleans as integers, a boolean before
+/// an equal integer, and floats as floats, after all integers.
+impl Ord for Typed {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match (self, other) {
+            (Typed::Float(a), Typed::Float(b)) => float(*a).total_cmp(&float(*b)),
+            (Typed::Float(_), _) => std::cmp::Ordering::Greater,
+            (_, Typed::Float(_)) => std::cmp::Ordering::Less,
+            _ => Typed::data(self)
+                .cmp(&Typed::data(other))
+                .then(matches!(self, Typed::Int(_)).cmp(&matches!(other, Typed::Int(_)))),
+        }
+    }
+}
+
+/// Data of 64 bits with their types: data objects are integers,
+/// while atoms give the types they return, like `bool` for `int-less`.
+impl Datum for Typed {
+    fn registered() -> &'static Registry<Typed> {
+        static ATOMS: Registry<Typed> = Registry::new();
+        &ATOMS
+    }
+
+    fn of(d: Data) -> Self {
+        Typed::Int(d)
+    }
+
+    fn data(self) -> Data {
+        Typed::data(&self)
+    }
+
+    fn from_hex(s: &str) -> Result<Self, ParseIntError> {
+        Data::from_hex(s).map(Typed::Int)
+    }
+
+    fn calc(self, op: Calc, other: Self, arith: Arithmetic) -> Option<Self> {
+        Typed::data(&self)
+            .calc(op, Typed::data(&other), arith)
+            .map(Typed::Int)
+    }
+
+    fn typed(self, t: Type) -> Self {
+        Typed::of(Typed::data(&self), t)
+    }
+}
+
+/// A value with its type, like `bool:true`, or a plain integer.
+impl FromStr for Typed {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = s.split_once(':').unwrap_or(("int", s));
+        match kind {
+            "int" => value
+                .parse()
+                .map(Typed::Int)
+                .map_err(|e| format!("Invalid integer '{}': {}", value, e)),
+            "bool" => value
+                .parse()
+                .map(Typed::Bool)
+                .map_err(|e| format!("Invalid boolean '{}': {}", value, e)),
+            "float" => value
+                .parse()
+                .map(|f| Typed::Float(of_float(f)))
+                .map_err(|e| format!("Invalid float '{}': {}", value, e)),
+            _ => Err(format!(
+                "Unknown type '{}' in '{}', try int, bool or float",
+                kind, s
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "bigint")]
+use num_bigint::BigInt;
+#[cfg(feature = "rational")]
+use num_rational::BigRational;
+#[cfg(feature = "bigint")]
+use std::fmt;
+
+/// A value kept by the `Emu` in the big mode, while its baskets
+/// keep only the handles of such values.
+#[cfg(feature = "bigint")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Value {
+    Int(BigInt),
+    #[cfg(feature = "rational")]
+    Rat(BigRational),
+}
+
+#[cfg(feature = "bigint")]
+impl From<BigInt> for Value {
+    fn from(v: BigInt) -> Self {
+        Value::Int(v)
+    }
+}
+
+#[cfg(feature = "rational")]
+impl From<BigRational> for Value {
+    fn from(v: BigRational) -> Self {
+        Value::Rat(v)
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(v) => write!(f, "{}", v),
+            #[cfg(feature = "rational")]
+            Value::Rat(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// An integer of any size, available with the `bigint` feature: the
+/// `Emu` keeps it among its big values, see `Opt::BigData`, while
+/// baskets keep its handle, so that it's `Copy` like other data:
+///
+/// ```
+/// use phie::data::Big;
+/// use phie::emu::Emu;
+/// use phie::program::Program;
+/// let prog: Program = "
+///   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+///   ν1(𝜋) ↦ ⟦ λ ↦ int-times, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧
+///   ν2(𝜋) ↦ ⟦ Δ ↦ 0x7FFFFFFFFFFFFFFF ⟧
+/// ".parse().unwrap();
+/// let mut emu = Emu::<Big>::of(&prog);
+/// let big = emu.dataize().0;
+/// assert_eq!("85070591730234615847396907784232501249", emu.big(big).to_string());
+/// ```
+#[cfg(feature = "bigint")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Big(u32);
+
+/// The handle, as it's printed in baskets.
+#[cfg(feature = "bigint")]
+impl fmt::Display for Big {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl UpperHex for Big {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        UpperHex::fmt(&self.0, f)
+    }
+}
+
+/// Handles of integers of any size: the atoms of the big mode calculate
+/// them, see `atom::big_atom()`, so they never overflow.
+#[cfg(feature = "bigint")]
+impl Datum for Big {
+    const BIG: bool = true;
+
+    fn registered() -> &'static Registry<Big> {
+        static ATOMS: Registry<Big> = Registry::new();
+        &ATOMS
+    }
+
+    fn of(d: Data) -> Self {
+        Big(d as u32)
+    }
+
+    fn data(self) -> Data {
+        self.0 as Data
+    }
+
+    fn from_hex(s: &str) -> Result<Self, ParseIntError> {
+        u32::from_str_radix(s, 16).map(Big)
+    }
+
+    fn calc(self, op: Calc, _other: Self, _arith: Arithmetic) -> Option<Self> {
+        panic!(
+            "The handles of big integers can't be calculated with {:?}",
+            op
+        )
+    }
+}
+
+#[test]
+fn prints_and_parses_typed() {
+    for t in [
+        Typed::Int(-7),
+        Typed::Bool(false),
+        Typed::Float(of_float(-2.5)),
+    ] {
+        assert_eq!(t, Typed::from_str(&t.to_string()).unwrap());
+    }
+    assert_eq!(Typed::Bool(true), Typed::of(1, Type::Bool));
+    assert!(Typed::from_str("string:1.5").is_err());
+    assert!(Typed::from_str("bool:1").is_err());
+}
+
+#[test]
+fn keeps_floats_in_single_precision() {
+    assert_eq!(0x3FC0_0000, of_float(1.5));
+    assert_eq!(1.5, float(0x3FC0_0000));
+    assert_eq!(70000.0, float(of_float(70000.0)));
+    assert_eq!(1.0 / 3.0, float(of_float(1.0 / 3.0)));
+    assert!(float(of_float(f32::NAN)).is_nan());
+}
+
+#[test]
+fn orders_typed_by_values() {
+    let mut all = vec![
+        Typed::Float(of_float(-1.5)),
+        Typed::Int(2),
+        Typed::Float(of_float(-2.5)),
+        Typed::Int(-3),
+        Typed::Bool(true),
+        Typed::Int(1),
+    ];
+    all.sort();
+    assert_eq!(
+        vec![
+            Typed::Int(-3),
+            Typed::Bool(true),
+            Typed::Int(1),
+            Typed::Int(2),
+            Typed::Float(of_float(-2.5)),
+            Typed::Float(of_float(-1.5)),
+        ],
+        all
+    );
+}
@@ -0,0 +1,177 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! The structural difference between two programs, which are both
+//! canonicalized first, see `Program::canonicalize()`, so that only
+//! their objects are compared, not the numbers they were given:
+//!
+//! ```
+//! use phie::diff::diff;
+//! use phie::program::Program;
+//! use std::str::FromStr;
+//! let was = Program::from_str("
+//!   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν5(𝜋) ⟧
+//!   ν5(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+//! ").unwrap();
+//! let now = Program::from_str("
+//!   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+//!   ν1(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+//! ").unwrap();
+//! let changes = diff(was, now);
+//! assert_eq!(
+//!     "ν1(𝜋) ↦ ⟦! Δ↦0x002A⟧ → ⟦! Δ↦0x0007⟧\n  - Δ↦0x002A\n  + Δ↦0x0007",
+//!     changes[0].to_string()
+//! );
+//! ```
+
+use crate::object::{Ob, Object};
+use crate::program::Program;
+use std::fmt;
+
+/// One difference between two programs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// The object is only in the second program.
+    Added(Ob, String),
+    /// The object is only in the first program.
+    Removed(Ob, String),
+    /// The object is in both programs, but is not the same; the parts
+    /// of it that are only in the first one and only in the second one
+    /// are listed, with `!`, `%` and `~` markers among them.
+    Changed {
+        ob: Ob,
+        before: String,
+        after: String,
+        removed: Vec<String>,
+        added: Vec<String>,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Added(ob, obj) => write!(f, "+ ν{}(𝜋) ↦ {}", ob, obj),
+            Change::Removed(ob, obj) => write!(f, "- ν{}(𝜋) ↦ {}", ob, obj),
+            Change::Changed {
+                ob,
+                before,
+                after,
+                removed,
+                added,
+            } => {
+                write!(f, "ν{}(𝜋) ↦ {} → {}", ob, before, after)?;
+                for p in removed {
+                    write!(f, "\n  - {}", p)?;
+                }
+                for p in added {
+                    write!(f, "\n  + {}", p)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The markers and the parts of the object, see `Object::parts()`.
+fn parts(obj: &Object) -> Vec<String> {
+    let mut parts = vec![];
+    if obj.constant {
+        parts.push("!".to_string());
+    }
+    match obj.memo {
+        Some(true) => parts.push("%".to_string()),
+        Some(false) => parts.push("~".to_string()),
+        None => {}
+    }
+    parts.extend(obj.parts());
+    parts
+}
+
+/// The changes that turn the first program into the second one, by the
+/// numbers of objects in the canonical form of both of them.
+pub fn diff(mut was: Program, mut now: Program) -> Vec<Change> {
+    was.canonicalize();
+    now.canonicalize();
+    let mut obs: Vec<Ob> = was.iter().chain(now.iter()).map(|(ob, _)| *ob).collect();
+    obs.sort();
+    obs.dedup();
+    obs.into_iter()
+        .filter_map(|ob| match (was.get(ob), now.get(ob)) {
+            (Some(a), None) => Some(Change::Removed(ob, a.to_string())),
+            (None, Some(b)) => Some(Change::Added(ob, b.to_string())),
+            (Some(a), Some(b)) => {
+                let (before, after) = (parts(a), parts(b));
+                if before == after {
+                    return None;
+                }
+                Some(Change::Changed {
+                    ob,
+                    before: a.to_string(),
+                    after: b.to_string(),
+                    removed: before
+                        .iter()
+                        .filter(|p| !after.contains(p))
+                        .cloned()
+                        .collect(),
+                    added: after
+                        .iter()
+                        .filter(|p| !before.contains(p))
+                        .cloned()
+                        .collect(),
+                })
+            }
+            (None, None) => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn compares_canonical_forms() {
+    let was = Program::from_str("ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν3(𝜋) ⟧\nν3(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧").unwrap();
+    let now =
+        Program::from_str("ν3(𝜋) ↦ ⟦Δ↦0x002A⟧\nν0(𝜋) ↦ ⟦𝜑↦ν7(𝜋)⟧\nν7(𝜋) ↦ ⟦𝜑↦ν3(𝜋)⟧").unwrap();
+    assert_eq!(
+        vec![
+            "ν1(𝜋) ↦ ⟦! Δ↦0x002A⟧ → ⟦𝜑↦ν2(𝜋)⟧\n  - !\n  - Δ↦0x002A\n  + 𝜑↦ν2(𝜋)",
+            "+ ν2(𝜋) ↦ ⟦! Δ↦0x002A⟧",
+        ],
+        diff(was, now)
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<String>>()
+    );
+    let same = Program::from_str("ν4(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧\nν0(𝜋) ↦ ⟦ 𝜑 ↦ ν4(𝜋) ⟧").unwrap();
+    let was = Program::from_str("ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν3(𝜋) ⟧\nν3(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧").unwrap();
+    assert!(diff(was, same).is_empty());
+}
+
+#[test]
+fn reports_changed_attributes_and_removed_objects() {
+    let was = Program::from_str(
+        "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧\nν2(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧\nν3(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧",
+    )
+    .unwrap();
+    let now = Program::from_str(
+        "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦%! λ ↦ int-sub, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧\nν2(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧",
+    )
+    .unwrap();
+    let changes = diff(was, now);
+    assert_eq!(2, changes.len());
+    assert_eq!(
+        Change::Changed {
+            ob: Ob::new(1),
+            before: "⟦λ↦int-add, ρ↦ν2(𝜋), 𝛼0↦ν2(𝜋)⟧".to_string(),
+            after: "⟦!% λ↦int-sub, ρ↦ν2(𝜋), 𝛼0↦ν2(𝜋)⟧".to_string(),
+            removed: vec!["λ↦int-add".to_string()],
+            added: vec!["!".to_string(), "%".to_string(), "λ↦int-sub".to_string()],
+        },
+        changes[0]
+    );
+    assert_eq!(
+        Change::Removed(Ob::new(3), "⟦! Δ↦0x0002⟧".to_string()),
+        changes[1]
+    );
+}
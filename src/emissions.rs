@@ -0,0 +1,154 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! The data a program writes with `io-write`, one by one, as soon as
+//! each of them is dataized, for programs that make sequences, instead
+//! of waiting for the result of the first object:
+//!
+//! ```
+//! use phie::emu::Emu;
+//! let mut emu: Emu = "
+//!   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+//!   ν1(𝜋) ↦ ⟦ λ ↦ io-write, ρ ↦ ν2(𝜋) ⟧
+//!   ν2(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν3(𝜋) ⟧
+//!   ν3(𝜋) ↦ ⟦ λ ↦ io-write, ρ ↦ ν4(𝜋) ⟧
+//!   ν4(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+//! ".parse().unwrap();
+//! let mut emitted = emu.emissions();
+//! assert_eq!(Some(Ok(7)), emitted.next());
+//! assert_eq!(Some(Ok(-7)), emitted.next());
+//! assert_eq!(None, emitted.next());
+//! assert_eq!(Some(-7), emitted.result());
+//! ```
+//!
+//! The cycles are limited by `Opt::MaxCycles` and stuck programs are
+//! stopped by `Opt::StopWhenStuck`, like in `Emu::dataize()`; a failure
+//! is the last item.
+
+use crate::data::Datum;
+use crate::emu::Emu;
+use crate::error::message;
+use crate::perf::Perf;
+use std::panic;
+
+/// The iterator made by `Emu::emissions()`.
+pub struct Emissions<'a, D: Datum> {
+    emu: &'a mut Emu<D>,
+    perf: Perf,
+    /// How many data on the output tape were already given out.
+    seen: usize,
+    result: Option<D>,
+    over: bool,
+}
+
+impl<D: Datum> Emu<D> {
+    /// Dataize the first object, giving out the data written by
+    /// `io-write` on the way, see `Emissions`.
+    pub fn emissions(&mut self) -> Emissions<'_, D> {
+        let seen = self.tape_out.len();
+        Emissions {
+            emu: self,
+            perf: Perf::new(),
+            seen,
+            result: None,
+            over: false,
+        }
+    }
+}
+
+impl<D: Datum> Emissions<'_, D> {
+    /// The result of the first object, once all data are given out.
+    pub fn result(&self) -> Option<D> {
+        self.result
+    }
+
+    /// The numbers of the cycles made so far.
+    pub fn perf(&self) -> &Perf {
+        &self.perf
+    }
+
+    /// The next datum on the output tape, if it's already there.
+    fn written(&mut self) -> Option<D> {
+        let d = *self.emu.tape_out.get(self.seen)?;
+        self.seen += 1;
+        Some(D::of(d))
+    }
+}
+
+impl<D: Datum> Iterator for Emissions<'_, D> {
+    type Item = Result<D, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(d) = self.written() {
+                return Some(Ok(d));
+            }
+            if self.over {
+                return None;
+            }
+            let cycles = self.perf.cycles;
+            let step = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                self.emu.step_checked(&mut self.perf, cycles)
+            }));
+            let failure = match step {
+                Ok(Ok(Some(d))) => {
+                    self.result = Some(d);
+                    self.over = true;
+                    continue;
+                }
+                Ok(Ok(None)) => continue,
+                Ok(Err(e)) => e.to_string(),
+                Err(e) => message(e.as_ref()),
+            };
+            self.over = true;
+            return Some(Err(failure));
+        }
+    }
+}
+
+#[cfg(test)]
+use crate::emu::Opt;
+
+#[test]
+fn stops_at_failure() {
+    let mut emu: Emu = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\n\
+        ν1(𝜋) ↦ ⟦ λ ↦ int-div, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧\n\
+        ν2(𝜋) ↦ ⟦ λ ↦ io-write, ρ ↦ ν4(𝜋) ⟧\n\
+        ν3(𝜋) ↦ ⟦ Δ ↦ 0x0000 ⟧\nν4(𝜋) ↦ ⟦ Δ ↦ 0x0005 ⟧"
+        .parse()
+        .unwrap();
+    let all: Vec<Result<i64, String>> = emu.emissions().collect();
+    assert_eq!(2, all.len(), "{:?}", all);
+    assert_eq!(Ok(5), all[0]);
+    assert!(all[1].is_err());
+}
+
+#[test]
+fn stops_when_stuck() {
+    let mut emu: Emu = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦! 𝜑 ↦ ν2(𝜋) ⟧\nν2(𝜋) ↦ ⟦! 𝜑 ↦ ν1(𝜋) ⟧"
+        .parse()
+        .unwrap();
+    emu.opt(Opt::StopWhenStuck);
+    let mut emitted = emu.emissions();
+    assert!(emitted
+        .next()
+        .unwrap()
+        .unwrap_err()
+        .starts_with("We are stuck"));
+    assert!(emitted.next().is_none());
+    assert!(emitted.result().is_none());
+}
+
+#[test]
+fn stops_after_too_many_cycles() {
+    let mut emu: Emu = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦! 𝜑 ↦ ν2(𝜋) ⟧\nν2(𝜋) ↦ ⟦! 𝜑 ↦ ν1(𝜋) ⟧"
+        .parse()
+        .unwrap();
+    emu.opt(Opt::MaxCycles(3));
+    let all: Vec<Result<i64, String>> = emu.emissions().collect();
+    assert_eq!(1, all.len(), "{:?}", all);
+    assert!(all[0]
+        .as_ref()
+        .unwrap_err()
+        .starts_with("Too many cycles (4)"));
+}
@@ -1,53 +1,552 @@
 // SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
 // SPDX-License-Identifier: MIT
 
+#[cfg(feature = "bigint")]
+mod big;
 mod dataization;
+mod explain;
+mod invariants;
 mod tests;
 mod tests_transitions;
 mod transitions;
 
+use crate::atom::{Atom, Meta};
 use crate::basket::{Basket, Bk, Kid};
-use crate::data::Data;
+use crate::breakpoint::{Break, Condition};
+use crate::checkpoint::Checkpoint;
+use crate::data::{Data, Datum, Type};
+use crate::error::Error;
+use crate::event::{Event, EventFilter};
 use crate::loc::Loc;
+use crate::locator::Locator;
 use crate::object::{Ob, Object};
+use crate::partial::Partial;
+use crate::perf::{Perf, Transition};
+use crate::plan::Plan;
+use crate::program::Program;
+use crate::provenance::{Origin, Provenance};
+use crate::sandbox::Profile;
+use crate::scheduler::{Schedule, Scheduler};
+use crate::semantics::{self, Change, Version};
+use crate::stream::Stream;
 use arr_macro::arr;
-use log::trace;
-use regex::Regex;
-use std::collections::HashSet;
+use itertools::Itertools;
+use log::{trace, warn};
+use serde_json::json;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 
-pub const ROOT_BK: Bk = 0;
-pub const ROOT_OB: Ob = 0;
+pub const ROOT_BK: Bk = Bk::new(0);
+pub const ROOT_OB: Ob = Ob::new(0);
 
-const MAX_OBJECTS: usize = 16;
+/// How many objects there may be, unless `Opt::MaxObjects` says otherwise.
+pub const MAX_OBJECTS: usize = 16;
+/// How many steps resolving a locator may take, unless
+/// `Opt::MaxResolutionHops` says otherwise, before it's clear that it
+/// walks in circles, like `Φ.ρ` does when ν0 has no ρ, but has a 𝜑
+/// that refers back to it.
+pub const MAX_HOPS: usize = 4096;
 const MAX_BASKETS: usize = 128;
+/// How many checkpoints are kept, the oldest ones are dropped.
+const MAX_CHECKPOINTS: usize = 16;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Opt {
+    /// Never delete baskets, which can't be with `Opt::Speculate`.
     DontDelete,
     LogSnapshots,
     StopWhenTooManyCycles,
+    /// Stop after this many cycles, instead of the default limit
+    /// of `Opt::StopWhenTooManyCycles`, which it doesn't need.
+    MaxCycles(usize),
+    /// Make room for this many objects, instead of `MAX_OBJECTS`.
+    MaxObjects(usize),
+    /// Take a `Checkpoint` of all baskets every this many cycles,
+    /// keeping only the most recent ones, see `Emu::rewind()`.
+    CheckpointEvery(usize),
+    /// Fail when resolving a locator takes more than this many steps,
+    /// instead of `MAX_HOPS`, no matter how many cycles are left.
+    MaxResolutionHops(usize),
+    /// Tell the callback set by `Emu::on_progress()` how far the
+    /// dataization got, every this many cycles.
+    Progress(usize),
+    /// Check the invariants set by `Emu::assert_invariant()` every this
+    /// many cycles, instead of after each of them.
+    InvariantsEvery(usize),
     StopWhenStuck,
+    Speculate,
+    Schedule(Schedule),
+    RecordEvents,
+    /// Remember where every dataized value came from, see `Emu::provenance()`.
+    TrackProvenance,
+    /// Don't take integers for booleans: `bool-if` fails unless its
+    /// condition is made by an atom returning a bool, like `int-to-bool`.
+    StrictTypes,
+    /// Remember the results of pure atoms by the data of their arguments
+    /// and don't call them again for the same data, see `atom::Meta`.
+    Memoize,
+    LogLevel(EmuLog),
+    /// How float atoms round and treat NaN, see `Emu::float_mode()`.
+    FloatMode(FloatMode),
+    /// What integer atoms do on overflow, see `Emu::arithmetic()`.
+    Arithmetic(Arithmetic),
+    /// Behave like this version of the emulator did, see `semantics`.
+    Semantics(Version),
+    /// Write a `crash::Dump` to this directory when dataization panics.
+    CrashDumps(PathBuf),
+    Paranoid,
+    /// Make all NaNs the same, so that
+    /// float atoms give the same bits on every platform, see `data::soft_float()`.
+    DeterministicFloats,
+    /// Write down every read of these attributes, like `Δ` of an object
+    /// that keeps a secret, see `Emu::audit()`.
+    AuditLocs(Vec<Loc>),
+    /// Tell in plain words what each transition does and by which rule
+    /// of the calculus, see `Emu::explanation()`.
+    Explain,
+    /// Keep big integers in the baskets, see `Emu::dataize_big()`.
+    #[cfg(feature = "bigint")]
+    BigData,
+    /// Print snapshots in the short way, see `compact`.
+    #[cfg(feature = "compact")]
+    CompactSnapshots,
 }
 
-pub struct Emu {
-    pub objects: [Object; MAX_OBJECTS],
-    pub baskets: [Basket; MAX_BASKETS],
+/// Where the data of a kid came from, to know whether it's tainted.
+pub(crate) enum Taint {
+    Delta,
+    Kid(Bk, Loc),
+    Args(&'static [(Loc, Option<Type>)]),
+}
+
+/// A read of an attribute watched by `Opt::AuditLocs`: the data of
+/// the kid of the basket, or the Δ of its object, taken by the reader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Access {
+    pub bk: Bk,
+    pub ob: Ob,
+    pub loc: Loc,
+    /// The basket that took the data.
+    pub reader: Bk,
+    pub data: Data,
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "β{}.{} of ν{} read by β{}: 0x{:04X}",
+            self.bk, self.loc, self.ob, self.reader, self.data
+        )
+    }
+}
+
+/// How far a dataization got, reported every N cycles with
+/// `Opt::Progress(N)` to the callback set by `Emu::on_progress()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// The number of cycles made so far.
+    pub cycles: usize,
+    /// The number of live baskets.
+    pub baskets: usize,
+    /// The number of dataized kids in them.
+    pub dtzd: usize,
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} cycles, {} baskets, {} kids dataized",
+            self.cycles, self.baskets, self.dtzd
+        )
+    }
+}
+
+/// The callback set by `Emu::on_progress()`.
+type OnProgress = Box<dyn FnMut(&Progress) + Send>;
+
+/// The predicate of an invariant, see `Emu::assert_invariant()`.
+type Predicate = Box<dyn Fn(Data) -> bool + Send>;
+
+/// How much the `Emu` logs while dataizing, each level including
+/// everything the previous ones log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EmuLog {
+    /// The final result and the results of atom calls.
+    Results,
+    /// Plus every transition made to the baskets.
+    Transitions,
+    /// Plus the snapshot of all baskets after each cycle.
+    Snapshots,
+    /// Plus the reading of attributes by atoms.
+    Everything,
+}
+
+/// How the float atoms round their results to integers and what they
+/// do when they get NaN or an infinity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FloatMode {
+    /// Round to the nearest integer, keeping NaN as it is.
+    Nearest,
+    /// Round towards zero, keeping NaN as it is.
+    Truncate,
+    /// Round to the nearest integer and fail on NaN or an infinity.
+    Strict,
+}
+
+/// What the integer atoms do when their result doesn't fit in the data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Arithmetic {
+    /// Wrap around, like the hardware does.
+    Wrapping,
+    /// Stop at the bounds of the data.
+    Saturating,
+    /// Fail with the atom and its arguments.
+    Checked,
+}
+
+/// The options without arguments, by their names in `+opt` lines.
+pub(crate) fn flags() -> Vec<(&'static str, Opt)> {
+    vec![
+        ("dont-delete", Opt::DontDelete),
+        ("log-snapshots", Opt::LogSnapshots),
+        ("stop-when-too-many-cycles", Opt::StopWhenTooManyCycles),
+        ("stop-when-stuck", Opt::StopWhenStuck),
+        ("speculate", Opt::Speculate),
+        ("record-events", Opt::RecordEvents),
+        ("track-provenance", Opt::TrackProvenance),
+        ("strict-types", Opt::StrictTypes),
+        ("memoize", Opt::Memoize),
+        ("paranoid", Opt::Paranoid),
+        ("deterministic-floats", Opt::DeterministicFloats),
+        ("explain", Opt::Explain),
+        #[cfg(feature = "bigint")]
+        ("big-data", Opt::BigData),
+        #[cfg(feature = "compact")]
+        ("compact-snapshots", Opt::CompactSnapshots),
+    ]
+}
+
+pub(crate) const SCHEDULES: [(&str, Schedule); 3] = [
+    ("fifo", Schedule::Fifo),
+    ("lifo", Schedule::Lifo),
+    ("deepest", Schedule::Deepest),
+];
+
+pub(crate) const FLOAT_MODES: [(&str, FloatMode); 3] = [
+    ("nearest", FloatMode::Nearest),
+    ("truncate", FloatMode::Truncate),
+    ("strict", FloatMode::Strict),
+];
+
+pub(crate) const ARITHMETICS: [(&str, Arithmetic); 3] = [
+    ("wrapping", Arithmetic::Wrapping),
+    ("saturating", Arithmetic::Saturating),
+    ("checked", Arithmetic::Checked),
+];
+
+pub(crate) const LEVELS: [(&str, EmuLog); 4] = [
+    ("results", EmuLog::Results),
+    ("transitions", EmuLog::Transitions),
+    ("snapshots", EmuLog::Snapshots),
+    ("everything", EmuLog::Everything),
+];
+
+/// An option as it's written in a program file after `+opt`,
+/// like `max-cycles 100000` or `stop-when-stuck`.
+impl FromStr for Opt {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let pick = |arg: &str, list: &[(&str, Opt)]| {
+            list.iter()
+                .find(|(n, _)| *n == arg)
+                .map(|(_, o)| o.clone())
+                .ok_or_else(|| format!("Unknown value '{}' of the option '{}'", arg, parts[0]))
+        };
+        match parts.as_slice() {
+            [name] => pick(name, &flags()).map_err(|_| format!("Unknown option '{}'", name)),
+            ["max-cycles", n] => n
+                .parse()
+                .map(Opt::MaxCycles)
+                .map_err(|e| format!("Invalid number of cycles '{}': {}", n, e)),
+            ["max-objects", n] => match n.parse() {
+                Ok(0) => Err("There must be room for at least one object".to_string()),
+                Ok(n) => Ok(Opt::MaxObjects(n)),
+                Err(e) => Err(format!("Invalid number of objects '{}': {}", n, e)),
+            },
+            ["checkpoint-every", n] => match n.parse() {
+                Ok(0) => Err("Checkpoints can't be taken every 0 cycles".to_string()),
+                Ok(n) => Ok(Opt::CheckpointEvery(n)),
+                Err(e) => Err(format!("Invalid number of cycles '{}': {}", n, e)),
+            },
+            ["invariants-every", n] => match n.parse() {
+                Ok(0) => Err("Invariants can't be checked every 0 cycles".to_string()),
+                Ok(n) => Ok(Opt::InvariantsEvery(n)),
+                Err(e) => Err(format!("Invalid number of cycles '{}': {}", n, e)),
+            },
+            ["progress", n] => match n.parse() {
+                Ok(0) => Err("Progress can't be reported every 0 cycles".to_string()),
+                Ok(n) => Ok(Opt::Progress(n)),
+                Err(e) => Err(format!("Invalid number of cycles '{}': {}", n, e)),
+            },
+            ["max-resolution-hops", n] => match n.parse() {
+                Ok(0) => Err("A locator can't be resolved in 0 hops".to_string()),
+                Ok(n) => Ok(Opt::MaxResolutionHops(n)),
+                Err(e) => Err(format!("Invalid number of hops '{}': {}", n, e)),
+            },
+            ["schedule", s] => pick(s, &SCHEDULES.map(|(n, s)| (n, Opt::Schedule(s)))),
+            ["log-level", l] => pick(l, &LEVELS.map(|(n, l)| (n, Opt::LogLevel(l)))),
+            ["float-mode", m] => pick(m, &FLOAT_MODES.map(|(n, m)| (n, Opt::FloatMode(m)))),
+            ["arithmetic", a] => pick(a, &ARITHMETICS.map(|(n, a)| (n, Opt::Arithmetic(a)))),
+            ["crash-dumps", dir] => Ok(Opt::CrashDumps(PathBuf::from(dir))),
+            ["audit-locs", locs @ ..] if !locs.is_empty() => Ok(Opt::AuditLocs(
+                locs.iter()
+                    .map(|l| Loc::from_str(l))
+                    .collect::<Result<Vec<Loc>, String>>()?,
+            )),
+            ["semantics", v] => v
+                .parse()
+                .map_err(|e| format!("Invalid version of semantics '{}': {}", v, e))
+                .and_then(semantics::check)
+                .map(Opt::Semantics),
+            _ => Err(format!("Unknown option '{}'", s.trim())),
+        }
+    }
+}
+
+impl Opt {
+    /// The option of a `+opt` line of a program. A program can't pick
+    /// the directory the emulator writes crash dumps into, only the
+    /// embedder can, or the environment, or the command line.
+    pub fn of_program(s: &str) -> Result<Opt, String> {
+        match Opt::from_str(s)? {
+            Opt::CrashDumps(_) => {
+                Err("The option 'crash-dumps' can't be set by a program".to_string())
+            }
+            opt => Ok(opt),
+        }
+    }
+
+    /// The options set by `PHIE_*` environment variables, named after
+    /// the options: `PHIE_MAX_CYCLES=100000`, `PHIE_SCHEDULE=lifo`,
+    /// `PHIE_LOG_SNAPSHOTS=1`, and so on.
+    pub fn from_env() -> Result<Vec<Opt>, String> {
+        Opt::from_vars(std::env::vars())
+    }
+
+    /// The options set by these variables, see `Opt::from_env()`.
+    /// Options without arguments are set by `1`, `true`, `yes` or `on`
+    /// and not set by `0`, `false`, `no`, `off` or nothing.
+    ///
+    /// ```
+    /// use phie::emu::Opt;
+    /// let vars = [("PHIE_MAX_CYCLES", "100"), ("PHIE_PARANOID", "yes"), ("HOME", "/")]
+    ///     .map(|(k, v)| (k.to_string(), v.to_string()));
+    /// assert_eq!(vec![Opt::MaxCycles(100), Opt::Paranoid], Opt::from_vars(vars).unwrap());
+    /// ```
+    pub fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> Result<Vec<Opt>, String> {
+        let mut opts = vec![];
+        let mut vars: Vec<(String, String)> = vars
+            .into_iter()
+            .filter(|(k, _)| k.starts_with("PHIE_"))
+            .collect();
+        vars.sort();
+        for (key, value) in vars {
+            let name = key["PHIE_".len()..].to_lowercase().replace('_', "-");
+            let value = value.trim();
+            if flags().iter().any(|(n, _)| *n == name) {
+                match value.to_lowercase().as_str() {
+                    "1" | "true" | "yes" | "on" => opts.push(Opt::from_str(&name)?),
+                    "" | "0" | "false" | "no" | "off" => {}
+                    _ => return Err(format!("Invalid value '{}' of {}", value, key)),
+                }
+            } else if [
+                "max-cycles",
+                "max-objects",
+                "checkpoint-every",
+                "max-resolution-hops",
+                "progress",
+                "invariants-every",
+                "schedule",
+                "log-level",
+                "float-mode",
+                "arithmetic",
+                "semantics",
+                "crash-dumps",
+                "audit-locs",
+            ]
+            .contains(&name.as_str())
+            {
+                opts.push(
+                    Opt::from_str(&format!("{} {}", name, value))
+                        .map_err(|e| format!("Invalid value of {}: {}", key, e))?,
+                );
+            }
+        }
+        Ok(opts)
+    }
+}
+
+/// The name of the float mode, as in `+opt float-mode`.
+fn float_mode_name(mode: FloatMode) -> &'static str {
+    FLOAT_MODES
+        .iter()
+        .find(|(_, m)| *m == mode)
+        .map(|(n, _)| *n)
+        .unwrap_or_default()
+}
+
+impl fmt::Display for Arithmetic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(
+            ARITHMETICS
+                .iter()
+                .find(|(_, a)| a == self)
+                .map(|(n, _)| *n)
+                .unwrap_or_default(),
+        )
+    }
+}
+
+impl FromStr for Arithmetic {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ARITHMETICS
+            .iter()
+            .find(|(n, _)| *n == s)
+            .map(|(_, a)| *a)
+            .ok_or_else(|| format!("Unknown arithmetic '{}'", s))
+    }
+}
+
+impl fmt::Display for Opt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn name<T: PartialEq>(list: &[(&'static str, T)], x: &T) -> &'static str {
+            list.iter()
+                .find(|(_, o)| o == x)
+                .map(|(n, _)| *n)
+                .unwrap_or_default()
+        }
+        match self {
+            Opt::MaxCycles(n) => write!(f, "max-cycles {}", n),
+            Opt::MaxObjects(n) => write!(f, "max-objects {}", n),
+            Opt::CheckpointEvery(n) => write!(f, "checkpoint-every {}", n),
+            Opt::MaxResolutionHops(n) => write!(f, "max-resolution-hops {}", n),
+            Opt::Progress(n) => write!(f, "progress {}", n),
+            Opt::InvariantsEvery(n) => write!(f, "invariants-every {}", n),
+            Opt::Schedule(s) => write!(f, "schedule {}", name(&SCHEDULES, s)),
+            Opt::LogLevel(l) => write!(f, "log-level {}", name(&LEVELS, l)),
+            Opt::FloatMode(m) => write!(f, "float-mode {}", name(&FLOAT_MODES, m)),
+            Opt::Arithmetic(a) => write!(f, "arithmetic {}", name(&ARITHMETICS, a)),
+            Opt::Semantics(v) => write!(f, "semantics {}", v),
+            Opt::CrashDumps(dir) => write!(f, "crash-dumps {}", dir.display()),
+            Opt::AuditLocs(locs) => write!(f, "audit-locs {}", locs.iter().join(" ")),
+            _ => f.write_str(name(&flags(), self)),
+        }
+    }
+}
+
+/// A pure atom by its name and the data of its arguments, together
+/// with the arithmetic and float mode it ran with, since its result
+/// depends on them too.
+pub type MemoKey = ((Arithmetic, FloatMode), String, Vec<Data>);
+
+/// The results of pure atoms by their keys, see `Opt::Memoize`.
+pub type Memo = HashMap<MemoKey, Data>;
+
+/// A `Memo` that many emulators read and extend at once, from many
+/// threads, see `Emu::share_memo()` and `Program::scope()`.
+pub type SharedMemo = Arc<RwLock<Memo>>;
+
+/// The data to bind to named inputs, see `Emu::bind()` and `Emu::sweep()`.
+pub type Bindings = Vec<(String, Data)>;
+
+/// The emulator, keeping the data of baskets as `D`, which is `Data`
+/// unless another width is asked for, see `Datum`.
+pub struct Emu<D: Datum = Data> {
+    pub objects: Vec<Object>,
+    pub baskets: [Basket<D>; MAX_BASKETS],
     pub opts: HashSet<Opt>,
+    pub file: Option<PathBuf>,
+    events: Vec<Event>,
+    /// The latest events, kept for a `crash::Dump`, see `Opt::CrashDumps`.
+    recent: VecDeque<Event>,
+    /// Is `Opt::CrashDumps` set, to not look for it on every transition.
+    dumps: bool,
+    /// The policy of `Opt::Schedule`, to not look for it on every cycle.
+    schedule: Schedule,
+    /// The arithmetic of `Opt::Arithmetic`, to not look for it on every
+    /// call of an atom.
+    arith: Arithmetic,
+    /// The baskets made for branches of `bool-if` started before its
+    /// condition is known, with the `bool-if` and the branch, see
+    /// `Opt::Speculate`; atoms with effects don't run in them.
+    speculative: HashMap<Bk, (Bk, Loc)>,
+    /// The error an atom ran into while reading its arguments, see
+    /// `Emu::read()`, for `delegate()` to return once the atom is done.
+    failure: Option<Error>,
+    /// The reason `opt()` refused an option, see `Emu::try_opt()`, for
+    /// the next cycle to fail with.
+    refused: Option<String>,
+    checkpoints: VecDeque<Checkpoint>,
+    sandbox: Option<Profile>,
+    stream: Option<Stream>,
+    provenance: HashMap<(Bk, Loc), Arc<Provenance>>,
+    reads: Vec<Arc<Provenance>>,
+    memo: Memo,
+    /// The memo of other emulators, see `Emu::share_memo()`.
+    shared: Option<SharedMemo>,
+    /// The most kids the baskets of each object held, by its number.
+    sizes: Vec<usize>,
+    pub(crate) inputs: HashMap<String, Ob>,
+    pub(crate) plans: HashMap<(Ob, Loc), Plan>,
+    /// The arithmetic the program declares with `+arith`, if it does.
+    contract: Option<Arithmetic>,
+    partial: Option<Partial>,
+    progress: Option<OnProgress>,
+    /// The conditions of breakpoints, see `Emu::break_when()`.
+    breaks: Vec<Condition>,
+    /// The first condition that held, not taken yet.
+    broken: Option<Break>,
+    /// The reads of attributes watched by `Opt::AuditLocs`.
+    audit: Vec<Access>,
+    /// The objects whose Δ is tainted, see `Emu::taint()`.
+    taints: HashSet<Ob>,
+    /// The kids with tainted data.
+    pub(crate) tainted: HashSet<(Bk, Loc)>,
+    /// The tainted data written by `io-write`, with its basket.
+    leaks: Vec<(Bk, Data)>,
+    /// The sentences about the transitions made, see `Opt::Explain`.
+    explanation: Vec<String>,
+    /// The objects whose data must always satisfy the predicates.
+    invariants: Vec<(Ob, Predicate)>,
+    /// The data `io-read` takes, the first one first.
+    pub(crate) tape_in: VecDeque<Data>,
+    /// The data `io-write` gave, in the order it gave them.
+    pub(crate) tape_out: Vec<Data>,
+    #[cfg(feature = "bigint")]
+    values: Vec<crate::data::Value>,
+    /// The handles of the big values, so that equal values share one.
+    #[cfg(feature = "bigint")]
+    handles: HashMap<crate::data::Value, Data>,
 }
 
-impl fmt::Display for Emu {
+impl<D: Datum> fmt::Display for Emu<D> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut lines = vec![];
         for i in self.objects.iter().enumerate() {
-            let (ob, obj): (usize, &Object) = i;
+            let (ob, obj): (Ob, &Object) = (Ob::new(i.0), i.1);
             if obj.is_empty() {
                 continue;
             }
             lines.push(format!(
-                "ν{} {}{}",
-                ob,
+                "{} {}{}",
+                obj.label(ob),
                 obj,
                 self.baskets
                     .iter()
@@ -65,29 +564,14 @@ impl fmt::Display for Emu {
 impl FromStr for Emu {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut emu = Emu::empty();
-        let re_line = Regex::new("ν(\\d+)\\(𝜋\\) ↦ (⟦.*⟧)")
-            .map_err(|e| format!("Invalid emu line regex pattern: {}", e))?;
-        for line in s.trim().split('\n').map(|t| t.trim()) {
-            let caps = re_line
-                .captures(line)
-                .ok_or_else(|| format!("Can't parse emu line: '{}'", line))?;
-            let v_str = caps
-                .get(1)
-                .ok_or_else(|| format!("Missing object number in line: '{}'", line))?
-                .as_str();
-            let v: Ob = v_str
-                .parse()
-                .map_err(|e| format!("Can't parse object number '{}': {}", v_str, e))?;
-            let obj_str = caps
-                .get(2)
-                .ok_or_else(|| format!("Missing object definition in line: '{}'", line))?
-                .as_str();
-            let obj = Object::from_str(obj_str)
-                .map_err(|e| format!("Can't parse object in line '{}': {}", line, e))?;
-            emu.put(v, obj);
-        }
-        Ok(emu)
+        Ok(Emu::from(Program::from_str(s)?))
+    }
+}
+
+impl TryFrom<&Path> for Emu {
+    type Error = Error;
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        Emu::from_file(path)
     }
 }
 
@@ -106,60 +590,1273 @@ macro_rules! assert_dataized_eq {
     };
 }
 
+/// Dataize the program, stopping when it's stuck or makes too many
+/// cycles, and make sure the result is as expected. Otherwise, fail
+/// with a message that shows both numbers and the last snapshot:
+///
+/// ```
+/// use phie::assert_dataizes_to;
+/// assert_dataizes_to!("ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧", 42);
+/// ```
+#[macro_export]
+macro_rules! assert_dataizes_to {
+    ($txt:expr, $eq:expr) => {{
+        let expected: $crate::data::Data = $eq;
+        let mut emu = <$crate::emu::Emu as std::str::FromStr>::from_str($txt)
+            .unwrap_or_else(|e| panic!("Can't parse the program: {}", e));
+        emu.opt($crate::emu::Opt::StopWhenTooManyCycles);
+        emu.opt($crate::emu::Opt::StopWhenStuck);
+        match emu.try_dataize() {
+            Ok((actual, _)) => assert!(
+                actual == expected,
+                "The result of dataization is wrong\n  expected: {}\n  actual:   {}\nThe last snapshot:\n{}",
+                expected,
+                actual,
+                emu
+            ),
+            Err(e) => panic!(
+                "The dataization failed\n  expected: {}\n  actual:   {}",
+                expected, e
+            ),
+        }
+    }};
+}
+
+/// Dataize the program and make sure it gets stuck, with no
+/// transitions possible, showing the last snapshot if it doesn't:
+///
+/// ```
+/// use phie::assert_stuck;
+/// assert_stuck!("ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦! 𝜑 ↦ ν2(𝜋) ⟧\nν2(𝜋) ↦ ⟦! 𝜑 ↦ ν1(𝜋) ⟧");
+/// ```
+#[macro_export]
+macro_rules! assert_stuck {
+    ($txt:expr) => {{
+        let mut emu = <$crate::emu::Emu as std::str::FromStr>::from_str($txt)
+            .unwrap_or_else(|e| panic!("Can't parse the program: {}", e));
+        emu.opt($crate::emu::Opt::StopWhenTooManyCycles);
+        emu.opt($crate::emu::Opt::StopWhenStuck);
+        match emu.try_dataize() {
+            Ok((actual, _)) => panic!(
+                "The program is dataized to {}, while it was expected to get stuck\nThe last snapshot:\n{}",
+                actual, emu
+            ),
+            Err(e) => assert!(
+                matches!(e, $crate::error::Error::Stuck(_)),
+                "The program failed, but it's not stuck:\n{}",
+                e
+            ),
+        }
+    }};
+}
+
 impl Emu {
     /// Make an empty Emu, which you can later extend with
     /// additional objects.
     pub fn empty() -> Emu {
+        Emu::blank()
+    }
+
+    /// Read the program from a file, resolving its `+include` directives,
+    /// and remember the name of the file for diagnostics.
+    pub fn from_file(path: &Path) -> Result<Emu, Error> {
+        let mut emu = Emu::from(Program::from_file(path)?);
+        emu.file = Some(path.to_path_buf());
+        Ok(emu)
+    }
+}
+
+impl<D: Datum> Emu<D> {
+    /// Make an `Emu` of the program, with data of any width, while
+    /// `Emu::from()` makes one with the default `Data`.
+    pub fn of(prog: &Program) -> Emu<D> {
+        let mut emu = Emu::blank();
+        emu.plans = prog.plans().clone();
+        emu.inputs = prog.inputs().clone().into_iter().collect();
+        for opt in prog.opts().iter() {
+            emu.opt(opt.clone());
+        }
+        if let Some(v) = prog.semantics() {
+            emu.opt(Opt::Semantics(v));
+        }
+        if let Some(a) = prog.arith() {
+            emu.opt(Opt::Arithmetic(a));
+            emu.contract = Some(a);
+        }
+        for (ob, obj) in prog.iter() {
+            emu.put(*ob, obj.clone());
+        }
+        emu
+    }
+
+    fn blank() -> Emu<D> {
         let mut emu = Emu {
-            objects: arr![Object::open(); 16],
+            objects: (0..MAX_OBJECTS).map(|_| Object::open()).collect(),
             baskets: arr![Basket::empty(); 128],
             opts: HashSet::new(),
+            file: None,
+            events: vec![],
+            recent: VecDeque::new(),
+            dumps: false,
+            schedule: Schedule::Fifo,
+            arith: Arithmetic::Wrapping,
+            speculative: HashMap::new(),
+            failure: None,
+            refused: None,
+            checkpoints: VecDeque::new(),
+            sandbox: None,
+            stream: None,
+            provenance: HashMap::new(),
+            reads: vec![],
+            memo: HashMap::new(),
+            shared: None,
+            sizes: vec![],
+            inputs: HashMap::new(),
+            plans: HashMap::new(),
+            contract: None,
+            partial: None,
+            progress: None,
+            breaks: vec![],
+            broken: None,
+            audit: vec![],
+            explanation: vec![],
+            taints: HashSet::new(),
+            tainted: HashSet::new(),
+            leaks: vec![],
+            invariants: vec![],
+            tape_in: VecDeque::new(),
+            tape_out: vec![],
+            #[cfg(feature = "bigint")]
+            values: vec![],
+            #[cfg(feature = "bigint")]
+            handles: HashMap::new(),
         };
-        let mut basket = Basket::start(0, 0);
+        let mut basket = Basket::start(ROOT_OB, ROOT_BK);
         basket.kids.insert(Loc::Phi, Kid::Rqtd);
         emu.baskets[0] = basket;
         emu
     }
 
+    /// Empty all baskets, keeping the memory their kids took, and start
+    /// over with the root one, as if the `Emu` was just made of its
+    /// objects, with the tapes of `io-read` and `io-write` emptied too;
+    /// only the memoized results of atoms and the plans of locators
+    /// stay. Returns the number of baskets freed and the number of kids
+    /// the baskets still have room for.
+    pub fn recycle(&mut self) -> (usize, usize) {
+        let mut freed = 0;
+        let mut retained = 0;
+        for bsk in self.baskets.iter_mut() {
+            if !bsk.is_empty() {
+                freed += 1;
+                Self::held(&mut self.sizes, bsk.ob, bsk.kids.len());
+            }
+            bsk.clear();
+            retained += bsk.kids.capacity();
+        }
+        let root = &mut self.baskets[ROOT_BK.index()];
+        root.psi = ROOT_BK;
+        root.kids.insert(Loc::Phi, Kid::Rqtd);
+        self.events.clear();
+        self.audit.clear();
+        self.explanation.clear();
+        self.tainted.clear();
+        self.leaks.clear();
+        self.recent.clear();
+        self.checkpoints.clear();
+        self.provenance.clear();
+        self.reads.clear();
+        self.speculative.clear();
+        self.failure = None;
+        self.tape_in.clear();
+        self.tape_out.clear();
+        #[cfg(feature = "bigint")]
+        {
+            self.values.clear();
+            self.handles.clear();
+        }
+        self.partial = None;
+        (freed, retained)
+    }
+
+    /// Empty the basket, remembering how many kids it had.
+    pub(crate) fn free(&mut self, bk: Bk) {
+        let bsk = &mut self.baskets[bk.index()];
+        Self::held(&mut self.sizes, bsk.ob, bsk.kids.len());
+        bsk.clear();
+        self.speculative.remove(&bk);
+    }
+
+    fn held(sizes: &mut Vec<usize>, ob: Ob, kids: usize) {
+        if sizes.len() <= ob.index() {
+            sizes.resize(ob.index() + 1, 0);
+        }
+        sizes[ob.index()] = sizes[ob.index()].max(kids);
+    }
+
+    /// The most kids the baskets of the object held so far, in this run
+    /// or the ones before it, see `export_memo()`; new baskets of the
+    /// object are made with room for that many.
+    pub fn expected_kids(&self, ob: Ob) -> usize {
+        self.sizes.get(ob.index()).copied().unwrap_or(0)
+    }
+
+    /// Set the option, replacing the one of the same kind. An option
+    /// `try_opt()` would refuse is not set, and the next cycle fails
+    /// with the reason:
+    ///
+    /// ```
+    /// use phie::emu::{Emu, Opt};
+    /// let mut emu: Emu = "+arith checked\nν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+    /// emu.opt(Opt::Semantics(7));
+    /// assert!(emu.try_dataize().is_err());
+    /// ```
     pub fn opt(&mut self, opt: Opt) {
+        if let Err(e) = self.try_opt(opt) {
+            self.refused.get_or_insert(e);
+        }
+    }
+
+    /// Set the option, replacing the one of the same kind, unless it
+    /// changes the arithmetic the program declares, asks for a version
+    /// of semantics the emulator doesn't know, or leaves an arithmetic
+    /// the selected version doesn't know.
+    pub fn try_opt(&mut self, opt: Opt) -> Result<(), String> {
+        self.honors(&opt)?;
+        self.coheres(std::slice::from_ref(&opt))?;
+        self.replace(opt);
+        Ok(())
+    }
+
+    /// Set the option instead of the one of the same kind.
+    fn replace(&mut self, opt: Opt) {
+        self.opts
+            .retain(|o| std::mem::discriminant(o) != std::mem::discriminant(&opt));
+        self.adopt(opt);
+    }
+
+    /// Set the option, unless an option of the same kind is set already,
+    /// for example by a `+opt` line of the program; it's refused like
+    /// by `opt()`, if it can't be set.
+    pub fn opt_default(&mut self, opt: Opt) {
+        if self
+            .opts
+            .iter()
+            .any(|o| std::mem::discriminant(o) == std::mem::discriminant(&opt))
+        {
+            return;
+        }
+        match self
+            .honors(&opt)
+            .and_then(|_| self.coheres(std::slice::from_ref(&opt)))
+        {
+            Ok(()) => self.adopt(opt),
+            Err(e) => {
+                self.refused.get_or_insert(e);
+            }
+        }
+    }
+
+    /// Remember the option and make room for objects, if it asks to.
+    /// There is never less room than the objects already there take.
+    fn adopt(&mut self, opt: Opt) {
+        if let Opt::MaxObjects(n) = opt {
+            let used = self
+                .objects
+                .iter()
+                .rposition(|obj| !obj.is_empty())
+                .map_or(0, |ob| ob + 1);
+            self.objects.resize_with(n.max(used), Object::open);
+        }
+        self.dumps |= matches!(opt, Opt::CrashDumps(_));
+        if let Opt::Schedule(s) = opt {
+            self.schedule = s;
+        }
+        if let Opt::Arithmetic(a) = opt {
+            self.arith = a;
+        }
         self.opts.insert(opt);
     }
 
-    /// Add an additional object
-    pub fn put(&mut self, ob: Ob, obj: Object) -> &mut Emu {
+    /// Set the options of the environment, see `Opt::from_env()`, and
+    /// then the given ones, usually from the command line, each replacing
+    /// the option of the same kind. Together with `opt_default()` and
+    /// the `+opt` lines, this makes the order of precedence:
+    /// defaults < file < environment < command line.
+    pub fn tune(&mut self, cli: &[Opt]) -> Result<(), String> {
+        let opts: Vec<Opt> = Opt::from_env()?
+            .into_iter()
+            .chain(cli.iter().cloned())
+            .collect();
+        for opt in opts.iter() {
+            self.honors(opt)?;
+        }
+        self.coheres(&opts)?;
+        for opt in opts {
+            self.replace(opt);
+        }
+        Ok(())
+    }
+
+    /// Make sure the option doesn't break the arithmetic the program
+    /// declares with its `+arith` line, see `Program::arith()`, and
+    /// asks for a version of semantics the emulator knows.
+    fn honors(&self, opt: &Opt) -> Result<(), String> {
+        match (opt, self.contract) {
+            (Opt::Arithmetic(a), Some(c)) if *a != c => Err(format!(
+                "The program declares '+arith {}', it can't run with '{}'",
+                c, opt
+            )),
+            (Opt::Semantics(v), _) => semantics::check(*v).map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Make sure the options, set over the ones already there, leave an
+    /// arithmetic the selected version of semantics knows, only wrapping
+    /// is known to the first one, and don't keep the baskets of branches
+    /// cancelled by speculation, which would go on without end.
+    fn coheres(&self, opts: &[Opt]) -> Result<(), String> {
+        let has = |o: &Opt| opts.contains(o) || self.opts.contains(o);
+        if has(&Opt::Speculate) && has(&Opt::DontDelete) {
+            return Err(format!(
+                "The option '{}' deletes the baskets of cancelled branches, it can't run with '{}'",
+                Opt::Speculate,
+                Opt::DontDelete
+            ));
+        }
+        let version = opts
+            .iter()
+            .rev()
+            .find_map(|o| match o {
+                Opt::Semantics(v) => Some(*v),
+                _ => None,
+            })
+            .unwrap_or_else(|| self.semantics());
+        let arith = opts
+            .iter()
+            .rev()
+            .find_map(|o| match o {
+                Opt::Arithmetic(a) => Some(*a),
+                _ => None,
+            })
+            .unwrap_or(self.arith);
+        if arith == Arithmetic::Wrapping {
+            return Ok(());
+        }
+        semantics::require(version, Change::Arithmetic)
+    }
+
+    /// The version of semantics selected by `Opt::Semantics`, the latest by default.
+    pub fn semantics(&self) -> Version {
+        self.opts
+            .iter()
+            .find_map(|o| match o {
+                Opt::Semantics(v) => Some(*v),
+                _ => None,
+            })
+            .unwrap_or(semantics::LATEST)
+    }
+
+    /// Make sure the selected version of semantics has the change.
+    pub fn requires(&self, change: Change) -> Result<(), String> {
+        semantics::require(self.semantics(), change)
+    }
+
+    /// The level selected by `Opt::LogLevel`; without it, transitions are
+    /// logged, and snapshots too if `Opt::LogSnapshots` is set.
+    pub fn log_level(&self) -> EmuLog {
+        self.opts
+            .iter()
+            .find_map(|o| match o {
+                Opt::LogLevel(l) => Some(*l),
+                _ => None,
+            })
+            .unwrap_or(if self.opts.contains(&Opt::LogSnapshots) {
+                EmuLog::Snapshots
+            } else {
+                EmuLog::Transitions
+            })
+    }
+
+    /// Should something of this level be logged?
+    pub fn logs(&self, level: EmuLog) -> bool {
+        self.log_level() >= level
+    }
+
+    /// The mode selected by `Opt::FloatMode`, `FloatMode::Nearest` by default.
+    pub fn float_mode(&self) -> FloatMode {
+        self.opts
+            .iter()
+            .find_map(|o| match o {
+                Opt::FloatMode(m) => Some(*m),
+                _ => None,
+            })
+            .unwrap_or(FloatMode::Nearest)
+    }
+
+    /// The arithmetic of integer atoms selected by `Opt::Arithmetic`
+    /// or declared by the program, wrapping by default. Only wrapping
+    /// is known to the first version of semantics, the others are
+    /// refused when they are set, see `Emu::try_opt()`.
+    pub fn arithmetic(&self) -> Arithmetic {
+        self.arith
+    }
+
+    /// The scheduler selected by `Opt::Schedule`, FIFO by default.
+    pub fn scheduler(&self) -> Box<dyn Scheduler> {
+        self.schedule.scheduler()
+    }
+
+    /// The policy selected by `Opt::Schedule`, FIFO by default.
+    pub fn schedule(&self) -> Schedule {
+        self.schedule
+    }
+
+    /// Baskets keep handles of big values, see `Opt::BigData`, which
+    /// is always so for `Big` data.
+    #[cfg(feature = "bigint")]
+    pub fn big_mode(&self) -> bool {
+        D::BIG || self.opts.contains(&Opt::BigData)
+    }
+
+    /// The data of the Δ of the object, as it must be kept in a basket:
+    /// in the big mode it's a handle of its copy among the big values.
+    pub fn lift(&mut self, ob: Ob) -> Option<D> {
+        let d = self.objects[ob.index()].delta?;
+        #[cfg(feature = "rational")]
+        if let Some(den) = self.objects[ob.index()].denominator {
+            assert!(
+                self.big_mode(),
+                "The fraction in ν{} can only be dataized in the big mode",
+                ob
+            );
+            return Some(self.store(num_rational::BigRational::new(d.into(), den.into())));
+        }
+        #[cfg(feature = "bigint")]
+        if self.big_mode() {
+            return Some(self.store(num_bigint::BigInt::from(d)));
+        }
+        Some(D::of(d))
+    }
+
+    /// Is this data in a basket a TRUE, as `bool-if` understands it?
+    pub fn truth(&self, d: D) -> bool {
+        #[cfg(feature = "bigint")]
+        if self.big_mode() {
+            return *self.value(d) == crate::data::Value::Int(1.into());
+        }
+        d.data() == 1
+    }
+
+    /// The ξ of every basket, negative for empty ones, which is all
+    /// a `Scheduler` needs to know about them.
+    pub fn psis(&self) -> Vec<Bk> {
+        self.baskets.iter().map(|bsk| bsk.psi).collect()
+    }
+
+    /// Allow only the atoms of this profile to be called.
+    pub fn sandbox(&mut self, profile: Profile) {
+        self.sandbox = Some(profile);
+    }
+
+    /// The function to call for the atom of the object: in the big mode
+    /// it's its counterpart working with big integers. Fails if the
+    /// sandbox doesn't allow it.
+    pub fn atom(&self, ob: Ob, meta: &Meta<D>) -> Result<Atom<D>, Error> {
+        if self.sandbox.as_ref().is_some_and(|p| !p.allows(meta.name)) {
+            return Err(Error::AtomForbidden(meta.name.to_string(), ob));
+        }
+        #[cfg(feature = "bigint")]
+        if self.big_mode() {
+            if let Some(f) = crate::atom::big_atom(meta.name) {
+                return Ok(f);
+            }
+        }
+        Ok(meta.func)
+    }
+
+    /// Add an additional object, forgetting the plans of resolution,
+    /// which may not know it.
+    pub fn put(&mut self, ob: Ob, obj: Object) -> &mut Emu<D> {
+        assert!(
+            ob.index() < self.objects.len(),
+            "The object ν{} is beyond the limit of {} objects, see the max-objects option",
+            ob,
+            self.objects.len()
+        );
         assert!(
-            self.objects[ob].is_empty(),
+            self.objects[ob.index()].is_empty(),
             "The object ν{} already occupied",
             ob
         );
-        self.objects[ob] = obj;
+        self.objects[ob.index()] = obj;
+        self.plans.clear();
         self
     }
 
+    /// Change the Δ of the object named by an `+input` line, so that
+    /// the next `dataize()` works with it. Which baskets used the input
+    /// is not known, so all of them are emptied, except the ones of
+    /// other objects with Δ, which are linked to the root basket; what
+    /// makes the next run faster is the memoized results of atoms, kept
+    /// by the data of their arguments, see `Opt::Memoize`.
+    ///
+    /// ```
+    /// use phie::emu::Emu;
+    /// let mut emu: Emu = "+input x ν1\nν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(7, emu.dataize().0);
+    /// emu.bind("x", 42).unwrap();
+    /// assert_eq!(42, emu.dataize().0);
+    /// ```
+    pub fn bind(&mut self, name: &str, d: Data) -> Result<(), String> {
+        let ob = *self
+            .inputs
+            .get(name)
+            .ok_or_else(|| format!("There is no input '{}', see the +input lines", name))?;
+        if self.objects[ob.index()].delta.is_none() {
+            return Err(format!("The input '{}' is ν{}, which has no Δ", name, ob));
+        }
+        self.objects[ob.index()].delta = Some(d);
+        for bk in (0..self.baskets.len()).map(Bk::new) {
+            let bsk = &self.baskets[bk.index()];
+            if bsk.is_empty() {
+                continue;
+            }
+            let obj = &self.objects[bsk.ob.index()];
+            if bk == ROOT_BK || bsk.ob == ob || obj.delta.is_none() || obj.lambda.is_some() {
+                self.free(bk);
+            } else {
+                self.baskets[bk.index()].psi = ROOT_BK;
+            }
+        }
+        let root = &mut self.baskets[ROOT_BK.index()];
+        root.psi = ROOT_BK;
+        root.kids.insert(Loc::Phi, Kid::Rqtd);
+        let baskets = &self.baskets;
+        self.provenance
+            .retain(|(bk, _), _| *bk != ROOT_BK && !baskets[bk.index()].is_empty());
+        self.checkpoints.clear();
+        Ok(())
+    }
+
+    /// The objects named by `+input` lines, see `Emu::bind()`.
+    pub fn inputs(&self) -> &HashMap<String, Ob> {
+        &self.inputs
+    }
+
     /// Inject a basket
-    pub fn inject(&mut self, bk: Bk, bsk: Basket) -> &mut Emu {
+    pub fn inject(&mut self, bk: Bk, bsk: Basket<D>) -> &mut Emu<D> {
         assert!(
-            self.baskets[bk as usize].is_empty(),
+            self.baskets[bk.index()].is_empty(),
             "The basket β{} already occupied",
             bk
         );
-        self.baskets[bk as usize] = bsk;
+        self.baskets[bk.index()] = bsk;
         self
     }
 
+    /// Write all events to the stream, as they happen.
+    pub fn stream(&mut self, stream: Stream) {
+        self.stream = Some(stream);
+    }
+
+    /// The stream of events, if there is one.
+    pub(crate) fn streaming(&mut self) -> Option<&mut Stream> {
+        self.stream.as_mut()
+    }
+
+    /// Live baskets, one per line, each with the definition of its
+    /// object, as they are logged with `Opt::LogSnapshots`, unless
+    /// `Opt::CompactSnapshots` asks for them to be short:
+    ///
+    /// ```
+    /// use phie::emu::Emu;
+    /// let emu: Emu = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+    /// assert_eq!("β0 [ν0 ⟦! Δ↦0x002A⟧, ξ:β0, 𝜑→?]", emu.snapshot());
+    /// ```
+    pub fn snapshot(&self) -> String {
+        #[cfg(feature = "compact")]
+        if self.opts.contains(&Opt::CompactSnapshots) {
+            return self.compact_snapshot();
+        }
+        self.baskets
+            .iter()
+            .enumerate()
+            .filter(|(_, bsk)| !bsk.is_empty())
+            .map(|(bk, bsk)| format!("β{} {}", bk, bsk.display_of(Some(self.object(bsk.ob)))))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Recorded events that pass the filter, in the order they happened.
+    pub fn events(&self, filter: EventFilter) -> impl Iterator<Item = &Event> {
+        self.events.iter().filter(move |e| filter.accepts(e))
+    }
+
+    /// The latest events, the oldest first, kept only when
+    /// `Opt::CrashDumps` is set, see `crash::RECENT`.
+    pub fn recent(&self) -> impl Iterator<Item = &Event> {
+        self.recent.iter()
+    }
+
+    /// The directory set by `Opt::CrashDumps`, if any.
+    pub fn crash_dumps(&self) -> Option<&Path> {
+        self.opts.iter().find_map(|o| match o {
+            Opt::CrashDumps(dir) => Some(dir.as_path()),
+            _ => None,
+        })
+    }
+
+    /// Stop when a kid is dataized and the condition holds, see
+    /// `take_break()`. Returns the position of the condition.
+    pub fn break_when(&mut self, cond: Condition) -> usize {
+        self.breaks.push(cond);
+        self.breaks.len() - 1
+    }
+
+    /// Forget all conditions of breakpoints and the break not taken yet.
+    pub fn clear_breaks(&mut self) {
+        self.breaks.clear();
+        self.broken = None;
+    }
+
+    /// The first condition that held since the previous call, if any.
+    pub fn take_break(&mut self) -> Option<Break> {
+        self.broken.take()
+    }
+
+    /// Make sure every basket of the object the locator points to, like
+    /// `ν5`, is dataized to the data that satisfy the predicate, checking
+    /// them after each cycle, or as often as `Opt::InvariantsEvery` says.
+    /// A violation stops dataization with the snapshot of the baskets:
+    ///
+    /// ```
+    /// use phie::emu::Emu;
+    /// let mut emu: Emu = "
+    ///   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+    ///   ν1(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν2(𝜋) ⟧
+    ///   ν2(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+    /// ".parse().unwrap();
+    /// emu.assert_invariant("ν1", |d| d >= 0).unwrap();
+    /// let err = emu.try_dataize().err().unwrap().to_string();
+    /// assert!(err.starts_with("The invariant #0 of ν1 is violated"), "{}", err);
+    /// ```
+    ///
+    /// Returns the position of the invariant, in the order they were set.
+    pub fn assert_invariant(
+        &mut self,
+        locator: &str,
+        predicate: impl Fn(Data) -> bool + Send + 'static,
+    ) -> Result<usize, String> {
+        let ob = match Locator::from_str(locator)?.locs() {
+            [Loc::Obj(ob)]
+                if ob.index() < self.objects.len() && !self.objects[ob.index()].is_empty() =>
+            {
+                *ob
+            }
+            [Loc::Obj(ob)] => return Err(format!("There is no ν{} in the program", ob)),
+            _ => {
+                return Err(format!(
+                    "The locator '{}' of an invariant must point to an object, like ν5",
+                    locator
+                ))
+            }
+        };
+        self.invariants.push((ob, Box::new(predicate)));
+        Ok(self.invariants.len() - 1)
+    }
+
+    /// Check the invariants, if it's time to, see `Emu::assert_invariant()`.
+    pub(crate) fn verify(&self, cycle: usize) -> Result<(), Error> {
+        if self.invariants.is_empty() {
+            return Ok(());
+        }
+        let every = self
+            .opts
+            .iter()
+            .find_map(|o| match o {
+                Opt::InvariantsEvery(n) => Some(*n),
+                _ => None,
+            })
+            .unwrap_or(1);
+        if !cycle.is_multiple_of(every) {
+            return Ok(());
+        }
+        for (i, (ob, holds)) in self.invariants.iter().enumerate() {
+            for (bk, bsk) in self.baskets.iter().enumerate() {
+                if bsk.is_empty() || bsk.ob != *ob {
+                    continue;
+                }
+                if let Some(d) = bsk.kids.get(&Loc::Phi).and_then(Kid::data) {
+                    if !holds(d.data()) {
+                        return Err(Error::Failed(format!(
+                            "The invariant #{} of ν{} is violated by β{}, dataized to {}, in the cycle #{}:\n{}",
+                            i, ob, bk, d, cycle, self
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Load the input tape, which `io-read` takes the data from, one
+    /// by one, instead of a real console, so that a program that reads
+    /// and writes is dataized the same way every time:
+    ///
+    /// ```
+    /// use phie::emu::Emu;
+    /// let mut emu: Emu = "
+    ///   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+    ///   ν1(𝜋) ↦ ⟦ λ ↦ io-write, ρ ↦ ν2(𝜋) ⟧
+    ///   ν2(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν3(𝜋) ⟧
+    ///   ν3(𝜋) ↦ ⟦ λ ↦ io-read ⟧
+    /// ".parse().unwrap();
+    /// emu.set_input_tape([7, 8]);
+    /// assert_eq!(-7, emu.dataize().0);
+    /// assert_eq!(&[-7], emu.output_tape());
+    /// ```
+    pub fn set_input_tape(&mut self, data: impl IntoIterator<Item = Data>) {
+        self.tape_in = data.into_iter().collect();
+    }
+
+    /// The data on the input tape, not read yet.
+    pub fn input_tape(&self) -> impl Iterator<Item = &Data> {
+        self.tape_in.iter()
+    }
+
+    /// The data written by `io-write` so far, the first one first.
+    pub fn output_tape(&self) -> &[Data] {
+        &self.tape_out
+    }
+
+    /// All reads of the attributes watched by `Opt::AuditLocs`, in the
+    /// order they were made:
+    ///
+    /// ```
+    /// use phie::emu::{Emu, Opt};
+    /// use phie::loc::Loc;
+    /// let mut emu: Emu = "
+    ///   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+    ///   ν1(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν2(𝜋) ⟧
+    ///   ν2(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+    /// ".parse().unwrap();
+    /// emu.opt(Opt::AuditLocs(vec![Loc::Delta, Loc::Rho]));
+    /// emu.dataize();
+    /// let reads: Vec<String> = emu.audit().iter().map(|a| a.to_string()).collect();
+    /// assert_eq!(vec!["β2.Δ of ν2 read by β2: 0x0007", "β1.ρ of ν1 read by β1: 0x0007"], reads);
+    /// ```
+    pub fn audit(&self) -> &[Access] {
+        &self.audit
+    }
+
+    /// The transitions made so far, one sentence each, in the order they
+    /// were made, if `Opt::Explain` is set:
+    ///
+    /// ```
+    /// use phie::emu::{Emu, Opt};
+    /// let mut emu: Emu = "
+    ///   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+    ///   ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+    /// ".parse().unwrap();
+    /// emu.opt(Opt::Explain);
+    /// emu.dataize();
+    /// assert_eq!(
+    ///     "β0 finds 𝜑 of ν0 at ν1, which is to be copied with ψ=β0",
+    ///     emu.explanation()[0]
+    /// );
+    /// ```
+    pub fn explanation(&self) -> &[String] {
+        &self.explanation
+    }
+
+    /// Write down the read, if `Opt::AuditLocs` watches the attribute.
+    pub(crate) fn audited(&mut self, bk: Bk, loc: &Loc, reader: Bk, data: Data) {
+        let watched = self.opts.iter().any(|o| match o {
+            Opt::AuditLocs(locs) => locs.contains(loc),
+            _ => false,
+        });
+        if watched {
+            let ob = self.basket(bk).ob;
+            self.audit.push(Access {
+                bk,
+                ob,
+                loc: loc.clone(),
+                reader,
+                data,
+            });
+        }
+    }
+
+    /// Mark the Δ of the object as tainted, so that everything made of
+    /// it is tainted too: the kids it's copied to and the results of
+    /// atoms that have a tainted argument. A tainted result, or tainted
+    /// data written by `io-write`, is reported as a warning:
+    ///
+    /// ```
+    /// use phie::emu::{Emu, ROOT_BK};
+    /// use phie::object::Ob;
+    /// let mut emu: Emu = "
+    ///   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+    ///   ν1(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧
+    ///   ν2(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+    ///   ν3(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧
+    /// ".parse().unwrap();
+    /// emu.taint(Ob::new(3));
+    /// assert_eq!(8, emu.dataize().0);
+    /// assert!(emu.is_tainted(ROOT_BK, &phie::loc::Loc::Phi));
+    /// ```
+    ///
+    /// Nothing is tracked until some object is tainted.
+    pub fn taint(&mut self, ob: Ob) {
+        self.taints.insert(ob);
+    }
+
+    /// Is the data of the kid of the basket tainted, see `Emu::taint()`?
+    pub fn is_tainted(&self, bk: Bk, loc: &Loc) -> bool {
+        !self.tainted.is_empty() && self.tainted.contains(&(bk, loc.clone()))
+    }
+
+    /// The tainted data written by `io-write`, with the baskets that
+    /// wrote them, in the order they were written.
+    pub fn leaks(&self) -> &[(Bk, Data)] {
+        &self.leaks
+    }
+
+    /// The `io-write` of the basket is writing the data, which may be tainted.
+    pub(crate) fn leak(&mut self, bk: Bk, d: Data) {
+        if self.is_tainted(bk, &Loc::Rho) {
+            warn!("The tainted data 0x{:04X} is written by β{}", d, bk);
+            self.leaks.push((bk, d));
+        }
+    }
+
+    /// The kid got its data from the Δ of its object, from another kid,
+    /// or from the atom of its basket, see `Emu::taint()`.
+    pub(crate) fn spread_taint(&mut self, bk: Bk, loc: &Loc, from: Taint) {
+        if self.taints.is_empty() {
+            return;
+        }
+        let tainted = match from {
+            Taint::Delta => self.taints.contains(&self.basket(bk).ob),
+            Taint::Kid(b, l) => self.tainted.contains(&(b, l)),
+            Taint::Args(args) => args
+                .iter()
+                .any(|(l, _)| self.tainted.contains(&(bk, l.clone()))),
+        };
+        if tainted {
+            self.tainted.insert((bk, loc.clone()));
+        }
+    }
+
+    /// Checkpoints taken so far, the oldest first, see `Opt::CheckpointEvery`.
+    pub fn checkpoints(&self) -> impl Iterator<Item = &Checkpoint> {
+        self.checkpoints.iter()
+    }
+
+    /// Call this function with the `Progress` of dataization every N
+    /// cycles, when `Opt::Progress(N)` is set:
+    ///
+    /// ```
+    /// use phie::emu::{Emu, Opt};
+    /// use std::sync::{Arc, Mutex};
+    /// let seen = Arc::new(Mutex::new(vec![]));
+    /// let mut emu: Emu = "
+    ///   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+    ///   ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+    ///   ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧
+    /// ".parse().unwrap();
+    /// emu.opt(Opt::Progress(2));
+    /// let log = seen.clone();
+    /// emu.on_progress(move |p| log.lock().unwrap().push(p.cycles));
+    /// emu.dataize();
+    /// assert_eq!(vec![2, 4], *seen.lock().unwrap());
+    /// ```
+    pub fn on_progress(&mut self, f: impl FnMut(&Progress) + Send + 'static) {
+        self.progress = Some(Box::new(f));
+    }
+
+    /// Report the progress, if it's time for it.
+    pub(crate) fn report(&mut self, cycle: usize) {
+        let every = self.opts.iter().find_map(|o| match o {
+            Opt::Progress(n) => Some(*n),
+            _ => None,
+        });
+        if !every.is_some_and(|n| cycle.is_multiple_of(n)) {
+            return;
+        }
+        if let Some(f) = self.progress.as_mut() {
+            let live = self.baskets.iter().filter(|bsk| !bsk.is_empty());
+            f(&Progress {
+                cycles: cycle,
+                baskets: live.clone().count(),
+                dtzd: live
+                    .flat_map(|bsk| bsk.kids.values())
+                    .filter(|k| k.data().is_some())
+                    .count(),
+            });
+        }
+    }
+
+    /// Take a checkpoint, if it's time for it.
+    pub(crate) fn save(&mut self, cycle: usize) {
+        let every = self.opts.iter().find_map(|o| match o {
+            Opt::CheckpointEvery(n) => Some(*n),
+            _ => None,
+        });
+        if every.is_some_and(|n| cycle.is_multiple_of(n)) {
+            if self.checkpoints.len() == MAX_CHECKPOINTS {
+                self.checkpoints.pop_front();
+            }
+            self.checkpoints
+                .push_back(Checkpoint::take(cycle, &self.baskets));
+        }
+    }
+
+    /// Put the baskets of the checkpoint in place of the current ones.
+    /// Provenance of the values, if tracked, is lost.
+    pub fn restore(&mut self, cp: &Checkpoint) -> Result<(), String> {
+        let mut baskets = cp.baskets()?;
+        if let Some((bk, _)) = baskets
+            .iter()
+            .find(|(bk, _)| bk.is_none() || bk.index() >= MAX_BASKETS)
+        {
+            return Err(format!("There is no room for β{} in the Emu", bk));
+        }
+        for bsk in self.baskets.iter_mut() {
+            *bsk = Basket::empty();
+        }
+        for (bk, bsk) in baskets.drain(..) {
+            self.baskets[bk.index()] = bsk;
+        }
+        self.provenance.clear();
+        Ok(())
+    }
+
+    /// Go back to the latest checkpoint taken at this cycle or before it,
+    /// forgetting the ones after it, and return the cycle it was taken at.
+    pub fn rewind(&mut self, cycle: usize) -> Option<usize> {
+        while self.checkpoints.back().is_some_and(|cp| cp.cycle > cycle) {
+            self.checkpoints.pop_back();
+        }
+        let cp = self.checkpoints.back()?.clone();
+        self.restore(&cp).ok()?;
+        Some(cp.cycle)
+    }
+
+    /// Where the result of dataization came from, if it's ready
+    /// and `Opt::TrackProvenance` or `Opt::StrictTypes` is set.
+    pub fn provenance(&self) -> Option<&Provenance> {
+        self.provenance
+            .get(&(ROOT_BK, Loc::Phi))
+            .map(|p| p.as_ref())
+    }
+
+    fn tracks(&self) -> bool {
+        self.opts.contains(&Opt::TrackProvenance) || self.opts.contains(&Opt::StrictTypes)
+    }
+
+    /// Make sure the atom of the basket gets the data of the type it
+    /// expects at this attribute, see `Error::TypeMismatch`.
+    fn check_type(&self, bk: Bk, loc: &Loc) -> Result<(), Error> {
+        let ob = self.basket(bk).ob;
+        let Some(m) = self.object(ob).atom() else {
+            return Ok(());
+        };
+        let expected = m.args.iter().find(|(l, _)| l == loc).and_then(|(_, t)| *t);
+        match (expected, self.kind_of(bk, loc)) {
+            (Some(expected), Some(found)) if expected != found => Err(Error::TypeMismatch {
+                atom: m.name.to_string(),
+                object: ob,
+                loc: loc.clone(),
+                expected,
+                found,
+                stack: self.call_stack(ob),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// The baskets from the one of the object, which is being dataized,
+    /// up to the root through their ξ, deepest first, like a call stack.
+    pub fn call_stack(&self, ob: Ob) -> Vec<(Bk, Ob)> {
+        let mut stack = vec![];
+        let mut bk = self
+            .baskets
+            .iter()
+            .position(|b| !b.is_empty() && b.ob == ob && b.kids.get(&Loc::Phi) == Some(&Kid::Rqtd))
+            .map_or(Bk::NONE, Bk::new);
+        while !bk.is_none() && stack.len() < self.baskets.len() {
+            let bsk = self.basket(bk);
+            stack.push((bk, bsk.ob));
+            if bk == ROOT_BK {
+                break;
+            }
+            bk = bsk.psi;
+        }
+        stack
+    }
+
+    /// The type of the dataized kid, as its provenance tells, if it's known.
+    pub fn kind_of(&self, bk: Bk, loc: &Loc) -> Option<Type> {
+        self.provenance.get(&(bk, loc.clone())).map(|p| p.kind())
+    }
+
+    /// Remember where the value of the kid came from.
+    pub(crate) fn trace_origin(&mut self, bk: Bk, loc: Loc, origin: Origin, value: Data) {
+        if self.tracks() {
+            let inputs = match origin {
+                Origin::Atom(_) => std::mem::take(&mut self.reads),
+                Origin::Delta => vec![],
+            };
+            let prov = Provenance {
+                value,
+                ob: self.basket(bk).ob,
+                origin,
+                inputs,
+            };
+            self.provenance.insert((bk, loc), Arc::new(prov));
+        }
+    }
+
+    /// The value of the kid was copied from another kid, as is.
+    pub(crate) fn copy_origin(&mut self, from: (Bk, Loc), to: (Bk, Loc)) {
+        if let Some(p) = self.provenance.get(&from).cloned() {
+            self.provenance.insert(to, p);
+        }
+    }
+
+    /// The basket is gone, together with all its values.
+    pub(crate) fn drop_origins(&mut self, bk: Bk) {
+        if self.tracks() {
+            self.provenance.retain(|(b, _), _| *b != bk);
+        }
+    }
+
+    /// The atom of the basket with the data of all its arguments, if
+    /// they are ready and its result may be remembered, see `Opt::Memoize`
+    /// and the `%` and `~` markers of objects.
+    pub(crate) fn memo_key(&self, bk: Bk, meta: &Meta<D>) -> Option<MemoKey> {
+        let bsk = self.basket(bk);
+        let wanted = self
+            .object(bsk.ob)
+            .memo
+            .unwrap_or(self.opts.contains(&Opt::Memoize));
+        if !wanted || !meta.pure {
+            return None;
+        }
+        #[cfg(feature = "bigint")]
+        if self.big_mode() {
+            return None;
+        }
+        let args = meta
+            .args
+            .iter()
+            .map(|(loc, _)| bsk.kids.get(loc).and_then(|k| k.data()).map(D::data))
+            .collect::<Option<Vec<Data>>>()?;
+        Some((self.modes(), meta.name.to_string(), args))
+    }
+
+    /// The arithmetic and float mode atoms run with now.
+    fn modes(&self) -> (Arithmetic, FloatMode) {
+        (self.arithmetic(), self.float_mode())
+    }
+
+    /// The result of the atom for these data, if it's already known.
+    pub(crate) fn recall(&self, key: &MemoKey) -> Option<D> {
+        self.memo
+            .get(key)
+            .copied()
+            .or_else(|| self.shared.as_ref()?.read().ok()?.get(key).copied())
+            .map(D::of)
+    }
+
+    /// Remember the result of the atom for these data, here and in
+    /// the shared memo, if there is one.
+    pub(crate) fn memorize(&mut self, key: MemoKey, d: D) {
+        if let Some(mut shared) = self.shared.as_ref().and_then(|s| s.write().ok()) {
+            shared.insert(key.clone(), d.data());
+        }
+        self.memo.insert(key, d.data());
+    }
+
+    /// Read the results of atoms memoized by other emulators, and add
+    /// the ones memoized here, through the memo they all share, see
+    /// `Program::scope()`. Emulators of other arithmetic or float mode
+    /// don't see each other's results, since they are kept by the modes.
+    pub fn share_memo(&mut self, memo: SharedMemo) {
+        self.shared = Some(memo);
+    }
+
+    /// A hash of all objects and inputs, see `Program::fingerprint()`.
+    pub fn fingerprint(&self) -> u64 {
+        crate::program::fingerprint(
+            self.objects
+                .iter()
+                .enumerate()
+                .map(|(ob, obj)| (Ob::new(ob), obj)),
+            self.inputs.iter(),
+        )
+    }
+
+    /// The memoized results of atoms as JSON, to be imported by
+    /// `import_memo()` in the next run of the same program, together
+    /// with the number of kids the baskets of each object held, see
+    /// `expected_kids()`. Only the results of the current arithmetic and
+    /// float mode are exported:
+    ///
+    /// ```
+    /// use phie::emu::{Emu, Opt};
+    /// let text = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν2(𝜋) ⟧\nν2(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧";
+    /// let mut emu: Emu = text.parse().unwrap();
+    /// emu.opt(Opt::Memoize);
+    /// emu.dataize();
+    /// let mut next: Emu = text.parse().unwrap();
+    /// next.opt(Opt::Memoize);
+    /// assert_eq!(Ok(1), next.import_memo(&emu.export_memo()));
+    /// assert_eq!(1, next.dataize().1.memo_hits);
+    /// ```
+    pub fn export_memo(&self) -> String {
+        let modes = self.modes();
+        let mut results: Vec<_> = self
+            .memo
+            .iter()
+            .filter(|((m, _, _), _)| *m == modes)
+            .map(|((_, name, args), d)| json!([name, args, d]))
+            .collect();
+        results.sort_by_key(|r| r.to_string());
+        let sizes: Vec<_> = self
+            .sizes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| **n > 0)
+            .map(|(ob, n)| json!([ob, n]))
+            .collect();
+        json!({
+            "program": format!("{:016x}", self.fingerprint()),
+            "arithmetic": modes.0.to_string(),
+            "float-mode": float_mode_name(modes.1),
+            "results": results,
+            "sizes": sizes
+        })
+        .to_string()
+    }
+
+    /// Take the memoized results of atoms exported by `export_memo()`,
+    /// returning how many of them there are, unless they were exported
+    /// for another program or with another arithmetic or float mode.
+    pub fn import_memo(&mut self, text: &str) -> Result<usize, String> {
+        let json: serde_json::Value =
+            serde_json::from_str(text).map_err(|e| format!("Can't parse the memo: {}", e))?;
+        if json["program"] != format!("{:016x}", self.fingerprint()) {
+            return Err("The memo was made for another program".to_string());
+        }
+        let modes = self.modes();
+        if json["arithmetic"] != modes.0.to_string()
+            || json["float-mode"] != float_mode_name(modes.1)
+        {
+            return Err(format!(
+                "The memo was made with another arithmetic or float mode, not {} and {}",
+                modes.0,
+                float_mode_name(modes.1)
+            ));
+        }
+        let results: Vec<(String, Vec<Data>, Data)> =
+            serde_json::from_value(json["results"].clone())
+                .map_err(|e| format!("Can't parse the results in the memo: {}", e))?;
+        let sizes: Vec<(usize, usize)> = match json.get("sizes") {
+            Some(s) => serde_json::from_value(s.clone())
+                .map_err(|e| format!("Can't parse the sizes in the memo: {}", e))?,
+            None => vec![],
+        };
+        let count = results.len();
+        for (name, args, d) in results {
+            self.memo.insert((modes, name, args), d);
+        }
+        for (ob, n) in sizes {
+            Self::held(&mut self.sizes, Ob::new(ob), n);
+        }
+        Ok(count)
+    }
+
+    /// Start collecting the values read by an atom.
+    pub(crate) fn reset_reads(&mut self) {
+        self.reads.clear();
+    }
+
+    /// Count a hit of the transition and record it as an event,
+    /// if `Opt::RecordEvents` is set.
+    pub fn record(&mut self, perf: &mut Perf, kind: Transition, bk: Bk, loc: Option<Loc>) {
+        perf.hit(kind);
+        if self.opts.contains(&Opt::Explain) {
+            let sentence = self.explain(kind, bk, loc.as_ref());
+            self.explanation.push(sentence);
+        }
+        let dumps = self.dumps;
+        if self.opts.contains(&Opt::RecordEvents) || self.stream.is_some() || dumps {
+            let event = Event {
+                cycle: perf.cycles,
+                kind,
+                bk,
+                ob: self.basket(bk).ob,
+                loc,
+            };
+            if let Some(s) = self.stream.as_mut() {
+                s.transition(&event);
+            }
+            if dumps {
+                if self.recent.len() == crate::crash::RECENT {
+                    self.recent.pop_front();
+                }
+                self.recent.push_back(event.clone());
+            }
+            if self.opts.contains(&Opt::RecordEvents) {
+                self.events.push(event);
+            }
+        }
+    }
+
     /// Read data if available.
-    pub fn read(&mut self, bk: Bk, loc: Loc) -> Option<Data> {
+    pub fn read(&mut self, bk: Bk, loc: Loc) -> Option<D> {
         match self.basket(bk).kids.get(&loc) {
-            None => panic!("Can't find {} in β{}:\n{}", loc, bk, self),
+            None => match self.object(self.basket(bk).ob).atom() {
+                Some(m) => panic!(
+                    "The atom '{}' of ν{} needs {}, which is absent in β{}:\n{}",
+                    m.name,
+                    self.basket(bk).ob,
+                    loc,
+                    bk,
+                    self
+                ),
+                None => panic!("Can't find {} in β{}:\n{}", loc, bk, self),
+            },
             Some(Kid::Empt) => {
-                let _ = &self.baskets[bk as usize]
-                    .kids
-                    .insert(loc.clone(), Kid::Rqtd);
-                trace!("read(β{}, {}): was empty, requested", bk, loc);
+                let _ = &self.baskets[bk.index()].kids.insert(loc.clone(), Kid::Rqtd);
+                if self.logs(EmuLog::Everything) {
+                    trace!("read(β{}, {}): was empty, requested", bk, loc);
+                }
                 None
             }
             Some(Kid::Need(_, _)) | Some(Kid::Wait(_, _)) | Some(Kid::Rqtd) => None,
-            Some(Kid::Dtzd(d)) => Some(*d),
+            Some(Kid::Dtzd(d)) => {
+                let d = *d;
+                self.audited(bk, &loc, bk, d.data());
+                if self.opts.contains(&Opt::StrictTypes) {
+                    if let Err(e) = self.check_type(bk, &loc) {
+                        self.failure = Some(e);
+                        return None;
+                    }
+                }
+                if !self.provenance.is_empty() {
+                    if let Some(p) = self.provenance.get(&(bk, loc)) {
+                        self.reads.push(p.clone());
+                    }
+                }
+                Some(d)
+            }
         }
     }
 }
@@ -0,0 +1,162 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+use crate::data::{Data, Datum, Value};
+use crate::emu::{Emu, Opt};
+use crate::perf::Perf;
+use num_bigint::BigInt;
+#[cfg(feature = "rational")]
+use num_rational::BigRational;
+
+impl<D: Datum> Emu<D> {
+    /// Dataize the first object in the big mode, where all integers are
+    /// unbounded: baskets keep handles of big integers instead of the
+    /// integers themselves, and the integer atoms work with them.
+    ///
+    /// ```
+    /// use phie::emu::Emu;
+    /// use num_bigint::BigInt;
+    /// use std::str::FromStr;
+    /// let mut emu = Emu::from_str("
+    ///   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2 ⟧
+    ///   ν1(𝜋) ↦ ⟦ Δ ↦ 0x7FFF ⟧
+    ///   ν2(𝜋) ↦ ⟦ λ ↦ int-times, ρ ↦ ν1, 𝛼0 ↦ ν1 ⟧
+    /// ").unwrap();
+    /// assert_eq!(BigInt::from(32767 * 32767), emu.dataize_big().0);
+    /// ```
+    pub fn dataize_big(&mut self) -> (BigInt, Perf) {
+        let (v, perf) = self.dataize_value();
+        match v {
+            Value::Int(i) => (i, perf),
+            #[allow(unreachable_patterns)]
+            other => panic!("The result {} is not an integer", other),
+        }
+    }
+
+    /// Dataize the first object in the big mode, returning
+    /// the value of whatever kind it is.
+    pub fn dataize_value(&mut self) -> (Value, Perf) {
+        self.opt(Opt::BigData);
+        let (d, perf) = self.dataize();
+        (self.value(d).clone(), perf)
+    }
+
+    /// Keep the value and return its handle, the one it already has,
+    /// if an equal value is kept. All values are forgotten when the
+    /// baskets are recycled, see `Emu::recycle()`.
+    pub fn store(&mut self, v: impl Into<Value>) -> D {
+        let v = v.into();
+        if let Some(handle) = self.handles.get(&v) {
+            return D::of(*handle);
+        }
+        let handle = self.values.len() as Data;
+        assert!(
+            D::of(handle).data() == handle,
+            "No more room for big values, {} already stored",
+            self.values.len()
+        );
+        self.handles.insert(v.clone(), handle);
+        self.values.push(v);
+        D::of(handle)
+    }
+
+    /// The value behind the handle.
+    pub fn value(&self, d: D) -> &Value {
+        self.values
+            .get(d.data() as usize)
+            .unwrap_or_else(|| panic!("There is no big value #{}", d))
+    }
+
+    /// The big integer behind the handle.
+    pub fn big(&self, d: D) -> &BigInt {
+        match self.value(d) {
+            Value::Int(v) => v,
+            #[allow(unreachable_patterns)]
+            other => panic!("The value #{} is not an integer: {}", d, other),
+        }
+    }
+
+    /// The fraction behind the handle, which may be an integer too.
+    #[cfg(feature = "rational")]
+    pub fn ratio(&self, d: D) -> BigRational {
+        match self.value(d) {
+            Value::Int(v) => BigRational::from_integer(v.clone()),
+            Value::Rat(v) => v.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn multiplies_without_overflow() {
+    let mut emu = Emu::from_str(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν3 ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x7FFF ⟧
+        ν2(𝜋) ↦ ⟦ λ ↦ int-times, ρ ↦ ν1, 𝛼0 ↦ ν1 ⟧
+        ν3(𝜋) ↦ ⟦ λ ↦ int-times, ρ ↦ ν2, 𝛼0 ↦ ν1 ⟧
+        ",
+    )
+    .unwrap();
+    let expected = BigInt::from(32767) * BigInt::from(32767) * BigInt::from(32767);
+    assert_eq!(expected, emu.dataize_big().0);
+}
+
+#[test]
+fn compares_and_branches() {
+    let mut emu = Emu::from_str(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν4 ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+        ν2(𝜋) ↦ ⟦ λ ↦ int-less, ρ ↦ ν1, 𝛼0 ↦ ν3 ⟧
+        ν3(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+        ν4(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ ν2, 𝛼0 ↦ ν3, 𝛼1 ↦ ν1 ⟧
+        ",
+    )
+    .unwrap();
+    assert_eq!(BigInt::from(42), emu.dataize_big().0);
+}
+
+#[test]
+fn keeps_equal_values_once() {
+    let mut emu = Emu::from_str("ν0(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧").unwrap();
+    let first = emu.store(BigInt::from(7));
+    assert_eq!(first, emu.store(BigInt::from(7)));
+    assert_ne!(first, emu.store(BigInt::from(8)));
+    emu.recycle();
+    assert_eq!(0, emu.store(BigInt::from(8)));
+}
+
+#[cfg(feature = "rational")]
+#[test]
+fn adds_and_multiplies_fractions() {
+    let mut emu = Emu::from_str(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν4 ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 1/3 ⟧
+        ν2(𝜋) ↦ ⟦ Δ ↦ 1/6 ⟧
+        ν3(𝜋) ↦ ⟦ λ ↦ rat-add, ρ ↦ ν1, 𝛼0 ↦ ν2 ⟧
+        ν4(𝜋) ↦ ⟦ λ ↦ rat-mul, ρ ↦ ν3, 𝛼0 ↦ ν5 ⟧
+        ν5(𝜋) ↦ ⟦ Δ ↦ 0x0003 ⟧
+        ",
+    )
+    .unwrap();
+    assert_eq!("3/2", emu.dataize_value().0.to_string());
+}
+
+#[cfg(feature = "rational")]
+#[test]
+fn compares_fractions() {
+    let mut emu = Emu::from_str(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν3 ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 2/7 ⟧
+        ν2(𝜋) ↦ ⟦ Δ ↦ 1/4 ⟧
+        ν3(𝜋) ↦ ⟦ λ ↦ rat-cmp, ρ ↦ ν1, 𝛼0 ↦ ν2 ⟧
+        ",
+    )
+    .unwrap();
+    assert_eq!(BigInt::from(1), emu.dataize_big().0);
+}
@@ -2,87 +2,335 @@
 // SPDX-License-Identifier: MIT
 
 use crate::basket::{Bk, Kid};
+use crate::crash::Dump;
+#[cfg(feature = "parallel")]
 use crate::data::Data;
-use crate::emu::{Emu, Opt, ROOT_BK};
+use crate::data::Datum;
+use crate::emu::{Bindings, Emu, EmuLog, Opt, MAX_BASKETS, MAX_HOPS, ROOT_BK};
+use crate::error::{message, Error};
 use crate::loc::Loc;
+use crate::partial::{Outcome, Partial};
 use crate::perf::Perf;
-use log::debug;
+#[cfg(feature = "parallel")]
+use crate::program::Program;
+use crate::scheduler::Schedule;
+use log::{debug, error, warn};
+use std::panic;
+use std::path::Path;
 use std::time::Instant;
 
 const MAX_CYCLES: usize = 65536;
 
-impl Emu {
+impl<D: Datum> Emu<D> {
     /// Dataize the first object.
-    pub fn dataize(&mut self) -> (Data, Perf) {
-        let mut cycles = 0;
+    pub fn dataize(&mut self) -> (D, Perf) {
         let mut perf = Perf::new();
+        let d = self.dataize_with(&mut perf);
+        (d, perf)
+    }
+
+    /// Dataize the first object, adding to the numbers of the `Perf`,
+    /// which may be a `Scope` of it:
+    ///
+    /// ```
+    /// use phie::emu::Emu;
+    /// use phie::perf::Perf;
+    /// let mut emu: Emu = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+    /// let mut perf = Perf::new();
+    /// assert_eq!(42, emu.dataize_with(&mut perf.scope("first")));
+    /// assert_eq!(1, perf.sections[0].cycles);
+    /// ```
+    pub fn dataize_with(&mut self, perf: &mut Perf) -> D {
+        self.dataize_dumped(perf)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Dataize the first object, writing a crash dump if it fails,
+    /// see `Opt::CrashDumps`.
+    fn dataize_dumped(&mut self, perf: &mut Perf) -> Result<D, Error> {
+        let Some(dir) = self.crash_dumps().map(|d| d.to_path_buf()) else {
+            return self.dataize_measured(perf);
+        };
+        match panic::catch_unwind(panic::AssertUnwindSafe(|| self.dataize_measured(perf))) {
+            Ok(Ok(d)) => Ok(d),
+            Ok(Err(e)) => {
+                self.dump(&dir, &e.to_string(), perf.cycles);
+                Err(e)
+            }
+            Err(e) => {
+                self.dump(&dir, &message(e.as_ref()), perf.cycles);
+                panic::resume_unwind(e)
+            }
+        }
+    }
+
+    /// Write down the crash dump of the failure into the directory.
+    fn dump(&self, dir: &Path, msg: &str, cycles: usize) {
+        match Dump::of(self, msg, cycles).write(dir) {
+            Ok(path) => error!("The crash dump is in '{}'", path.display()),
+            Err(err) => error!("{}", err),
+        }
+    }
+
+    fn dataize_measured(&mut self, perf: &mut Perf) -> Result<D, Error> {
+        #[cfg(feature = "heap")]
+        {
+            let (d, bytes) = crate::heap::account("emulation", || self.dataize_cycles(perf));
+            perf.allocated += bytes;
+            d
+        }
+        #[cfg(not(feature = "heap"))]
+        self.dataize_cycles(perf)
+    }
+
+    fn dataize_cycles(&mut self, perf: &mut Perf) -> Result<D, Error> {
+        let mut cycles = 0;
+        let mut halfway = None;
         let time = Instant::now();
         loop {
-            let before = perf.total_hits();
-            self.cycle(&mut perf);
-            perf.peak(self.baskets.iter().filter(|bsk| !bsk.is_empty()).count());
-            if self.opts.contains(&Opt::LogSnapshots) {
-                debug!(
-                    "dataize() +{} hits in cycle #{}:\n{}",
-                    perf.total_hits() - before,
-                    cycles,
-                    self
-                );
+            match self.step_checked(perf, cycles) {
+                Ok(Some(d)) => {
+                    if self.logs(EmuLog::Transitions) {
+                        debug!(
+                            "dataize() -> 0x{:04X} in {:?}\n{}\n{}",
+                            d,
+                            time.elapsed(),
+                            perf,
+                            self
+                        );
+                    } else {
+                        debug!("dataize() -> 0x{:04X} in {:?}", d, time.elapsed());
+                    }
+                    return Ok(d);
+                }
+                Ok(None) => {}
+                Err(e @ Error::Cycles(_)) => {
+                    self.partial = Some(Partial::take(cycles + 1, &self.baskets, halfway));
+                    return Err(e);
+                }
+                Err(e) => return Err(e),
+            }
+            cycles += 1;
+            if self.max_cycles().is_some_and(|max| cycles == max / 2) {
+                halfway = Some(Partial::pending(&self.baskets));
             }
-            if self.opts.contains(&Opt::StopWhenStuck) && before == perf.total_hits() {
+        }
+    }
+
+    /// Run one cycle, like `step()` does, after this many cycles of the
+    /// dataization, and fail if it makes no hits, while `Opt::StopWhenStuck`
+    /// is set, or if it's one too many, see `Opt::MaxCycles`.
+    pub(crate) fn step_checked(
+        &mut self,
+        perf: &mut Perf,
+        cycles: usize,
+    ) -> Result<Option<D>, Error> {
+        let before = perf.total_hits();
+        let result = self.step(perf)?;
+        if self.logs(EmuLog::Snapshots) {
+            debug!(
+                "dataize() +{} hits in cycle #{}:\n{}",
+                perf.total_hits() - before,
+                cycles,
+                self.snapshot()
+            );
+        }
+        if self.opts.contains(&Opt::StopWhenStuck) && before == perf.total_hits() {
+            return Err(Error::Stuck(format!(
+                "We are stuck{}, no hits after {}, in the recent cycle #{}:\n{}",
+                self.origin(),
+                perf.total_hits(),
+                cycles,
+                self
+            )));
+        }
+        if result.is_none() && self.max_cycles().is_some_and(|max| cycles + 1 > max) {
+            return Err(Error::Cycles(format!(
+                "Too many cycles ({}){}, most probably endless recursion:\n{}",
+                cycles + 1,
+                self.origin(),
+                self
+            )));
+        }
+        Ok(result)
+    }
+
+    /// The number of steps resolving a locator may take, set by
+    /// `Opt::MaxResolutionHops`, or `MAX_HOPS`.
+    pub fn max_hops(&self) -> usize {
+        self.opts
+            .iter()
+            .find_map(|o| match o {
+                Opt::MaxResolutionHops(n) => Some(*n),
+                _ => None,
+            })
+            .unwrap_or(MAX_HOPS)
+    }
+
+    /// The number of cycles after which dataization stops, if it does:
+    /// set by `Opt::MaxCycles`, or the default one, if only
+    /// `Opt::StopWhenTooManyCycles` is set.
+    pub fn max_cycles(&self) -> Option<usize> {
+        self.opts
+            .iter()
+            .find_map(|o| match o {
+                Opt::MaxCycles(n) => Some(*n),
+                _ => None,
+            })
+            .or(if self.opts.contains(&Opt::StopWhenTooManyCycles) {
+                Some(MAX_CYCLES)
+            } else {
+                None
+            })
+    }
+
+    /// Dataize the first object, or tell why it's impossible, like
+    /// being stuck; a panic of an atom, like a division by zero, is
+    /// a failure with its message.
+    pub fn try_dataize(&mut self) -> Result<(D, Perf), Error> {
+        let mut perf = Perf::new();
+        panic::catch_unwind(panic::AssertUnwindSafe(|| self.dataize_dumped(&mut perf)))
+            .unwrap_or_else(|e| Err(Error::Failed(message(e.as_ref()))))
+            .map(|d| (d, perf))
+    }
+
+    /// Dataize the first object, like `try_dataize()` does, but if the
+    /// cycles run out, see `Opt::MaxCycles`, tell how far it got instead
+    /// of failing, see `Partial`.
+    pub fn outcome(&mut self) -> Result<Outcome<D>, Error> {
+        self.partial = None;
+        match self.try_dataize() {
+            Ok((d, perf)) => Ok(Outcome::Dataized(d, perf)),
+            Err(e) => self.partial.take().map(Outcome::Partial).ok_or(e),
+        }
+    }
+
+    /// Dataize once for each of the bindings, in their order, keeping the
+    /// baskets and the memoized results that don't depend on the inputs
+    /// from one run to the next, see `Emu::bind()`:
+    ///
+    /// ```
+    /// use phie::emu::Emu;
+    /// let mut emu: Emu = "+input x ν1\nν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x0000 ⟧\n\
+    ///     ν2(𝜋) ↦ ⟦ λ ↦ int-times, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧".parse().unwrap();
+    /// let squares = emu.sweep((1..4).map(|x| vec![("x".to_string(), x)]));
+    /// assert_eq!(vec![1, 4, 9], squares.into_iter().map(|r| r.unwrap()).collect::<Vec<_>>());
+    /// ```
+    pub fn sweep(&mut self, inputs: impl Iterator<Item = Bindings>) -> Vec<Result<D, Error>> {
+        inputs
+            .map(|bindings| {
+                for (name, d) in bindings {
+                    self.bind(&name, d).map_err(Error::Usage)?;
+                }
+                self.try_dataize().map(|(d, _)| d)
+            })
+            .collect()
+    }
+
+    /// Run one cycle of dataization and return the result of the
+    /// first object, if it's already known; it fails at once if an
+    /// option was refused, see `Emu::opt()`.
+    pub fn step(&mut self, perf: &mut Perf) -> Result<Option<D>, Error> {
+        if let Some(e) = &self.refused {
+            return Err(Error::Usage(e.clone()));
+        }
+        if let Some(s) = self.streaming() {
+            s.cycle(perf.cycles);
+        }
+        self.cycle(perf)?;
+        if cfg!(debug_assertions) || self.opts.contains(&Opt::Paranoid) {
+            if let Err(e) = self.check_invariants() {
                 panic!(
-                    "We are stuck, no hits after {}, in the recent cycle #{}:\n{}",
-                    perf.total_hits(),
-                    cycles,
+                    "{}{}, in the cycle #{}:\n{}",
+                    e,
+                    self.origin(),
+                    perf.cycles,
                     self
                 );
             }
-            perf.cycles += 1;
-            if let Some(Kid::Dtzd(d)) = self.basket(ROOT_BK).kids.get(&Loc::Phi) {
-                debug!(
-                    "dataize() -> 0x{:04X} in {:?}\n{}\n{}",
-                    *d,
-                    time.elapsed(),
-                    perf,
-                    self
-                );
-                return (*d, perf);
+        }
+        perf.peak(self.baskets.iter().filter(|bsk| !bsk.is_empty()).count());
+        perf.cycles += 1;
+        self.save(perf.cycles);
+        self.verify(perf.cycles)?;
+        self.report(perf.cycles);
+        if let Some(d) = self.basket(ROOT_BK).kids.get(&Loc::Phi).and_then(Kid::data) {
+            if let Some(s) = self.streaming() {
+                s.result(perf.cycles, d.data());
             }
-            cycles += 1;
-            if self.opts.contains(&Opt::StopWhenTooManyCycles) && cycles > MAX_CYCLES {
-                panic!(
-                    "Too many cycles ({}), most probably endless recursion:\n{}",
-                    cycles, self
-                );
+            if self.is_tainted(ROOT_BK, &Loc::Phi) {
+                warn!("The result 0x{:04X} is tainted{}", d.data(), self.origin());
             }
+            Ok(Some(d))
+        } else {
+            Ok(None)
         }
     }
 
-    fn cycle(&mut self, perf: &mut Perf) {
-        self.cycle_one(perf, |s, p, bk| s.copy(p, bk));
-        self.cycle_one(perf, |s, p, bk| s.delegate(p, bk));
+    fn cycle(&mut self, perf: &mut Perf) -> Result<(), Error> {
+        self.cycle_one(perf, |s, p, bk| {
+            s.copy(p, bk);
+            Ok(())
+        })?;
+        self.cycle_one(perf, |s, p, bk| s.delegate(p, bk))?;
+        if self.opts.contains(&Opt::Speculate) {
+            self.cycle_one(perf, |s, p, bk| {
+                s.speculate(p, bk);
+                Ok(())
+            })?;
+        }
         if !self.opts.contains(&Opt::DontDelete) {
-            self.cycle_one(perf, |s, p, bk| s.delete(p, bk));
+            self.cycle_one(perf, |s, p, bk| {
+                s.delete(p, bk);
+                Ok(())
+            })?;
         }
         self.cycle_one(perf, |s, p, bk| {
             for loc in s.locs(bk) {
                 s.propagate(p, bk, loc.clone());
-                s.find(p, bk, loc.clone());
-                s.new(p, bk, loc);
+                s.find(p, bk, loc.clone())?;
+                s.new(p, bk, loc)?;
             }
-        });
+            Ok(())
+        })
     }
 
-    fn cycle_one(&mut self, perf: &mut Perf, f: fn(&mut Emu, &mut Perf, Bk)) {
-        for i in 0..self.baskets.len() {
-            let bk = i as Bk;
-            if self.basket(bk).is_empty() {
-                continue;
+    /// Visit the baskets that are not empty in the order of the schedule:
+    /// FIFO and LIFO go through the positions as they are, without
+    /// asking the `Scheduler`.
+    fn cycle_one(
+        &mut self,
+        perf: &mut Perf,
+        f: fn(&mut Emu<D>, &mut Perf, Bk) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let mut visit = |emu: &mut Emu<D>, bk: Bk| {
+            if emu.basket(bk).is_empty() {
+                Ok(())
+            } else {
+                f(emu, perf, bk)
             }
-            f(self, perf, bk);
+        };
+        match self.schedule() {
+            Schedule::Fifo => (0..MAX_BASKETS).try_for_each(|i| visit(self, Bk::new(i))),
+            Schedule::Lifo => (0..MAX_BASKETS)
+                .rev()
+                .try_for_each(|i| visit(self, Bk::new(i))),
+            s => s
+                .scheduler()
+                .order(&self.psis())
+                .into_iter()
+                .try_for_each(|bk| visit(self, bk)),
         }
     }
 
+    /// The file the program was read from, to mention in diagnostics.
+    fn origin(&self) -> String {
+        self.file
+            .as_ref()
+            .map(|f| format!(" in '{}'", f.display()))
+            .unwrap_or_default()
+    }
+
     /// Take all locs from the given basket.
     fn locs(&self, bk: Bk) -> Vec<Loc> {
         let mut keys = vec![];
@@ -92,3 +340,30 @@ impl Emu {
         keys
     }
 }
+
+#[cfg(feature = "parallel")]
+impl Emu {
+    /// Like `sweep()`, but on all cores: the bindings are split into one
+    /// share for each thread, which makes its own `Emu` of the program,
+    /// tunes it and sweeps through its share. The results are in the
+    /// order of the bindings.
+    pub fn par_sweep(
+        prog: &Program,
+        inputs: Vec<Bindings>,
+        tune: impl Fn(&mut Emu) + Sync,
+    ) -> Vec<Result<Data, Error>> {
+        use rayon::prelude::*;
+        let share = inputs.len().div_ceil(rayon::current_num_threads()).max(1);
+        inputs
+            .par_chunks(share)
+            .map(|chunk| {
+                let mut emu = Emu::from(prog);
+                tune(&mut emu);
+                emu.sweep(chunk.iter().cloned())
+            })
+            .collect::<Vec<Vec<Result<Data, Error>>>>()
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+}
@@ -0,0 +1,77 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+use crate::basket::{Bk, Kid};
+use crate::data::Datum;
+use crate::emu::Emu;
+use crate::loc::Loc;
+use crate::perf::Transition;
+
+impl<D: Datum> Emu<D> {
+    /// The sentence that tells what the transition just did to the basket
+    /// and which rule of the calculus it follows, see `Opt::Explain`.
+    pub(crate) fn explain(&self, kind: Transition, bk: Bk, loc: Option<&Loc>) -> String {
+        let bsk = self.basket(bk);
+        let ob = bsk.ob;
+        let kid = loc.and_then(|l| bsk.kids.get(l));
+        let at = loc.map(|l| l.to_string()).unwrap_or_default();
+        match (kind, kid) {
+            (Transition::CPY, _) => format!(
+                "β{} takes the Δ of ν{} as its 𝜑, since an object with data is dataized to it",
+                bk, ob
+            ),
+            (Transition::DLG, _) => format!(
+                "β{} gives control to the atom '{}' of ν{}, since the λ of an object makes its 𝜑",
+                bk,
+                self.object(ob).atom().map_or("?", |m| m.name),
+                ob
+            ),
+            (Transition::DEL, _) => format!(
+                "β{} of ν{} is deleted, since its kids are dataized and no basket waits for them",
+                bk, ob
+            ),
+            (Transition::FIND, Some(Kid::Need(tob, psi))) => format!(
+                "β{} finds {} of ν{} at ν{}, which is to be copied with ψ=β{}",
+                bk, at, ob, tob, psi
+            ),
+            (Transition::FIND, Some(Kid::Wait(wbk, wloc))) => format!(
+                "β{} finds {} of ν{} in β{}.{}, which is shared, so it waits for its data",
+                bk, at, ob, wbk, wloc
+            ),
+            (Transition::NEW, Some(Kid::Wait(nbk, _))) => {
+                let nbsk = self.basket(*nbk);
+                let fresh = nbsk.kids.iter().all(|(l, k)| match l {
+                    Loc::Phi => matches!(k, Kid::Rqtd),
+                    _ => matches!(k, Kid::Empt),
+                });
+                format!(
+                    "β{} requests {} of ν{}; ν{} {} β{} with ψ=β{}",
+                    bk,
+                    at,
+                    ob,
+                    nbsk.ob,
+                    if fresh {
+                        "starts a new basket"
+                    } else {
+                        "shares its basket"
+                    },
+                    nbk,
+                    nbsk.psi
+                )
+            }
+            (Transition::PPG, Some(Kid::Dtzd(d))) => format!(
+                "β{}.{} gets 0x{:04X} from the basket it waits for, since a copy is dataized to the same data",
+                bk, at, d.data()
+            ),
+            (Transition::SPC, Some(Kid::Empt)) => format!(
+                "β{} cancels {} of ν{}, since the condition of 'bool-if' doesn't choose it",
+                bk, at, ob
+            ),
+            (Transition::SPC, _) => format!(
+                "β{} requests {} of ν{} before the condition of 'bool-if' is known",
+                bk, at, ob
+            ),
+            (kind, _) => format!("β{} makes {} at {}", bk, kind, at),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+use crate::basket::{Bk, Kid};
+use crate::data::Datum;
+use crate::emu::{Emu, MAX_BASKETS};
+use crate::object::Ob;
+
+impl<D: Datum> Emu<D> {
+    /// Make sure the baskets are consistent: every live basket belongs to
+    /// an existing object, and its ξ and kids point to live baskets only.
+    /// The ξ of a basket with all kids settled may be freed already,
+    /// since nobody is going to read through it.
+    /// It runs after each cycle in debug builds, or with `Opt::Paranoid`.
+    pub(crate) fn check_invariants(&self) -> Result<(), String> {
+        for (i, bsk) in self.baskets.iter().enumerate() {
+            if bsk.is_empty() {
+                if !bsk.kids.is_empty() {
+                    return Err(format!("The empty basket β{} has kids", i));
+                }
+                continue;
+            }
+            self.live_object(bsk.ob)
+                .map_err(|e| format!("The basket β{} belongs to {}", i, e))?;
+            let settled = bsk.kids.values().all(Kid::is_terminal);
+            if !settled {
+                self.live_basket(bsk.psi)
+                    .map_err(|e| format!("The ξ of β{} is {}", i, e))?;
+            }
+            for (loc, kid) in bsk.kids.iter() {
+                match kid {
+                    Kid::Need(ob, psi) => {
+                        self.live_object(*ob)
+                            .map_err(|e| format!("The kid β{}.{} needs {}", i, loc, e))?;
+                        self.live_basket(*psi)
+                            .map_err(|e| format!("The ξ of the kid β{}.{} is {}", i, loc, e))?;
+                    }
+                    Kid::Wait(bk, _) => {
+                        self.live_basket(*bk)
+                            .map_err(|e| format!("The kid β{}.{} waits for {}", i, loc, e))?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn live_object(&self, ob: Ob) -> Result<(), String> {
+        if ob.index() >= self.objects.len() {
+            return Err(format!("ν{}, which is out of range", ob));
+        }
+        if self.objects[ob.index()].is_empty() {
+            return Err(format!("ν{}, which is absent", ob));
+        }
+        Ok(())
+    }
+
+    fn live_basket(&self, bk: Bk) -> Result<(), String> {
+        if bk.is_none() || bk.index() >= MAX_BASKETS {
+            return Err(format!("β{}, which is out of range", bk));
+        }
+        if self.basket(bk).is_empty() {
+            return Err(format!("β{}, which is freed", bk));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+use crate::basket::Basket;
+#[cfg(test)]
+use crate::emu::Opt;
+#[cfg(test)]
+use crate::loc::Loc;
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn finds_no_problems_in_fresh_emu() {
+    let emu = Emu::from_str("ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧").unwrap();
+    assert!(emu.check_invariants().is_ok());
+}
+
+#[test]
+fn finds_kid_waiting_for_freed_basket() {
+    let mut emu = Emu::from_str("ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧").unwrap();
+    emu.baskets[0].put(Loc::Phi, Kid::Wait(Bk::new(5), Loc::Phi));
+    assert!(emu
+        .check_invariants()
+        .unwrap_err()
+        .contains("waits for β5, which is freed"));
+}
+
+#[test]
+fn finds_basket_of_absent_object() {
+    let mut emu = Emu::from_str("ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧").unwrap();
+    let mut bsk = Basket::start(Ob::new(9), Bk::new(0));
+    bsk.put(Loc::Phi, Kid::Rqtd);
+    emu.inject(Bk::new(1), bsk);
+    assert!(emu
+        .check_invariants()
+        .unwrap_err()
+        .contains("ν9, which is absent"));
+}
+
+#[test]
+#[should_panic(expected = "which is freed")]
+fn stops_at_broken_cycle_when_paranoid() {
+    let mut emu = Emu::from_str("ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧").unwrap();
+    emu.opt(Opt::Paranoid);
+    emu.baskets[0].put(Loc::Phi, Kid::Wait(Bk::new(5), Loc::Phi));
+    emu.dataize();
+}
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: MIT
 
 #[cfg(test)]
-use crate::emu::{Emu, Opt};
+use crate::emu::{Arithmetic, Emu, EmuLog, Opt, MAX_HOPS, ROOT_BK};
 
 #[cfg(test)]
 use crate::perf::Transition;
@@ -14,16 +14,22 @@ use crate::loc::Loc;
 use crate::locator::Locator;
 
 #[cfg(test)]
-use crate::data::Data;
+use crate::data::{Data, Typed};
 
 #[cfg(test)]
 use crate::ph;
 
 #[cfg(test)]
-use crate::assert_dataized_eq;
+use crate::{assert_dataized_eq, assert_dataizes_to, assert_stuck};
 
 #[cfg(test)]
-use crate::object::Object;
+use crate::object::{Ob, Object};
+
+#[cfg(test)]
+use crate::scheduler::Schedule;
+
+#[cfg(test)]
+use crate::event::EventFilter;
 
 #[cfg(test)]
 use std::str::FromStr;
@@ -31,28 +37,28 @@ use std::str::FromStr;
 #[test]
 pub fn simple_dataization_cycle() {
     let mut emu = Emu::empty();
-    emu.put(0, Object::open().with(Loc::Phi, ph!("ν1"), true));
-    emu.put(1, Object::dataic(42));
+    emu.put(Ob::new(0), Object::open().with(Loc::Phi, ph!("ν1"), true));
+    emu.put(Ob::new(1), Object::dataic(42));
     assert_eq!(42, emu.dataize().0);
 }
 
 #[test]
 pub fn with_simple_decorator() {
     let mut emu = Emu::empty();
-    emu.put(0, Object::open().with(Loc::Phi, ph!("ν2"), true));
-    emu.put(1, Object::dataic(42));
-    emu.put(2, Object::open().with(Loc::Phi, ph!("ν1"), false));
+    emu.put(Ob::new(0), Object::open().with(Loc::Phi, ph!("ν2"), true));
+    emu.put(Ob::new(1), Object::dataic(42));
+    emu.put(Ob::new(2), Object::open().with(Loc::Phi, ph!("ν1"), false));
     assert_eq!(42, emu.dataize().0);
 }
 
 #[test]
 pub fn with_many_decorators() {
     let mut emu = Emu::empty();
-    emu.put(0, Object::open().with(Loc::Phi, ph!("ν4"), true));
-    emu.put(1, Object::dataic(42));
-    emu.put(2, Object::open().with(Loc::Phi, ph!("ν1"), false));
-    emu.put(3, Object::open().with(Loc::Phi, ph!("ν2"), false));
-    emu.put(4, Object::open().with(Loc::Phi, ph!("ν3"), false));
+    emu.put(Ob::new(0), Object::open().with(Loc::Phi, ph!("ν4"), true));
+    emu.put(Ob::new(1), Object::dataic(42));
+    emu.put(Ob::new(2), Object::open().with(Loc::Phi, ph!("ν1"), false));
+    emu.put(Ob::new(3), Object::open().with(Loc::Phi, ph!("ν2"), false));
+    emu.put(Ob::new(4), Object::open().with(Loc::Phi, ph!("ν3"), false));
     assert_eq!(42, emu.dataize().0);
 }
 
@@ -403,7 +409,13 @@ pub fn simple_recursion() {
     .unwrap();
     emu.opt(Opt::DontDelete);
     let (_result, perf) = emu.dataize();
-    assert_eq!(9, emu.baskets.iter().filter(|bsk| bsk.ob == 1).count());
+    assert_eq!(
+        9,
+        emu.baskets
+            .iter()
+            .filter(|bsk| bsk.ob == Ob::new(1))
+            .count()
+    );
     assert_eq!(4, *perf.hits.get(&Transition::CPY).unwrap());
 }
 
@@ -472,3 +484,775 @@ fn fails_on_invalid_object_in_line() {
     let err = result.err().unwrap();
     assert!(err.contains("Can't parse object in line"));
 }
+
+#[test]
+pub fn speculates_on_both_branches() {
+    let mut emu = Emu::from_str(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x0005 ⟧
+        ν2(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν1(𝜋) ⟧
+        ν3(𝜋) ↦ ⟦ 𝜑 ↦ ν13(𝜋) ⟧
+        ν5(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧
+        ν6(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ 𝜋.𝜋.𝛼0, 𝛼0 ↦ ν5(𝜋) ⟧
+        ν7(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧
+        ν8(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ 𝜋.𝜋.𝛼0, 𝛼0 ↦ ν7(𝜋) ⟧
+        ν9(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν8(𝜋) ⟧
+        ν10(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν6(𝜋) ⟧
+        ν11(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν9(𝜋), 𝛼0 ↦ ν10(𝜋) ⟧
+        ν12(𝜋) ↦ ⟦ λ ↦ int-less, ρ ↦ 𝜋.𝛼0, 𝛼0 ↦ ν5(𝜋) ⟧
+        ν13(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ ν12(𝜋), 𝛼0 ↦ ν7(𝜋), 𝛼1 ↦ ν11(𝜋) ⟧
+        ",
+    )
+    .unwrap();
+    emu.opt(Opt::Speculate);
+    emu.opt(Opt::StopWhenStuck);
+    let (result, perf) = emu.dataize();
+    assert_eq!(fibo(5), result);
+    assert!(perf.speculated > 0);
+    assert!(perf.wasted > 0);
+    let err = emu.try_opt(Opt::DontDelete).err().unwrap();
+    assert!(err.contains("can't run with 'dont-delete'"), "{}", err);
+}
+
+#[test]
+pub fn speculates_without_effects() {
+    let text = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν5(𝜋), 𝛼1 ↦ ν6(𝜋) ⟧
+        ν2(𝜋) ↦ ⟦ λ ↦ int-less, ρ ↦ ν3(𝜋), 𝛼0 ↦ ν4(𝜋) ⟧
+        ν3(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν4(𝜋), 𝛼0 ↦ ν4(𝜋) ⟧
+        ν4(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧
+        ν5(𝜋) ↦ ⟦ λ ↦ io-write, ρ ↦ ν7(𝜋) ⟧
+        ν6(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+        ν7(𝜋) ↦ ⟦ Δ ↦ 0x0063 ⟧
+    ";
+    for speculate in [false, true] {
+        let mut emu = Emu::from_str(text).unwrap();
+        if speculate {
+            emu.opt(Opt::Speculate);
+        }
+        emu.opt(Opt::StopWhenStuck);
+        assert_eq!(7, emu.dataize().0);
+        assert!(emu.output_tape().is_empty(), "{:?}", emu.output_tape());
+    }
+}
+
+#[test]
+pub fn dataizes_with_any_schedule() {
+    for schedule in [Schedule::Fifo, Schedule::Lifo, Schedule::Deepest] {
+        let mut emu = Emu::from_str(
+            "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧
+            ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν9(𝜋), 𝛼0 ↦ ν9(𝜋) ⟧
+            ν3(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν4(𝜋), 𝛼0 ↦ ν9(𝜋) ⟧
+            ν4(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν9(𝜋) ⟧
+            ν9(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+            ",
+        )
+        .unwrap();
+        emu.opt(Opt::Schedule(schedule));
+        emu.opt(Opt::StopWhenStuck);
+        assert_eq!(84, emu.dataize().0, "Wrong result with {:?}", schedule);
+    }
+}
+
+#[test]
+pub fn keeps_only_one_schedule() {
+    let mut emu = Emu::empty();
+    emu.opt(Opt::Schedule(Schedule::Lifo));
+    emu.opt(Opt::Schedule(Schedule::Deepest));
+    assert_eq!(1, emu.opts.len());
+    assert_eq!(Schedule::Deepest, emu.schedule());
+    emu.opt_default(Opt::Schedule(Schedule::Fifo));
+    assert_eq!(Schedule::Deepest, emu.schedule());
+}
+
+#[test]
+pub fn records_events_once() {
+    let mut emu = Emu::from_str(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧
+        ν2(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+        ",
+    )
+    .unwrap();
+    emu.opt(Opt::RecordEvents);
+    let (result, perf) = emu.dataize();
+    assert_eq!(84, result);
+    assert_eq!(
+        *perf.hits.get(&Transition::DLG).unwrap(),
+        emu.events(EventFilter::any().object(Ob::new(1)).kind(Transition::DLG))
+            .count()
+    );
+    assert_eq!(
+        1,
+        emu.events(EventFilter::any().kind(Transition::CPY)).count()
+    );
+    assert!(emu.events(EventFilter::any().basket(ROOT_BK)).count() > 0);
+}
+
+#[test]
+pub fn records_nothing_by_default() {
+    let mut emu = Emu::from_str("ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧").unwrap();
+    emu.dataize();
+    assert_eq!(0, emu.events(EventFilter::any()).count());
+}
+
+#[test]
+pub fn picks_log_level() {
+    let mut emu = Emu::empty();
+    assert_eq!(EmuLog::Transitions, emu.log_level());
+    emu.opt(Opt::LogSnapshots);
+    assert!(emu.logs(EmuLog::Snapshots));
+    assert!(!emu.logs(EmuLog::Everything));
+    emu.opt(Opt::LogLevel(EmuLog::Everything));
+    emu.opt(Opt::LogLevel(EmuLog::Results));
+    assert_eq!(EmuLog::Results, emu.log_level());
+    assert!(!emu.logs(EmuLog::Transitions));
+}
+
+#[test]
+pub fn reads_itself_from_file() {
+    let path = std::path::Path::new("tests/resources/written_sum_test");
+    let mut emu = Emu::try_from(path).unwrap();
+    assert_eq!(Some(path.to_path_buf()), emu.file);
+    assert_eq!(84, emu.dataize().0);
+}
+
+#[test]
+fn asserts_results_and_getting_stuck() {
+    assert_dataizes_to!(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+        ν2(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν1(𝜋) ⟧
+        ",
+        -7
+    );
+    assert_stuck!(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦! 𝜑 ↦ ν2(𝜋) ⟧
+        ν2(𝜋) ↦ ⟦! 𝜑 ↦ ν1(𝜋) ⟧
+        "
+    );
+}
+
+#[test]
+#[should_panic(expected = "expected: 42\n  actual:   7")]
+fn explains_wrong_result() {
+    assert_dataizes_to!("ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧", 42);
+}
+
+#[test]
+fn keeps_options_of_program_over_defaults() {
+    let mut emu = Emu::from_str("+opt max-cycles 7\nν0(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧").unwrap();
+    emu.opt_default(Opt::MaxCycles(100));
+    emu.opt_default(Opt::StopWhenStuck);
+    assert_eq!(Some(7), emu.max_cycles());
+    assert!(emu.opts.contains(&Opt::StopWhenStuck));
+    emu.opt(Opt::MaxCycles(100));
+    assert_eq!(Some(100), emu.max_cycles());
+}
+
+#[test]
+fn rejects_broken_environment() {
+    let vars = |k: &str, v: &str| vec![(k.to_string(), v.to_string())];
+    assert!(Opt::from_vars(vars("PHIE_MAX_CYCLES", "lots")).is_err());
+    assert!(Opt::from_vars(vars("PHIE_STOP_WHEN_STUCK", "maybe")).is_err());
+    assert!(Opt::from_vars(vars("PHIE_SPECULATE", "off"))
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        vec![Opt::LogLevel(EmuLog::Results)],
+        Opt::from_vars(vars("PHIE_LOG_LEVEL", "results")).unwrap()
+    );
+}
+
+#[test]
+fn explains_where_result_came_from() {
+    let mut emu: Emu = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν3(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧
+        ν2(𝜋) ↦ ⟦ λ ↦ int-less, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧
+        ν3(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν1(𝜋), 𝛼1 ↦ ν4(𝜋) ⟧
+        ν4(𝜋) ↦ ⟦ Δ ↦ 0x0053 ⟧
+    "
+    .parse()
+    .unwrap();
+    emu.opt(Opt::TrackProvenance);
+    assert_eq!(83, emu.dataize().0);
+    assert_eq!(
+        "83 ← ν3 bool-if\n  0 ← ν2 int-less\n    1 ← ν1 Δ\n    1 ← ν1 Δ\n  83 ← ν4 Δ",
+        emu.provenance().unwrap().to_string()
+    );
+}
+
+#[test]
+fn tracks_nothing_by_default() {
+    let mut emu: Emu = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+    emu.dataize();
+    assert!(emu.provenance().is_none());
+}
+
+#[test]
+fn rewinds_to_checkpoint() {
+    let mut emu: Emu = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+        ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧
+    "
+    .parse()
+    .unwrap();
+    emu.opt(Opt::CheckpointEvery(2));
+    let (d, perf) = emu.dataize();
+    assert_eq!(84, d);
+    let cycles: Vec<usize> = emu.checkpoints().map(|cp| cp.cycle).collect();
+    assert_eq!(
+        (1..=perf.cycles / 2).map(|i| i * 2).collect::<Vec<_>>(),
+        cycles
+    );
+    assert_eq!(Some(2), emu.rewind(3));
+    assert_eq!(1, emu.checkpoints().count());
+    assert_eq!(84, emu.dataize().0);
+}
+
+#[test]
+fn keeps_limited_number_of_checkpoints() {
+    let mut emu: Emu = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦! 𝜑 ↦ ν2(𝜋) ⟧
+        ν2(𝜋) ↦ ⟦! 𝜑 ↦ ν1(𝜋) ⟧
+    "
+    .parse()
+    .unwrap();
+    emu.opt(Opt::CheckpointEvery(1));
+    emu.opt(Opt::MaxCycles(100));
+    assert!(emu.try_dataize().is_err());
+    let cycles: Vec<usize> = emu.checkpoints().map(|cp| cp.cycle).collect();
+    assert_eq!(16, cycles.len());
+    assert_eq!(Some(&101), cycles.last());
+    assert_eq!(None, emu.rewind(10));
+}
+
+#[cfg(test)]
+use crate::sandbox::Profile;
+
+#[test]
+fn dataizes_atoms_allowed_by_sandbox() {
+    let mut emu: Emu = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+        ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧
+    "
+    .parse()
+    .unwrap();
+    emu.sandbox(Profile::pure());
+    assert_eq!(84, emu.dataize().0);
+}
+
+#[test]
+fn forbids_atoms_outside_of_sandbox() {
+    let mut emu: Emu = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν3(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧
+        ν2(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧
+        ν3(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧
+    "
+    .parse()
+    .unwrap();
+    emu.sandbox(Profile::none().allow("int-add"));
+    let err = emu.try_dataize().err().unwrap();
+    assert_eq!("The atom 'int-sub' is forbidden in ν3", err.to_string());
+    assert_eq!(6, err.code());
+}
+
+#[test]
+fn stops_locator_escaping_through_rho() {
+    let mut emu: Emu = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ Φ.𝛼0.ρ ⟧
+        ν3(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧
+    "
+    .parse()
+    .unwrap();
+    let err = emu.try_dataize().err().unwrap();
+    assert_eq!(
+        "The locator Φ.𝛼0.ρ of ν1 escaped the graph at ν3",
+        err.to_string()
+    );
+    assert_eq!(8, err.code());
+}
+
+#[test]
+fn finds_home_of_object_made_with_pi() {
+    let mut emu: Emu = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν2(𝜋) ⟧
+        ν2(𝜋) ↦ ⟦ Δ ↦ 0x0005 ⟧
+        ν3(𝜋) ↦ ⟦ 𝜑 ↦ ν5(𝜋), 𝛼0 ↦ ν4(𝜋) ⟧
+        ν4(𝜋) ↦ ⟦ Δ ↦ 0x0009 ⟧
+        ν5(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ σ.𝛼0, 𝛼0 ↦ σ.σ.𝛼0 ⟧
+    "
+    .parse()
+    .unwrap();
+    assert_eq!(Some(Ob::new(3)), emu.home(Ob::new(5)));
+    assert_eq!(None, emu.home(Ob::new(0)));
+    assert_eq!(4, emu.dataize().0);
+}
+
+#[test]
+fn stops_locator_escaping_through_sigma() {
+    let mut emu: Emu = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ σ.σ.𝛼0 ⟧
+    "
+    .parse()
+    .unwrap();
+    let err = emu.try_dataize().err().unwrap();
+    assert_eq!(
+        "The locator σ.σ.𝛼0 of ν1 escaped the graph at ν0",
+        err.to_string()
+    );
+}
+
+#[test]
+fn traces_attribute_not_found_through_phi() {
+    let mut emu: Emu = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν3(𝜋), 𝛼0 ↦ ν9(𝜋) ⟧
+        ν3(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ Φ.𝛼0.𝛼1 ⟧
+        ν9(𝜋) ↦ ⟦ 𝜑 ↦ ν10(𝜋) ⟧
+        ν10(𝜋) ↦ ⟦ 𝜑 ↦ ν11(𝜋) ⟧
+        ν11(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧
+    "
+    .parse()
+    .unwrap();
+    let err = emu.try_dataize().err().unwrap();
+    assert!(matches!(
+        &err,
+        crate::error::Error::AttributeNotFound { object, from, chain, .. }
+            if (object.index(), from.index()) == (9, 3)
+                && chain.iter().map(|o| o.index()).collect::<Vec<usize>>() == [9, 10, 11]
+    ));
+    assert_eq!(9, err.code());
+}
+
+#[test]
+fn stops_locator_walking_in_circles() {
+    let mut emu: Emu = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ Φ.ρ ⟧
+    "
+    .parse()
+    .unwrap();
+    let err = emu.try_dataize().err().unwrap();
+    assert!(matches!(
+        &err,
+        crate::error::Error::TooManyHops { from, limit: MAX_HOPS, chain, .. }
+            if from.index() == 1
+                && chain.iter().map(|o| o.index()).collect::<Vec<usize>>() == [0, 1, 0]
+    ));
+    assert_eq!(10, err.code());
+}
+
+#[test]
+fn limits_resolution_hops() {
+    let text = "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ Φ.𝛼0.𝛼0 ⟧
+        ν3(𝜋) ↦ ⟦ 𝜑 ↦ ν4(𝜋) ⟧
+        ν4(𝜋) ↦ ⟦ 𝛼0 ↦ ν5(𝜋) ⟧
+        ν5(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+    ";
+    assert_dataized_eq!(-7, text);
+    let mut emu = Emu::from_str(text).unwrap();
+    emu.opt(Opt::MaxResolutionHops(3));
+    assert_eq!(
+        "The locator Φ.𝛼0.𝛼0 of ν1 made more than 3 hops, see the max-resolution-hops option: ν0 → ν3",
+        emu.try_dataize().err().unwrap().to_string()
+    );
+    assert_eq!(
+        "max-resolution-hops 3",
+        Opt::from_str("max-resolution-hops 3").unwrap().to_string()
+    );
+    assert!(Opt::from_str("max-resolution-hops 0").is_err());
+}
+
+#[test]
+fn makes_room_for_more_objects() {
+    let text = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν20(𝜋) ⟧\nν20(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧";
+    assert_dataized_eq!(42, format!("+opt max-objects 32\n{}", text).as_str());
+    let err = std::panic::catch_unwind(|| Emu::from_str(text))
+        .err()
+        .unwrap();
+    assert!(err
+        .downcast_ref::<String>()
+        .unwrap()
+        .contains("ν20 is beyond the limit of 16 objects"));
+    assert_eq!(
+        "max-objects 32",
+        Opt::from_str("max-objects 32").unwrap().to_string()
+    );
+    assert!(Opt::from_str("max-objects 0").is_err());
+}
+
+#[test]
+fn reports_progress() {
+    let seen = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+    let mut emu = Emu::from_str(&(crate::examples::FIBONACCI.program)(5)).unwrap();
+    emu.opt(Opt::Progress(100));
+    let log = seen.clone();
+    emu.on_progress(move |p| log.lock().unwrap().push(*p));
+    let cycles = emu.dataize().1.cycles;
+    let seen = seen.lock().unwrap();
+    assert_eq!(cycles / 100, seen.len());
+    assert!(seen.iter().all(|p| p.baskets > 0 && p.cycles % 100 == 0));
+    assert_eq!("progress 100", Opt::Progress(100).to_string());
+    assert!(Opt::from_str("progress 0").is_err());
+}
+
+#[test]
+fn recycles_baskets() {
+    let mut emu = Emu::from_str(&(crate::examples::FIBONACCI.program)(5)).unwrap();
+    emu.opt(Opt::DontDelete);
+    let (first, perf) = emu.dataize();
+    let (freed, retained) = emu.recycle();
+    assert!(freed > 1);
+    assert!(retained >= freed);
+    assert!(emu.baskets[1..].iter().all(|bsk| bsk.is_empty()));
+    let (again, same) = emu.dataize();
+    assert_eq!(first, again);
+    assert_eq!(perf.cycles, same.cycles);
+}
+
+#[test]
+fn keeps_objects_when_shrinking() {
+    let mut emu = Emu::from_str("ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν5(𝜋) ⟧\nν5(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧").unwrap();
+    emu.opt(Opt::MaxObjects(2));
+    assert_eq!(6, emu.objects.len());
+    assert_eq!(7, emu.dataize().0);
+}
+
+#[test]
+fn memoizes_pure_atoms() {
+    let program = (crate::examples::FIBONACCI.program)(7);
+    let mut emu = Emu::from_str(&program).unwrap();
+    emu.opt(Opt::StopWhenStuck);
+    let (plain, was) = emu.dataize();
+    let mut emu = Emu::from_str(&program).unwrap();
+    emu.opt(Opt::StopWhenStuck);
+    emu.opt(Opt::Memoize);
+    let (memoized, perf) = emu.dataize();
+    assert_eq!(21, plain);
+    assert_eq!(plain, memoized);
+    assert!(perf.memo_hits > 0, "{}", perf);
+    assert_eq!(was.total_atoms(), perf.total_atoms() + perf.memo_hits);
+    assert!(perf.to_string().contains("Memo: "));
+}
+
+#[test]
+fn presizes_baskets_by_memo() {
+    let text = (crate::examples::FIBONACCI.program)(4);
+    let mut emu = Emu::from_str(&text).unwrap();
+    emu.opt(Opt::Memoize);
+    assert_eq!(0, emu.expected_kids(Ob::new(13)));
+    let (first, _) = emu.dataize();
+    let held = emu.expected_kids(Ob::new(13));
+    assert_eq!(4, held);
+    let mut next = Emu::from_str(&text).unwrap();
+    next.opt(Opt::Memoize);
+    assert!(next.import_memo(&emu.export_memo()).is_ok());
+    assert_eq!(held, next.expected_kids(Ob::new(13)));
+    assert_eq!(first, next.dataize().0);
+    assert_eq!(held, next.expected_kids(Ob::new(13)));
+}
+
+#[test]
+fn refuses_memo_of_other_arithmetic() {
+    let text = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧\nν2(𝜋) ↦ ⟦ Δ ↦ 0x7FFFFFFFFFFFFFFF ⟧\nν3(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧";
+    let mut emu = Emu::from_str(text).unwrap();
+    emu.opt(Opt::Memoize);
+    assert_eq!(i64::MIN, emu.dataize().0);
+    let mut next = Emu::from_str(text).unwrap();
+    next.opt(Opt::Memoize);
+    next.opt(Opt::Arithmetic(Arithmetic::Checked));
+    assert!(next
+        .import_memo(&emu.export_memo())
+        .unwrap_err()
+        .contains("another arithmetic"));
+    assert!(next.try_dataize().is_err());
+}
+
+#[test]
+fn honors_cache_markers() {
+    let program = |marker: &str| {
+        format!(
+            "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν4(𝜋) ⟧
+            ν4(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦{} λ ↦ int-add, ρ ↦ ν3(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧
+            ν2(𝜋) ↦ ⟦{} λ ↦ int-add, ρ ↦ ν3(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧
+            ν3(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧
+            ",
+            marker, marker
+        )
+    };
+    let (d, perf) = Emu::from_str(&program("%")).unwrap().dataize();
+    assert_eq!(8, d);
+    assert_eq!(1, perf.memo_hits);
+    let mut emu = Emu::from_str(&program("~")).unwrap();
+    emu.opt(Opt::Memoize);
+    let (d, perf) = emu.dataize();
+    assert_eq!(8, d);
+    assert_eq!(0, perf.memo_hits);
+    assert_eq!(1, perf.memo_misses);
+}
+
+#[test]
+fn dataizes_again_after_binding_input() {
+    let program = format!("+input x ν1\n{}", (crate::examples::FIBONACCI.program)(7));
+    let mut emu = Emu::from_str(&program).unwrap();
+    emu.opt(Opt::StopWhenStuck);
+    emu.opt(Opt::Memoize);
+    assert_eq!(21, emu.dataize().0);
+    emu.bind("x", 8).unwrap();
+    assert!(emu
+        .baskets
+        .iter()
+        .filter(|bsk| !bsk.is_empty())
+        .all(|bsk| !emu.baskets[bsk.psi.index()].is_empty()));
+    let (d, perf) = emu.dataize();
+    assert_eq!(34, d);
+    assert!(perf.memo_hits > perf.memo_misses, "{}", perf);
+    emu.bind("x", 7).unwrap();
+    assert_eq!(21, emu.dataize().0);
+    assert!(emu.bind("y", 1).is_err());
+}
+
+#[test]
+fn sweeps_through_inputs() {
+    let program = format!("+input x ν1\n{}", (crate::examples::FIBONACCI.program)(1));
+    let mut emu = Emu::from_str(&program).unwrap();
+    emu.opt(Opt::StopWhenStuck);
+    emu.opt(Opt::Memoize);
+    let results = emu.sweep((1..9).map(|x| vec![("x".to_string(), x)]));
+    assert_eq!(
+        vec![1, 2, 3, 5, 8, 13, 21, 34],
+        results
+            .into_iter()
+            .map(|r| r.unwrap())
+            .collect::<Vec<Data>>()
+    );
+    let failed = emu.sweep(vec![vec![("y".to_string(), 1)]].into_iter());
+    assert!(failed[0]
+        .as_ref()
+        .unwrap_err()
+        .to_string()
+        .contains("no input 'y'"));
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn sweeps_on_all_cores() {
+    let program = format!("+input x ν1\n{}", (crate::examples::FIBONACCI.program)(1));
+    let prog = crate::program::Program::from_str(&program).unwrap();
+    let results = Emu::par_sweep(
+        &prog,
+        (1..9).map(|x| vec![("x".to_string(), x)]).collect(),
+        |emu| emu.opt(Opt::StopWhenStuck),
+    );
+    assert_eq!(
+        vec![1, 2, 3, 5, 8, 13, 21, 34],
+        results
+            .into_iter()
+            .map(|r| r.unwrap())
+            .collect::<Vec<Data>>()
+    );
+}
+
+#[test]
+fn dataizes_with_any_width() {
+    let prog = crate::program::Program::from_str(&(crate::examples::FIBONACCI.program)(7)).unwrap();
+    assert_eq!(21, Emu::<i16>::of(&prog).dataize().0);
+    assert_eq!(21, Emu::<i32>::of(&prog).dataize().0);
+    assert_eq!(21, Emu::<i64>::of(&prog).dataize().0);
+}
+
+#[test]
+fn keeps_types_of_data() {
+    let prog = crate::program::Program::from_str(
+        "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ λ ↦ int-less, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧\n\
+        ν2(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧\nν3(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧",
+    )
+    .unwrap();
+    assert_eq!(Typed::Bool(true), Emu::<Typed>::of(&prog).dataize().0);
+    let prog = crate::program::Program::from_str(&(crate::examples::FIBONACCI.program)(7)).unwrap();
+    assert_eq!(Typed::Int(21), Emu::<Typed>::of(&prog).dataize().0);
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn dataizes_big_integers() {
+    use crate::data::{Big, Datum};
+    let prog = crate::program::Program::from_str(
+        "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧\n\
+        ν2(𝜋) ↦ ⟦ Δ ↦ 0x7FFFFFFFFFFFFFFF ⟧",
+    )
+    .unwrap();
+    let mut emu = Emu::<Big>::of(&prog);
+    let big = emu.dataize().0;
+    assert_eq!("18446744073709551614", emu.big(big).to_string());
+    assert_eq!(big, Big::from_hex(&format!("{:X}", big)).unwrap());
+    emu.recycle();
+    assert!(emu.values.is_empty());
+    let prog = crate::program::Program::from_str(&(crate::examples::FIBONACCI.program)(7)).unwrap();
+    let mut emu = Emu::<Big>::of(&prog);
+    let d = emu.dataize().0;
+    assert_eq!(&num_bigint::BigInt::from(21), emu.big(d));
+}
+
+#[test]
+fn reads_and_writes_tapes() {
+    let mut emu: Emu = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\n\
+        ν1(𝜋) ↦ ⟦ λ ↦ io-write, ρ ↦ ν2(𝜋) ⟧\n\
+        ν2(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ ν3(𝜋), 𝛼0 ↦ ν4(𝜋) ⟧\n\
+        ν3(𝜋) ↦ ⟦ λ ↦ io-read ⟧\n\
+        ν4(𝜋) ↦ ⟦ λ ↦ io-read ⟧"
+        .parse()
+        .unwrap();
+    emu.opt(Opt::Memoize);
+    emu.set_input_tape([10, 3, 5]);
+    let (d, _) = emu.try_dataize().unwrap();
+    assert_eq!(7, d);
+    assert_eq!(&[d], emu.output_tape());
+    assert_eq!(vec![&5], emu.input_tape().collect::<Vec<&Data>>());
+    emu.recycle();
+    assert!(emu.output_tape().is_empty());
+    assert_eq!(0, emu.input_tape().count());
+}
+
+#[test]
+fn fails_on_empty_input_tape() {
+    let mut emu: Emu = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ λ ↦ io-read ⟧"
+        .parse()
+        .unwrap();
+    assert!(emu
+        .try_dataize()
+        .err()
+        .unwrap()
+        .to_string()
+        .contains("The input tape is empty"));
+    assert!(emu.output_tape().is_empty());
+}
+
+#[test]
+fn keeps_invariants_of_objects() {
+    let txt = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\n\
+        ν1(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧\n\
+        ν2(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧";
+    let mut emu: Emu = txt.parse().unwrap();
+    assert_eq!(Ok(0), emu.assert_invariant("ν1", |d| d > 0));
+    assert_eq!(Ok(1), emu.assert_invariant("ν2", |d| d == 7));
+    assert_eq!(14, emu.try_dataize().unwrap().0);
+    let mut emu: Emu = txt.parse().unwrap();
+    emu.opt(Opt::InvariantsEvery(1000));
+    emu.assert_invariant("ν1", |d| d < 10).unwrap();
+    assert_eq!(14, emu.try_dataize().unwrap().0);
+    let mut emu: Emu = txt.parse().unwrap();
+    emu.assert_invariant("ν1", |d| d < 10).unwrap();
+    let err = emu.try_dataize().err().unwrap().to_string();
+    assert!(err.contains("dataized to 14"), "{}", err);
+    assert!(emu.assert_invariant("ν9", |_| true).is_err());
+    assert!(emu.assert_invariant("𝜋.𝛼0", |_| true).is_err());
+    assert_eq!(
+        Ok(Opt::InvariantsEvery(5)),
+        "invariants-every 5".parse::<Opt>()
+    );
+}
+
+#[test]
+fn audits_reads_of_watched_attributes() {
+    let mut emu: Emu = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\n\
+        ν1(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧\n\
+        ν2(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧\nν3(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧"
+        .parse()
+        .unwrap();
+    let opt: Opt = "audit-locs 𝛼0".parse().unwrap();
+    assert_eq!(Opt::AuditLocs(vec![Loc::Attr(0)]), opt);
+    assert_eq!("audit-locs 𝛼0", opt.to_string());
+    emu.opt(opt);
+    assert_eq!(8, emu.dataize().0);
+    let reads: Vec<(Loc, Data)> = emu
+        .audit()
+        .iter()
+        .map(|a| (a.loc.clone(), a.data))
+        .collect();
+    assert_eq!(vec![(Loc::Attr(0), 1)], reads);
+    assert!(emu.audit().iter().all(|a| a.ob == Ob::new(1)));
+    emu.opt(Opt::AuditLocs(vec![Loc::Phi]));
+    emu.recycle();
+    assert!(emu.audit().is_empty());
+    emu.dataize();
+    assert!(emu
+        .audit()
+        .iter()
+        .any(|a| a.ob == Ob::new(2) && a.reader != a.bk));
+    assert!("audit-locs".parse::<Opt>().is_err());
+}
+
+#[test]
+fn spreads_taint_through_atoms() {
+    let txt = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\n\
+        ν1(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν4(𝜋) ⟧\n\
+        ν2(𝜋) ↦ ⟦ λ ↦ io-write, ρ ↦ ν3(𝜋) ⟧\n\
+        ν3(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧\nν4(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧";
+    let mut emu: Emu = txt.parse().unwrap();
+    emu.taint(Ob::new(4));
+    assert_eq!(8, emu.dataize().0);
+    assert!(emu.is_tainted(ROOT_BK, &Loc::Phi));
+    assert!(emu.leaks().is_empty());
+    emu.recycle();
+    assert!(!emu.is_tainted(ROOT_BK, &Loc::Phi));
+    let mut emu: Emu = txt.parse().unwrap();
+    emu.taint(Ob::new(3));
+    assert_eq!(8, emu.dataize().0);
+    assert!(emu.is_tainted(ROOT_BK, &Loc::Phi));
+    assert_eq!(
+        vec![7],
+        emu.leaks().iter().map(|(_, d)| *d).collect::<Vec<Data>>()
+    );
+    let mut emu: Emu = txt.parse().unwrap();
+    assert_eq!(8, emu.dataize().0);
+    assert!(!emu.is_tainted(ROOT_BK, &Loc::Phi));
+}
+
+#[test]
+fn explains_every_transition() {
+    let mut emu: Emu = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\n\
+        ν1(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν2(𝜋) ⟧\nν2(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧"
+        .parse()
+        .unwrap();
+    emu.opt(Opt::Explain);
+    let (d, perf) = emu.dataize();
+    assert_eq!(-7, d);
+    let text = emu.explanation().join("\n");
+    assert_eq!(
+        perf.hits.values().sum::<usize>(),
+        emu.explanation().len(),
+        "{}",
+        text
+    );
+    assert!(
+        text.contains("β0 requests 𝜑 of ν0; ν1 starts a new basket β1 with ψ=β0"),
+        "{}",
+        text
+    );
+    assert!(text.contains("the atom 'int-neg' of ν1"), "{}", text);
+    assert!(text.contains("gets 0x0007 from the basket"), "{}", text);
+    assert!(text.contains("β2 takes the Δ of ν2"), "{}", text);
+    emu.recycle();
+    assert!(emu.explanation().is_empty());
+}
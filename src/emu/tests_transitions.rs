@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: MIT
 
 #[cfg(test)]
-use crate::basket::Basket;
+use crate::basket::{Basket, Bk};
 
 #[cfg(test)]
 use std::str::FromStr;
@@ -16,7 +16,7 @@ use crate::perf::Perf;
 #[test]
 pub fn deletes_one_basket() {
     let mut emu = Emu::empty();
-    let bk = 1;
+    let bk = Bk::new(1);
     emu.inject(bk, Basket::from_str("[ν1, ξ:β1, 𝜑⇶0x002A]").unwrap());
     let mut perf = Perf::new();
     emu.delete(&mut perf, bk);
@@ -2,11 +2,17 @@
 // SPDX-License-Identifier: MIT
 
 use crate::basket::{Basket, Bk, Kid};
-use crate::emu::{Emu, MAX_BASKETS, ROOT_BK, ROOT_OB};
+use crate::breakpoint::Break;
+use crate::data::Datum;
+use crate::emu::{Emu, EmuLog, Opt, Taint, MAX_BASKETS, ROOT_BK, ROOT_OB};
+use crate::error::Error;
 use crate::loc::Loc;
 use crate::locator::Locator;
 use crate::object::{Ob, Object};
 use crate::perf::{Perf, Transition};
+use crate::plan::{Plan, Step};
+use crate::provenance::Origin;
+use crate::semantics::Change;
 use itertools::Itertools;
 use log::trace;
 
@@ -16,16 +22,20 @@ macro_rules! join {
     };
 }
 
-impl Emu {
+impl<D: Datum> Emu<D> {
     /// Copy data from object to basket.
     pub fn copy(&mut self, perf: &mut Perf, bk: Bk) {
         let bsk = self.basket(bk);
         if let Some(Kid::Rqtd) = bsk.kids.get(&Loc::Phi) {
-            let obj = self.object(bsk.ob);
-            if let Some(d) = obj.delta {
-                let _ = &self.baskets[bk as usize].put(Loc::Phi, Kid::Dtzd(d));
-                trace!("copy(β{}) -> 0x{:04X}", bk, d);
-                perf.hit(Transition::CPY);
+            if let Some(d) = self.lift(bsk.ob) {
+                self.audited(bk, &Loc::Delta, bk, d.data());
+                self.settle(bk, Loc::Phi, d);
+                self.spread_taint(bk, &Loc::Phi, Taint::Delta);
+                self.trace_origin(bk, Loc::Phi, Origin::Delta, d.data());
+                if self.logs(EmuLog::Transitions) {
+                    trace!("copy(β{}) -> 0x{:04X}", bk, d);
+                }
+                self.record(perf, Transition::CPY, bk, None);
             }
         }
         perf.tick(Transition::CPY);
@@ -36,14 +46,14 @@ impl Emu {
         let mut changes = vec![];
         if let Some(Kid::Dtzd(d)) = self.basket(bk).kids.get(&loc) {
             for i in 0..self.baskets.len() {
-                let bsk = self.basket(i as Bk);
+                let bsk = self.basket(Bk::new(i));
                 if bsk.is_empty() {
                     continue;
                 }
                 for k in bsk.kids.keys() {
                     if let Some(Kid::Wait(b, l)) = &bsk.kids.get(k) {
                         if *b == bk && *l == loc {
-                            changes.push((i as Bk, k.clone(), *d));
+                            changes.push((Bk::new(i), k.clone(), *d));
                         }
                     }
                     perf.tick(Transition::PPG);
@@ -51,12 +61,36 @@ impl Emu {
             }
         }
         for (b, l, d) in changes.iter() {
-            let _ = &self.baskets[*b as usize].put(l.clone(), Kid::Dtzd(*d));
-            perf.hit(Transition::PPG);
+            self.audited(bk, &loc, *b, d.data());
+            self.settle(*b, l.clone(), *d);
+            self.spread_taint(*b, l, Taint::Kid(bk, loc.clone()));
+            self.copy_origin((bk, loc.clone()), (*b, l.clone()));
+            self.record(perf, Transition::PPG, *b, Some(l.clone()));
         }
         perf.tick(Transition::PPG);
     }
 
+    /// Give the kid of the basket its data and see whether any
+    /// condition of a breakpoint holds, see `Emu::break_when()`.
+    fn settle(&mut self, bk: Bk, loc: Loc, d: D) {
+        if !self.breaks.is_empty() && self.broken.is_none() {
+            let ob = self.basket(bk).ob;
+            if let Some(i) = self.breaks.iter().position(|c| c.holds(ob, &loc, d.data())) {
+                self.broken = Some(Break {
+                    condition: i,
+                    bk,
+                    ob,
+                    loc: loc.clone(),
+                    data: d.data(),
+                });
+            }
+        }
+        if !self.tainted.is_empty() {
+            self.tainted.remove(&(bk, loc.clone()));
+        }
+        self.baskets[bk.index()].put(loc, Kid::Dtzd(d));
+    }
+
     /// Delete the basket if it's already finished.
     pub fn delete(&mut self, perf: &mut Perf, bk: Bk) {
         if bk == ROOT_BK {
@@ -64,18 +98,18 @@ impl Emu {
         }
         let bsk = self.basket(bk);
         let obj = self.object(bsk.ob);
-        if obj.constant {
+        if obj.cacheable() {
             return;
         }
         let mut ready = true;
         for kid in bsk.kids.values() {
-            if !matches!(kid, Kid::Empt) && !matches!(kid, Kid::Dtzd(_)) {
+            if !kid.is_terminal() {
                 ready = false;
                 break;
             }
-            if matches!(kid, Kid::Dtzd(_)) {
+            if kid.data().is_some() {
                 for i in 0..self.baskets.len() {
-                    let wbsk = self.basket(i as Bk);
+                    let wbsk = self.basket(Bk::new(i));
                     if wbsk.is_empty() {
                         continue;
                     }
@@ -91,183 +125,525 @@ impl Emu {
             }
         }
         if ready {
-            self.baskets[bk as usize] = Basket::empty();
-            trace!("delete(β{})", bk);
-            perf.hit(Transition::DEL);
+            self.record(perf, Transition::DEL, bk, None);
+            self.free(bk);
+            self.drop_origins(bk);
+            if self.logs(EmuLog::Transitions) {
+                trace!("delete(β{})", bk);
+            }
         }
         perf.tick(Transition::DEL);
     }
 
     /// Give control to the atom of the basket.
-    pub fn delegate(&mut self, perf: &mut Perf, bk: Bk) {
+    pub fn delegate(&mut self, perf: &mut Perf, bk: Bk) -> Result<(), Error> {
         let bsk = self.basket(bk);
         if let Some(Kid::Rqtd) = bsk.kids.get(&Loc::Phi) {
             if !bsk.kids.values().any(|k| matches!(&k, Kid::Wait(_, _))) {
                 let obj = self.object(bsk.ob);
-                if let Some((n, func)) = &obj.lambda {
-                    let name = n.clone();
-                    perf.hit(Transition::DLG);
-                    if let Some(d) = func(self, bk) {
-                        perf.atom(name);
-                        let _ = &self.baskets[bk as usize].put(Loc::Phi, Kid::Dtzd(d));
-                        trace!("delegate(β{}) -> 0x{:04X}", bk, d);
+                if let Some(id) = obj.lambda {
+                    let ob = bsk.ob;
+                    let m = D::atoms().get(id).ok_or_else(|| {
+                        Error::Failed(format!(
+                            "The atom #{} of ν{} is not known to this data, see atom::register()",
+                            id, ob
+                        ))
+                    })?;
+                    let name = m.name;
+                    if !m.pure && self.speculative.contains_key(&bk) {
+                        perf.tick(Transition::DLG);
+                        return Ok(());
+                    }
+                    let func = self.atom(ob, m)?;
+                    self.record(perf, Transition::DLG, bk, None);
+                    self.reset_reads();
+                    let key = self.memo_key(bk, m);
+                    let known = key.as_ref().and_then(|k| self.recall(k));
+                    match (&key, known) {
+                        (Some(_), Some(_)) => perf.memo_hits += 1,
+                        (Some(_), None) => perf.memo_misses += 1,
+                        _ => {}
+                    }
+                    let made = known.or_else(|| func(self, bk));
+                    if let Some(e) = self.failure.take() {
+                        return Err(e);
+                    }
+                    if let Some(d) = made.map(|d| m.returns.map_or(d, |t| d.typed(t))) {
+                        if let (Some(k), None) = (key, known) {
+                            self.memorize(k, d);
+                        }
+                        self.settle(bk, Loc::Phi, d);
+                        self.spread_taint(bk, &Loc::Phi, Taint::Args(m.args));
+                        if self.tracks() {
+                            self.trace_origin(
+                                bk,
+                                Loc::Phi,
+                                Origin::Atom(name.to_string()),
+                                d.data(),
+                            );
+                        }
+                        if let Some(s) = self.streaming() {
+                            s.atom(perf.cycles, bk, ob, name, d.data());
+                        }
+                        if known.is_none() {
+                            perf.atom(name);
+                        }
+                        if self.logs(EmuLog::Results) {
+                            trace!("delegate(β{}) -> 0x{:04X}", bk, d);
+                        }
                     }
                 }
             }
         }
         perf.tick(Transition::DLG);
+        Ok(())
+    }
+
+    /// Start both branches of `bool-if` before its condition is known,
+    /// and cancel the losing one as soon as the condition is dataized.
+    pub fn speculate(&mut self, perf: &mut Perf, bk: Bk) {
+        let bsk = self.basket(bk);
+        let obj = self.object(bsk.ob);
+        if obj.atom().is_none_or(|m| m.name != "bool-if") {
+            perf.tick(Transition::SPC);
+            return;
+        }
+        if let Some(Kid::Dtzd(term)) = bsk.kids.get(&Loc::Rho) {
+            let win = Loc::Attr(if self.truth(*term) { 0 } else { 1 });
+            let lose = Loc::Attr(if self.truth(*term) { 1 } else { 0 });
+            if matches!(
+                bsk.kids.get(&lose),
+                Some(Kid::Rqtd) | Some(Kid::Need(_, _)) | Some(Kid::Wait(_, _))
+            ) {
+                let _ = &self.baskets[bk.index()].put(lose.clone(), Kid::Empt);
+                for i in 0..self.baskets.len() {
+                    for kid in self.baskets[i].kids.values_mut() {
+                        if matches!(kid, Kid::Wait(b, l) if *b == bk && *l == lose) {
+                            *kid = Kid::Empt;
+                        }
+                    }
+                }
+                let wasted = self.discard_unreachable();
+                if self.logs(EmuLog::Transitions) {
+                    trace!(
+                        "speculate(β{}) -> {} cancelled, {} baskets wasted",
+                        bk,
+                        lose,
+                        wasted
+                    );
+                }
+                perf.wasted += wasted;
+                self.record(perf, Transition::SPC, bk, Some(lose));
+            }
+            self.confirm(bk, &win);
+        } else if let Some(Kid::Rqtd) = bsk.kids.get(&Loc::Phi) {
+            for i in 0..2 {
+                let loc = Loc::Attr(i);
+                if let Some(Kid::Empt) = self.basket(bk).kids.get(&loc) {
+                    let _ = &self.baskets[bk.index()].put(loc.clone(), Kid::Rqtd);
+                    if self.logs(EmuLog::Transitions) {
+                        trace!("speculate(β{}) -> {} requested", bk, loc);
+                    }
+                    perf.speculated += 1;
+                    self.record(perf, Transition::SPC, bk, Some(loc));
+                }
+            }
+        }
+        perf.tick(Transition::SPC);
+    }
+
+    /// The branch of the `bool-if` in the basket is chosen, so its baskets
+    /// are speculative only if the `bool-if` itself is.
+    fn confirm(&mut self, bk: Bk, win: &Loc) {
+        if self.speculative.is_empty() {
+            return;
+        }
+        let outer = self.speculative.get(&bk).cloned();
+        let mut confirmed = vec![];
+        for (b, origin) in self.speculative.iter() {
+            if origin.0 == bk && origin.1 == *win {
+                confirmed.push(*b);
+            }
+        }
+        for b in confirmed {
+            match &outer {
+                Some(o) => self.speculative.insert(b, o.clone()),
+                None => self.speculative.remove(&b),
+            };
+        }
+    }
+
+    /// The speculative branch a new basket made for the kid of the basket
+    /// runs for: the kid itself, if it's a branch of `bool-if` whose
+    /// condition is not known yet, or the one of the basket.
+    fn speculation(&self, bk: Bk, loc: &Loc) -> Option<(Bk, Loc)> {
+        let bsk = self.basket(bk);
+        let branch = matches!(loc, Loc::Attr(0) | Loc::Attr(1))
+            && self
+                .object(bsk.ob)
+                .atom()
+                .is_some_and(|m| m.name == "bool-if")
+            && !matches!(bsk.kids.get(&Loc::Rho), Some(Kid::Dtzd(_)));
+        if branch {
+            Some((bk, loc.clone()))
+        } else {
+            self.speculative.get(&bk).cloned()
+        }
+    }
+
+    /// Discard all baskets that can't be reached from the root one,
+    /// returning the number of baskets discarded.
+    fn discard_unreachable(&mut self) -> usize {
+        let mut alive = [false; MAX_BASKETS];
+        alive[ROOT_BK.index()] = true;
+        let mut todo = vec![ROOT_BK];
+        while let Some(bk) = todo.pop() {
+            let bsk = self.basket(bk);
+            let mut next = vec![bsk.psi];
+            for kid in bsk.kids.values() {
+                match kid {
+                    Kid::Wait(b, _) => next.push(*b),
+                    Kid::Need(_, p) => next.push(*p),
+                    _ => {}
+                }
+            }
+            for b in next {
+                if !b.is_none() && !alive[b.index()] && !self.basket(b).is_empty() {
+                    alive[b.index()] = true;
+                    todo.push(b);
+                }
+            }
+        }
+        let mut total = 0;
+        for (i, live) in alive.iter().enumerate() {
+            if !live && !self.baskets[i].is_empty() {
+                self.free(Bk::new(i));
+                if self.logs(EmuLog::Transitions) {
+                    trace!("sweep(β{})", i);
+                }
+                total += 1;
+            }
+        }
+        total
     }
 
     /// Make new basket for this attribute.
-    pub fn find(&mut self, perf: &mut Perf, bk: Bk, loc: Loc) {
+    pub fn find(&mut self, perf: &mut Perf, bk: Bk, loc: Loc) -> Result<(), Error> {
         if let Some(Kid::Rqtd) = self.basket(bk).kids.get(&loc) {
             let ob = self.basket(bk).ob;
             let obj = self.object(ob);
             if let Some((locator, advice)) = obj.attrs.get(&loc) {
-                let (tob, psi, attr) = self
-                    .search(bk, locator)
-                    .unwrap_or_else(|_| panic!("Can't find {} from β{}/ν{}", locator, bk, ob));
+                let fallback;
+                let plan = match self.plans.get(&(ob, loc.clone())) {
+                    Some(p) => p,
+                    None => {
+                        fallback = Plan::of(locator);
+                        &fallback
+                    }
+                };
+                let (tob, psi, attr) = self.search(bk, locator, plan)?;
                 let tpsi = if *advice { bk } else { psi };
+                let attr = attr.filter(|(pbk, ploc)| {
+                    *ploc == Loc::Root || self.basket(*pbk).kids.contains_key(ploc)
+                });
                 if let Some((pbk, ploc)) = attr {
                     let bsk = self.basket(pbk);
                     if let Some(Kid::Empt) = bsk.kids.get(&ploc) {
-                        let _ = &self.baskets[pbk as usize]
+                        let _ = &self.baskets[pbk.index()]
                             .put(ploc.clone(), Kid::Wait(bk, loc.clone()));
-                        let _ = &self.baskets[bk as usize].put(loc.clone(), Kid::Need(tob, tpsi));
+                        let _ = &self.baskets[bk.index()].put(loc.clone(), Kid::Need(tob, tpsi));
                     } else {
-                        let _ = &self.baskets[bk as usize]
+                        let _ = &self.baskets[bk.index()]
                             .put(loc.clone(), Kid::Wait(pbk, ploc.clone()));
                     }
                 } else {
-                    let _ = &self.baskets[bk as usize].put(loc.clone(), Kid::Need(tob, tpsi));
+                    let _ = &self.baskets[bk.index()].put(loc.clone(), Kid::Need(tob, tpsi));
                 }
-                perf.hit(Transition::FIND);
+                self.record(perf, Transition::FIND, bk, Some(loc.clone()));
             }
         }
         perf.tick(Transition::FIND);
+        Ok(())
     }
 
     /// Make new basket for this attribute.
     #[allow(clippy::new_ret_no_self)]
-    pub fn new(&mut self, perf: &mut Perf, bk: Bk, loc: Loc) {
-        if let Some(Kid::Need(tob, psi)) = self.basket(bk).kids.get(&loc) {
+    pub fn new(&mut self, perf: &mut Perf, bk: Bk, loc: Loc) -> Result<(), Error> {
+        if let Some(&Kid::Need(tob, psi)) = self.basket(bk).kids.get(&loc) {
             let ob = self.basket(bk).ob;
-            let nbk = if let Some(ebk) = self.stashed(*tob, *psi) {
-                trace!("new(β{}/ν{}, {}) -> link to stashed β{}", bk, ob, loc, ebk);
+            let nbk = if let Some(ebk) = self.stashed(tob, psi) {
+                if self.logs(EmuLog::Transitions) {
+                    trace!("new(β{}/ν{}, {}) -> link to stashed β{}", bk, ob, loc, ebk);
+                }
                 ebk
             } else {
                 let id = self
                     .baskets
                     .iter()
                     .find_position(|b| b.is_empty())
-                    .unwrap_or_else(|| {
-                        panic!("No more empty baskets left in the pool of {}", MAX_BASKETS)
-                    })
-                    .0 as Bk;
-                let mut bsk = Basket::start(*tob, *psi);
-                for k in self.object(*tob).attrs.keys() {
-                    bsk.put(k.clone(), Kid::Empt);
+                    .ok_or_else(|| {
+                        Error::Failed(format!(
+                            "No more empty baskets left in the pool of {}",
+                            MAX_BASKETS
+                        ))
+                    })?
+                    .0;
+                let id = Bk::new(id);
+                let keys: Vec<Loc> = self.object(tob).attrs.keys().cloned().collect();
+                let room = self.expected_kids(tob);
+                let bsk = &mut self.baskets[id.index()];
+                bsk.ob = tob;
+                bsk.psi = psi;
+                bsk.kids.reserve(room);
+                for k in keys {
+                    bsk.put(k, Kid::Empt);
                 }
                 bsk.put(Loc::Phi, Kid::Rqtd);
-                self.baskets[id as usize] = bsk;
-                trace!("new(β{}/ν{}, {}) -> β{} created", bk, ob, loc, id);
+                if self.opts.contains(&Opt::Speculate) {
+                    if let Some(origin) = self.speculation(bk, &loc) {
+                        self.speculative.insert(id, origin);
+                    }
+                }
+                if self.logs(EmuLog::Transitions) {
+                    trace!("new(β{}/ν{}, {}) -> β{} created", bk, ob, loc, id);
+                }
                 id
             };
-            perf.hit(Transition::NEW);
-            let _ = &self.baskets[bk as usize].put(loc.clone(), Kid::Wait(nbk, Loc::Phi));
+            let _ = &self.baskets[bk.index()].put(loc.clone(), Kid::Wait(nbk, Loc::Phi));
+            self.record(perf, Transition::NEW, bk, Some(loc.clone()));
         }
         perf.tick(Transition::NEW);
+        Ok(())
     }
 
     /// Suppose, the incoming locator is `^.0.@.2`. We have to find the right
     /// object in the catalog of them and return the position of the found one
-    /// together with the suggested \psi.
+    /// together with the suggested \psi. The steps of the plan compiled for
+    /// the locator are made one by one, the dynamic ones expanding into more.
     #[allow(clippy::type_complexity)]
-    fn search(&self, bk: Bk, locator: &Locator) -> Result<(Ob, Bk, Option<(Bk, Loc)>), String> {
+    fn search(
+        &self,
+        bk: Bk,
+        locator: &Locator,
+        plan: &Plan,
+    ) -> Result<(Ob, Bk, Option<(Bk, Loc)>), Error> {
         let mut bsk = self.basket(bk);
-        let mut attr = None;
-        let mut locs = locator.to_vec();
-        let mut ret = Err("Nothing found".to_string());
-        let mut ob = 0;
+        let mut attr: Option<(Bk, Loc)> = None;
+        let mut todo: Vec<Step> = plan.steps().iter().rev().cloned().collect();
+        let mut ret = Err(Error::Failed(format!(
+            "Can't find {} from β{}/ν{}",
+            locator, bk, bsk.ob
+        )));
+        let mut ob = ROOT_OB;
         let mut log = vec![];
         let mut psi: Bk = bsk.psi;
-        ret = loop {
-            if locs.is_empty() {
-                break ret;
+        let mut at = bk;
+        let mut loose = false;
+        let mut fallen: Vec<(Loc, Ob)> = vec![];
+        let mut chain: Vec<Ob> = vec![];
+        let limit = self.max_hops();
+        let mut hops = 0;
+        while let Some(step) = todo.pop() {
+            hops += 1;
+            if hops > limit {
+                if let Some(end) = (1..chain.len()).find(|i| chain[..*i].contains(&chain[*i])) {
+                    chain.truncate(end + 1);
+                }
+                return Err(Error::TooManyHops {
+                    from: self.basket(bk).ob,
+                    locator: locator.clone(),
+                    limit,
+                    chain,
+                });
             }
-            let loc = locs.remove(0);
-            log.push(loc.to_string());
-            let next = match loc {
-                Loc::Root => ROOT_OB,
-                Loc::Pi => {
+            log.push(step.to_string());
+            let next = match step {
+                Step::Object(i) => i,
+                Step::Psi => {
                     if bsk.psi == ROOT_BK {
-                        return Err(format!("Object Φ doesn't have 𝜋: {}", join!(log)));
+                        return Err(Error::LocatorEscaped {
+                            from: self.basket(bk).ob,
+                            locator: locator.clone(),
+                            stopped_at: bsk.ob,
+                        });
                     }
                     psi = bsk.psi;
+                    at = psi;
+                    loose = false;
                     attr = Some((psi, Loc::Root));
                     bsk = self.basket(psi);
                     log.push(format!("𝜋=β{}/ν{}", psi, bsk.ob));
                     bsk.ob
                 }
-                Loc::Obj(i) => i as Ob,
-                _ => match self.object(ob).attrs.get(&loc) {
+                Step::Sigma => {
+                    self.requires(Change::Sigma).map_err(|e| {
+                        Error::Failed(format!(
+                            "The locator {} of ν{}: {}",
+                            locator,
+                            self.basket(bk).ob,
+                            e
+                        ))
+                    })?;
+                    let here = if log.len() == 1 { bsk.ob } else { ob };
+                    let home = self.home(here).ok_or_else(|| Error::LocatorEscaped {
+                        from: self.basket(bk).ob,
+                        locator: locator.clone(),
+                        stopped_at: here,
+                    })?;
+                    let own = !loose && here == bsk.ob;
+                    let from = if own { bsk.psi } else { at };
+                    if let Some(hbk) = self.home_basket(home, from, own.then_some(at)) {
+                        psi = hbk;
+                        at = hbk;
+                        loose = false;
+                        attr = Some((hbk, Loc::Root));
+                        bsk = self.basket(hbk);
+                        log.push(format!("σ=β{}/ν{}", hbk, home));
+                    } else {
+                        loose = true;
+                        attr = None;
+                        log.push(format!("σ=ν{}", home));
+                    }
+                    home
+                }
+                Step::Mark(loc) => {
+                    fallen.retain(|(l, _)| *l != loc);
+                    if !loose {
+                        let bk_val = attr.map(|(bk, _)| bk).unwrap_or(psi);
+                        attr = Some((bk_val, loc));
+                    }
+                    ob
+                }
+                Step::Fall(loc) => {
+                    fallen.push((loc.clone(), ob));
+                    if !loose {
+                        let bk_val = attr.map(|(bk, _)| bk).unwrap_or(psi);
+                        attr = Some((bk_val, loc));
+                    }
+                    ob
+                }
+                Step::Attr(loc) => match self.object(ob).attrs.get(&loc) {
                     None => match self.object(ob).attrs.get(&Loc::Phi) {
+                        None if loc == Loc::Rho => {
+                            return Err(Error::LocatorEscaped {
+                                from: self.basket(bk).ob,
+                                locator: locator.clone(),
+                                stopped_at: ob,
+                            })
+                        }
                         None => {
-                            return Err(format!(
-                                "Can't find {} in ν{} and there is no 𝜑: {}",
-                                loc,
-                                ob,
-                                join!(log)
-                            ))
+                            let mut chain: Vec<Ob> = fallen
+                                .iter()
+                                .filter(|(l, _)| *l == loc)
+                                .map(|(_, o)| *o)
+                                .collect();
+                            chain.push(ob);
+                            return Err(Error::AttributeNotFound {
+                                attr: loc,
+                                object: chain[0],
+                                locator: locator.clone(),
+                                from: self.basket(bk).ob,
+                                chain,
+                            });
                         }
                         Some((p, _psi)) => {
-                            locs.insert(0, loc.clone());
-                            let bk_val = attr.map(|(bk, _)| bk).unwrap_or(psi);
-                            attr = Some((bk_val, loc));
-                            locs.splice(0..0, p.to_vec());
+                            fallen.push((loc.clone(), ob));
+                            todo.push(Step::Attr(loc.clone()));
+                            if !loose {
+                                let bk_val = attr.map(|(bk, _)| bk).unwrap_or(psi);
+                                attr = Some((bk_val, loc));
+                            }
+                            todo.extend(p.to_vec().iter().rev().map(Step::of));
                             log.push(format!("++{}", p));
                             ob
                         }
                     },
                     Some((p, _psi)) => {
-                        let bk_val = attr.map(|(bk, _)| bk).unwrap_or(psi);
-                        attr = Some((bk_val, loc.clone()));
-                        locs.splice(0..0, p.to_vec());
+                        fallen.retain(|(l, _)| *l != loc);
+                        if !loose {
+                            let bk_val = attr.map(|(bk, _)| bk).unwrap_or(psi);
+                            attr = Some((bk_val, loc.clone()));
+                        }
+                        todo.extend(p.to_vec().iter().rev().map(Step::of));
                         log.push(format!("+{}", p));
                         ob
                     }
                 },
             };
+            if chain.last() != Some(&next) {
+                chain.push(next);
+            }
             ob = next;
             ret = Ok((next, psi, attr.clone()))
-        };
+        }
         if let Ok((next, _psi, _attr)) = ret.clone() {
             if self.object(next).is_empty() {
-                return Err(format!(
+                return Err(Error::Failed(format!(
                     "Object ν{} is found by β{}.{}, but it's empty",
                     next, bk, locator
-                ));
+                )));
             }
         }
         if let Ok((ob, psi, attr_opt)) = &ret {
-            trace!(
-                "find(β{}/ν{}, {}) -> (ν{}, β{}) : {} {}",
-                bk,
-                self.basket(bk).ob,
-                locator,
-                ob,
-                psi,
-                join!(log),
-                if let Some((bk, loc)) = attr_opt {
-                    format!("[β{}.{}]", bk, loc)
-                } else {
-                    "".to_string()
-                }
-            );
+            if self.logs(EmuLog::Transitions) {
+                trace!(
+                    "find(β{}/ν{}, {}) -> (ν{}, β{}) : {} {}",
+                    bk,
+                    self.basket(bk).ob,
+                    locator,
+                    ob,
+                    psi,
+                    join!(log),
+                    if let Some((bk, loc)) = attr_opt {
+                        format!("[β{}.{}]", bk, loc)
+                    } else {
+                        "".to_string()
+                    }
+                );
+            }
         }
         ret
     }
 
+    /// The object the given one is declared in, which is the one with
+    /// an attribute referring to it by its number alone, like `𝛼0 ↦ ν3`;
+    /// the one with the smallest number, if there are a few of them.
+    pub fn home(&self, ob: Ob) -> Option<Ob> {
+        self.objects.iter().enumerate().find_map(|(h, obj)| {
+            obj.attrs
+                .values()
+                .any(|(p, _)| p.locs() == [Loc::Obj(ob)])
+                .then_some(Ob::new(h))
+        })
+    }
+
+    /// The basket of the home object, which is the nearest one with it
+    /// up the ψ chain, starting from the basket `from`, or the one that
+    /// made the basket `kid` and waits for it, if the object was made
+    /// with `(𝜋)` and the ψ chain skips its home.
+    fn home_basket(&self, home: Ob, from: Bk, kid: Option<Bk>) -> Option<Bk> {
+        let mut b = from;
+        loop {
+            let bsk = self.basket(b);
+            if !bsk.is_empty() && bsk.ob == home {
+                return Some(b);
+            }
+            if b == ROOT_BK || bsk.psi.is_none() {
+                break;
+            }
+            b = bsk.psi;
+        }
+        let kid = kid?;
+        self.baskets
+            .iter()
+            .position(|bsk| {
+                !bsk.is_empty()
+                    && bsk.ob == home
+                    && bsk.kids.values().any(|k| *k == Kid::Wait(kid, Loc::Phi))
+            })
+            .map(Bk::new)
+    }
+
     /// Find already existing basket.
     fn stashed(&self, ob: Ob, psi: Bk) -> Option<Bk> {
         if let Some((pos, _bsk)) = self.baskets.iter().find_position(|bsk| {
@@ -278,21 +654,21 @@ impl Emu {
             if obj.delta.is_some() {
                 return true;
             }
-            if !obj.constant {
+            if !obj.cacheable() {
                 return false;
             }
             bsk.psi == psi
         }) {
-            return Some(pos as Bk);
+            return Some(Bk::new(pos));
         }
         None
     }
 
     pub fn object(&self, ob: Ob) -> &Object {
-        &self.objects[ob]
+        &self.objects[ob.index()]
     }
 
-    pub fn basket(&self, bk: Bk) -> &Basket {
-        &self.baskets[bk as usize]
+    pub fn basket(&self, bk: Bk) -> &Basket<D> {
+        &self.baskets[bk.index()]
     }
 }
@@ -0,0 +1,307 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+use crate::basket::Bk;
+use crate::data::Type;
+use crate::loc::Loc;
+use crate::locator::Locator;
+use crate::object::Ob;
+use std::any::Any;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// What may go wrong while loading a program from a file and running it.
+#[derive(Debug)]
+pub enum Error {
+    /// The file can't be read.
+    Io(PathBuf, io::Error),
+    /// The file is read, but its content is broken.
+    Parse(PathBuf, String),
+    /// The file includes itself, directly or through other files.
+    Cycle(PathBuf),
+    /// The command line or an option is wrong.
+    Usage(String),
+    /// The result is not the one expected.
+    Mismatch(String),
+    /// No transitions are possible, while the result is not ready.
+    Stuck(String),
+    /// The dataization took too many cycles.
+    Cycles(String),
+    /// The sandbox doesn't allow the atom, which the object has.
+    AtomForbidden(String, Ob),
+    /// With `Opt::StrictTypes`, the atom of the object got the data of
+    /// a wrong type at the attribute; the stack, deepest first, is the
+    /// baskets that were waiting for it, if they are known.
+    TypeMismatch {
+        atom: String,
+        object: Ob,
+        loc: Loc,
+        expected: Type,
+        found: Type,
+        stack: Vec<(Bk, Ob)>,
+    },
+    /// The locator of the object walked out of the graph: through 𝜋
+    /// of the root basket or through ρ of an object that doesn't have
+    /// it, stopping at that object.
+    LocatorEscaped {
+        from: Ob,
+        locator: Locator,
+        stopped_at: Ob,
+    },
+    /// The locator of the object `from` needed the attribute, but neither
+    /// the object nor any object along its 𝜑 chain has it; the chain is
+    /// all of them, consulted one after another, the last one with no 𝜑.
+    AttributeNotFound {
+        attr: Loc,
+        object: Ob,
+        locator: Locator,
+        from: Ob,
+        chain: Vec<Ob>,
+    },
+    /// Resolving the locator of the object took more steps than the
+    /// limit, see `Opt::MaxResolutionHops`; the chain is the objects it
+    /// visited, up to the first one visited again, if there is one.
+    TooManyHops {
+        from: Ob,
+        locator: Locator,
+        limit: usize,
+        chain: Vec<Ob>,
+    },
+    /// The dataization failed in some other way.
+    Failed(String),
+}
+
+/// The message of the panic, as `panic!()` made it.
+pub(crate) fn message(e: &(dyn Any + Send)) -> String {
+    if let Some(s) = e.downcast_ref::<String>() {
+        s.clone()
+    } else if let Some(s) = e.downcast_ref::<&str>() {
+        s.to_string()
+    } else {
+        "Dataization failed".to_string()
+    }
+}
+
+impl Error {
+    /// The exit code of a command that failed this way.
+    pub fn code(&self) -> i32 {
+        match self {
+            Error::Mismatch(_) => 2,
+            Error::Parse(_, _) | Error::Cycle(_) => 3,
+            Error::Stuck(_) => 4,
+            Error::Cycles(_) => 5,
+            Error::AtomForbidden(_, _) => 6,
+            Error::TypeMismatch { .. } => 7,
+            Error::LocatorEscaped { .. } => 8,
+            Error::AttributeNotFound { .. } => 9,
+            Error::TooManyHops { .. } => 10,
+            Error::Io(_, _) | Error::Usage(_) | Error::Failed(_) => 1,
+        }
+    }
+}
+
+/// The error of reading a file is copied with its kind and message only.
+impl Clone for Error {
+    fn clone(&self) -> Self {
+        match self {
+            Error::Io(path, e) => Error::Io(path.clone(), io::Error::new(e.kind(), e.to_string())),
+            Error::Parse(path, msg) => Error::Parse(path.clone(), msg.clone()),
+            Error::Cycle(path) => Error::Cycle(path.clone()),
+            Error::Usage(msg) => Error::Usage(msg.clone()),
+            Error::Mismatch(msg) => Error::Mismatch(msg.clone()),
+            Error::Stuck(msg) => Error::Stuck(msg.clone()),
+            Error::Cycles(msg) => Error::Cycles(msg.clone()),
+            Error::AtomForbidden(name, ob) => Error::AtomForbidden(name.clone(), *ob),
+            Error::TypeMismatch {
+                atom,
+                object,
+                loc,
+                expected,
+                found,
+                stack,
+            } => Error::TypeMismatch {
+                atom: atom.clone(),
+                object: *object,
+                loc: loc.clone(),
+                expected: *expected,
+                found: *found,
+                stack: stack.clone(),
+            },
+            Error::LocatorEscaped {
+                from,
+                locator,
+                stopped_at,
+            } => Error::LocatorEscaped {
+                from: *from,
+                locator: locator.clone(),
+                stopped_at: *stopped_at,
+            },
+            Error::AttributeNotFound {
+                attr,
+                object,
+                locator,
+                from,
+                chain,
+            } => Error::AttributeNotFound {
+                attr: attr.clone(),
+                object: *object,
+                locator: locator.clone(),
+                from: *from,
+                chain: chain.clone(),
+            },
+            Error::TooManyHops {
+                from,
+                locator,
+                limit,
+                chain,
+            } => Error::TooManyHops {
+                from: *from,
+                locator: locator.clone(),
+                limit: *limit,
+                chain: chain.clone(),
+            },
+            Error::Failed(msg) => Error::Failed(msg.clone()),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "Failed to read file '{}': {}", path.display(), e),
+            Error::Parse(path, msg) => write!(f, "Can't parse '{}': {}", path.display(), msg),
+            Error::Cycle(path) => write!(f, "Circular +include of '{}'", path.display()),
+            Error::AtomForbidden(name, ob) => {
+                write!(f, "The atom '{}' is forbidden in ν{}", name, ob)
+            }
+            Error::TypeMismatch {
+                atom,
+                object,
+                loc,
+                expected,
+                found,
+                stack,
+            } => {
+                write!(
+                    f,
+                    "The atom '{}' of ν{} expects {} at {}, but got {}",
+                    atom, object, expected, loc, found
+                )?;
+                for (bk, ob) in stack {
+                    write!(f, "\n  in β{} ν{}", bk, ob)?;
+                }
+                Ok(())
+            }
+            Error::LocatorEscaped {
+                from,
+                locator,
+                stopped_at,
+            } => write!(
+                f,
+                "The locator {} of ν{} escaped the graph at ν{}",
+                locator, from, stopped_at
+            ),
+            Error::AttributeNotFound {
+                attr,
+                object,
+                locator,
+                from,
+                chain,
+            } => {
+                write!(
+                    f,
+                    "The attribute {} of ν{} is not found by the locator {} of ν{}",
+                    attr, object, locator, from
+                )?;
+                write!(f, "\nWhy was {} of ν{} not found?", attr, object)?;
+                for (i, ob) in chain.iter().enumerate() {
+                    if i + 1 < chain.len() {
+                        write!(f, "\n  ν{} has no {}, so its 𝜑 is consulted", ob, attr)?;
+                    } else {
+                        write!(f, "\n  ν{} has no {} and no 𝜑", ob, attr)?;
+                    }
+                }
+                Ok(())
+            }
+            Error::TooManyHops {
+                from,
+                locator,
+                limit,
+                chain,
+            } => write!(
+                f,
+                "The locator {} of ν{} made more than {} hops, see the max-resolution-hops option: {}",
+                locator,
+                from,
+                limit,
+                chain
+                    .iter()
+                    .map(|o| format!("ν{}", o))
+                    .collect::<Vec<String>>()
+                    .join(" → ")
+            ),
+            Error::Usage(msg)
+            | Error::Mismatch(msg)
+            | Error::Stuck(msg)
+            | Error::Cycles(msg)
+            | Error::Failed(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for String {
+    fn from(e: Error) -> String {
+        e.to_string()
+    }
+}
+
+#[test]
+fn prints_io_error() {
+    let e = Error::Io(
+        PathBuf::from("a.phi"),
+        io::Error::new(io::ErrorKind::NotFound, "oops"),
+    );
+    assert_eq!("Failed to read file 'a.phi': oops", e.to_string());
+}
+
+#[test]
+fn converts_to_string() {
+    let s: String = Error::Cycle(PathBuf::from("x.phi")).into();
+    assert!(s.contains("Circular +include"));
+}
+
+#[test]
+fn explains_missing_attributes() {
+    let err = Error::AttributeNotFound {
+        attr: Loc::Attr(1),
+        object: Ob::new(9),
+        locator: "𝜋.𝛼0.𝛼1".parse().unwrap(),
+        from: Ob::new(3),
+        chain: vec![Ob::new(9), Ob::new(10)],
+    };
+    let msg = err.to_string();
+    assert_eq!(
+        "Why was 𝛼1 of ν9 not found?\n  ν9 has no 𝛼1, so its 𝜑 is consulted\n  ν10 has no 𝛼1 and no 𝜑",
+        msg.split_once('\n').unwrap().1
+    );
+    assert_eq!(9, err.code());
+    assert_eq!(msg, err.clone().to_string());
+}
+
+#[test]
+fn tells_message_of_panic() {
+    let e = std::panic::catch_unwind(|| panic!("The {} is broken", "atom")).unwrap_err();
+    assert_eq!("The atom is broken", message(e.as_ref()));
+    let e = std::panic::catch_unwind(|| std::panic::panic_any(42)).unwrap_err();
+    assert_eq!("Dataization failed", message(e.as_ref()));
+}
@@ -0,0 +1,260 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! A facade for services that dataize parts of programs again and
+//! again: the `Evaluator` keeps the results of the latest queries, by
+//! the fingerprint of the program, the entry locator and the bindings
+//! of the inputs, and answers the same query without emulation, until
+//! it's pushed out by newer ones:
+//!
+//! ```
+//! use phie::evaluator::Evaluator;
+//! use phie::program::Program;
+//! let prog: Program = "
+//!   +input x ν1
+//!   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+//!   ν1(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+//!   ν2(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν1(𝜋) ⟧
+//! ".parse().unwrap();
+//! let mut ev = Evaluator::new(16);
+//! let x = vec![("x".to_string(), 5)];
+//! assert_eq!(-5, ev.evaluate(&prog, "ν2", &x).unwrap());
+//! assert_eq!(-5, ev.evaluate(&prog, "ν2", &x).unwrap());
+//! assert_eq!(7, ev.evaluate(&prog, "ν1", &vec![]).unwrap());
+//! assert_eq!((1, 2), (ev.hits, ev.misses));
+//! ```
+//!
+//! Failures are not kept, they are emulated every time they are asked.
+//! The entry can't start at `ν0`, since `ν0` is replaced by the entry.
+
+use crate::data::Data;
+use crate::emu::{Bindings, ROOT_OB};
+use crate::error::Error;
+use crate::loc::Loc;
+use crate::locator::Locator;
+use crate::program::Program;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::str::FromStr;
+
+/// What a result is kept by: the fingerprint of the program with its
+/// entry, see `Program::fingerprint_at()`, its options, the entry and
+/// the bindings, the latest one of a name winning, as in `Emu::bind()`.
+type Key = (u64, String, String, BTreeMap<String, Data>);
+
+pub struct Evaluator {
+    capacity: usize,
+    /// The results with the moments they were used last.
+    results: HashMap<Key, (Data, u64)>,
+    /// The keys with the moments they were used, the earliest first;
+    /// a key used again later is stale here, and is skipped.
+    order: VecDeque<(u64, Key)>,
+    /// The moment of the latest use of a result.
+    now: u64,
+    /// Queries answered from the cache.
+    pub hits: usize,
+    /// Queries answered by emulation.
+    pub misses: usize,
+}
+
+impl Evaluator {
+    /// Make an evaluator that keeps up to this many results.
+    pub fn new(capacity: usize) -> Evaluator {
+        Evaluator {
+            capacity,
+            results: HashMap::new(),
+            order: VecDeque::new(),
+            now: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Dataize the object the entry locator points to, like `ν5`, with
+    /// the inputs bound to the data, see `Program::dataize_at()`, unless
+    /// the result is already known.
+    pub fn evaluate(
+        &mut self,
+        prog: &Program,
+        entry: &str,
+        bindings: &Bindings,
+    ) -> Result<Data, Error> {
+        let locator = Locator::from_str(entry).map_err(Error::Usage)?;
+        if locator.loc(0) == Some(&Loc::Obj(ROOT_OB)) {
+            return Err(Error::Usage(format!(
+                "The entry {} can't start at ν0, dataize the program itself",
+                locator
+            )));
+        }
+        let key = (
+            prog.fingerprint_at(&locator),
+            header(prog),
+            locator.to_string(),
+            bindings.iter().cloned().collect(),
+        );
+        if let Some((d, _)) = self.results.get(&key).copied() {
+            self.hits += 1;
+            self.touch(key);
+            return Ok(d);
+        }
+        self.misses += 1;
+        let (d, _) = prog.dataize_at(&locator, bindings)?;
+        self.keep(key, d);
+        Ok(d)
+    }
+
+    /// How many results are kept now.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// There are no results kept.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Forget all results.
+    pub fn clear(&mut self) {
+        self.results.clear();
+        self.order.clear();
+    }
+
+    /// Mark the kept result as the most recently used one. The stale
+    /// keys are dropped from the order once they are as many as the
+    /// results, so that it takes constant time on average.
+    fn touch(&mut self, key: Key) {
+        self.now += 1;
+        if let Some(r) = self.results.get_mut(&key) {
+            r.1 = self.now;
+            self.order.push_back((self.now, key));
+        }
+        if self.order.len() > 2 * self.results.len() {
+            let results = &self.results;
+            self.order
+                .retain(|(t, k)| results.get(k).is_some_and(|r| r.1 == *t));
+        }
+    }
+
+    /// Keep the result, pushing out the least recently used ones.
+    fn keep(&mut self, key: Key, d: Data) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.results.len() >= self.capacity {
+            match self.order.pop_front() {
+                Some((t, old)) => {
+                    if self.results.get(&old).is_some_and(|r| r.1 == t) {
+                        self.results.remove(&old);
+                    }
+                }
+                None => break,
+            };
+        }
+        self.now += 1;
+        self.order.push_back((self.now, key.clone()));
+        self.results.insert(key, (d, self.now));
+    }
+}
+
+/// The lines of the program that change how it's dataized, but are
+/// not in its fingerprint.
+fn header(prog: &Program) -> String {
+    prog.semantics()
+        .map(|v| format!("+semantics {}\n", v))
+        .into_iter()
+        .chain(prog.arith().map(|a| format!("+arith {}\n", a)))
+        .chain(prog.opts().iter().map(|o| format!("+opt {}\n", o)))
+        .collect()
+}
+
+#[cfg(test)]
+fn sum() -> Program {
+    "+input x ν1\nν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧\n\
+        ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧"
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn pushes_out_least_recently_used() {
+    let prog = sum();
+    let mut ev = Evaluator::new(2);
+    let x = |d| vec![("x".to_string(), d)];
+    assert_eq!(2, ev.evaluate(&prog, "ν2", &x(1)).unwrap());
+    assert_eq!(4, ev.evaluate(&prog, "ν2", &x(2)).unwrap());
+    assert_eq!(2, ev.evaluate(&prog, "ν2", &x(1)).unwrap());
+    assert_eq!(6, ev.evaluate(&prog, "ν2", &x(3)).unwrap());
+    assert_eq!(2, ev.len());
+    assert_eq!((1, 3), (ev.hits, ev.misses));
+    ev.evaluate(&prog, "ν2", &x(1)).unwrap();
+    ev.evaluate(&prog, "ν2", &x(2)).unwrap();
+    assert_eq!((2, 4), (ev.hits, ev.misses));
+}
+
+#[test]
+fn tells_programs_apart() {
+    let mut ev = Evaluator::new(8);
+    let mut other = sum();
+    other.opt(crate::emu::Opt::Memoize);
+    assert_eq!(14, ev.evaluate(&sum(), "ν2", &vec![]).unwrap());
+    assert_eq!(14, ev.evaluate(&other, "ν2", &vec![]).unwrap());
+    assert_eq!(7, ev.evaluate(&sum(), "ν1", &vec![]).unwrap());
+    assert_eq!(0, ev.hits);
+    assert!(ev
+        .evaluate(&sum(), "ν2", &vec![("y".to_string(), 1)])
+        .is_err());
+    assert!(ev.evaluate(&sum(), "ν2 ν3", &vec![]).is_err());
+    assert_eq!(3, ev.len());
+}
+
+#[test]
+fn refuses_entry_at_root() {
+    let mut ev = Evaluator::new(8);
+    let err = ev.evaluate(&sum(), "ν0", &vec![]).unwrap_err();
+    assert!(err.to_string().contains("can't start at ν0"), "{}", err);
+}
+
+#[test]
+fn tells_renumbered_programs_apart() {
+    let mut ev = Evaluator::new(8);
+    let first: Program = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧\nν2(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧"
+        .parse()
+        .unwrap();
+    let second: Program = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧\nν2(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧"
+        .parse()
+        .unwrap();
+    assert_eq!(first.fingerprint(), second.fingerprint());
+    assert_eq!(2, ev.evaluate(&first, "ν2", &vec![]).unwrap());
+    assert_eq!(1, ev.evaluate(&second, "ν2", &vec![]).unwrap());
+}
+
+#[test]
+fn keeps_order_through_many_hits() {
+    let prog = sum();
+    let mut ev = Evaluator::new(2);
+    let x = |d| vec![("x".to_string(), d)];
+    ev.evaluate(&prog, "ν2", &x(1)).unwrap();
+    ev.evaluate(&prog, "ν2", &x(2)).unwrap();
+    for _ in 0..10 {
+        ev.evaluate(&prog, "ν2", &x(1)).unwrap();
+    }
+    assert!(ev.order.len() <= 4, "{}", ev.order.len());
+    ev.evaluate(&prog, "ν2", &x(3)).unwrap();
+    ev.evaluate(&prog, "ν2", &x(1)).unwrap();
+    assert_eq!((11, 3), (ev.hits, ev.misses));
+}
+
+#[test]
+fn keys_repeated_names_by_latest() {
+    let prog = sum();
+    let mut ev = Evaluator::new(8);
+    let first = vec![("x".to_string(), 1), ("x".to_string(), 2)];
+    let second = vec![("x".to_string(), 2), ("x".to_string(), 1)];
+    assert_eq!(4, ev.evaluate(&prog, "ν2", &first).unwrap());
+    assert_eq!(2, ev.evaluate(&prog, "ν2", &second).unwrap());
+    assert_eq!(
+        4,
+        ev.evaluate(&prog, "ν2", &vec![("x".to_string(), 2)])
+            .unwrap()
+    );
+    assert_eq!((1, 2), (ev.hits, ev.misses));
+}
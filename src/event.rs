@@ -0,0 +1,211 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+use crate::basket::Bk;
+use crate::loc::{latex, Loc};
+use crate::object::Ob;
+use crate::perf::Transition;
+use itertools::Itertools;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A single transition that happened to a basket, recorded
+/// when `Opt::RecordEvents` is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub cycle: usize,
+    pub kind: Transition,
+    pub bk: Bk,
+    pub ob: Ob,
+    pub loc: Option<Loc>,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "#{} {} β{}/ν{}{}",
+            self.cycle,
+            self.kind,
+            self.bk,
+            self.ob,
+            self.loc
+                .as_ref()
+                .map(|l| format!(".{}", l))
+                .unwrap_or_default()
+        )
+    }
+}
+
+/// Which events to take from the log of the `Emu`:
+///
+/// ```
+/// use phie::emu::{Emu, Opt};
+/// use phie::event::EventFilter;
+/// use phie::perf::Transition;
+/// let mut emu: Emu = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+/// emu.opt(Opt::RecordEvents);
+/// emu.dataize();
+/// assert_eq!(1, emu.events(EventFilter::any().kind(Transition::CPY)).count());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    ob: Option<Ob>,
+    bk: Option<Bk>,
+    kind: Option<Transition>,
+}
+
+impl EventFilter {
+    /// A filter that lets all events through.
+    pub fn any() -> EventFilter {
+        EventFilter::default()
+    }
+
+    /// Only events that happened to baskets of this object.
+    pub fn object(mut self, ob: Ob) -> EventFilter {
+        self.ob = Some(ob);
+        self
+    }
+
+    /// Only events that happened to this basket.
+    pub fn basket(mut self, bk: Bk) -> EventFilter {
+        self.bk = Some(bk);
+        self
+    }
+
+    /// Only events of this kind.
+    pub fn kind(mut self, kind: Transition) -> EventFilter {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Does this event pass the filter?
+    pub fn accepts(&self, event: &Event) -> bool {
+        self.ob.is_none_or(|ob| ob == event.ob)
+            && self.bk.is_none_or(|bk| bk == event.bk)
+            && self.kind.as_ref().is_none_or(|k| *k == event.kind)
+    }
+}
+
+/// The events of a dataization, cycle by cycle, see `Opt::RecordEvents`:
+///
+/// ```
+/// use phie::emu::{Emu, Opt};
+/// use phie::event::{EventFilter, Trace};
+/// let mut emu: Emu = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+/// emu.opt(Opt::RecordEvents);
+/// emu.dataize();
+/// let trace = Trace::of(emu.events(EventFilter::any()));
+/// assert!(trace.to_latex().contains("0 & $\\textsc{cpy}(\\beta_{0})$ \\\\"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    cycles: BTreeMap<usize, Vec<Event>>,
+}
+
+impl Trace {
+    pub fn of<'a>(events: impl IntoIterator<Item = &'a Event>) -> Trace {
+        let mut cycles: BTreeMap<usize, Vec<Event>> = BTreeMap::new();
+        for e in events {
+            cycles.entry(e.cycle).or_default().push(e.clone());
+        }
+        Trace { cycles }
+    }
+
+    /// The number of cycles with events.
+    pub fn len(&self) -> usize {
+        self.cycles.len()
+    }
+
+    /// There are no events at all.
+    pub fn is_empty(&self) -> bool {
+        self.cycles.is_empty()
+    }
+
+    /// Print the events in a `tabular` for LaTeX, one row per cycle,
+    /// with the transitions in small capitals, in the order they were
+    /// made, see `loc::latex()`.
+    pub fn to_latex(&self) -> String {
+        let rows = self.cycles.iter().map(|(cycle, events)| {
+            let made = events
+                .iter()
+                .map(|e| {
+                    let at = e
+                        .loc
+                        .as_ref()
+                        .map(|l| format!("β{}.{}", e.bk, l))
+                        .unwrap_or_else(|| format!("β{}", e.bk));
+                    format!(
+                        "$\\textsc{{{}}}({})$",
+                        e.kind.to_string().to_lowercase(),
+                        latex(&at)
+                    )
+                })
+                .join(", ");
+            format!("{} & {} \\\\", cycle, made)
+        });
+        format!(
+            "\\begin{{tabular}}{{r|l}}\n\\textit{{cycle}} & \\textit{{transitions}} \\\\ \\hline\n{}\n\\end{{tabular}}",
+            rows.collect::<Vec<String>>().join("\n")
+        )
+    }
+}
+
+#[cfg(test)]
+fn event(kind: Transition, bk: usize, ob: usize) -> Event {
+    Event {
+        cycle: 0,
+        kind,
+        bk: Bk::new(bk),
+        ob: Ob::new(ob),
+        loc: None,
+    }
+}
+
+#[test]
+fn accepts_everything_by_default() {
+    assert!(EventFilter::any().accepts(&event(Transition::NEW, 1, 2)));
+}
+
+#[test]
+fn filters_by_all_criteria() {
+    let filter = EventFilter::any()
+        .object(Ob::new(2))
+        .basket(Bk::new(1))
+        .kind(Transition::NEW);
+    assert!(filter.accepts(&event(Transition::NEW, 1, 2)));
+    assert!(!filter.accepts(&event(Transition::CPY, 1, 2)));
+    assert!(!filter.accepts(&event(Transition::NEW, 3, 2)));
+    assert!(!filter.accepts(&event(Transition::NEW, 1, 3)));
+}
+
+#[test]
+fn prints_itself() {
+    let mut e = event(Transition::FIND, 4, 7);
+    e.cycle = 12;
+    e.loc = Some(Loc::Attr(0));
+    assert_eq!("#12 FIND β4/ν7.𝛼0", e.to_string());
+}
+
+#[test]
+fn prints_latex_cycle_by_cycle() {
+    let mut first = event(Transition::FIND, 0, 0);
+    first.cycle = 1;
+    first.loc = Some(Loc::Phi);
+    let mut second = event(Transition::NEW, 0, 0);
+    second.cycle = 1;
+    second.loc = Some(Loc::Phi);
+    let mut third = event(Transition::CPY, 1, 1);
+    third.cycle = 2;
+    let trace = Trace::of(&[first, second, third]);
+    assert_eq!(2, trace.len());
+    let tex = trace.to_latex();
+    assert!(
+        tex.contains(
+            "1 & $\\textsc{find}(\\beta_{0}.\\varphi)$, $\\textsc{new}(\\beta_{0}.\\varphi)$ \\\\\n\
+            2 & $\\textsc{cpy}(\\beta_{1})$ \\\\\n\\end{tabular}"
+        ),
+        "{}",
+        tex
+    );
+}
@@ -0,0 +1,305 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Small programs that exercise the emulator, all with the same
+//! command line: `<input> <repetitions> [--json] [--perf]`.
+//!
+//! Fibonacci makes two recursive calls per level, factorial makes one
+//! and multiplies on the way back, while Ackermann passes the result of
+//! one call as an argument of another, which keeps many baskets waiting.
+//!
+//! ```
+//! use phie::examples::{emulate, FACTORIAL};
+//! assert_eq!(120, emulate(&FACTORIAL, 5).unwrap().0);
+//! ```
+
+use crate::data::{Data, Datum};
+use crate::emu::{Emu, Opt};
+use crate::perf::Perf;
+use crate::program::Program;
+use serde_json::json;
+use std::str::FromStr;
+
+pub struct Example {
+    /// The name of the binary, used in the usage message.
+    pub name: &'static str,
+    /// What the result is called, as in "7-th Fibonacci number is 21".
+    pub title: &'static str,
+    /// The program, for the given input.
+    pub program: fn(Data) -> String,
+}
+
+pub const FIBONACCI: Example = Example {
+    name: "fibonacci",
+    title: "Fibonacci number",
+    program: fibonacci,
+};
+
+pub const FACTORIAL: Example = Example {
+    name: "factorial",
+    title: "factorial",
+    program: factorial,
+};
+
+/// Ackermann function A(2, n), which is 2n + 3.
+pub const ACKERMANN: Example = Example {
+    name: "ackermann",
+    title: "Ackermann number",
+    program: ackermann,
+};
+
+fn fibonacci(x: Data) -> String {
+    format!(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x{:04X} ⟧
+        ν2(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν1(𝜋) ⟧
+        ν3(𝜋) ↦ ⟦ 𝜑 ↦ ν13(𝜋) ⟧
+        ν5(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧
+        ν6(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ 𝜋.𝜋.𝛼0, 𝛼0 ↦ ν5(𝜋) ⟧
+        ν7(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧
+        ν8(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ 𝜋.𝜋.𝛼0, 𝛼0 ↦ ν7(𝜋) ⟧
+        ν9(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν8(𝜋) ⟧
+        ν10(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν6(𝜋) ⟧
+        ν11(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν9(𝜋), 𝛼0 ↦ ν10(𝜋) ⟧
+        ν12(𝜋) ↦ ⟦ λ ↦ int-less, ρ ↦ 𝜋.𝛼0, 𝛼0 ↦ ν5(𝜋) ⟧
+        ν13(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ ν12(𝜋), 𝛼0 ↦ ν7(𝜋), 𝛼1 ↦ ν11(𝜋) ⟧
+        ",
+        x
+    )
+}
+
+fn factorial(x: Data) -> String {
+    format!(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x{:04X} ⟧
+        ν2(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν1(𝜋) ⟧
+        ν3(𝜋) ↦ ⟦ 𝜑 ↦ ν9(𝜋) ⟧
+        ν4(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧
+        ν5(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧
+        ν6(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ 𝜋.𝜋.𝛼0, 𝛼0 ↦ ν5(𝜋) ⟧
+        ν7(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν6(𝜋) ⟧
+        ν8(𝜋) ↦ ⟦ λ ↦ int-times, ρ ↦ 𝜋.𝛼0, 𝛼0 ↦ ν7(𝜋) ⟧
+        ν9(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ ν10(𝜋), 𝛼0 ↦ ν5(𝜋), 𝛼1 ↦ ν8(𝜋) ⟧
+        ν10(𝜋) ↦ ⟦ λ ↦ int-less, ρ ↦ 𝜋.𝛼0, 𝛼0 ↦ ν4(𝜋) ⟧
+        ",
+        x
+    )
+}
+
+fn ackermann(x: Data) -> String {
+    format!(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x{:04X} ⟧
+        ν2(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν5(𝜋), 𝛼1 ↦ ν1(𝜋) ⟧
+        ν3(𝜋) ↦ ⟦ 𝜑 ↦ ν15(𝜋) ⟧
+        ν4(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧
+        ν5(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧
+        ν6(𝜋) ↦ ⟦ λ ↦ int-less, ρ ↦ 𝜋.𝛼0, 𝛼0 ↦ ν4(𝜋) ⟧
+        ν7(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ 𝜋.𝛼1, 𝛼0 ↦ ν4(𝜋) ⟧
+        ν8(𝜋) ↦ ⟦ λ ↦ int-less, ρ ↦ 𝜋.𝛼1, 𝛼0 ↦ ν4(𝜋) ⟧
+        ν9(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ 𝜋.𝜋.𝛼0, 𝛼0 ↦ ν4(𝜋) ⟧
+        ν10(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ 𝜋.𝜋.𝜋.𝛼1, 𝛼0 ↦ ν4(𝜋) ⟧
+        ν11(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ 𝜋.𝜋.𝛼0, 𝛼1 ↦ ν10(𝜋) ⟧
+        ν12(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν9(𝜋), 𝛼1 ↦ ν11(𝜋) ⟧
+        ν13(𝜋) ↦ ⟦ 𝜑 ↦ ν3(ξ), 𝛼0 ↦ ν9(𝜋), 𝛼1 ↦ ν4(𝜋) ⟧
+        ν14(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ ν8(𝜋), 𝛼0 ↦ ν13(𝜋), 𝛼1 ↦ ν12(𝜋) ⟧
+        ν15(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ ν6(𝜋), 𝛼0 ↦ ν7(𝜋), 𝛼1 ↦ ν14(𝜋) ⟧
+        ",
+        x
+    )
+}
+
+/// The options all examples run with, unless the program sets them.
+const DEFAULTS: [Opt; 3] = [
+    Opt::LogSnapshots,
+    Opt::StopWhenTooManyCycles,
+    Opt::StopWhenStuck,
+];
+
+/// Turn on the options all examples run with, unless the program
+/// sets them, and then the ones of the environment.
+pub fn configure<D: Datum>(emu: &mut Emu<D>) -> Result<(), String> {
+    for opt in DEFAULTS {
+        emu.opt_default(opt);
+    }
+    emu.tune(&[])
+}
+
+/// Build the program of the example for this input, with the options
+/// `configure()` turns on added to it.
+pub fn program(example: &Example, input: Data) -> Result<Program, String> {
+    let mut prog = Program::from_str(&(example.program)(input))
+        .map_err(|e| format!("Failed to parse {} emulator: {}", example.name, e))?;
+    for opt in DEFAULTS {
+        let kind = std::mem::discriminant(&opt);
+        if !prog
+            .opts()
+            .iter()
+            .any(|o| std::mem::discriminant(o) == kind)
+        {
+            prog.opt(opt);
+        }
+    }
+    for opt in Opt::from_env()? {
+        prog.opt(opt);
+    }
+    Ok(prog)
+}
+
+/// Dataize with the options all examples run with.
+pub fn dataize(mut emu: Emu) -> Result<(Data, Perf), String> {
+    configure(&mut emu)?;
+    Ok(emu.dataize())
+}
+
+/// Build the program of the example for this input and dataize it.
+pub fn emulate(example: &Example, input: Data) -> Result<(Data, Perf), String> {
+    let emu: Emu = (example.program)(input)
+        .parse()
+        .map_err(|e| format!("Failed to parse {} emulator: {}", example.name, e))?;
+    dataize(emu)
+}
+
+/// The command line of an example.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Args {
+    pub input: Data,
+    pub repetitions: i32,
+    pub json: bool,
+    pub perf: bool,
+}
+
+pub fn parse_args(name: &str, args: &[String]) -> Result<Args, String> {
+    let (flags, rest): (Vec<&String>, Vec<&String>) =
+        args.iter().skip(1).partition(|a| a.starts_with("--"));
+    if let Some(f) = flags
+        .iter()
+        .find(|f| !["--json", "--perf"].contains(&f.as_str()))
+    {
+        return Err(format!("Unknown option '{}'", f));
+    }
+    if rest.len() < 2 {
+        return Err(format!(
+            "Usage: {} <input> <repetitions> [--json] [--perf]",
+            args.first().map(|s| s.as_str()).unwrap_or(name)
+        ));
+    }
+    let input = rest[0]
+        .parse()
+        .map_err(|e| format!("Invalid input argument '{}': {}", rest[0], e))?;
+    let repetitions = rest[1]
+        .parse()
+        .map_err(|e| format!("Invalid repetitions argument '{}': {}", rest[1], e))?;
+    Ok(Args {
+        input,
+        repetitions,
+        json: flags.iter().any(|f| *f == "--json"),
+        perf: flags.iter().any(|f| *f == "--perf"),
+    })
+}
+
+/// Dataize the example again and again, see `Program::evaluate_many()`,
+/// returning the last result, the sum of all of them, and the
+/// performance of the last run.
+pub fn repeat(example: &Example, input: Data, times: i32) -> Result<(Data, Data, Perf), String> {
+    let mut total: Data = 0;
+    let mut last = (0, Perf::new());
+    for run in program(example, input)?.evaluate_many(times.max(0) as usize) {
+        last = run?;
+        total = total.wrapping_add(last.0);
+    }
+    Ok((last.0, total, last.1))
+}
+
+/// Parse the command line, run the example, and render the outcome.
+pub fn run(example: &Example, args: &[String]) -> Result<String, String> {
+    let a = parse_args(example.name, args)?;
+    let (result, total, perf) = repeat(example, a.input, a.repetitions)?;
+    if a.json {
+        let mut out = json!({
+            "example": example.name,
+            "input": a.input,
+            "repetitions": a.repetitions,
+            "result": result,
+            "sum": total
+        });
+        if a.perf {
+            out["perf"] = json!({
+                "cycles": perf.cycles,
+                "peak": perf.peak,
+                "hits": perf.total_hits(),
+                "ticks": perf.total_ticks(),
+                "atoms": perf.total_atoms()
+            });
+        }
+        return Ok(out.to_string());
+    }
+    let mut out = format!(
+        "{}-th {} is {}\nSum of results is {}",
+        a.input, example.title, result, total
+    );
+    if a.perf {
+        out.push_str(&format!("\n{}", perf));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn calculates_factorials() {
+    for (x, f) in [(0, 1), (1, 1), (3, 6), (5, 120), (7, 5040)] {
+        assert_eq!(f, emulate(&FACTORIAL, x).unwrap().0, "{}!", x);
+    }
+}
+
+#[test]
+fn calculates_ackermann_numbers() {
+    for n in 0..3 {
+        assert_eq!(2 * n + 3, emulate(&ACKERMANN, n).unwrap().0, "A(2, {})", n);
+    }
+}
+
+#[test]
+fn calculates_fibonacci_numbers() {
+    assert_eq!(21, emulate(&FIBONACCI, 7).unwrap().0);
+}
+
+#[test]
+fn parses_flags_anywhere() {
+    let a = parse_args("x", &args(&["x", "--perf", "5", "3", "--json"])).unwrap();
+    assert_eq!(
+        Args {
+            input: 5,
+            repetitions: 3,
+            json: true,
+            perf: true
+        },
+        a
+    );
+}
+
+#[test]
+fn rejects_unknown_flags() {
+    let e = parse_args("x", &args(&["x", "5", "3", "--fast"])).unwrap_err();
+    assert!(e.contains("Unknown option"), "{}", e);
+}
+
+#[test]
+fn renders_json() {
+    let out = run(
+        &FACTORIAL,
+        &args(&["factorial", "4", "2", "--json", "--perf"]),
+    )
+    .unwrap();
+    let v: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(24, v["result"]);
+    assert_eq!(48, v["sum"]);
+    assert!(v["perf"]["cycles"].as_u64().unwrap() > 0);
+}
@@ -0,0 +1,123 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Counting of heap allocations, available with the `heap` feature.
+//!
+//! The counting happens in `Counting`, which must be installed as the
+//! global allocator by the binary that wants the numbers:
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static HEAP: phie::heap::Counting = phie::heap::Counting;
+//! ```
+//!
+//! Then parsing of a `Program` and dataization in `Emu` add the bytes
+//! they allocate to the `ledger()`, under "parsing" and "emulation",
+//! while `Perf::allocated` has the bytes of one dataization. The bytes
+//! are counted per thread and never decrease on deallocation, the same
+//! way heaptrack reports them. Without `Counting` installed,
+//! all numbers are zero.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+
+thread_local! {
+    static BYTES: Cell<usize> = const { Cell::new(0) };
+    static LEDGER: RefCell<BTreeMap<&'static str, usize>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// The system allocator, which also counts the bytes it gives out.
+pub struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count(new_size.saturating_sub(layout.size()));
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// The thread may be gone already, then the bytes are not counted.
+fn count(bytes: usize) {
+    let _ = BYTES.try_with(|b| b.set(b.get() + bytes));
+}
+
+/// How many bytes this thread has allocated so far.
+pub fn allocated() -> usize {
+    BYTES.try_with(|b| b.get()).unwrap_or(0)
+}
+
+/// Run the function and add the bytes it allocated to the module
+/// in the ledger, returning them together with its result.
+pub fn account<T>(module: &'static str, f: impl FnOnce() -> T) -> (T, usize) {
+    let before = allocated();
+    let ret = f();
+    let bytes = allocated() - before;
+    LEDGER.with(|l| *l.borrow_mut().entry(module).or_insert(0) += bytes);
+    (ret, bytes)
+}
+
+/// The bytes allocated by each module of this thread, since the start
+/// or the last `reset()`.
+pub fn ledger() -> Vec<(&'static str, usize)> {
+    LEDGER.with(|l| l.borrow().iter().map(|(m, b)| (*m, *b)).collect())
+}
+
+/// Forget the ledger of this thread.
+pub fn reset() {
+    LEDGER.with(|l| l.borrow_mut().clear());
+}
+
+#[cfg(test)]
+#[global_allocator]
+static HEAP: Counting = Counting;
+
+#[cfg(test)]
+use crate::emu::Emu;
+#[cfg(test)]
+use crate::program::Program;
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn counts_allocations() {
+    let (v, bytes) = account("test", || vec![0u8; 1000]);
+    assert_eq!(1000, v.len());
+    assert!(bytes >= 1000, "{}", bytes);
+}
+
+#[test]
+fn splits_parsing_and_emulation() {
+    reset();
+    let prog = Program::from_str(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+        ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧
+        ",
+    )
+    .unwrap();
+    let (d, perf) = Emu::from(prog).dataize();
+    assert_eq!(84, d);
+    assert!(perf.allocated > 0);
+    let ledger = ledger();
+    assert_eq!(
+        vec!["emulation", "parsing"],
+        ledger.iter().map(|(m, _)| *m).collect::<Vec<_>>()
+    );
+    assert!(ledger.iter().all(|(_, b)| *b > 0), "{:?}", ledger);
+}
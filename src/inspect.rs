@@ -0,0 +1,288 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Queries over the baskets saved in a `crash::Dump` or a `Checkpoint`,
+//! for `phie inspect`, to look into a failure without running the
+//! program again:
+//!
+//! ```
+//! use phie::inspect::State;
+//! let state = State::of_checkpoint(&"#5\nβ0 [ν0, ξ:β0, 𝜑⇉β1.𝜑]\nβ1 [ν3, ξ:β0, 𝜑→?]".parse().unwrap());
+//! assert_eq!("β1 [ν3, ξ:β0, 𝜑→?]", state.query("baskets(ob=3)").unwrap());
+//! assert_eq!("β0.𝜑 ⇉β1.𝜑", state.query("kids(state=wait)").unwrap());
+//! assert_eq!("β1 ν3 → β0 ν0", state.query("path(β1)").unwrap());
+//! ```
+//!
+//! The queries are `baskets(...)`, filtered by `bk`, `ob` and `psi`,
+//! `kids(...)`, filtered by `bk`, `ob`, `loc` and `state`, which is
+//! one of `empty`, `requested`, `need`, `wait` and `dataized`, then
+//! `path(βN)`, the chain of baskets from this one to the root through
+//! their `ξ`, and `object(νN)`, if the objects are known. Filters go
+//! inside the parentheses, like `kids(ob=5, state=wait)`.
+
+use crate::basket::{Basket, Bk, Kid};
+use crate::checkpoint::Checkpoint;
+use crate::crash::Dump;
+use crate::emu::ROOT_BK;
+use crate::loc::Loc;
+use crate::object::Ob;
+use crate::program::Program;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+pub struct State {
+    /// The number of cycles made before the baskets were saved.
+    pub cycle: usize,
+    baskets: Vec<(Bk, Basket)>,
+    program: Option<Program>,
+}
+
+impl State {
+    /// The baskets of the checkpoint, without the objects.
+    pub fn of_checkpoint(cp: &Checkpoint) -> State {
+        State {
+            cycle: cp.cycle,
+            baskets: cp.baskets().unwrap_or_default(),
+            program: None,
+        }
+    }
+
+    /// The baskets and the objects of the crash dump.
+    pub fn of_dump(dump: &Dump) -> Result<State, String> {
+        let mut state = State::of_checkpoint(&dump.checkpoint);
+        state.program = Some(Program::from_str(&dump.program)?);
+        Ok(state)
+    }
+
+    /// Read a crash dump, see `Dump::write()`, or a checkpoint, saved
+    /// as `Checkpoint::to_string()` prints it.
+    pub fn read(path: &Path) -> Result<State, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Can't read the state '{}': {}", path.display(), e))?;
+        if text.trim_start().starts_with('{') {
+            State::of_dump(&Dump::from_json(&text)?)
+        } else {
+            Ok(State::of_checkpoint(&Checkpoint::from_str(&text)?))
+        }
+    }
+
+    /// Answer the query, one line per basket, kid or object found.
+    pub fn query(&self, q: &str) -> Result<String, String> {
+        let q = q.trim();
+        let (name, args) = q
+            .strip_suffix(')')
+            .and_then(|t| t.split_once('('))
+            .ok_or_else(|| format!("Can't parse the query '{}', try 'help'", q))?;
+        let args: Vec<&str> = args
+            .split(',')
+            .map(|a| a.trim())
+            .filter(|a| !a.is_empty())
+            .collect();
+        let lines = match name.trim() {
+            "baskets" => self.baskets(&filters(&args, &["bk", "ob", "psi"])?)?,
+            "kids" => self.kids(&filters(&args, &["bk", "ob", "loc", "state"])?)?,
+            "path" => self.path(bk(single(&args)?)?)?,
+            "object" => self.object(ob(single(&args)?)?)?,
+            other => return Err(format!("Unknown query '{}', try 'help'", other)),
+        };
+        Ok(lines.join("\n"))
+    }
+
+    fn baskets(&self, filters: &HashMap<&str, &str>) -> Result<Vec<String>, String> {
+        let want = |key: &str, n: usize| -> Result<bool, String> {
+            Ok(match filters.get(key) {
+                Some(v) => number(v)? == n,
+                None => true,
+            })
+        };
+        let mut lines = vec![];
+        for (b, bsk) in self.baskets.iter() {
+            if want("bk", b.index())?
+                && want("ob", bsk.ob.index())?
+                && want("psi", bsk.psi.index())?
+            {
+                lines.push(format!("β{} {}", b, bsk));
+            }
+        }
+        Ok(lines)
+    }
+
+    fn kids(&self, filters: &HashMap<&str, &str>) -> Result<Vec<String>, String> {
+        let loc = filters.get("loc").map(|l| Loc::from_str(l)).transpose()?;
+        let state = filters.get("state").map(|s| kind(s)).transpose()?;
+        let bk = filters.get("bk").map(|b| number(b)).transpose()?;
+        let ob = filters.get("ob").map(|o| number(o)).transpose()?;
+        let mut lines = vec![];
+        for (b, bsk) in self.baskets.iter() {
+            if bk.is_some_and(|x| x != b.index()) || ob.is_some_and(|x| x != bsk.ob.index()) {
+                continue;
+            }
+            let mut kids: Vec<(&Loc, &Kid)> = bsk.kids.iter().collect();
+            kids.sort_by(|a, b| a.0.cmp(b.0));
+            for (l, kid) in kids {
+                if loc.as_ref().is_some_and(|x| x != l) || state.is_some_and(|s| s != name(kid)) {
+                    continue;
+                }
+                lines.push(format!("β{}.{} {}", b, l, kid));
+            }
+        }
+        Ok(lines)
+    }
+
+    fn path(&self, start: Bk) -> Result<Vec<String>, String> {
+        let mut chain = vec![];
+        let mut bk = start;
+        loop {
+            let bsk = self
+                .baskets
+                .iter()
+                .find(|(b, _)| *b == bk)
+                .map(|(_, bsk)| bsk)
+                .ok_or_else(|| format!("There is no β{} in the state", bk))?;
+            chain.push(format!("β{} ν{}", bk, bsk.ob));
+            if bk == ROOT_BK || bsk.psi == bk || chain.len() > self.baskets.len() {
+                break;
+            }
+            bk = bsk.psi;
+        }
+        Ok(vec![chain.join(" → ")])
+    }
+
+    fn object(&self, ob: Ob) -> Result<Vec<String>, String> {
+        let prog = self
+            .program
+            .as_ref()
+            .ok_or("There are no objects in the state, only baskets")?;
+        let obj = prog
+            .get(ob)
+            .ok_or_else(|| format!("There is no ν{} in the program", ob))?;
+        Ok(vec![format!("ν{}(𝜋) ↦ {}", ob, obj)])
+    }
+}
+
+/// What `phie inspect` prints on `help`.
+pub const HELP: &str = "baskets(bk=N, ob=N, psi=N)\n\
+    kids(bk=N, ob=N, loc=𝛼0, state=empty|requested|need|wait|dataized)\n\
+    path(βN)\n\
+    object(νN)\n\
+    quit";
+
+/// The filters of a query, like `ob=5`, checked against the known keys.
+fn filters<'a>(args: &[&'a str], known: &[&str]) -> Result<HashMap<&'a str, &'a str>, String> {
+    args.iter()
+        .map(|a| {
+            let (k, v) = a
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .ok_or_else(|| format!("The filter '{}' must be like 'key=value'", a))?;
+            if known.contains(&k) {
+                Ok((k, v))
+            } else {
+                Err(format!("Unknown filter '{}', try {}", k, known.join(", ")))
+            }
+        })
+        .collect()
+}
+
+/// The only argument of a query.
+fn single<'a>(args: &[&'a str]) -> Result<&'a str, String> {
+    match args {
+        [one] => Ok(one),
+        _ => Err(format!("One argument is expected, not {}", args.len())),
+    }
+}
+
+/// A number, with or without its `β` or `ν`.
+fn number(s: &str) -> Result<usize, String> {
+    let digits = s.trim_start_matches(['β', 'ν']);
+    digits
+        .parse()
+        .map_err(|e| format!("Can't parse the number '{}': {}", s, e))
+}
+
+fn bk(s: &str) -> Result<Bk, String> {
+    s.trim_start_matches('β').parse()
+}
+
+fn ob(s: &str) -> Result<Ob, String> {
+    s.trim_start_matches('ν').parse()
+}
+
+/// The name of the state of the kid, as the `state` filter takes it.
+fn name(kid: &Kid) -> &'static str {
+    match kid {
+        Kid::Empt => "empty",
+        Kid::Rqtd => "requested",
+        Kid::Need(_, _) => "need",
+        Kid::Wait(_, _) => "wait",
+        Kid::Dtzd(_) => "dataized",
+    }
+}
+
+fn kind(s: &str) -> Result<&'static str, String> {
+    ["empty", "requested", "need", "wait", "dataized"]
+        .into_iter()
+        .find(|k| *k == s)
+        .ok_or_else(|| {
+            format!(
+                "Unknown state '{}', try empty, requested, need, wait or dataized",
+                s
+            )
+        })
+}
+
+#[cfg(test)]
+fn stuck() -> State {
+    use crate::emu::{Emu, Opt};
+    let mut emu: Emu = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧\n\
+        ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧\nν3(𝜋) ↦ ⟦ 𝜑 ↦ ν3(𝜋) ⟧"
+        .parse()
+        .unwrap();
+    emu.opt(Opt::MaxCycles(20));
+    let err = emu.try_dataize().err().unwrap().to_string();
+    State::of_dump(&Dump::of(&emu, &err, 21)).unwrap()
+}
+
+#[test]
+fn queries_baskets_of_dump() {
+    let state = stuck();
+    let all = state.query("baskets()").unwrap();
+    assert!(all.starts_with("β0 [ν0, ξ:β0, 𝜑⇉β"), "{}", all);
+    let found = state.query("baskets(ob=2)").unwrap();
+    assert_eq!(1, found.lines().count(), "{}", found);
+    assert!(found.contains("[ν2, ξ:β0"), "{}", found);
+    assert_eq!("", state.query("baskets(ob=1, psi=7)").unwrap());
+    assert_eq!(
+        "ν2(𝜋) ↦ ⟦λ↦int-add, ρ↦ν1(𝜋), 𝛼0↦ν3(𝜋)⟧",
+        state.query("object(ν2)").unwrap()
+    );
+}
+
+#[test]
+fn queries_kids_and_paths() {
+    let state = stuck();
+    let waits = state.query("kids(state=wait, ob=2)").unwrap();
+    assert!(waits.contains(".𝛼0 ⇉β"), "{}", waits);
+    let deepest = state.baskets.iter().map(|(b, _)| *b).max().unwrap();
+    let path = state.query(&format!("path(β{})", deepest)).unwrap();
+    assert!(path.ends_with("→ β0 ν0"), "{}", path);
+}
+
+#[test]
+fn refuses_broken_queries() {
+    let state = stuck();
+    for (q, msg) in [
+        ("baskets", "Can't parse the query"),
+        ("sort(ob=1)", "Unknown query 'sort'"),
+        ("kids(color=red)", "Unknown filter 'color'"),
+        ("kids(state=sleeping)", "Unknown state 'sleeping'"),
+        ("path(β1, β2)", "One argument"),
+        ("path(β1000)", "There is no β1000"),
+        ("object(ν42)", "There is no ν42"),
+    ] {
+        let err = state.query(q).err().unwrap();
+        assert!(err.contains(msg), "{}: {}", q, err);
+    }
+}
@@ -0,0 +1,182 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Dataization in a child process, available with the `unix` feature,
+//! for embedders who can't trust a program even with the `Budget` of
+//! a `pool::Runtime`: the child is forked with limits of CPU time and
+//! memory set by the kernel, and the result comes back over a pipe, so
+//! that a program that allocates too much kills the child only:
+//!
+//! ```
+//! use phie::isolate::{isolate, Limits};
+//! let prog = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+//! // SAFETY: the doctest runs in a process of one thread.
+//! let d = unsafe { isolate(&prog, Limits::default(), |_| {}) };
+//! assert_eq!(42, d.unwrap());
+//! ```
+
+use crate::data::Data;
+use crate::emu::Emu;
+use crate::error::{message, Error};
+use crate::program::Program;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::fd::FromRawFd;
+use std::panic::{self, AssertUnwindSafe};
+
+/// What the child process may spend, before the kernel stops it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Seconds of CPU time, see `RLIMIT_CPU`.
+    pub cpu: u64,
+    /// Bytes of address space, see `RLIMIT_AS`.
+    pub memory: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            cpu: 10,
+            memory: 1 << 30,
+        }
+    }
+}
+
+/// Dataize the program in a forked child, within the limits, after the
+/// setup has set the options or the sandbox of its `Emu`. The result
+/// is sent back as text, either `ok` with the data or `err` with the
+/// message of the failure; a child killed by a signal is a failure too,
+/// and so is a panic in the child, which never returns from here.
+///
+/// # Safety
+///
+/// The process must have only one thread at the moment of the call.
+/// After `fork()` in a process of many threads, only async-signal-safe
+/// functions may be called in the child, while this one allocates,
+/// parses and dataizes, and would hang or worse if another thread of
+/// the parent held a lock at the moment of the fork, like the one of
+/// the allocator or of a `Mutex` the setup takes.
+pub unsafe fn isolate(
+    prog: &Program,
+    limits: Limits,
+    setup: impl FnOnce(&mut Emu),
+) -> Result<Data, Error> {
+    let mut fds = [0; 2];
+    // SAFETY: the array has room for the two descriptors of the pipe.
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(Error::Failed(format!(
+            "Can't make a pipe: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    // SAFETY: the caller promises there are no other threads, see above,
+    // and the child only runs `child()` below and leaves by `_exit()`.
+    let pid = unsafe { libc::fork() };
+    if pid < 0 {
+        // SAFETY: both descriptors are open and used by nobody else.
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+        return Err(Error::Failed(format!(
+            "Can't fork: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    if pid == 0 {
+        // SAFETY: the child has its own copy of the descriptor, open
+        // and not used here.
+        unsafe { libc::close(fds[0]) };
+        // SAFETY: the descriptor is open and owned by nothing else.
+        let mut pipe = unsafe { File::from_raw_fd(fds[1]) };
+        let report = panic::catch_unwind(AssertUnwindSafe(|| child(prog, &limits, setup)))
+            .unwrap_or_else(|e| format!("err {}", message(e.as_ref())));
+        let _ = pipe.write_all(report.as_bytes());
+        // SAFETY: the child leaves at once, never going back to the code
+        // of the parent, its destructors or its handlers of exit.
+        unsafe { libc::_exit(0) };
+    }
+    // SAFETY: the descriptor is open and the parent doesn't write to it.
+    unsafe { libc::close(fds[1]) };
+    let mut report = String::new();
+    // SAFETY: the descriptor is open and owned by nothing else.
+    let read = unsafe { File::from_raw_fd(fds[0]) }.read_to_string(&mut report);
+    let mut status = 0;
+    loop {
+        // SAFETY: the pid is of our child, not waited for yet.
+        if unsafe { libc::waitpid(pid, &mut status, 0) } == pid {
+            break;
+        }
+        let e = std::io::Error::last_os_error();
+        if e.kind() != std::io::ErrorKind::Interrupted {
+            return Err(Error::Failed(format!("Can't wait for the child: {}", e)));
+        }
+    }
+    if libc::WIFSIGNALED(status) {
+        let sig = libc::WTERMSIG(status);
+        return Err(Error::Failed(match sig {
+            libc::SIGXCPU | libc::SIGKILL => format!(
+                "The child was killed by signal {}, it's out of {} seconds of CPU time",
+                sig, limits.cpu
+            ),
+            _ => format!(
+                "The child was killed by signal {}, maybe it's out of {} bytes of memory",
+                sig, limits.memory
+            ),
+        }));
+    }
+    if let Err(e) = read {
+        return Err(Error::Failed(format!("Can't read from the child: {}", e)));
+    }
+    match report.split_once(' ') {
+        Some(("ok", d)) => d
+            .parse()
+            .map_err(|e| Error::Failed(format!("The child sent broken data '{}': {}", d, e))),
+        Some(("stuck", msg)) => Err(Error::Stuck(msg.to_string())),
+        Some(("cycles", msg)) => Err(Error::Cycles(msg.to_string())),
+        Some(("err", msg)) => Err(Error::Failed(msg.to_string())),
+        _ => Err(Error::Failed(format!(
+            "The child exited with status {} and sent nothing",
+            libc::WEXITSTATUS(status)
+        ))),
+    }
+}
+
+/// Dataize the program in the child, within the limits, and tell how
+/// it went, as it's sent to the parent: the errors that carry more than
+/// a message are sent as failures.
+fn child(prog: &Program, limits: &Limits, setup: impl FnOnce(&mut Emu)) -> String {
+    if let Err(e) = limit(limits) {
+        return format!("err {}", e);
+    }
+    let mut emu = Emu::from(prog);
+    setup(&mut emu);
+    match emu.try_dataize() {
+        Ok((d, _)) => format!("ok {}", d),
+        Err(Error::Stuck(msg)) => format!("stuck {}", msg),
+        Err(Error::Cycles(msg)) => format!("cycles {}", msg),
+        Err(e) => format!("err {}", e),
+    }
+}
+
+/// Set the limits of the current process, which is the child.
+fn limit(limits: &Limits) -> Result<(), String> {
+    for (resource, max) in [
+        (libc::RLIMIT_CPU, limits.cpu),
+        (libc::RLIMIT_AS, limits.memory),
+    ] {
+        let rl = libc::rlimit {
+            rlim_cur: max as libc::rlim_t,
+            rlim_max: max as libc::rlim_t,
+        };
+        // SAFETY: the limit is a valid `rlimit` that outlives the call.
+        if unsafe { libc::setrlimit(resource, &rl) } != 0 {
+            return Err(format!(
+                "Can't set the limit of {}: {}",
+                max,
+                std::io::Error::last_os_error()
+            ));
+        }
+    }
+    Ok(())
+}
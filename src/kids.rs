@@ -0,0 +1,185 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! The kids of a basket by their locs: Φ, ρ, 𝜑, 𝜋, Δ and σ have their
+//! own slots, found without hashing, while 𝛼0, 𝛼1, ... and the rest go
+//! to a short list after them:
+//!
+//! ```
+//! use phie::basket::Kid;
+//! use phie::kids::Kids;
+//! use phie::loc::Loc;
+//! let mut kids: Kids = Kids::new();
+//! kids.insert(Loc::Phi, Kid::Rqtd);
+//! kids.insert(Loc::Attr(0), Kid::Dtzd(42));
+//! assert_eq!(Some(&Kid::Dtzd(42)), kids.get(&Loc::Attr(0)));
+//! assert_eq!(2, kids.len());
+//! ```
+
+#![forbid(unsafe_code)]
+
+use crate::basket::Kid;
+use crate::data::{Data, Datum};
+use crate::loc::Loc;
+
+/// The locs with their own slots, in the order of the slots.
+const FIXED: [Loc; 6] = [
+    Loc::Root,
+    Loc::Rho,
+    Loc::Phi,
+    Loc::Pi,
+    Loc::Delta,
+    Loc::Sigma,
+];
+
+#[derive(Clone, Debug)]
+pub struct Kids<D: Datum = Data> {
+    fixed: [Option<Kid<D>>; FIXED.len()],
+    spill: Vec<(Loc, Kid<D>)>,
+}
+
+impl<D: Datum> Default for Kids<D> {
+    fn default() -> Self {
+        Kids {
+            fixed: Default::default(),
+            spill: vec![],
+        }
+    }
+}
+
+impl<D: Datum> Kids<D> {
+    pub fn new() -> Kids<D> {
+        Kids::default()
+    }
+
+    /// The slot of the loc, if it has one.
+    fn slot(loc: &Loc) -> Option<usize> {
+        match loc {
+            Loc::Root => Some(0),
+            Loc::Rho => Some(1),
+            Loc::Phi => Some(2),
+            Loc::Pi => Some(3),
+            Loc::Delta => Some(4),
+            Loc::Sigma => Some(5),
+            Loc::Attr(_) | Loc::Obj(_) => None,
+        }
+    }
+
+    pub fn get(&self, loc: &Loc) -> Option<&Kid<D>> {
+        match Self::slot(loc) {
+            Some(i) => self.fixed[i].as_ref(),
+            None => self.spill.iter().find(|(l, _)| l == loc).map(|(_, k)| k),
+        }
+    }
+
+    pub fn get_mut(&mut self, loc: &Loc) -> Option<&mut Kid<D>> {
+        match Self::slot(loc) {
+            Some(i) => self.fixed[i].as_mut(),
+            None => self
+                .spill
+                .iter_mut()
+                .find(|(l, _)| l == loc)
+                .map(|(_, k)| k),
+        }
+    }
+
+    pub fn contains_key(&self, loc: &Loc) -> bool {
+        self.get(loc).is_some()
+    }
+
+    /// Put the kid, returning the one that was there before, if any.
+    pub fn insert(&mut self, loc: Loc, kid: Kid<D>) -> Option<Kid<D>> {
+        if let Some(i) = Self::slot(&loc) {
+            return self.fixed[i].replace(kid);
+        }
+        match self.get_mut(&loc) {
+            Some(k) => Some(std::mem::replace(k, kid)),
+            None => {
+                self.spill.push((loc, kid));
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, loc: &Loc) -> Option<Kid<D>> {
+        match Self::slot(loc) {
+            Some(i) => self.fixed[i].take(),
+            None => {
+                let pos = self.spill.iter().position(|(l, _)| l == loc)?;
+                Some(self.spill.swap_remove(pos).1)
+            }
+        }
+    }
+
+    /// All kids, the ones with their own slots first.
+    pub fn iter(&self) -> impl Iterator<Item = (&Loc, &Kid<D>)> {
+        FIXED
+            .iter()
+            .zip(self.fixed.iter())
+            .filter_map(|(l, k)| Some((l, k.as_ref()?)))
+            .chain(self.spill.iter().map(|(l, k)| (l, k)))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Loc> {
+        self.iter().map(|(l, _)| l)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Kid<D>> {
+        self.iter().map(|(_, k)| k)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Kid<D>> {
+        self.fixed
+            .iter_mut()
+            .flatten()
+            .chain(self.spill.iter_mut().map(|(_, k)| k))
+    }
+
+    pub fn len(&self) -> usize {
+        self.fixed.iter().flatten().count() + self.spill.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all kids, keeping the room they took.
+    pub fn clear(&mut self) {
+        self.fixed = Default::default();
+        self.spill.clear();
+    }
+
+    /// How many kids fit without allocating.
+    pub fn capacity(&self) -> usize {
+        FIXED.len() + self.spill.capacity()
+    }
+
+    /// Make room for this many more kids, as if none of them had a slot.
+    pub fn reserve(&mut self, additional: usize) {
+        self.spill.reserve(additional.saturating_sub(FIXED.len()));
+    }
+}
+
+#[test]
+fn keeps_kids_in_slots_and_spill() {
+    let mut kids: Kids = Kids::new();
+    assert!(kids.is_empty());
+    kids.insert(Loc::Rho, Kid::Empt);
+    kids.insert(Loc::Attr(1), Kid::Rqtd);
+    kids.insert(Loc::Attr(0), Kid::Empt);
+    assert_eq!(Some(Kid::Empt), kids.insert(Loc::Attr(0), Kid::Dtzd(7)));
+    assert_eq!(Some(Kid::Empt), kids.insert(Loc::Rho, Kid::Dtzd(1)));
+    assert_eq!(3, kids.len());
+    assert_eq!(
+        vec![Loc::Rho, Loc::Attr(1), Loc::Attr(0)],
+        kids.keys().cloned().collect::<Vec<Loc>>()
+    );
+    assert_eq!(Some(Kid::Rqtd), kids.remove(&Loc::Attr(1)));
+    assert!(!kids.contains_key(&Loc::Attr(1)));
+    for kid in kids.values_mut() {
+        *kid = Kid::Empt;
+    }
+    assert!(kids.values().all(|k| *k == Kid::Empt));
+    kids.clear();
+    assert_eq!(0, kids.len());
+}
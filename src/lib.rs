@@ -4,14 +4,54 @@
 #![deny(warnings)]
 
 pub mod atom;
+pub mod baseline;
 pub mod basket;
+pub mod breakpoint;
+pub mod check;
+pub mod checkpoint;
 pub mod cli;
+#[cfg(feature = "compact")]
+pub mod compact;
+pub mod conformance;
+pub mod crash;
+pub mod dap;
 pub mod data;
+pub mod diff;
+pub mod emissions;
 pub mod emu;
+pub mod error;
+pub mod evaluator;
+pub mod event;
+pub mod examples;
+#[cfg(feature = "heap")]
+pub mod heap;
+pub mod inspect;
+#[cfg(all(unix, feature = "unix"))]
+pub mod isolate;
+pub mod kids;
 pub mod loc;
 pub mod locator;
+pub mod lsp;
 pub mod object;
+pub mod optimize;
+pub mod partial;
 pub mod perf;
+pub mod plan;
+pub mod pool;
+pub mod program;
+pub mod provenance;
+pub mod reduce;
+pub mod sandbox;
+pub mod scheduler;
+pub mod schema;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod semantics;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod stream;
+pub mod symexec;
+pub mod syntax;
 
 #[cfg(test)]
 use simple_logger::SimpleLogger;
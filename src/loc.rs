@@ -4,8 +4,161 @@
 use crate::object::Ob;
 use regex::Regex;
 use rstest::rstest;
+use std::cmp::Ordering;
 use std::fmt;
 use std::str::FromStr;
+use unicode_normalization::UnicodeNormalization;
+
+/// Characters that look just like the ones of 𝜑-calculus, but are
+/// encoded differently, as it happens when the code is copied from
+/// a PDF or a paper, together with the characters they stand for.
+pub(crate) const ALIASES: [(char, char); 24] = [
+    ('φ', '𝜑'),
+    ('ϕ', '𝜑'),
+    ('𝛗', '𝜑'),
+    ('𝜙', '𝜑'),
+    ('𝝋', '𝜑'),
+    ('π', '𝜋'),
+    ('𝛑', '𝜋'),
+    ('𝝅', '𝜋'),
+    ('α', '𝛼'),
+    ('𝛂', '𝛼'),
+    ('𝜶', '𝛼'),
+    ('𝜈', 'ν'),
+    ('𝛎', 'ν'),
+    ('𝜌', 'ρ'),
+    ('ϱ', 'ρ'),
+    ('𝛒', 'ρ'),
+    ('𝜉', 'ξ'),
+    ('𝜎', 'σ'),
+    ('∆', 'Δ'),
+    ('𝛥', 'Δ'),
+    ('𝜆', 'λ'),
+    ('𝛷', 'Φ'),
+    ('〚', '⟦'),
+    ('〛', '⟧'),
+];
+
+/// Bring the text to NFC and replace all look-alike characters
+/// with the ones the parsers expect.
+pub fn normalize(s: &str) -> String {
+    s.nfc()
+        .map(|c| {
+            ALIASES
+                .iter()
+                .find(|(alias, _)| *alias == c)
+                .map_or(c, |(_, real)| *real)
+        })
+        .collect()
+}
+
+/// The characters of 𝜑-calculus with the LaTeX commands that print
+/// them, `⟦` and `⟧` coming from the `stmaryrd` package.
+const LATEX: [(char, &str); 14] = [
+    ('⟦', "\\llbracket"),
+    ('⟧', "\\rrbracket"),
+    ('↦', "\\mapsto"),
+    ('Δ', "\\Delta"),
+    ('λ', "\\lambda"),
+    ('ρ', "\\rho"),
+    ('𝜑', "\\varphi"),
+    ('𝜋', "\\pi"),
+    ('ξ', "\\xi"),
+    ('σ', "\\sigma"),
+    ('Φ', "\\Phi"),
+    ('ν', "\\nu"),
+    ('𝛼', "\\alpha"),
+    ('β', "\\beta"),
+];
+
+/// Turn the text printed by objects, locators and baskets into LaTeX for
+/// the math mode: the numbers of `ν`, `𝛼` and `β` become subscripts, while
+/// atom names and data are typewritten:
+///
+/// ```
+/// use phie::loc::latex;
+/// assert_eq!(
+///     "\\llbracket\\lambda\\mapsto\\texttt{int-neg},\\ \\rho\\mapsto\\nu_{12}(\\pi)\\rrbracket",
+///     latex("⟦λ↦int-neg, ρ↦ν12(𝜋)⟧")
+/// );
+/// ```
+pub fn latex(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if let Some((_, cmd)) = LATEX.iter().find(|(u, _)| *u == c) {
+            out.push_str(cmd);
+            if matches!(c, 'ν' | '𝛼' | 'β') && chars.peek().is_some_and(char::is_ascii_digit) {
+                out.push_str("_{");
+                while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                    out.push(d);
+                }
+                out.push('}');
+            }
+            continue;
+        }
+        if c.is_ascii_alphanumeric() {
+            let mut word = c.to_string();
+            while let Some(w) = chars.next_if(|w| w.is_ascii_alphanumeric() || *w == '-') {
+                word.push(w);
+            }
+            out.push_str(&format!("\\texttt{{{}}}", word));
+            continue;
+        }
+        match c {
+            ' ' => {}
+            ',' => out.push_str(",\\ "),
+            '%' | '#' | '_' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '~' => out.push_str("{\\sim}"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// The locs that have names, with the ASCII letters that may be
+/// written instead of them.
+pub(crate) const NAMES: [(&str, &str, Loc); 6] = [
+    ("Φ", "Q", Loc::Root),
+    ("Δ", "D", Loc::Delta),
+    ("𝜋", "P", Loc::Pi),
+    ("ρ", "^", Loc::Rho),
+    ("𝜑", "@", Loc::Phi),
+    ("σ", "&", Loc::Sigma),
+];
+
+/// The largest number of an attribute, like `𝛼127`.
+pub const MAX_ATTR: usize = i8::MAX as usize;
+
+/// Parse the digits of a number, like in `𝛼12` or `ν7`, failing with
+/// an error that names the limit, if it's larger:
+///
+/// ```
+/// use phie::loc::index;
+/// assert_eq!(Ok(12), index("12", 127, "𝛼"));
+/// assert_eq!(
+///     Err("The number of 𝛼300 is beyond the limit of 127".to_string()),
+///     index("300", 127, "𝛼")
+/// );
+/// ```
+pub fn index(digits: &str, limit: usize, prefix: &str) -> Result<usize, String> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!(
+            "The number of {}{} is not a number",
+            prefix, digits
+        ));
+    }
+    match digits.parse::<usize>() {
+        Ok(n) if n <= limit => Ok(n),
+        _ => Err(format!(
+            "The number of {}{} is beyond the limit of {}",
+            prefix, digits, limit
+        )),
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Loc {
@@ -19,9 +172,51 @@ pub enum Loc {
     Obj(Ob),
 }
 
+impl Loc {
+    /// The place of the kind of the loc in the canonical order.
+    fn rank(&self) -> (u8, usize) {
+        match self {
+            Loc::Root => (0, 0),
+            Loc::Rho => (1, 0),
+            Loc::Phi => (2, 0),
+            Loc::Pi => (3, 0),
+            Loc::Sigma => (4, 0),
+            Loc::Delta => (5, 0),
+            Loc::Attr(i) => (6, *i as usize),
+            Loc::Obj(ob) => (7, ob.index()),
+        }
+    }
+}
+
+/// The canonical order, used everywhere attributes are printed:
+/// Φ, ρ, 𝜑, 𝜋, σ, Δ, then 𝛼0, 𝛼1, ... by number, and ν0, ν1, ...
+/// by number, so that `𝛼2` goes before `𝛼10`:
+///
+/// ```
+/// use phie::loc::Loc;
+/// use phie::object::Ob;
+/// let one = Loc::Obj(Ob::new(1));
+/// let mut locs = vec![Loc::Attr(10), one.clone(), Loc::Attr(2), Loc::Delta, Loc::Rho];
+/// locs.sort();
+/// assert_eq!(vec![Loc::Rho, Loc::Delta, Loc::Attr(2), Loc::Attr(10), one], locs);
+/// ```
+impl Ord for Loc {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+impl PartialOrd for Loc {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl FromStr for Loc {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = normalize(s);
+        let s = s.as_str();
         let re_arg =
             Regex::new("^𝛼?(\\d+)$").map_err(|e| format!("Invalid RE_ARG regex pattern: {}", e))?;
         let re_obj =
@@ -32,29 +227,19 @@ impl FromStr for Loc {
                 .get(1)
                 .ok_or_else(|| format!("Missing capture group in attr pattern: '{}'", s))?
                 .as_str();
-            let attr_num = attr_str
-                .parse::<i8>()
-                .map_err(|e| format!("Failed to parse attr number '{}': {}", attr_str, e))?;
-            Ok(Loc::Attr(attr_num))
+            Ok(Loc::Attr(index(attr_str, MAX_ATTR, "𝛼")? as i8))
         } else if let Some(caps) = re_obj.captures(s) {
             let obj_str = caps
                 .get(1)
                 .ok_or_else(|| format!("Missing capture group in obj pattern: '{}'", s))?
                 .as_str();
-            let obj_num = obj_str
-                .parse::<Ob>()
-                .map_err(|e| format!("Failed to parse obj number '{}': {}", obj_str, e))?;
-            Ok(Loc::Obj(obj_num))
+            Ok(Loc::Obj(Ob::new(index(obj_str, Ob::LIMIT, "ν")?)))
         } else {
-            match s {
-                "Φ" | "Q" => Ok(Loc::Root),
-                "Δ" | "D" => Ok(Loc::Delta),
-                "𝜋" | "P" => Ok(Loc::Pi),
-                "ρ" | "^" => Ok(Loc::Rho),
-                "𝜑" | "@" => Ok(Loc::Phi),
-                "σ" | "&" => Ok(Loc::Sigma),
-                _ => Err(format!("Unknown loc: '{}'", s)),
-            }
+            NAMES
+                .iter()
+                .find(|(name, letter, _)| s == *name || s == *letter)
+                .map(|(_, _, loc)| loc.clone())
+                .ok_or_else(|| format!("Unknown loc: '{}'", s))
         }
     }
 }
@@ -93,6 +278,22 @@ pub fn parses_and_prints(#[case] txt: &str) {
     assert_eq!(loc1, loc2)
 }
 
+#[rstest]
+#[case("φ", Loc::Phi)]
+#[case("π", Loc::Pi)]
+#[case("α3", Loc::Attr(3))]
+#[case("𝜈12", Loc::Obj(Ob::new(12)))]
+#[case("∆", Loc::Delta)]
+pub fn parses_look_alikes(#[case] txt: &str, #[case] loc: Loc) {
+    assert_eq!(loc, Loc::from_str(txt).unwrap())
+}
+
+#[test]
+fn normalizes_composed_characters() {
+    assert_eq!("\u{00E9}", normalize("e\u{0301}"));
+    assert_eq!("𝜋.𝛼0", normalize("π.α0"));
+}
+
 #[test]
 fn fails_on_unknown_loc() {
     let result = Loc::from_str("unknown");
@@ -106,7 +307,13 @@ fn fails_on_invalid_attr_number() {
     let result = Loc::from_str("𝛼999999999999");
     assert!(result.is_err());
     let err = result.err().unwrap();
-    assert!(err.contains("Failed to parse attr number"));
+    assert!(
+        err.contains("𝛼999999999999 is beyond the limit of 127"),
+        "{}",
+        err
+    );
+    assert_eq!(Ok(Loc::Attr(127)), Loc::from_str("𝛼127"));
+    assert!(Loc::from_str("𝛼128").is_err());
 }
 
 #[test]
@@ -114,5 +321,27 @@ fn fails_on_invalid_obj_number() {
     let result = Loc::from_str("ν99999999999999999999999999");
     assert!(result.is_err());
     let err = result.err().unwrap();
-    assert!(err.contains("Failed to parse obj number"));
+    assert!(err.contains("is beyond the limit of"), "{}", err);
+}
+
+#[test]
+fn orders_canonically() {
+    let locs: Vec<Loc> = ["Φ", "ρ", "𝜑", "𝜋", "σ", "Δ", "𝛼0", "𝛼2", "𝛼10", "ν0", "ν3"]
+        .iter()
+        .map(|s| Loc::from_str(s).unwrap())
+        .collect();
+    let mut sorted = locs.clone();
+    sorted.reverse();
+    sorted.sort();
+    assert_eq!(locs, sorted);
+}
+
+#[test]
+fn prints_latex_of_objects() {
+    assert_eq!(
+        "\\llbracket!\\%\\Delta\\mapsto\\texttt{0x002A}\\rrbracket",
+        latex("⟦! % Δ↦0x002A⟧")
+    );
+    assert_eq!("\\beta_{3}.\\alpha_{0}", latex("β3.𝛼0"));
+    assert_eq!("\\Phi.\\sigma.\\xi", latex("Φ.σ.ξ"));
 }
@@ -1,16 +1,120 @@
 // SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
 // SPDX-License-Identifier: MIT
 
-use crate::loc::Loc;
+use crate::loc::{normalize, Loc};
 use rstest::rstest;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::str::FromStr;
+use std::sync::Arc;
 
 /// Locator is a chain of attributes connected with dots,
-/// for example `𝜋.𝜋.𝛼0` is a locator.
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// for example `𝜋.𝜋.𝛼0` is a locator. Its clones share the same
+/// attributes, which is what `Locators` relies on.
+#[derive(Debug, Clone, Eq)]
 pub struct Locator {
-    locs: Vec<Loc>,
+    locs: Arc<[Loc]>,
+}
+
+impl PartialEq for Locator {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.locs, &other.locs) || self.locs == other.locs
+    }
+}
+
+impl Hash for Locator {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.locs.hash(state);
+    }
+}
+
+/// Attribute by attribute, in the order of `Loc`, a shorter locator
+/// going before the longer one it starts.
+impl Ord for Locator {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.locs.cmp(&other.locs)
+    }
+}
+
+impl PartialOrd for Locator {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Maps keyed by locators can be searched by a slice of attributes,
+/// without making a locator of them:
+///
+/// ```
+/// use phie::loc::Loc;
+/// use phie::locator::Locator;
+/// use std::collections::HashMap;
+/// let mut hits: HashMap<Locator, usize> = HashMap::new();
+/// hits.insert(Locator::from_vec(vec![Loc::Pi, Loc::Attr(0)]), 7);
+/// assert_eq!(Some(&7), hits.get([Loc::Pi, Loc::Attr(0)].as_slice()));
+/// ```
+impl Borrow<[Loc]> for Locator {
+    fn borrow(&self) -> &[Loc] {
+        &self.locs
+    }
+}
+
+impl AsRef<[Loc]> for Locator {
+    fn as_ref(&self) -> &[Loc] {
+        &self.locs
+    }
+}
+
+/// The number of a locator among `Locators`.
+pub type LocatorId = usize;
+
+/// A table of unique locators, where each one is kept once and all
+/// objects that use it share it: large generated programs repeat
+/// the same locators, like `𝜋.𝛼0`, thousands of times.
+#[derive(Default)]
+pub struct Locators {
+    all: Vec<Locator>,
+    ids: HashMap<Locator, LocatorId>,
+}
+
+impl Locators {
+    pub fn new() -> Locators {
+        Locators::default()
+    }
+
+    /// Find the locator in the table, adding it if it's not there yet.
+    pub fn intern(&mut self, locator: &Locator) -> LocatorId {
+        if let Some(id) = self.ids.get(locator) {
+            return *id;
+        }
+        let id = self.all.len();
+        self.all.push(locator.clone());
+        self.ids.insert(locator.clone(), id);
+        id
+    }
+
+    /// The number of the locator, if it's in the table.
+    pub fn id(&self, locator: &Locator) -> Option<LocatorId> {
+        self.ids.get(locator).copied()
+    }
+
+    /// The locator by its number.
+    pub fn get(&self, id: LocatorId) -> &Locator {
+        &self.all[id]
+    }
+
+    /// How many unique locators are there.
+    pub fn len(&self) -> usize {
+        self.all.len()
+    }
+
+    /// There are no locators at all.
+    pub fn is_empty(&self) -> bool {
+        self.all.is_empty()
+    }
 }
 
 /// Use this macro to create a locator faster:
@@ -38,7 +142,7 @@ impl Locator {
     /// let k = Locator::from_vec(vec![Loc::Phi, Loc::Delta]);
     /// ```
     pub fn from_vec(locs: Vec<Loc>) -> Locator {
-        Locator { locs }
+        Locator { locs: locs.into() }
     }
 
     /// Make a locator from a single attribute:
@@ -57,9 +161,14 @@ impl Locator {
         self.locs.get(id)
     }
 
+    /// All attributes, without copying them.
+    pub fn locs(&self) -> &[Loc] {
+        &self.locs
+    }
+
     /// Turn it into a vector of attributes.
     pub fn to_vec(&self) -> Vec<Loc> {
-        self.locs.clone()
+        self.locs.to_vec()
     }
 }
 
@@ -68,8 +177,9 @@ type CheckFn = fn(&Locator) -> Option<String>;
 impl FromStr for Locator {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = normalize(s);
         let locs_result: Result<Vec<Loc>, String> = s.split('.').map(Loc::from_str).collect();
-        let p = Locator { locs: locs_result? };
+        let p = Locator::from_vec(locs_result?);
 
         let checks: [CheckFn; 4] = [
             |p: &Locator| -> Option<String> {
@@ -262,3 +372,35 @@ fn fails_on_leading_dot() {
     let result = Locator::from_str(".P");
     assert!(result.is_err());
 }
+
+#[test]
+fn interns_same_locators_once() {
+    let mut locators = Locators::new();
+    let first = locators.intern(&ph!("𝜋.𝛼0"));
+    let second = locators.intern(&ph!("𝜋.𝛼0"));
+    let third = locators.intern(&ph!("ρ.𝜑"));
+    assert_eq!(first, second);
+    assert_ne!(first, third);
+    assert_eq!(2, locators.len());
+    assert_eq!("ρ.𝜑", locators.get(third).to_string());
+    assert_eq!(Some(first), locators.id(&ph!("𝜋.𝛼0")));
+}
+
+#[test]
+fn orders_and_keys_maps() {
+    let mut all: Vec<Locator> = ["𝜋.𝛼10", "ρ", "𝜋.𝛼2", "𝜋", "ν3"]
+        .iter()
+        .map(|s| ph!(s))
+        .collect();
+    all.sort();
+    assert_eq!(
+        "ρ 𝜋 𝜋.𝛼2 𝜋.𝛼10 ν3",
+        all.iter()
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    let tree: std::collections::BTreeMap<Locator, usize> =
+        all.into_iter().enumerate().map(|(i, k)| (k, i)).collect();
+    assert_eq!(Some(&2), tree.get([Loc::Pi, Loc::Attr(2)].as_slice()));
+}
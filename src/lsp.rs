@@ -0,0 +1,364 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Language Server Protocol support for program files.
+//!
+//! The `Server` keeps the texts of open documents and answers requests,
+//! while the `phie-lsp` binary only moves messages between it and stdio.
+//! It offers diagnostics from `check::verify`, hover with the formatted
+//! definition of an object, go-to-definition on `νN` references, and
+//! formatting of the whole document.
+//!
+//! ```
+//! use phie::lsp::Server;
+//! use serde_json::json;
+//! let mut server = Server::new();
+//! let out = server.handle(&json!({"jsonrpc": "2.0", "id": 1, "method": "initialize"}));
+//! assert!(out[0]["result"]["capabilities"]["hoverProvider"].as_bool().unwrap());
+//! ```
+
+use crate::check::{verify, Level};
+use crate::object::Ob;
+use crate::program::Program;
+use regex::Regex;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+pub struct Server {
+    docs: HashMap<String, String>,
+    pub done: bool,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Server {
+    pub fn new() -> Server {
+        Server {
+            docs: HashMap::new(),
+            done: false,
+        }
+    }
+
+    /// Handle one incoming message and return the messages to send back.
+    pub fn handle(&mut self, msg: &Value) -> Vec<Value> {
+        let id = msg.get("id").cloned();
+        let params = &msg["params"];
+        let method = msg["method"].as_str().unwrap_or("");
+        match method {
+            "initialize" => reply(
+                id,
+                json!({
+                    "capabilities": {
+                        "textDocumentSync": 1,
+                        "hoverProvider": true,
+                        "definitionProvider": true,
+                        "documentFormattingProvider": true
+                    },
+                    "serverInfo": {"name": "phie-lsp"}
+                }),
+            ),
+            "textDocument/didOpen" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                let text = params["textDocument"]["text"].as_str().unwrap_or("");
+                self.docs.insert(uri.to_string(), text.to_string());
+                vec![self.diagnostics(uri)]
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                if let Some(text) = params["contentChanges"]
+                    .as_array()
+                    .and_then(|c| c.last())
+                    .and_then(|c| c["text"].as_str())
+                {
+                    self.docs.insert(uri.to_string(), text.to_string());
+                }
+                vec![self.diagnostics(uri)]
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                self.docs.remove(uri);
+                vec![]
+            }
+            "textDocument/hover" => {
+                let result = self
+                    .reference(params)
+                    .and_then(|(uri, ob)| {
+                        definition(&self.docs[&uri], ob).map(|(_, line)| {
+                            let text = match Program::parse_line(line.trim()) {
+                                Ok((ob, obj)) => format!("ν{}(𝜋) ↦ {}", ob, obj),
+                                Err(_) => line.trim().to_string(),
+                            };
+                            json!({"contents": {
+                                "kind": "markdown",
+                                "value": format!("```\n{}\n```", text)
+                            }})
+                        })
+                    })
+                    .unwrap_or(Value::Null);
+                reply(id, result)
+            }
+            "textDocument/definition" => {
+                let result = self
+                    .reference(params)
+                    .and_then(|(uri, ob)| {
+                        definition(&self.docs[&uri], ob)
+                            .map(|(num, line)| json!({"uri": uri, "range": range(num, line)}))
+                    })
+                    .unwrap_or(Value::Null);
+                reply(id, result)
+            }
+            "textDocument/formatting" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                let result = self
+                    .docs
+                    .get(uri)
+                    .and_then(|text| format(text).map(|f| (text, f)))
+                    .map(|(text, formatted)| {
+                        let lines: Vec<&str> = text.split('\n').collect();
+                        let last = lines.len() - 1;
+                        json!([{
+                            "range": {
+                                "start": {"line": 0, "character": 0},
+                                "end": {"line": last, "character": utf16_len(lines[last])}
+                            },
+                            "newText": formatted
+                        }])
+                    })
+                    .unwrap_or(Value::Null);
+                reply(id, result)
+            }
+            "shutdown" => reply(id, Value::Null),
+            "exit" => {
+                self.done = true;
+                vec![]
+            }
+            _ => match id {
+                Some(_) if !method.is_empty() => vec![json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": {"code": -32601, "message": format!("Unknown method '{}'", method)}
+                })],
+                _ => vec![],
+            },
+        }
+    }
+
+    /// The notification with all problems found in the document.
+    fn diagnostics(&self, uri: &str) -> Value {
+        let text = self.docs.get(uri).map(|t| t.as_str()).unwrap_or("");
+        let lines: Vec<&str> = text.split('\n').collect();
+        let diags: Vec<Value> = verify(text)
+            .iter()
+            .map(|d| {
+                let line = lines.get(d.line - 1).copied().unwrap_or("");
+                json!({
+                    "range": range(d.line - 1, line),
+                    "severity": if d.level == Level::Error { 1 } else { 2 },
+                    "code": d.rule,
+                    "source": "phie",
+                    "message": d.message
+                })
+            })
+            .collect();
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": {"uri": uri, "diagnostics": diags}
+        })
+    }
+
+    /// The document and the `νN` object under the cursor, if any.
+    fn reference(&self, params: &Value) -> Option<(String, Ob)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let text = self.docs.get(uri)?;
+        let num = params["position"]["line"].as_u64()? as usize;
+        let col = params["position"]["character"].as_u64()? as usize;
+        let line = text.split('\n').nth(num)?;
+        object_at(line, col).map(|ob| (uri.to_string(), ob))
+    }
+}
+
+fn reply(id: Option<Value>, result: Value) -> Vec<Value> {
+    vec![json!({"jsonrpc": "2.0", "id": id, "result": result})]
+}
+
+/// The range covering the whole line.
+fn range(num: usize, line: &str) -> Value {
+    json!({
+        "start": {"line": num, "character": 0},
+        "end": {"line": num, "character": utf16_len(line)}
+    })
+}
+
+/// The length of the text in UTF-16 code units, as LSP counts them.
+fn utf16_len(s: &str) -> usize {
+    s.chars().map(|c| c.len_utf16()).sum()
+}
+
+/// The number of the object referenced as `νN` at the UTF-16 column.
+pub fn object_at(line: &str, col: usize) -> Option<Ob> {
+    let re = Regex::new("ν(\\d+)").ok()?;
+    let found = re.captures_iter(line).find_map(|caps| {
+        let all = caps.get(0)?;
+        let start = utf16_len(&line[..all.start()]);
+        let end = utf16_len(&line[..all.end()]);
+        if col >= start && col <= end {
+            caps.get(1)?.as_str().parse().ok()
+        } else {
+            None
+        }
+    });
+    found
+}
+
+/// The number and the text of the line where the object is defined.
+pub fn definition(text: &str, ob: Ob) -> Option<(usize, &str)> {
+    let re = Regex::new("^\\s*ν(\\d+)\\(𝜋\\)").ok()?;
+    text.split('\n').enumerate().find(|(_, line)| {
+        re.captures(line)
+            .and_then(|c| c.get(1))
+            .and_then(|m| m.as_str().parse::<Ob>().ok())
+            == Some(ob)
+    })
+}
+
+/// The document with every line printed canonically, or nothing
+/// if some line can't be parsed.
+pub fn format(text: &str) -> Option<String> {
+    let mut lines = vec![];
+    for line in text.trim().split('\n').map(|t| t.trim()) {
+        if line.is_empty() || line.starts_with('+') || line.starts_with('#') {
+            lines.push(line.to_string());
+            continue;
+        }
+        let (ob, obj) = Program::parse_line(line).ok()?;
+        lines.push(format!("ν{}(𝜋) ↦ {}", ob, obj));
+    }
+    Some(lines.join("\n") + "\n")
+}
+
+/// Read one message framed with the `Content-Length` header, or nothing
+/// if the stream is over.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(v) = header.strip_prefix("Content-Length:") {
+            length = v.trim().parse::<usize>().ok();
+        }
+    }
+    let length = length
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "No Content-Length header"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Write one message framed with the `Content-Length` header.
+pub fn write_message(output: &mut impl Write, msg: &Value) -> io::Result<()> {
+    let body = msg.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+#[cfg(test)]
+fn opened(text: &str) -> Server {
+    let mut server = Server::new();
+    server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {"textDocument": {"uri": "file:///a.phi", "text": text}}
+    }));
+    server
+}
+
+#[cfg(test)]
+fn at(method: &str, line: usize, character: usize) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": 7,
+        "method": method,
+        "params": {
+            "textDocument": {"uri": "file:///a.phi"},
+            "position": {"line": line, "character": character}
+        }
+    })
+}
+
+#[test]
+fn publishes_diagnostics() {
+    let mut server = Server::new();
+    let out = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {"textDocument": {"uri": "file:///a.phi", "text": "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν3(𝜋) ⟧"}}
+    }));
+    assert_eq!("textDocument/publishDiagnostics", out[0]["method"]);
+    assert_eq!("undefined", out[0]["params"]["diagnostics"][0]["code"]);
+    assert_eq!(1, out[0]["params"]["diagnostics"][0]["severity"]);
+}
+
+#[test]
+fn goes_to_definition() {
+    let mut server = opened("ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧");
+    let out = server.handle(&at("textDocument/definition", 0, 17));
+    assert_eq!(1, out[0]["result"]["range"]["start"]["line"]);
+}
+
+#[test]
+fn hovers_with_definition() {
+    let mut server = opened("ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧");
+    let out = server.handle(&at("textDocument/hover", 0, 16));
+    let value = out[0]["result"]["contents"]["value"].as_str().unwrap();
+    assert!(value.contains("ν1(𝜋) ↦ ⟦! Δ↦0x002A⟧"), "{}", value);
+    let none = server.handle(&at("textDocument/hover", 0, 9));
+    assert!(none[0]["result"].is_null());
+}
+
+#[test]
+fn formats_document() {
+    assert_eq!(
+        Some("ν0(𝜋) ↦ ⟦𝜑↦ν1(𝜋)⟧\n".to_string()),
+        format("   ν0(𝜋) ↦ ⟦ 𝜑 ↦   ν1(𝜋) ⟧  ")
+    );
+    assert!(format("broken").is_none());
+}
+
+#[test]
+fn counts_columns_in_utf16() {
+    let line = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν12(𝜋) ⟧";
+    assert_eq!(Some(Ob::new(0)), object_at(line, 1));
+    assert_eq!(Some(Ob::new(12)), object_at(line, 17));
+}
+
+#[test]
+fn reads_and_writes_messages() {
+    let mut buf = vec![];
+    write_message(&mut buf, &json!({"id": 1})).unwrap();
+    let msg = read_message(&mut io::Cursor::new(buf)).unwrap().unwrap();
+    assert_eq!(1, msg["id"]);
+    assert!(read_message(&mut io::Cursor::new(vec![]))
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn stops_on_exit() {
+    let mut server = Server::new();
+    assert!(server.handle(&json!({"method": "exit"})).is_empty());
+    assert!(server.done);
+}
@@ -3,22 +3,95 @@
 
 use crate::atom::*;
 use crate::data::Data;
-use crate::loc::Loc;
+use crate::loc::{normalize, Loc};
 use crate::locator::Locator;
+use crate::syntax;
 use itertools::Itertools;
 use regex::Regex;
 use rstest::rstest;
+use serde::{Serialize, Serializer};
 use std::collections::HashMap;
 use std::fmt;
+use std::num::NonZeroU32;
 use std::str::FromStr;
 
-pub type Ob = usize;
+/// The number of an object, like `ν5`. It's kept one more than it is in
+/// a `NonZeroU32`, so that `Option<Ob>` takes no more room than `Ob`
+/// does, and can't be mistaken for the number of a basket, see `Bk`:
+///
+/// ```
+/// use phie::object::Ob;
+/// assert_eq!("5", Ob::new(5).to_string());
+/// assert_eq!(5, Ob::new(5).index());
+/// assert_eq!(4, std::mem::size_of::<Option<Ob>>());
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Ob(NonZeroU32);
 
+impl Ob {
+    /// The largest number an object may have.
+    pub const LIMIT: usize = u32::MAX as usize - 1;
+
+    /// The object with this number, which must not be over `Ob::LIMIT`.
+    pub const fn new(n: usize) -> Ob {
+        assert!(n <= Ob::LIMIT, "The number of the object is too big");
+        match NonZeroU32::new(n as u32 + 1) {
+            Some(v) => Ob(v),
+            None => panic!("The number of the object is too big"),
+        }
+    }
+
+    /// The number of the object, to find it among others.
+    pub const fn index(self) -> usize {
+        self.0.get() as usize - 1
+    }
+}
+
+impl fmt::Display for Ob {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.index())
+    }
+}
+
+impl Serialize for Ob {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.index() as u64)
+    }
+}
+
+impl FromStr for Ob {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<usize>() {
+            Ok(n) if n <= Ob::LIMIT => Ok(Ob::new(n)),
+            _ => Err(format!("Can't parse the number of an object '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Debug for Ob {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.index())
+    }
+}
+
+#[derive(Clone)]
 pub struct Object {
     pub delta: Option<Data>,
-    pub lambda: Option<(String, Atom)>,
+    /// The atom, see `atom::id()`.
+    pub lambda: Option<AtomId>,
     pub constant: bool,
+    /// The results of its atom are memoized (`%` after `⟦`) or never
+    /// memoized (`~`), whatever `Opt::Memoize` says.
+    pub memo: Option<bool>,
     pub attrs: HashMap<Loc, (Locator, bool)>,
+    /// The namespace of the library it was linked from, see
+    /// `Program::link()`, together with its number there.
+    pub origin: Option<(String, Ob)>,
+    /// The Δ is a fraction with this denominator.
+    #[cfg(feature = "rational")]
+    pub denominator: Option<Data>,
 }
 
 impl Object {
@@ -27,7 +100,11 @@ impl Object {
             delta: None,
             lambda: None,
             constant: false,
+            memo: None,
             attrs: HashMap::new(),
+            origin: None,
+            #[cfg(feature = "rational")]
+            denominator: None,
         }
     }
 
@@ -36,24 +113,62 @@ impl Object {
             delta: Some(d),
             lambda: None,
             constant: true,
+            memo: None,
             attrs: HashMap::new(),
+            origin: None,
+            #[cfg(feature = "rational")]
+            denominator: None,
         }
     }
 
-    pub fn atomic(n: String, a: Atom) -> Object {
+    /// An object with the atom of this id, see `atom::id()` and
+    /// `atom::register()`.
+    pub fn atomic(id: AtomId) -> Object {
         Object {
             delta: None,
-            lambda: Some((n, a)),
+            lambda: Some(id),
             constant: false,
+            memo: None,
             attrs: HashMap::new(),
+            origin: None,
+            #[cfg(feature = "rational")]
+            denominator: None,
+        }
+    }
+
+    /// A constant fraction, which can only be dataized in the big mode.
+    #[cfg(feature = "rational")]
+    pub fn rational(n: Data, d: Data) -> Object {
+        let mut obj = Object::dataic(n);
+        obj.denominator = Some(d);
+        obj
+    }
+
+    /// Its name in diagnostics, like `ν20 (lib::ν7)`, if it was linked
+    /// from a library, or just `ν20`.
+    pub fn label(&self, ob: Ob) -> String {
+        match &self.origin {
+            Some((ns, o)) => format!("ν{} ({}::ν{})", ob, ns, o),
+            None => format!("ν{}", ob),
         }
     }
 
+    /// Its atom, if it has one.
+    pub fn atom(&self) -> Option<&'static Meta> {
+        self.lambda.map(by_id)
+    }
+
     /// This object is an empty one, with nothing inside.
     pub fn is_empty(&self) -> bool {
         self.lambda.is_none() && self.delta.is_none() && self.attrs.is_empty()
     }
 
+    /// Its baskets may be kept and used again: it's a constant and its
+    /// atom, if it has one, is pure, see `atom::Meta`.
+    pub fn cacheable(&self) -> bool {
+        self.constant && self.atom().is_none_or(|m| m.pure)
+    }
+
     /// Add a new attribute to it, by the locator loc:
     ///
     /// # Examples
@@ -102,24 +217,21 @@ impl Object {
         obj
     }
 
-    fn copy(&self) -> Object {
-        let mut obj = Object::open();
-        obj.lambda = self.lambda.clone();
-        obj.constant = self.constant;
-        obj.delta = self.delta;
-        obj.attrs.extend(self.attrs.clone());
-        obj
+    /// The Δ as it's printed: a hex number, or a fraction.
+    fn data(&self, d: Data) -> String {
+        #[cfg(feature = "rational")]
+        if let Some(den) = self.denominator {
+            return format!("{}/{}", d, den);
+        }
+        format!("0x{:04X}", d)
     }
-}
 
-impl fmt::Display for Object {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    /// Its λ, Δ and attributes as they are printed, like `𝜑↦ν1(𝜋)`,
+    /// in the canonical order, without the `!`, `%` and `~` markers.
+    pub fn parts(&self) -> Vec<String> {
         let mut parts = vec![];
-        if let Some(a) = &self.lambda {
-            parts.push(format!("λ↦{}", a.0));
-        }
         if let Some(p) = &self.delta {
-            parts.push(format!("Δ↦0x{:04X}", p));
+            parts.push((Loc::Delta, format!("Δ↦{}", self.data(*p))));
         }
         for i in self.attrs.iter() {
             let (attr, (locator, xi)) = i;
@@ -130,14 +242,44 @@ impl fmt::Display for Object {
             } else {
                 "".to_string()
             };
-            parts.push(format!("{}↦{}", attr, locator) + &suffix);
+            parts.push((attr.clone(), format!("{}↦{}{}", attr, locator, suffix)));
         }
         parts.sort();
+        self.atom()
+            .iter()
+            .map(|m| format!("λ↦{}", m.name))
+            .chain(parts.into_iter().map(|(_, p)| p))
+            .collect()
+    }
+
+    fn copy(&self) -> Object {
+        let mut obj = Object::open();
+        obj.lambda = self.lambda;
+        obj.constant = self.constant;
+        obj.memo = self.memo;
+        obj.delta = self.delta;
+        #[cfg(feature = "rational")]
+        {
+            obj.denominator = self.denominator;
+        }
+        obj.attrs.extend(self.attrs.clone());
+        obj
+    }
+}
+
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let marker = match self.memo {
+            Some(true) => "%",
+            Some(false) => "~",
+            None => "",
+        };
+        let flags = format!("{}{}", if self.constant { "!" } else { "" }, marker);
         write!(
             f,
             "⟦{}{}⟧",
-            if self.constant { "! " } else { "" },
-            parts.iter().join(", ")
+            if flags.is_empty() { flags } else { flags + " " },
+            self.parts().join(", ")
         )
     }
 }
@@ -145,8 +287,10 @@ impl fmt::Display for Object {
 impl FromStr for Object {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let re =
-            Regex::new("⟦(!?)(.*)⟧").map_err(|e| format!("Invalid object regex pattern: {}", e))?;
+        let s = normalize(s);
+        let s = s.as_str();
+        let re = Regex::new(syntax::OBJECT)
+            .map_err(|e| format!("Invalid object regex pattern: {}", e))?;
         let mut obj = Object::open();
         let caps = re
             .captures(s)
@@ -168,17 +312,23 @@ impl FromStr for Object {
                 .ok_or_else(|| format!("Empty attribute name in '{}'", pair))?;
             match first_char {
                 'λ' => {
-                    let lambda_fn = match p {
-                        "int-times" => int_times,
-                        "int-div" => int_div,
-                        "int-sub" => int_sub,
-                        "int-add" => int_add,
-                        "int-neg" => int_neg,
-                        "bool-if" => bool_if,
-                        "int-less" => int_less,
-                        _ => return Err(format!("Unknown lambda '{}' in '{}'", p, s)),
-                    };
-                    obj = Object::atomic(p.to_string(), lambda_fn);
+                    obj = Object::atomic(
+                        id(p).ok_or_else(|| format!("Unknown lambda '{}' in '{}'", p, s))?,
+                    );
+                }
+                #[cfg(feature = "rational")]
+                'Δ' if p.contains('/') => {
+                    let (n, d) = p
+                        .split('/')
+                        .map(|t| t.trim().parse::<Data>())
+                        .collect_tuple()
+                        .ok_or_else(|| format!("Can't parse fraction '{}' in '{}'", p, s))?;
+                    let n = n.map_err(|e| format!("Can't parse fraction '{}': {}", p, e))?;
+                    let d = d.map_err(|e| format!("Can't parse fraction '{}': {}", p, e))?;
+                    if d == 0 {
+                        return Err(format!("Zero denominator in '{}'", s));
+                    }
+                    obj = Object::rational(n, d);
                 }
                 'Δ' => {
                     let hex: String = p.chars().skip(2).collect();
@@ -209,13 +359,19 @@ impl FromStr for Object {
                 }
             };
         }
-        let constant_flag = caps
+        let flags = caps
             .get(1)
             .ok_or_else(|| format!("Missing constant flag capture in '{}'", s))?
             .as_str();
-        if !constant_flag.is_empty() {
+        if flags.contains('!') {
             obj.constant = true;
         }
+        obj.memo = match (flags.contains('%'), flags.contains('~')) {
+            (true, true) => return Err(format!("Both % and ~ markers in '{}'", s)),
+            (true, false) => Some(true),
+            (false, true) => Some(false),
+            (false, false) => None,
+        };
         Ok(obj)
     }
 }
@@ -257,6 +413,8 @@ fn prints_and_parses_simple_object() {
 #[rstest]
 #[case("ν7(𝜋) ↦ ⟦! λ ↦ int-sub, ρ ↦ 𝜋.𝜋.𝛼0, 𝛼0 ↦ ν8(𝜋) ⟧")]
 #[case("ν7(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧")]
+#[case("ν7(𝜋) ↦ ⟦!% λ ↦ int-sub, ρ ↦ 𝜋.𝜋.𝛼0, 𝛼0 ↦ ν8(𝜋) ⟧")]
+#[case("ν7(𝜋) ↦ ⟦~ λ ↦ int-add, ρ ↦ ν9(𝜋), 𝛼0 ↦ ν10(𝜋) ⟧")]
 #[case("ν11(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν9(𝜋), 𝛼0 ↦ ν10(𝜋) ⟧")]
 fn prints_and_parses_some_object(#[case] text: String) {
     let obj1 = Object::from_str(&text).unwrap();
@@ -341,3 +499,31 @@ fn fails_on_invalid_loc_in_attribute() {
     let err = result.err().unwrap();
     assert!(err.contains("Can't parse location"));
 }
+
+#[test]
+fn parses_copied_from_paper() {
+    let obj = Object::from_str("⟦ φ ↦ ν3(π), α0 ↦ ρ.𝜌 ⟧").unwrap();
+    assert_eq!("⟦𝜑↦ν3(𝜋), 𝛼0↦ρ.ρ⟧", obj.to_string());
+}
+
+#[cfg(feature = "rational")]
+#[test]
+fn prints_and_parses_fraction() {
+    let obj = Object::from_str("⟦! Δ ↦ 3/7⟧").unwrap();
+    assert_eq!(Some(7), obj.denominator);
+    assert_eq!("⟦! Δ↦3/7⟧", obj.to_string());
+    assert!(Object::from_str("⟦ Δ ↦ 3/0 ⟧").is_err());
+}
+
+#[test]
+fn parses_cache_markers() {
+    let obj = Object::from_str("⟦!% λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧").unwrap();
+    assert!(obj.constant);
+    assert_eq!(Some(true), obj.memo);
+    assert_eq!("⟦!% λ↦int-add, ρ↦ν1(𝜋), 𝛼0↦ν1(𝜋)⟧", obj.to_string());
+    assert_eq!(
+        Some(false),
+        Object::from_str("⟦~ Δ ↦ 0x0001 ⟧").unwrap().memo
+    );
+    assert!(Object::from_str("⟦%~ Δ ↦ 0x0001 ⟧").is_err());
+}
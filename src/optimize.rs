@@ -0,0 +1,344 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Static passes over a `Program`, which make it cheaper to dataize
+//! without changing its result.
+//!
+//! ```
+//! use phie::emu::Emu;
+//! use phie::optimize::collapse;
+//! use phie::program::Program;
+//! use std::str::FromStr;
+//! let mut prog = Program::from_str("
+//!   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+//!   ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+//!   ν2(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+//! ").unwrap();
+//! assert_eq!(1, collapse(&mut prog));
+//! assert_eq!(2, prog.len());
+//! assert_eq!(42, Emu::from(prog).dataize().0);
+//! ```
+
+use crate::check::{Flow, Ranges};
+use crate::emu::ROOT_OB;
+use crate::loc::Loc;
+use crate::locator::Locator;
+use crate::object::{Ob, Object};
+use crate::program::Program;
+use std::collections::HashMap;
+
+/// The object only forwards its 𝜑 to another one, like `ν3 ↦ ⟦ 𝜑 ↦ ν13(𝜋) ⟧`
+/// does, so its basket is just one more hop on the way to the real one.
+fn forwards(obj: &Object) -> Option<Ob> {
+    if obj.delta.is_some() || obj.lambda.is_some() || obj.constant || obj.attrs.len() != 1 {
+        return None;
+    }
+    match obj.attrs.get(&Loc::Phi) {
+        Some((locator, false)) => match locator.to_vec().as_slice() {
+            [Loc::Obj(t)] => Some(*t),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Make all references to forwarding objects point to the objects at
+/// the end of their chains, and remove the forwarding objects that are
+/// not referenced anymore. Returns the number of removed objects.
+pub fn collapse(prog: &mut Program) -> usize {
+    let hops: HashMap<Ob, Ob> = prog
+        .iter()
+        .filter_map(|(ob, obj)| forwards(obj).map(|t| (*ob, t)))
+        .collect();
+    let target = |mut ob: Ob| {
+        let mut seen = vec![ob];
+        while let Some(t) = hops.get(&ob) {
+            if seen.contains(t) || prog.get(*t).is_none() {
+                break;
+            }
+            seen.push(*t);
+            ob = *t;
+        }
+        ob
+    };
+    let mut changes = vec![];
+    for (ob, obj) in prog.iter() {
+        for (loc, (locator, _)) in obj.attrs.iter() {
+            if let [Loc::Obj(r)] = locator.to_vec().as_slice() {
+                let t = target(*r);
+                if t != *r {
+                    changes.push((*ob, loc.clone(), t));
+                }
+            }
+        }
+    }
+    for (ob, loc, t) in changes {
+        if let Some(mut obj) = prog.remove(ob) {
+            if let Some((locator, _)) = obj.attrs.get_mut(&loc) {
+                *locator = Locator::from_loc(Loc::Obj(t));
+            }
+            prog.insert(ob, obj);
+        }
+    }
+    let used: Vec<Ob> = prog
+        .iter()
+        .flat_map(|(_, obj)| obj.attrs.values())
+        .filter_map(|(locator, _)| match locator.loc(0) {
+            Some(Loc::Obj(r)) => Some(*r),
+            _ => None,
+        })
+        .collect();
+    let mut removed = 0;
+    for ob in hops.keys() {
+        if *ob != ROOT_OB && !used.contains(ob) && prog.remove(*ob).is_some() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+/// The bindings of an object like `⟦ 𝜑 ↦ ν2(ξ), 𝛼0 ↦ ν1(𝜋) ⟧`, which only
+/// decorates another object and binds its arguments to other objects.
+fn decorates(obj: &Object) -> Option<(Ob, HashMap<Loc, Ob>)> {
+    if obj.delta.is_some() || obj.lambda.is_some() || obj.constant {
+        return None;
+    }
+    let mut body = None;
+    let mut args = HashMap::new();
+    for (loc, (locator, xi)) in obj.attrs.iter() {
+        match (loc, locator.to_vec().as_slice(), xi) {
+            (Loc::Phi, [Loc::Obj(t)], true) => body = Some(*t),
+            (Loc::Attr(_), [Loc::Obj(a)], false) => {
+                args.insert(loc.clone(), *a);
+            }
+            _ => return None,
+        }
+    }
+    body.map(|t| (t, args))
+}
+
+/// The object, or any object it makes with its 𝜋, looks into its 𝜋.
+fn uses_pi(prog: &Program, ob: Ob, seen: &mut Vec<Ob>) -> bool {
+    if seen.contains(&ob) {
+        return false;
+    }
+    seen.push(ob);
+    let Some(obj) = prog.get(ob) else {
+        return false;
+    };
+    obj.attrs
+        .values()
+        .any(|(locator, xi)| match locator.loc(0) {
+            Some(Loc::Obj(c)) => *xi || uses_pi(prog, *c, seen),
+            Some(Loc::Root) => false,
+            _ => true,
+        })
+}
+
+/// The locator of the body, as it must look when the body takes the place
+/// of the object that decorates it: `𝜋.𝛼0` becomes the object bound to `𝛼0`,
+/// and `𝜋.𝜋` becomes just `𝜋`.
+fn rewrite(locator: &Locator, args: &HashMap<Loc, Ob>) -> Option<Locator> {
+    let locs = locator.to_vec();
+    match locs.as_slice() {
+        [Loc::Obj(_)] | [Loc::Root, ..] => Some(locator.clone()),
+        [Loc::Pi, a @ Loc::Attr(_)] => args.get(a).map(|t| Locator::from_loc(Loc::Obj(*t))),
+        [Loc::Pi, Loc::Pi, ..] => Some(Locator::from_vec(locs[1..].to_vec())),
+        _ => None,
+    }
+}
+
+/// Replace each object that only decorates another one with a copy of
+/// that one, where its arguments are referred to directly, and remove the
+/// bodies that are not used anymore. This is only done when no object made
+/// by the body looks into 𝜋, since their 𝜋 would change. Returns the number
+/// of inlined objects.
+pub fn inline(prog: &mut Program) -> usize {
+    let mut copies = vec![];
+    for (ob, obj) in prog.iter() {
+        let Some((t, args)) = decorates(obj) else {
+            continue;
+        };
+        let Some(body) = prog.get(t) else {
+            continue;
+        };
+        if t == *ob || body.delta.is_some() {
+            continue;
+        }
+        let children = body
+            .attrs
+            .values()
+            .any(|(locator, xi)| match locator.loc(0) {
+                Some(Loc::Obj(c)) => *xi || uses_pi(prog, *c, &mut vec![t]),
+                _ => false,
+            });
+        if children {
+            continue;
+        }
+        let mut copy = body.as_constant();
+        copy.constant = body.constant;
+        let mut ok = true;
+        for (locator, _) in copy.attrs.values_mut() {
+            match rewrite(locator, &args) {
+                Some(r) => *locator = r,
+                None => ok = false,
+            }
+        }
+        if ok {
+            copies.push((*ob, t, copy));
+        }
+    }
+    let total = copies.len();
+    let mut bodies = vec![];
+    for (ob, t, copy) in copies {
+        prog.insert(ob, copy);
+        bodies.push(t);
+    }
+    for t in bodies {
+        let used = prog.iter().any(|(_, obj)| {
+            obj.attrs
+                .values()
+                .any(|(l, _)| l.loc(0) == Some(&Loc::Obj(t)))
+        });
+        if t != ROOT_OB && !used {
+            prog.remove(t);
+        }
+    }
+    total
+}
+
+/// Give a Δ to each object whose integer atom is proved by `Ranges` to
+/// always make the same data, so that its atom is never called and its
+/// checks of overflows and divisions by zero are not made, since they
+/// can't fail. Its attributes stay, for the locators that look into them.
+/// Returns the number of folded objects.
+pub fn fold(prog: &mut Program) -> usize {
+    let mut found = vec![];
+    {
+        let inputs = prog.inputs().values().copied().collect();
+        let mut ranges = Ranges::new(|ob| prog.get(ob), inputs);
+        for (ob, obj) in prog.iter() {
+            if obj.lambda.is_none() {
+                continue;
+            }
+            if let Some(Flow::Within(r)) = ranges.flow(*ob) {
+                if let Some(d) = r.single() {
+                    found.push((*ob, d));
+                }
+            }
+        }
+    }
+    for (ob, d) in found.iter() {
+        if let Some(mut obj) = prog.remove(*ob) {
+            obj.lambda = None;
+            obj.delta = Some(*d);
+            prog.insert(*ob, obj);
+        }
+    }
+    found.len()
+}
+
+#[cfg(test)]
+use crate::emu::{Emu, Opt};
+#[cfg(test)]
+use std::fs;
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn collapses_hops_in_fibonacci() {
+    let text = fs::read_to_string("tests/resources/written_fibonacci_test").unwrap();
+    let mut before = Emu::from_str(&text).unwrap();
+    before.opt(Opt::StopWhenStuck);
+    let (expected, slow) = before.dataize();
+    let mut prog = Program::from_str(&text).unwrap();
+    assert_eq!(1, collapse(&mut prog));
+    assert!(prog.get(Ob::new(3)).is_none());
+    assert_eq!("⟦𝜑↦ν13(ξ), 𝛼0↦ν8(𝜋)⟧", prog[Ob::new(9)].to_string());
+    let mut after = Emu::from(prog);
+    after.opt(Opt::StopWhenStuck);
+    let (result, fast) = after.dataize();
+    assert_eq!(expected, result);
+    assert!(
+        fast.cycles <= slow.cycles,
+        "{} > {}",
+        fast.cycles,
+        slow.cycles
+    );
+}
+
+#[test]
+fn keeps_objects_with_xi_or_more_attributes() {
+    let mut prog = Program::from_str(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν2(ξ) ⟧
+        ν2(𝜋) ↦ ⟦ 𝜑 ↦ ν3(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧
+        ν3(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+        ",
+    )
+    .unwrap();
+    assert_eq!(0, collapse(&mut prog));
+    assert_eq!(4, prog.len());
+}
+
+#[test]
+fn survives_forwarding_loops() {
+    let mut prog = Program::from_str(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+        ν2(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+        ",
+    )
+    .unwrap();
+    collapse(&mut prog);
+    assert!(prog.get(Ob::new(0)).is_some());
+}
+
+#[test]
+fn inlines_decorated_atom() {
+    let text = fs::read_to_string("tests/resources/written_sum_test").unwrap();
+    let mut prog = Program::from_str(&text).unwrap();
+    assert_eq!(1, inline(&mut prog));
+    assert_eq!(
+        "⟦λ↦int-add, ρ↦ν1(𝜋), 𝛼0↦ν1(𝜋)⟧",
+        prog[Ob::new(3)].to_string()
+    );
+    assert!(prog.get(Ob::new(2)).is_none());
+    let mut emu = Emu::from(prog);
+    emu.opt(Opt::StopWhenStuck);
+    assert_eq!(84, emu.dataize().0);
+}
+
+#[test]
+fn leaves_bodies_with_children_looking_into_pi() {
+    let text = fs::read_to_string("tests/resources/written_fibonacci_test").unwrap();
+    let mut prog = Program::from_str(&text).unwrap();
+    collapse(&mut prog);
+    assert_eq!(0, inline(&mut prog));
+}
+
+#[test]
+fn folds_atoms_with_known_data() {
+    let mut prog = Program::from_str(
+        "
+        +input x ν4
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν5(𝜋) ⟧
+        ν5(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν4(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ λ ↦ int-times, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧
+        ν2(𝜋) ↦ ⟦ Δ ↦ 0x0006 ⟧
+        ν3(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν2(𝜋) ⟧
+        ν4(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧
+        ",
+    )
+    .unwrap();
+    assert_eq!(2, fold(&mut prog));
+    assert_eq!(Some(-36), prog[Ob::new(1)].delta);
+    assert!(prog[Ob::new(5)].lambda.is_some());
+    let mut emu = Emu::from(prog);
+    emu.opt(Opt::StopWhenStuck);
+    assert_eq!(-34, emu.dataize().0);
+    emu.bind("x", 40).unwrap();
+    assert_eq!(4, emu.dataize().0);
+}
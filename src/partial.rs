@@ -0,0 +1,184 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! What a dataization got to before it ran out of cycles, see
+//! `Opt::MaxCycles` and `Emu::outcome()`: the objects already dataized,
+//! the baskets that went furthest, and how much is left to do, counted
+//! at the end and halfway through, which tells a slow program from one
+//! that never ends:
+//!
+//! ```
+//! use phie::emu::{Emu, Opt};
+//! use phie::partial::Outcome;
+//! let mut emu: Emu = "
+//!   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+//!   ν1(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν1(ξ) ⟧
+//! ".parse().unwrap();
+//! emu.opt(Opt::MaxCycles(20));
+//! match emu.outcome().unwrap() {
+//!     Outcome::Partial(p) => assert!(p.diverging()),
+//!     Outcome::Dataized(_, _) => panic!("It never ends"),
+//! }
+//! ```
+
+use crate::basket::{Basket, Bk, Kid};
+use crate::data::{Data, Datum};
+use crate::emu::ROOT_BK;
+use crate::loc::Loc;
+use crate::object::Ob;
+use crate::perf::Perf;
+use std::fmt;
+
+/// How many of the deepest baskets are kept.
+const DEEPEST: usize = 5;
+
+/// How a dataization ended, unless it failed.
+pub enum Outcome<D: Datum = Data> {
+    /// The first object is dataized.
+    Dataized(D, Perf),
+    /// The cycles ran out before it was.
+    Partial(Partial),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partial {
+    /// The number of cycles made.
+    pub cycles: usize,
+    /// The objects with their 𝜑 dataized in live baskets, by number.
+    pub dataized: Vec<(Ob, Data)>,
+    /// The live baskets furthest from the root by their ψ, the furthest
+    /// first, with how many steps away they are.
+    pub deepest: Vec<(Bk, Ob, usize)>,
+    /// The kids still requested, needed or waiting.
+    pub pending: usize,
+    /// The same, counted halfway through, if the limit was known.
+    pub halfway: Option<usize>,
+}
+
+impl Partial {
+    /// Take what the baskets have got to.
+    pub fn take<D: Datum>(cycles: usize, baskets: &[Basket<D>], halfway: Option<usize>) -> Partial {
+        let live = || {
+            baskets
+                .iter()
+                .enumerate()
+                .filter(|(_, bsk)| !bsk.is_empty())
+        };
+        let mut dataized: Vec<(Ob, Data)> = live()
+            .filter_map(|(_, bsk)| Some((bsk.ob, bsk.kids.get(&Loc::Phi)?.data()?.data())))
+            .collect();
+        dataized.sort();
+        dataized.dedup_by_key(|(ob, _)| *ob);
+        let mut deepest: Vec<(Bk, Ob, usize)> = live()
+            .map(|(bk, bsk)| (Bk::new(bk), bsk.ob, Partial::depth(baskets, Bk::new(bk))))
+            .collect();
+        deepest.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+        deepest.truncate(DEEPEST);
+        Partial {
+            cycles,
+            dataized,
+            deepest,
+            pending: Partial::pending(baskets),
+            halfway,
+        }
+    }
+
+    /// The kids of live baskets still requested, needed or waiting.
+    pub fn pending<D: Datum>(baskets: &[Basket<D>]) -> usize {
+        baskets
+            .iter()
+            .filter(|bsk| !bsk.is_empty())
+            .flat_map(|bsk| bsk.kids.values())
+            .filter(|k| matches!(k, Kid::Rqtd | Kid::Need(_, _) | Kid::Wait(_, _)))
+            .count()
+    }
+
+    /// Whether there is more left to do than halfway through, which is
+    /// what happens when the recursion never ends.
+    pub fn diverging(&self) -> bool {
+        self.halfway.is_some_and(|h| self.pending > h)
+    }
+
+    /// The number of ψ steps from the basket to the root one.
+    fn depth<D: Datum>(baskets: &[Basket<D>], bk: Bk) -> usize {
+        let mut depth = 0;
+        let mut b = bk;
+        while b != ROOT_BK && !b.is_none() && depth < baskets.len() {
+            b = baskets[b.index()].psi;
+            depth += 1;
+        }
+        depth
+    }
+}
+
+impl fmt::Display for Partial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Stopped after {} cycles, with {} kids pending",
+            self.cycles, self.pending
+        )?;
+        if let Some(h) = self.halfway {
+            write!(
+                f,
+                " ({} halfway), {}",
+                h,
+                if self.diverging() {
+                    "most probably endless"
+                } else {
+                    "most probably slow"
+                }
+            )?;
+        }
+        write!(
+            f,
+            "\nDataized: {}",
+            self.dataized
+                .iter()
+                .map(|(ob, d)| format!("ν{}=0x{:04X}", ob, d))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )?;
+        write!(
+            f,
+            "\nDeepest: {}",
+            self.deepest
+                .iter()
+                .map(|(bk, ob, depth)| format!("β{}/ν{} at {}", bk, ob, depth))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+use crate::emu::{Emu, Opt};
+
+#[test]
+fn tells_slow_from_endless() {
+    let mut emu: Emu = (crate::examples::FIBONACCI.program)(3).parse().unwrap();
+    emu.opt(Opt::MaxCycles(70));
+    let Ok(Outcome::Partial(slow)) = emu.outcome() else {
+        panic!("Fibonacci of 3 is not that fast")
+    };
+    assert_eq!(71, slow.cycles);
+    assert!(!slow.dataized.is_empty(), "{}", slow);
+    assert!(slow.deepest[0].2 > 1, "{}", slow);
+    assert!(!slow.diverging(), "{}", slow);
+    let mut emu: Emu = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν1(ξ) ⟧"
+        .parse()
+        .unwrap();
+    emu.opt(Opt::MaxCycles(20));
+    let Ok(Outcome::Partial(endless)) = emu.outcome() else {
+        panic!("It never ends")
+    };
+    assert!(endless.diverging(), "{}", endless);
+    assert!(endless.to_string().contains("most probably endless"));
+}
+
+#[test]
+fn dataizes_fully_within_limit() {
+    let mut emu: Emu = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+    emu.opt(Opt::MaxCycles(20));
+    assert!(matches!(emu.outcome(), Ok(Outcome::Dataized(42, _))));
+}
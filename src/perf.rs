@@ -4,8 +4,10 @@
 use itertools::Itertools;
 use std::collections::HashMap;
 use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::time::{Duration, Instant};
 
-#[derive(Hash, Eq, PartialEq, strum_macros::Display)]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, strum_macros::Display)]
 pub enum Transition {
     CPY,
     DEL,
@@ -13,14 +15,69 @@ pub enum Transition {
     DLG,
     PPG,
     FIND,
+    SPC,
 }
 
 pub struct Perf {
     pub cycles: usize,
     pub peak: usize,
+    pub speculated: usize,
+    pub wasted: usize,
+    /// Calls of atoms answered from the memo and the ones that missed it,
+    /// see `Opt::Memoize`.
+    pub memo_hits: usize,
+    pub memo_misses: usize,
     pub atoms: HashMap<String, usize>,
     pub hits: HashMap<Transition, usize>,
     pub ticks: HashMap<Transition, usize>,
+    /// Finished and running scopes, in the order they were started.
+    pub sections: Vec<Section>,
+    /// Baskets emptied before this run and the kids they kept room for,
+    /// see `Runtime::reset()`.
+    pub freed: usize,
+    pub retained: usize,
+    /// Bytes allocated while dataizing, see `heap`.
+    #[cfg(feature = "heap")]
+    pub allocated: usize,
+    depth: usize,
+    epoch: usize,
+}
+
+/// The numbers collected while a `Scope` was alive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    /// How many scopes were around this one.
+    pub depth: usize,
+    pub cycles: usize,
+    pub hits: usize,
+    pub ticks: usize,
+    pub atoms: usize,
+    pub elapsed: Duration,
+}
+
+/// A guard made by `Perf::scope()`, which stands for the `Perf` itself
+/// and records a `Section` when dropped.
+///
+/// ```
+/// use phie::perf::{Perf, Transition};
+/// let mut perf = Perf::new();
+/// {
+///     let mut outer = perf.scope("outer");
+///     outer.hit(Transition::CPY);
+///     let mut inner = outer.scope("inner");
+///     inner.hit(Transition::NEW);
+/// }
+/// assert_eq!(2, perf.sections[0].hits);
+/// assert_eq!(1, perf.sections[1].hits);
+/// assert_eq!(1, perf.sections[1].depth);
+/// ```
+pub struct Scope<'a> {
+    perf: &'a mut Perf,
+    index: usize,
+    epoch: usize,
+    start: [usize; 4],
+    time: Instant,
 }
 
 impl Default for Perf {
@@ -37,7 +94,76 @@ impl Perf {
             hits: HashMap::new(),
             cycles: 0,
             peak: 0,
+            speculated: 0,
+            wasted: 0,
+            memo_hits: 0,
+            memo_misses: 0,
+            freed: 0,
+            retained: 0,
+            sections: vec![],
+            #[cfg(feature = "heap")]
+            allocated: 0,
+            depth: 0,
+            epoch: 0,
+        }
+    }
+
+    /// Forget everything counted so far, including the sections.
+    /// The scopes which are still alive won't record theirs.
+    pub fn reset(&mut self) {
+        let (depth, epoch) = (self.depth, self.epoch);
+        *self = Perf::new();
+        self.depth = depth;
+        self.epoch = epoch + 1;
+    }
+
+    /// Start counting a section, which ends when the guard is dropped.
+    /// Scopes started from the guard are nested into it.
+    pub fn scope(&mut self, name: &str) -> Scope<'_> {
+        self.sections.push(Section {
+            name: name.to_string(),
+            depth: self.depth,
+            ..Section::default()
+        });
+        self.depth += 1;
+        Scope {
+            index: self.sections.len() - 1,
+            epoch: self.epoch,
+            start: self.counters(),
+            time: Instant::now(),
+            perf: self,
+        }
+    }
+
+    /// Cycles, hits, ticks and atoms, to subtract from.
+    fn counters(&self) -> [usize; 4] {
+        [
+            self.cycles,
+            self.total_hits(),
+            self.total_ticks(),
+            self.total_atoms(),
+        ]
+    }
+
+    /// All the numbers, together with the sections, if any.
+    pub fn report(&self) -> String {
+        let mut lines = vec![self.to_string()];
+        if !self.sections.is_empty() {
+            lines.push("Sections:".to_string());
+            lines.extend(self.sections.iter().map(|s| {
+                format!(
+                    "\t{}{}: {} cycles, {} hits, {} ticks, {} atoms, {:?}",
+                    "  ".repeat(s.depth),
+                    s.name,
+                    s.cycles,
+                    s.hits,
+                    s.ticks,
+                    s.atoms,
+                    s.elapsed
+                )
+            }));
         }
+        lines.join("\n")
     }
 
     pub fn tick(&mut self, t: Transition) {
@@ -48,8 +174,13 @@ impl Perf {
         *self.hits.entry(t).or_insert(0) += 1;
     }
 
-    pub fn atom(&mut self, a: String) {
-        *self.atoms.entry(a).or_insert(0) += 1;
+    pub fn atom(&mut self, a: &str) {
+        match self.atoms.get_mut(a) {
+            Some(c) => *c += 1,
+            None => {
+                self.atoms.insert(a.to_string(), 1);
+            }
+        }
     }
 
     pub fn peak(&mut self, s: usize) {
@@ -71,6 +202,35 @@ impl Perf {
     }
 }
 
+impl Deref for Scope<'_> {
+    type Target = Perf;
+    fn deref(&self) -> &Perf {
+        self.perf
+    }
+}
+
+impl DerefMut for Scope<'_> {
+    fn deref_mut(&mut self) -> &mut Perf {
+        self.perf
+    }
+}
+
+impl Drop for Scope<'_> {
+    fn drop(&mut self) {
+        self.perf.depth -= 1;
+        if self.perf.epoch != self.epoch {
+            return;
+        }
+        let now = self.perf.counters();
+        let section = &mut self.perf.sections[self.index];
+        section.cycles = now[0] - self.start[0];
+        section.hits = now[1] - self.start[1];
+        section.ticks = now[2] - self.start[2];
+        section.atoms = now[3] - self.start[3];
+        section.elapsed = self.time.elapsed();
+    }
+}
+
 macro_rules! print {
     ($lines:expr, $title:expr, $list:expr, $total:expr) => {
         $lines.push(format!("{}:", $title));
@@ -89,6 +249,26 @@ impl fmt::Display for Perf {
         let mut lines = vec![];
         lines.push(format!("Cycles: {}", self.cycles));
         lines.push(format!("Peak: {}", self.peak));
+        #[cfg(feature = "heap")]
+        lines.push(format!("Allocated: {} bytes", self.allocated));
+        if self.speculated > 0 {
+            lines.push(format!(
+                "Speculated: {}, wasted baskets: {}",
+                self.speculated, self.wasted
+            ));
+        }
+        if self.freed + self.retained > 0 {
+            lines.push(format!(
+                "Reset: {} baskets freed, room for {} kids retained",
+                self.freed, self.retained
+            ));
+        }
+        if self.memo_hits + self.memo_misses > 0 {
+            lines.push(format!(
+                "Memo: {} hits, {} misses",
+                self.memo_hits, self.memo_misses
+            ));
+        }
         print!(lines, "Atoms", self.atoms, self.total_atoms());
         print!(lines, "Ticks", self.ticks, self.total_ticks());
         print!(lines, "Hits", self.hits, self.total_hits());
@@ -125,9 +305,9 @@ pub fn counts_ticks() {
 #[test]
 pub fn counts_atoms() {
     let mut perf = Perf::new();
-    perf.atom("int-add".to_string());
-    perf.atom("int-add".to_string());
-    perf.atom("int-sub".to_string());
+    perf.atom("int-add");
+    perf.atom("int-add");
+    perf.atom("int-sub");
     assert_eq!(perf.total_atoms(), 3);
     assert!(perf.to_string().contains("Atoms:"));
 }
@@ -141,6 +321,17 @@ pub fn tracks_peak() {
     assert_eq!(perf.peak, 10);
 }
 
+#[test]
+pub fn prints_speculation_only_when_used() {
+    let mut perf = Perf::new();
+    assert!(!perf.to_string().contains("Speculated"));
+    perf.speculated = 2;
+    perf.wasted = 5;
+    assert!(perf
+        .to_string()
+        .contains("Speculated: 2, wasted baskets: 5"));
+}
+
 #[test]
 pub fn uses_default() {
     let perf = Perf::default();
@@ -148,3 +339,46 @@ pub fn uses_default() {
     assert_eq!(perf.peak, 0);
     assert_eq!(perf.total_hits(), 0);
 }
+
+#[test]
+pub fn resets_counters() {
+    let mut perf = Perf::new();
+    perf.hit(Transition::DEL);
+    perf.cycles = 3;
+    drop(perf.scope("gone"));
+    perf.reset();
+    assert_eq!(0, perf.total_hits());
+    assert_eq!(0, perf.cycles);
+    assert!(perf.sections.is_empty());
+}
+
+#[test]
+pub fn ignores_scope_reset_inside() {
+    let mut perf = Perf::new();
+    {
+        let mut outer = perf.scope("outer");
+        outer.reset();
+        let mut inner = outer.scope("inner");
+        inner.tick(Transition::CPY);
+    }
+    assert_eq!(1, perf.sections.len());
+    assert_eq!(1, perf.sections[0].ticks);
+    assert_eq!(1, perf.sections[0].depth);
+}
+
+#[test]
+pub fn reports_nested_sections() {
+    let mut perf = Perf::new();
+    {
+        let mut first = perf.scope("first");
+        first.cycles += 2;
+        first.scope("second").atom("int-add");
+    }
+    let report = perf.report();
+    assert!(
+        report.contains("Sections:\n\tfirst: 2 cycles, 0 hits, 0 ticks, 1 atoms"),
+        "{}",
+        report
+    );
+    assert!(report.contains("\t  second: 0 cycles"), "{}", report);
+}
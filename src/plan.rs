@@ -0,0 +1,185 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Precompiled resolution of locators.
+//!
+//! A locator is resolved by `Emu` step by step: `ν3` and `Φ` jump to an
+//! object, `𝜋` switches to the ψ basket, `σ` to the basket of the object
+//! the current one is declared in, while any other attribute is
+//! taken from the current object, replacing itself with its own locator.
+//! As long as the current object is known statically, the last kind of
+//! steps can be made once, before dataization, which is what `Plan` does:
+//!
+//! ```
+//! use phie::object::Object;
+//! use phie::loc::Loc;
+//! use phie::locator::Locator;
+//! use phie::plan::Plan;
+//! use phie::ph;
+//! use std::str::FromStr;
+//! let obj = Object::open().with(Loc::Attr(0), ph!("ν7"), false);
+//! let plan = Plan::compile(&ph!("Φ.𝛼0"), |ob| (ob.index() == 0).then_some(&obj));
+//! assert_eq!("Φ !𝛼0 ν7", plan.to_string());
+//! ```
+
+use crate::emu::ROOT_OB;
+use crate::loc::Loc;
+use crate::locator::Locator;
+use crate::object::{Ob, Object};
+use std::fmt;
+
+/// How deep the attributes of known objects are expanded,
+/// to stop on objects that refer to themselves.
+const MAX_STEPS: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Jump to the object, as `ν3` and `Φ` do.
+    Object(Ob),
+    /// Switch to the ψ basket, as `𝜋` does.
+    Psi,
+    /// Switch to the home of the current object, as `σ` does.
+    Sigma,
+    /// Take the attribute from the current object, at runtime.
+    Attr(Loc),
+    /// Remember that the attribute of the current object was taken,
+    /// while its locator is already expanded in the next steps.
+    Mark(Loc),
+    /// Remember that the current object has no such attribute, so its 𝜑
+    /// is consulted, already expanded in the next steps.
+    Fall(Loc),
+}
+
+impl Step {
+    /// The step that resolves this part of a locator.
+    pub fn of(loc: &Loc) -> Step {
+        match loc {
+            Loc::Root => Step::Object(ROOT_OB),
+            Loc::Obj(i) => Step::Object(*i),
+            Loc::Pi => Step::Psi,
+            Loc::Sigma => Step::Sigma,
+            other => Step::Attr(other.clone()),
+        }
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Step::Object(ob) if *ob == ROOT_OB => f.write_str("Φ"),
+            Step::Object(ob) => write!(f, "ν{}", ob),
+            Step::Psi => f.write_str("𝜋"),
+            Step::Sigma => f.write_str("σ"),
+            Step::Attr(loc) => write!(f, "{}", loc),
+            Step::Mark(loc) => write!(f, "!{}", loc),
+            Step::Fall(loc) => write!(f, "?{}", loc),
+        }
+    }
+}
+
+/// The steps to make in order to resolve a locator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    steps: Vec<Step>,
+}
+
+impl Plan {
+    /// Turn the locator into steps, taking attributes of the objects
+    /// that are known before dataization.
+    pub fn compile<'a>(locator: &Locator, object: impl Fn(Ob) -> Option<&'a Object>) -> Plan {
+        let mut todo: Vec<Step> = locator.to_vec().iter().rev().map(Step::of).collect();
+        let mut steps = vec![];
+        let mut known = None;
+        while let Some(step) = todo.pop() {
+            if steps.len() + todo.len() > MAX_STEPS {
+                known = None;
+            }
+            match (&step, known.and_then(&object)) {
+                (Step::Object(ob), _) => known = Some(*ob),
+                (Step::Psi, _) | (Step::Sigma, _) => known = None,
+                (Step::Attr(loc), Some(obj)) => {
+                    if let Some((p, _)) = obj.attrs.get(loc) {
+                        steps.push(Step::Mark(loc.clone()));
+                        todo.extend(p.to_vec().iter().rev().map(Step::of));
+                        continue;
+                    }
+                    if let Some((p, _)) = obj.attrs.get(&Loc::Phi) {
+                        steps.push(Step::Fall(loc.clone()));
+                        todo.push(step.clone());
+                        todo.extend(p.to_vec().iter().rev().map(Step::of));
+                        continue;
+                    }
+                    known = None;
+                }
+                _ => known = None,
+            }
+            steps.push(step);
+        }
+        Plan { steps }
+    }
+
+    /// The plan without any compilation, just the parts of the locator.
+    pub fn of(locator: &Locator) -> Plan {
+        Plan::compile(locator, |_| None)
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(
+            &self
+                .steps
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<String>>()
+                .join(" "),
+        )
+    }
+}
+
+#[cfg(test)]
+use crate::ph;
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn keeps_dynamic_steps() {
+    assert_eq!("𝜋 𝜋 𝛼0", Plan::of(&ph!("𝜋.𝜋.𝛼0")).to_string());
+    assert_eq!("ν5", Plan::of(&ph!("ν5")).to_string());
+    assert_eq!("σ σ 𝛼0", Plan::of(&ph!("σ.σ.𝛼0")).to_string());
+}
+
+#[test]
+fn expands_attributes_through_phi() {
+    let root = Object::open().with(Loc::Phi, ph!("ν1"), false);
+    let one = Object::open().with(Loc::Attr(2), ph!("𝜋.ρ"), false);
+    let plan = Plan::compile(&ph!("Φ.𝛼2"), |ob| match ob.index() {
+        0 => Some(&root),
+        1 => Some(&one),
+        _ => None,
+    });
+    assert_eq!("Φ ?𝛼2 ν1 !𝛼2 𝜋 ρ", plan.to_string());
+}
+
+#[test]
+fn stops_on_objects_referring_to_themselves() {
+    let root = Object::open().with(Loc::Phi, ph!("Φ"), false);
+    let plan = Plan::compile(&ph!("Φ.𝛼0"), |_| Some(&root));
+    assert!(plan.steps().len() <= MAX_STEPS + 2, "{}", plan);
+}
+
+#[test]
+fn remembers_objects_fallen_through() {
+    let root = Object::open().with(Loc::Phi, ph!("ν1"), false);
+    let one = Object::open();
+    let plan = Plan::compile(&ph!("Φ.𝛼2"), |ob| match ob.index() {
+        0 => Some(&root),
+        1 => Some(&one),
+        _ => None,
+    });
+    assert_eq!("Φ ?𝛼2 ν1 𝛼2", plan.to_string());
+}
@@ -0,0 +1,530 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Many isolated dataizations of one program, for services that
+//! evaluate untrusted snippets of many tenants at once.
+//!
+//! The `Pool` parses nothing itself: it owns a `Program` and makes a
+//! fresh `Emu` of it for every `Runtime` spawned. Each runtime has a
+//! `Budget` of fuel, which is the number of cycles it may make, and
+//! of memory, which is the number of baskets it may keep alive. The
+//! pool runs them in rounds, giving every unfinished runtime the same
+//! `quantum` of cycles in each round, so that a hungry tenant can't
+//! starve the others:
+//!
+//! ```
+//! use phie::pool::{Budget, Pool};
+//! let mut pool = Pool::new("
+//!   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+//!   ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+//!   ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧
+//! ".parse().unwrap());
+//! pool.spawn("alice", Budget { fuel: 100, memory: 16 });
+//! pool.spawn("bob", Budget { fuel: 2, memory: 16 });
+//! let results = pool.run();
+//! assert_eq!(84, *results[0].1.as_ref().unwrap());
+//! assert_eq!(5, results[1].1.as_ref().err().unwrap().code());
+//! ```
+//!
+//! With the `parallel` feature, the runtimes of a round make their
+//! cycles on all cores at once.
+
+use crate::basket::{Basket, Kid};
+use crate::crash::Dump;
+use crate::data::Data;
+use crate::emu::{Emu, Opt, SharedMemo, ROOT_BK};
+use crate::error::{message, Error};
+use crate::loc::Loc;
+use crate::object::Ob;
+use crate::perf::Perf;
+use crate::program::Program;
+use std::collections::HashMap;
+use std::panic;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread::{Scope, ScopedJoinHandle};
+
+/// Cycles each runtime makes in a round, unless `Pool::quantum()` says otherwise.
+pub const QUANTUM: usize = 16;
+
+/// What a runtime may spend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    /// How many cycles it may make.
+    pub fuel: usize,
+    /// How many baskets it may keep alive at once.
+    pub memory: usize,
+}
+
+/// One dataization of the program, for one tenant.
+pub struct Runtime {
+    pub tenant: String,
+    pub budget: Budget,
+    pub perf: Perf,
+    emu: Emu,
+    outcome: Option<Result<Data, Error>>,
+}
+
+impl Runtime {
+    /// A runtime of the program outside of any pool and without any
+    /// budget, to dataize it again and again, see `Runtime::reset()`.
+    pub fn of(program: &Program) -> Runtime {
+        Runtime {
+            tenant: String::new(),
+            budget: Budget {
+                fuel: usize::MAX,
+                memory: usize::MAX,
+            },
+            perf: Perf::new(),
+            emu: Emu::from(program),
+            outcome: None,
+        }
+    }
+
+    /// Dataize to the end in one go, like `Emu::try_dataize()` does,
+    /// no matter the budget.
+    pub fn finish(&mut self) -> Result<Data, Error> {
+        let outcome = self.emu.try_dataize().map(|(d, mut perf)| {
+            perf.freed = self.perf.freed;
+            perf.retained = self.perf.retained;
+            self.perf = perf;
+            d
+        });
+        self.outcome = Some(outcome.clone());
+        outcome
+    }
+
+    /// Get ready to dataize the program once again, emptying the baskets
+    /// in place, see `Emu::recycle()`, and forgetting the outcome and the
+    /// numbers of the previous run; the baskets freed and the room kept
+    /// for their kids are counted in `Perf::freed` and `Perf::retained`.
+    pub fn reset(&mut self) {
+        let (freed, retained) = self.emu.recycle();
+        self.perf = Perf::new();
+        self.perf.freed = freed;
+        self.perf.retained = retained;
+        self.outcome = None;
+    }
+
+    /// Before the first cycle, put back the finished baskets of a previous
+    /// run, saved in the dump, see `Dump::of()`, so that the objects which
+    /// didn't change since then are not dataized again, see
+    /// `Emu::inject()`. An object is kept if its text is the same in both
+    /// programs and it's either a Δ, or a constant whose atom is pure and
+    /// whose attributes only refer to kept objects by their numbers, like
+    /// `ν5`. Only the data of the kids are put back. Returns the number
+    /// of baskets put back:
+    ///
+    /// ```
+    /// use phie::crash::Dump;
+    /// use phie::emu::Emu;
+    /// use phie::pool::Runtime;
+    /// let before = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν3(𝜋) ⟧\nν1(𝜋) ↦ ⟦! Δ ↦ 0x0007 ⟧\n\
+    ///     ν2(𝜋) ↦ ⟦! λ ↦ int-neg, ρ ↦ ν1(𝜋) ⟧\nν3(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν4(𝜋) ⟧\n\
+    ///     ν4(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧";
+    /// let mut emu: Emu = before.parse().unwrap();
+    /// let (d, perf) = emu.dataize();
+    /// assert_eq!(-6, d);
+    /// let dump = Dump::of(&emu, "", perf.cycles);
+    /// let after = before.replace("0x0001", "0x0002");
+    /// let mut runtime = Runtime::of(&after.parse().unwrap());
+    /// assert!(runtime.warm_start(&dump).unwrap() > 0);
+    /// assert_eq!(-5, runtime.finish().unwrap());
+    /// ```
+    pub fn warm_start(&mut self, dump: &Dump) -> Result<usize, String> {
+        if self.perf.cycles > 0 || self.outcome.is_some() {
+            return Err("The runtime has started already, it's too late to warm it up".to_string());
+        }
+        let old = Program::from_str(&dump.program)?;
+        let mut kept = HashMap::new();
+        let mut count = 0;
+        for (bk, bsk) in dump.checkpoint.baskets::<Data>()? {
+            if bk == ROOT_BK
+                || !matches!(bsk.kids.get(&Loc::Phi), Some(Kid::Dtzd(_)))
+                || !self.emu.basket(bk).is_empty()
+                || !reusable(&self.emu, &old, bsk.ob, &mut kept)
+            {
+                continue;
+            }
+            let mut warm = Basket::start(bsk.ob, bsk.psi);
+            for (loc, kid) in bsk.kids.iter() {
+                if let Kid::Dtzd(d) = kid {
+                    warm.put(loc.clone(), Kid::Dtzd(*d));
+                }
+            }
+            self.emu.inject(bk, warm);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// The emulator, to set options or a sandbox before running.
+    pub fn emu(&mut self) -> &mut Emu {
+        &mut self.emu
+    }
+
+    /// The result, if it's finished already.
+    pub fn outcome(&self) -> Option<&Result<Data, Error>> {
+        self.outcome.as_ref()
+    }
+
+    /// Make up to this many cycles, unless it finishes earlier.
+    fn slice(&mut self, cycles: usize) {
+        for _ in 0..cycles {
+            if self.outcome.is_some() {
+                break;
+            }
+            let step =
+                panic::catch_unwind(panic::AssertUnwindSafe(|| self.emu.step(&mut self.perf)));
+            self.outcome = match step {
+                Err(e) => Some(Err(Error::Failed(message(e.as_ref())))),
+                Ok(Err(e)) => Some(Err(e)),
+                Ok(Ok(Some(d))) => Some(Ok(d)),
+                Ok(Ok(None)) if self.perf.cycles >= self.budget.fuel => {
+                    Some(Err(Error::Cycles(format!(
+                        "Too many cycles ({}), the tenant '{}' is out of fuel",
+                        self.perf.cycles, self.tenant
+                    ))))
+                }
+                Ok(Ok(None)) if self.live() > self.budget.memory => {
+                    Some(Err(Error::Failed(format!(
+                        "Too many baskets ({}), the tenant '{}' is out of memory",
+                        self.live(),
+                        self.tenant
+                    ))))
+                }
+                Ok(Ok(None)) => None,
+            };
+        }
+    }
+
+    fn live(&self) -> usize {
+        self.emu
+            .baskets
+            .iter()
+            .filter(|bsk| !bsk.is_empty())
+            .count()
+    }
+}
+
+pub struct Pool {
+    program: Program,
+    runtimes: Vec<Runtime>,
+    quantum: usize,
+}
+
+impl Pool {
+    /// Make a pool of runtimes of this program.
+    pub fn new(program: Program) -> Pool {
+        Pool {
+            program,
+            runtimes: vec![],
+            quantum: QUANTUM,
+        }
+    }
+
+    /// Set the number of cycles each runtime makes in a round.
+    pub fn quantum(&mut self, cycles: usize) -> &mut Pool {
+        self.quantum = cycles.max(1);
+        self
+    }
+
+    /// Start one more runtime, with a fresh copy of the program.
+    pub fn spawn(&mut self, tenant: &str, budget: Budget) -> &mut Runtime {
+        self.runtimes.push(Runtime {
+            tenant: tenant.to_string(),
+            budget,
+            perf: Perf::new(),
+            emu: Emu::from(&self.program),
+            outcome: None,
+        });
+        self.runtimes.last_mut().unwrap()
+    }
+
+    /// All runtimes, in the order they were spawned.
+    pub fn runtimes(&self) -> &[Runtime] {
+        &self.runtimes
+    }
+
+    /// Make one round, returning TRUE if some runtimes are still unfinished.
+    pub fn round(&mut self) -> bool {
+        let quantum = self.quantum;
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            self.runtimes.par_iter_mut().for_each(|r| r.slice(quantum));
+        }
+        #[cfg(not(feature = "parallel"))]
+        self.runtimes.iter_mut().for_each(|r| r.slice(quantum));
+        self.runtimes.iter().any(|r| r.outcome.is_none())
+    }
+
+    /// Make rounds until all runtimes finish, and take their results,
+    /// leaving the pool empty.
+    pub fn run(&mut self) -> Vec<(String, Result<Data, Error>)> {
+        while self.round() {}
+        self.runtimes
+            .drain(..)
+            .map(|r| (r.tenant, r.outcome.unwrap()))
+            .collect()
+    }
+}
+
+/// May the finished baskets of the object in the old program be put
+/// into the `Emu`, see `Runtime::warm_start()`? The answers are kept,
+/// and an object met again on the way is not reusable, to stop cycles.
+fn reusable(emu: &Emu, old: &Program, ob: Ob, kept: &mut HashMap<Ob, bool>) -> bool {
+    if let Some(k) = kept.get(&ob) {
+        return *k;
+    }
+    kept.insert(ob, false);
+    let Some(obj) = emu.objects.get(ob.index()).filter(|o| !o.is_empty()) else {
+        return false;
+    };
+    let same = old
+        .get(ob)
+        .is_some_and(|o| o.to_string() == obj.to_string());
+    let ok = same
+        && (obj.delta.is_some()
+            || (obj.cacheable()
+                && obj.attrs.values().all(|(locator, _)| match locator.locs() {
+                    [Loc::Obj(o)] => reusable(emu, old, *o, kept),
+                    _ => false,
+                })));
+    kept.insert(ob, ok);
+    ok
+}
+
+/// Spawns runtimes of one program on threads of a scope, sharing the
+/// memoized results of pure atoms among them, see `Program::scope()`.
+pub struct Spawner<'scope, 'env> {
+    scope: &'scope Scope<'scope, 'env>,
+    program: &'env Program,
+    memo: SharedMemo,
+}
+
+impl<'scope, 'env> Spawner<'scope, 'env> {
+    pub(crate) fn new(scope: &'scope Scope<'scope, 'env>, program: &'env Program) -> Self {
+        Spawner {
+            scope,
+            program,
+            memo: SharedMemo::default(),
+        }
+    }
+
+    /// Dataize the program on a new thread, by a fresh `Emu` with
+    /// `Opt::Memoize`, after the setup has set its options or bound its
+    /// inputs. Only the memo is shared, never the baskets. The setup may
+    /// change the arithmetic or float mode, results are kept by them.
+    pub fn spawn<F>(&self, setup: F) -> ScopedJoinHandle<'scope, Result<(Data, Perf), Error>>
+    where
+        F: FnOnce(&mut Emu) + Send + 'scope,
+    {
+        let program = self.program;
+        let memo = Arc::clone(&self.memo);
+        self.scope.spawn(move || {
+            let mut emu = Emu::from(program);
+            emu.opt(Opt::Memoize);
+            emu.share_memo(memo);
+            setup(&mut emu);
+            emu.try_dataize()
+        })
+    }
+
+    /// How many results of atoms are memoized by all runtimes so far.
+    pub fn memoized(&self) -> usize {
+        self.memo.read().map_or(0, |m| m.len())
+    }
+}
+
+#[cfg(test)]
+use crate::emu::Arithmetic;
+#[cfg(test)]
+use crate::sandbox::Profile;
+#[cfg(test)]
+fn looping() -> Program {
+    Program::from_str(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦! 𝜑 ↦ ν2(𝜋) ⟧
+        ν2(𝜋) ↦ ⟦! 𝜑 ↦ ν1(𝜋) ⟧
+        ",
+    )
+    .unwrap()
+}
+
+#[test]
+fn keeps_runtimes_isolated() {
+    let mut pool = Pool::new(
+        Program::from_str(
+            "
+            ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+            ν1(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+            ν2(𝜋) ↦ ⟦ λ ↦ int-times, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧
+            ",
+        )
+        .unwrap(),
+    );
+    let budget = Budget {
+        fuel: 100,
+        memory: 16,
+    };
+    pool.spawn("a", budget);
+    pool.spawn("b", budget).emu().sandbox(Profile::none());
+    pool.spawn("c", budget).emu().opt(Opt::DontDelete);
+    let results = pool.run();
+    assert_eq!(49, *results[0].1.as_ref().unwrap());
+    assert_eq!(6, results[1].1.as_ref().err().unwrap().code());
+    assert_eq!(49, *results[2].1.as_ref().unwrap());
+}
+
+#[test]
+fn shares_cycles_fairly() {
+    let mut pool = Pool::new(looping());
+    pool.quantum(5);
+    pool.spawn(
+        "greedy",
+        Budget {
+            fuel: 1000,
+            memory: 16,
+        },
+    );
+    pool.spawn(
+        "modest",
+        Budget {
+            fuel: 20,
+            memory: 16,
+        },
+    );
+    while pool.runtimes()[1].outcome().is_none() {
+        assert!(pool.round());
+    }
+    assert_eq!(20, pool.runtimes()[0].perf.cycles);
+    assert!(pool.runtimes()[0].outcome().is_none());
+    let err = pool.runtimes()[1]
+        .outcome()
+        .unwrap()
+        .as_ref()
+        .err()
+        .unwrap();
+    assert!(
+        err.to_string().contains("'modest' is out of fuel"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn stops_runtime_out_of_memory() {
+    let mut pool = Pool::new(looping());
+    pool.spawn(
+        "tiny",
+        Budget {
+            fuel: 1000,
+            memory: 1,
+        },
+    );
+    let results = pool.run();
+    let err = results[0].1.as_ref().err().unwrap();
+    assert!(err.to_string().contains("out of memory"), "{}", err);
+}
+
+#[test]
+fn resets_runtime_for_another_run() {
+    let mut runtime = Runtime::of(&looping());
+    runtime.emu().opt(Opt::MaxCycles(10));
+    assert_eq!(5, runtime.finish().err().unwrap().code());
+    runtime.reset();
+    assert!(runtime.outcome().is_none());
+    assert_eq!(0, runtime.perf.cycles);
+    assert_eq!(5, runtime.finish().err().unwrap().code());
+}
+
+#[test]
+fn counts_what_reset_frees_and_retains() {
+    let prog = Program::from_str(&(crate::examples::FIBONACCI.program)(5)).unwrap();
+    let mut runtime = Runtime::of(&prog);
+    runtime.emu().opt(Opt::DontDelete);
+    let first = runtime.finish().unwrap();
+    let cycles = runtime.perf.cycles;
+    runtime.reset();
+    let (freed, retained) = (runtime.perf.freed, runtime.perf.retained);
+    assert!(freed > 1);
+    assert!(retained >= freed);
+    assert_eq!(first, runtime.finish().unwrap());
+    assert_eq!(cycles, runtime.perf.cycles);
+    assert_eq!(freed, runtime.perf.freed);
+    assert!(runtime.perf.to_string().contains("baskets freed"));
+}
+
+#[test]
+fn shares_memo_among_threads() {
+    let program = format!("+input x ν1\n{}", (crate::examples::FIBONACCI.program)(1));
+    let prog = Program::from_str(&program).unwrap();
+    let (first, second) = prog.scope(|spawner| {
+        let first = spawner
+            .spawn(|emu| emu.bind("x", 8).unwrap())
+            .join()
+            .unwrap()
+            .unwrap();
+        assert!(spawner.memoized() > 0);
+        let second = spawner.spawn(|emu| emu.bind("x", 7).unwrap());
+        (first, second.join().unwrap().unwrap())
+    });
+    assert_eq!((34, 21), (first.0, second.0));
+    assert!(first.1.memo_misses > 0);
+    assert_eq!(0, second.1.memo_misses);
+    assert!(second.1.memo_hits > 0);
+    let results: Vec<Data> = prog.scope(|spawner| {
+        let handles: Vec<_> = (1..9)
+            .map(|x| spawner.spawn(move |emu| emu.bind("x", x).unwrap()))
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap().unwrap().0)
+            .collect()
+    });
+    assert_eq!(vec![1, 2, 3, 5, 8, 13, 21, 34], results);
+}
+
+#[test]
+fn shares_memo_only_within_arithmetic() {
+    let prog = Program::from_str(
+        "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧\nν2(𝜋) ↦ ⟦ Δ ↦ 0x7FFFFFFFFFFFFFFF ⟧\nν3(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧",
+    )
+    .unwrap();
+    let (saturated, wrapped) = prog.scope(|spawner| {
+        let saturated = spawner
+            .spawn(|emu| emu.opt(Opt::Arithmetic(Arithmetic::Saturating)))
+            .join()
+            .unwrap()
+            .unwrap();
+        let wrapped = spawner.spawn(|_| {}).join().unwrap().unwrap();
+        (saturated, wrapped)
+    });
+    assert_eq!((i64::MAX, i64::MIN), (saturated.0, wrapped.0));
+    assert_eq!(0, wrapped.1.memo_hits);
+}
+
+#[test]
+fn warms_up_with_unchanged_objects() {
+    let before = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν3(𝜋) ⟧\nν1(𝜋) ↦ ⟦! Δ ↦ 0x0007 ⟧\n\
+        ν2(𝜋) ↦ ⟦! λ ↦ int-times, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧\n\
+        ν3(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν4(𝜋) ⟧\nν4(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧";
+    let mut emu: Emu = before.parse().unwrap();
+    let (d, perf) = emu.dataize();
+    assert_eq!(48, d);
+    let dump = Dump::of(&emu, "", perf.cycles);
+    let after = Program::from_str(&before.replace("0x0001", "0x0009")).unwrap();
+    let mut cold = Runtime::of(&after);
+    assert_eq!(40, cold.finish().unwrap());
+    let mut warm = Runtime::of(&after);
+    assert!(warm.warm_start(&dump).unwrap() > 0);
+    assert_eq!(40, warm.finish().unwrap());
+    assert!(warm.perf.cycles < cold.perf.cycles);
+    assert!(warm.warm_start(&dump).is_err());
+    let changed = Program::from_str(&before.replace("0x0007", "0x0002")).unwrap();
+    let mut other = Runtime::of(&changed);
+    other.warm_start(&dump).unwrap();
+    assert_eq!(3, other.finish().unwrap());
+}
@@ -0,0 +1,1645 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+use crate::data::Data;
+use crate::emu::{Arithmetic, Bindings, Emu, Opt, ROOT_OB};
+use crate::error::Error;
+use crate::loc::{index, latex, normalize, Loc};
+use crate::locator::{Locator, Locators};
+use crate::object::{Ob, Object};
+use crate::perf::Perf;
+use crate::plan::Plan;
+use crate::pool::{Runtime, Spawner};
+use crate::semantics::{self, Version};
+use crate::syntax;
+use itertools::Itertools;
+use regex::Regex;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs;
+use std::ops::Index;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// A set of numbered objects, which can be turned into an `Emu`.
+///
+/// You can build it from Rust data structures directly, instead of
+/// formatting a text:
+///
+/// ```
+/// use phie::emu::Emu;
+/// use phie::loc::Loc;
+/// use phie::locator::Locator;
+/// use phie::object::{Ob, Object};
+/// use phie::program::Program;
+/// use phie::ph;
+/// use std::str::FromStr;
+/// let mut prog = Program::new();
+/// prog.insert(Ob::new(0), Object::open().with(Loc::Phi, ph!("ν1"), false));
+/// prog.insert(Ob::new(1), Object::dataic(42));
+/// assert_eq!(Some(42), prog[Ob::new(1)].delta);
+/// let mut emu = Emu::from(prog);
+/// assert_eq!(42, emu.dataize().0);
+/// ```
+///
+/// In the text, lines like `+opt max-cycles 1000` carry options,
+/// which the `Emu` made of the program gets, and lines like `+input x ν1`
+/// give names to objects with Δ, which `Emu::bind()` may change. A line
+/// like `+arith checked` declares the arithmetic the program is written
+/// for, which no option may change, and a line like `+semantics 1` the
+/// version of semantics it depends on, see `semantics`.
+#[derive(Default)]
+pub struct Program {
+    objects: BTreeMap<Ob, Object>,
+    opts: Vec<Opt>,
+    arith: Option<Arithmetic>,
+    semantics: Option<Version>,
+    inputs: BTreeMap<String, Ob>,
+    locators: Locators,
+    plans: OnceLock<HashMap<(Ob, Loc), Plan>>,
+    /// A number no other program has, given when it's first needed
+    /// and taken back on any change, see `Program::dataize_pinned()`.
+    serial: OnceLock<u64>,
+}
+
+/// How many objects a program may have to be dataized by the `Emu`
+/// pinned to the thread, see `Program::dataize_pinned()`.
+pub const TINY: usize = 32;
+
+/// The serial of the next program, see `Program::serial`.
+static SERIALS: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// The `Emu` of the program dataized last by `Program::dataize_pinned()`
+    /// on this thread, by the serial of the program.
+    static PINNED: RefCell<Option<(u64, Emu)>> = const { RefCell::new(None) };
+}
+
+impl Program {
+    /// Make an empty program.
+    pub fn new() -> Program {
+        Program::default()
+    }
+
+    /// Add an object, returning the one that was there before, if any.
+    /// Its locators are replaced with the same ones from the table of
+    /// the program, so that all objects share them.
+    pub fn insert(&mut self, ob: Ob, mut obj: Object) -> Option<Object> {
+        for (locator, _) in obj.attrs.values_mut() {
+            let id = self.locators.intern(locator);
+            *locator = self.locators.get(id).clone();
+        }
+        self.plans.take();
+        self.serial.take();
+        self.objects.insert(ob, obj)
+    }
+
+    /// Add all objects of the library, renumbering them to follow the
+    /// objects of the program and marking them with the namespace, so
+    /// that diagnostics show where they came from, like `lib::ν7`.
+    /// Returns the new numbers of the objects by their old ones, to
+    /// refer to them from the objects of the program:
+    ///
+    /// ```
+    /// use phie::emu::Emu;
+    /// use phie::object::Ob;
+    /// use phie::program::Program;
+    /// let mut prog: Program = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧".parse().unwrap();
+    /// let lib: Program = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+    /// let obs = prog.link(lib, "lib");
+    /// assert_eq!(Some(&Ob::new(1)), obs.get(&Ob::new(0)));
+    /// assert_eq!("ν0(𝜋) ↦ ⟦𝜑↦ν1(𝜋)⟧\nν1(𝜋) ↦ ⟦! Δ↦0x002A⟧ # lib::ν0", prog.to_string());
+    /// assert_eq!(42, Emu::from(prog).dataize().0);
+    /// ```
+    pub fn link(&mut self, lib: Program, namespace: &str) -> HashMap<Ob, Ob> {
+        let start = self
+            .objects
+            .keys()
+            .next_back()
+            .map_or(0, |ob| ob.index() + 1);
+        let obs: HashMap<Ob, Ob> = lib
+            .objects
+            .keys()
+            .enumerate()
+            .map(|(i, ob)| (*ob, Ob::new(start + i)))
+            .collect();
+        for (ob, mut obj) in lib {
+            for (locator, _) in obj.attrs.values_mut() {
+                if let Some(Loc::Obj(o)) = locator.loc(0) {
+                    let mut locs = locator.to_vec();
+                    locs[0] = Loc::Obj(*obs.get(o).unwrap_or(o));
+                    *locator = Locator::from_vec(locs);
+                }
+            }
+            obj.origin = Some(match obj.origin.take() {
+                Some((ns, o)) => (format!("{}::{}", namespace, ns), o),
+                None => (namespace.to_string(), ob),
+            });
+            self.insert(obs[&ob], obj);
+        }
+        obs
+    }
+
+    /// The plans of resolution of all attributes of all objects,
+    /// compiled once and kept until an object is inserted or removed.
+    pub fn plans(&self) -> &HashMap<(Ob, Loc), Plan> {
+        self.plans.get_or_init(|| {
+            self.objects
+                .iter()
+                .flat_map(|(ob, obj)| {
+                    obj.attrs.iter().map(|(loc, (locator, _))| {
+                        (
+                            (*ob, loc.clone()),
+                            Plan::compile(locator, |o| self.objects.get(&o)),
+                        )
+                    })
+                })
+                .collect()
+        })
+    }
+
+    /// Dataize the program like `Emu::try_dataize()` does, but, if it's
+    /// tiny, with fewer than `TINY` objects, by the `Emu` pinned to the
+    /// thread, which is made once and only recycled between the calls,
+    /// see `Emu::recycle()`. The `Emu` is an ordinary one, with its
+    /// baskets on the heap; what's saved is making it again, which
+    /// `benches/pinned.rs` measures:
+    ///
+    /// ```
+    /// use phie::program::Program;
+    /// use std::str::FromStr;
+    /// let prog = Program::from_str("
+    ///   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+    ///   ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+    ///   ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧
+    /// ").unwrap();
+    /// for _ in 0..1000 {
+    ///     assert_eq!(84, prog.dataize_pinned().unwrap().0);
+    /// }
+    /// ```
+    ///
+    /// Big data, see `Opt::BigData`, is never pinned.
+    pub fn dataize_pinned(&self) -> Result<(Data, Perf), Error> {
+        #[cfg(feature = "bigint")]
+        if self.opts.contains(&Opt::BigData) {
+            return Emu::from(self).try_dataize();
+        }
+        if self
+            .objects
+            .keys()
+            .next_back()
+            .is_some_and(|ob| ob.index() >= TINY)
+        {
+            return Emu::from(self).try_dataize();
+        }
+        let serial = *self
+            .serial
+            .get_or_init(|| SERIALS.fetch_add(1, Ordering::Relaxed));
+        PINNED.with(|pinned| {
+            let mut pinned = pinned.borrow_mut();
+            match pinned.as_mut() {
+                Some((s, emu)) if *s == serial => {
+                    emu.recycle();
+                }
+                _ => *pinned = Some((serial, Emu::from(self))),
+            }
+            let (_, emu) = pinned.as_mut().expect("The Emu is pinned just now");
+            emu.try_dataize()
+        })
+    }
+
+    /// Dataize the program this many times by one `Runtime`, which is
+    /// only reset between the runs, see `Runtime::reset()`, instead of
+    /// making a new `Emu` for each of them:
+    ///
+    /// ```
+    /// use phie::program::Program;
+    /// use std::str::FromStr;
+    /// let prog = Program::from_str("
+    ///   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+    ///   ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+    ///   ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧
+    /// ").unwrap();
+    /// let runs = prog.evaluate_many(3);
+    /// assert_eq!(3, runs.len());
+    /// assert!(runs.iter().all(|r| r.as_ref().unwrap().0 == 84));
+    /// ```
+    pub fn evaluate_many(&self, count: usize) -> Vec<Result<(Data, Perf), Error>> {
+        let mut runtime = Runtime::of(self);
+        (0..count)
+            .map(|i| {
+                if i > 0 {
+                    runtime.reset();
+                }
+                let d = runtime.finish()?;
+                Ok((d, std::mem::take(&mut runtime.perf)))
+            })
+            .collect()
+    }
+
+    /// Dataize the program on many threads at once, by the runtimes the
+    /// function spawns, which share the memoized results of pure atoms,
+    /// so that a sweep of inputs reuses what the others have computed,
+    /// see `Spawner`. All threads are joined before it returns:
+    ///
+    /// ```
+    /// use phie::program::Program;
+    /// use std::str::FromStr;
+    /// let prog = Program::from_str("
+    ///   +input x ν1
+    ///   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+    ///   ν1(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+    ///   ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧
+    /// ").unwrap();
+    /// let sums: Vec<i64> = prog.scope(|spawner| {
+    ///     let runs: Vec<_> = (1..4)
+    ///         .map(|x| spawner.spawn(move |emu| emu.bind("x", x).unwrap()))
+    ///         .collect();
+    ///     runs.into_iter().map(|r| r.join().unwrap().unwrap().0).collect()
+    /// });
+    /// assert_eq!(vec![2, 4, 6], sums);
+    /// ```
+    pub fn scope<'env, T>(
+        &'env self,
+        f: impl for<'scope> FnOnce(&Spawner<'scope, 'env>) -> T,
+    ) -> T {
+        std::thread::scope(|scope| f(&Spawner::new(scope, self)))
+    }
+
+    /// The same program, with `ν0` replaced by an object that only
+    /// refers to the entry, so that the entry is dataized instead:
+    ///
+    /// ```
+    /// use phie::emu::Emu;
+    /// use phie::program::Program;
+    /// let prog: Program = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧\nν2(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧"
+    ///     .parse()
+    ///     .unwrap();
+    /// let at = prog.at(&"ν2".parse().unwrap());
+    /// assert_eq!(7, Emu::from(at).dataize().0);
+    /// ```
+    pub fn at(&self, entry: &Locator) -> Program {
+        let mut at = Program::new();
+        for (ob, obj) in self.objects.iter().filter(|(ob, _)| **ob != ROOT_OB) {
+            at.insert(*ob, obj.clone());
+        }
+        at.insert(ROOT_OB, Object::open().with(Loc::Phi, entry.clone(), false));
+        at.inputs = self.inputs.clone();
+        at.opts = self.opts.clone();
+        at.arith = self.arith;
+        at.semantics = self.semantics;
+        at
+    }
+
+    /// The fingerprint of the program `at()` the entry would make,
+    /// without making it.
+    pub fn fingerprint_at(&self, entry: &Locator) -> u64 {
+        let root = Object::open().with(Loc::Phi, entry.clone(), false);
+        fingerprint(
+            self.objects
+                .iter()
+                .filter(|(ob, _)| **ob != ROOT_OB)
+                .map(|(ob, obj)| (*ob, obj))
+                .chain(std::iter::once((ROOT_OB, &root))),
+            self.inputs.iter(),
+        )
+    }
+
+    /// Dataize the entry instead of `ν0`, see `Program::at()`, with the
+    /// inputs bound to the data, see `Emu::bind()`.
+    pub fn dataize_at(&self, entry: &Locator, bindings: &Bindings) -> Result<(Data, Perf), Error> {
+        let mut emu = Emu::from(self.at(entry));
+        for (name, d) in bindings {
+            emu.bind(name, *d).map_err(Error::Usage)?;
+        }
+        emu.try_dataize()
+    }
+
+    /// Take the object out of the program.
+    pub fn remove(&mut self, ob: Ob) -> Option<Object> {
+        self.plans.take();
+        self.serial.take();
+        self.objects.remove(&ob)
+    }
+
+    /// The options from the `+opt` lines of the text.
+    pub fn opts(&self) -> &[Opt] {
+        &self.opts
+    }
+
+    /// Add an option, to be set in the `Emu` made of the program.
+    pub fn opt(&mut self, opt: Opt) {
+        self.serial.take();
+        self.opts.push(opt);
+    }
+
+    /// The arithmetic declared by the `+arith` line of the text, if any.
+    pub fn arith(&self) -> Option<Arithmetic> {
+        self.arith
+    }
+
+    /// Declare the arithmetic the program is written for, see `Emu::arithmetic()`.
+    pub fn declare(&mut self, arith: Arithmetic) {
+        self.serial.take();
+        self.arith = Some(arith);
+    }
+
+    /// The version of semantics of the `+semantics` line of the text, if any.
+    pub fn semantics(&self) -> Option<Version> {
+        self.semantics
+    }
+
+    /// Make the program depend on this version of semantics.
+    pub fn depend(&mut self, version: Version) {
+        self.serial.take();
+        self.semantics = Some(version);
+    }
+
+    /// The objects named by the `+input` lines of the text.
+    pub fn inputs(&self) -> &BTreeMap<String, Ob> {
+        &self.inputs
+    }
+
+    /// Name the object, so that `Emu::bind()` may change its Δ.
+    pub fn input(&mut self, name: &str, ob: Ob) {
+        self.serial.take();
+        self.inputs.insert(name.to_string(), ob);
+    }
+
+    /// All unique locators used by the objects.
+    pub fn locators(&self) -> &Locators {
+        &self.locators
+    }
+
+    /// Get the object, if it's there.
+    pub fn get(&self, ob: Ob) -> Option<&Object> {
+        self.objects.get(&ob)
+    }
+
+    /// How many objects are there.
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// There are no objects at all.
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// All objects, ordered by their numbers.
+    pub fn iter(&self) -> impl Iterator<Item = (&Ob, &Object)> {
+        self.objects.iter()
+    }
+
+    /// Renumber the objects in the order they are met walking from ν0
+    /// breadth-first through their attributes, sorted, and rewrite all
+    /// locators and inputs, so that two programs of the same structure
+    /// are printed the same, whatever numbers they had. Returns the new
+    /// numbers of the objects by their old ones:
+    ///
+    /// ```
+    /// use phie::object::Ob;
+    /// use phie::program::Program;
+    /// let mut prog: Program = "
+    ///   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν7(𝜋) ⟧
+    ///   ν3(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+    ///   ν7(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν3(𝜋) ⟧
+    /// ".parse().unwrap();
+    /// let obs = prog.canonicalize();
+    /// assert_eq!(Some(&Ob::new(1)), obs.get(&Ob::new(7)));
+    /// assert_eq!(
+    ///     "ν0(𝜋) ↦ ⟦𝜑↦ν1(𝜋)⟧\nν1(𝜋) ↦ ⟦λ↦int-neg, ρ↦ν2(𝜋)⟧\nν2(𝜋) ↦ ⟦! Δ↦0x002A⟧",
+    ///     prog.to_string()
+    /// );
+    /// ```
+    pub fn canonicalize(&mut self) -> HashMap<Ob, Ob> {
+        let obs = canonical(&self.objects.iter().map(|(ob, obj)| (*ob, obj)).collect());
+        let objects = std::mem::take(&mut self.objects);
+        self.locators = Locators::new();
+        for (ob, obj) in objects {
+            self.insert(obs[&ob], renumbered(obj, &obs));
+        }
+        for ob in self.inputs.values_mut() {
+            *ob = *obs.get(ob).unwrap_or(ob);
+        }
+        obs
+    }
+
+    /// A hash of the objects and inputs, which is the same for two
+    /// programs that differ only in formatting, comments, options,
+    /// the order of their lines and the numbers of their objects,
+    /// see `canonicalize()`:
+    ///
+    /// ```
+    /// use phie::program::Program;
+    /// let a: Program = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1 ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+    /// let b: Program = "# the answer\nν5(𝜋) ↦ ⟦Δ↦0x002A⟧\nν0(𝜋) ↦ ⟦𝜑↦ν5⟧".parse().unwrap();
+    /// assert_eq!(a.fingerprint(), b.fingerprint());
+    /// ```
+    ///
+    /// It is FNV-1a, which is stable between runs, builds and
+    /// versions of Rust, so it may be saved to files.
+    pub fn fingerprint(&self) -> u64 {
+        fingerprint(
+            self.objects.iter().map(|(ob, obj)| (*ob, obj)),
+            self.inputs.iter(),
+        )
+    }
+
+    /// Print the objects in the notation of 𝜑-calculus for LaTeX, one
+    /// per line of an `align*` environment, which needs the `amsmath`
+    /// and `stmaryrd` packages, see `loc::latex()`:
+    ///
+    /// ```
+    /// use phie::program::Program;
+    /// let prog: Program = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+    /// assert_eq!(
+    ///     "\\begin{align*}\n\\nu_{0}(\\pi) &\\mapsto \\llbracket!\\Delta\\mapsto\\texttt{0x002A}\\rrbracket\n\\end{align*}",
+    ///     prog.to_latex()
+    /// );
+    /// ```
+    pub fn to_latex(&self) -> String {
+        let lines = self
+            .objects
+            .iter()
+            .map(|(ob, obj)| {
+                format!(
+                    "{} &\\mapsto {}",
+                    latex(&format!("ν{}(𝜋)", ob)),
+                    latex(&obj.to_string())
+                )
+            })
+            .join(" \\\\\n");
+        format!("\\begin{{align*}}\n{}\n\\end{{align*}}", lines)
+    }
+
+    /// Count objects by their kinds, attributes, and measure the
+    /// longest locator and the depth of the graph, starting from ν0.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        for obj in self.objects.values() {
+            if obj.delta.is_some() {
+                stats.dataic += 1;
+            } else if let Some(m) = obj.atom() {
+                *stats.atomic.entry(m.name.to_string()).or_insert(0) += 1;
+            } else {
+                stats.composite += 1;
+            }
+            stats.attrs += obj.attrs.len();
+            for (locator, _) in obj.attrs.values() {
+                stats.max_locator = stats.max_locator.max(locator.to_vec().len());
+            }
+        }
+        if self.objects.contains_key(&ROOT_OB) {
+            stats.depth = self.depth(ROOT_OB, &mut vec![]);
+        }
+        stats
+    }
+
+    /// The number of objects on the longest path of references from
+    /// this one, not counting the ones that are already on the path.
+    fn depth(&self, ob: Ob, path: &mut Vec<Ob>) -> usize {
+        let Some(obj) = self.objects.get(&ob) else {
+            return 0;
+        };
+        path.push(ob);
+        let mut deepest = 0;
+        for (locator, _) in obj.attrs.values() {
+            if let Some(Loc::Obj(r)) = locator.loc(0) {
+                if !path.contains(r) {
+                    deepest = deepest.max(self.depth(*r, path));
+                }
+            }
+        }
+        path.pop();
+        deepest + 1
+    }
+
+    /// Read the program from a file. A line `+include other.phi` is
+    /// replaced by the content of that file, found relative to the
+    /// directory of the file that includes it.
+    pub fn from_file(path: &Path) -> Result<Program, Error> {
+        let text = Program::read(path)?;
+        Program::from_str(&text).map_err(|e| Error::Parse(path.to_path_buf(), e))
+    }
+
+    /// Read the text of the program from a file, with all `+include`
+    /// lines replaced, but not parsed yet.
+    pub fn read(path: &Path) -> Result<String, Error> {
+        Program::expand(path, &mut vec![], &mut HashSet::new())
+    }
+
+    /// Read the file and replace all `+include` lines in it, recursively.
+    /// The files on the stack are being expanded, a cycle if met again;
+    /// the ones done are already expanded and are included only once.
+    fn expand(
+        path: &Path,
+        stack: &mut Vec<PathBuf>,
+        done: &mut HashSet<PathBuf>,
+    ) -> Result<String, Error> {
+        let canonical = fs::canonicalize(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+        if stack.contains(&canonical) {
+            return Err(Error::Cycle(path.to_path_buf()));
+        }
+        if done.contains(&canonical) {
+            return Ok(String::new());
+        }
+        let text = fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+        stack.push(canonical);
+        let mut lines = vec![];
+        for line in text.trim().split('\n').map(|t| t.trim()) {
+            if let Some(inc) = line.strip_prefix("+include") {
+                let name = inc.trim();
+                if name.is_empty() {
+                    return Err(Error::Parse(
+                        path.to_path_buf(),
+                        "The +include directive needs a file name".to_string(),
+                    ));
+                }
+                let base = path.parent().unwrap_or_else(|| Path::new(""));
+                let included = Program::expand(&base.join(name), stack, done)?;
+                if !included.is_empty() {
+                    lines.push(included);
+                }
+            } else {
+                lines.push(line.to_string());
+            }
+        }
+        if let Some(canonical) = stack.pop() {
+            done.insert(canonical);
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+/// The FNV-1a hash of non-empty objects and inputs, printed as
+/// they are in a program after `Program::canonicalize()`.
+pub(crate) fn fingerprint<'a>(
+    objects: impl Iterator<Item = (Ob, &'a Object)>,
+    inputs: impl Iterator<Item = (&'a String, &'a Ob)>,
+) -> u64 {
+    let objects: BTreeMap<Ob, &Object> = objects.filter(|(_, obj)| !obj.is_empty()).collect();
+    let obs = canonical(&objects);
+    let mut lines: Vec<(Ob, String)> = objects
+        .iter()
+        .map(|(ob, obj)| (obs[ob], renumbered((*obj).clone(), &obs).to_string()))
+        .collect();
+    lines.sort();
+    let mut inputs: Vec<String> = inputs
+        .map(|(name, ob)| format!("+input {} ν{}\n", name, obs.get(ob).unwrap_or(ob)))
+        .collect();
+    inputs.sort();
+    lines
+        .into_iter()
+        .map(|(ob, obj)| format!("ν{} {}\n", ob, obj))
+        .chain(inputs)
+        .flat_map(|line| line.into_bytes())
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+/// The new numbers of the objects, in the order they are met walking
+/// through their attributes breadth-first from ν0, then from the ones
+/// that can't be reached from it, by their old numbers. The objects
+/// which are referred to, but are absent, go last.
+fn canonical(objects: &BTreeMap<Ob, &Object>) -> HashMap<Ob, Ob> {
+    let mut obs: HashMap<Ob, Ob> = HashMap::new();
+    let mut absent: Vec<Ob> = vec![];
+    let mut queue = VecDeque::new();
+    for start in objects.keys() {
+        if obs.contains_key(start) {
+            continue;
+        }
+        obs.insert(*start, Ob::new(obs.len()));
+        queue.push_back(*start);
+        while let Some(ob) = queue.pop_front() {
+            let obj = objects[&ob];
+            for loc in obj.attrs.keys().sorted() {
+                let Some(Loc::Obj(o)) = obj.attrs[loc].0.loc(0) else {
+                    continue;
+                };
+                if !objects.contains_key(o) {
+                    if !absent.contains(o) {
+                        absent.push(*o);
+                    }
+                } else if !obs.contains_key(o) {
+                    obs.insert(*o, Ob::new(obs.len()));
+                    queue.push_back(*o);
+                }
+            }
+        }
+    }
+    for o in absent {
+        obs.insert(o, Ob::new(obs.len()));
+    }
+    obs
+}
+
+/// The object with its references to other objects renumbered.
+fn renumbered(mut obj: Object, obs: &HashMap<Ob, Ob>) -> Object {
+    for (locator, _) in obj.attrs.values_mut() {
+        if let Some(Loc::Obj(o)) = locator.loc(0) {
+            *locator = Locator::from_loc(Loc::Obj(*obs.get(o).unwrap_or(o)));
+        }
+    }
+    obj
+}
+
+impl Index<Ob> for Program {
+    type Output = Object;
+    fn index(&self, ob: Ob) -> &Object {
+        self.objects
+            .get(&ob)
+            .unwrap_or_else(|| panic!("There is no ν{} in the program", ob))
+    }
+}
+
+impl FromIterator<(Ob, Object)> for Program {
+    fn from_iter<I: IntoIterator<Item = (Ob, Object)>>(iter: I) -> Self {
+        let mut prog = Program::new();
+        for (ob, obj) in iter {
+            prog.insert(ob, obj);
+        }
+        prog
+    }
+}
+
+impl IntoIterator for Program {
+    type Item = (Ob, Object);
+    type IntoIter = std::collections::btree_map::IntoIter<Ob, Object>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.objects.into_iter()
+    }
+}
+
+impl From<Program> for Emu {
+    fn from(prog: Program) -> Emu {
+        Emu::of(&prog)
+    }
+}
+
+/// A fresh `Emu` with copies of the objects, leaving the program
+/// for more of them, like in `Pool`.
+impl From<&Program> for Emu {
+    fn from(prog: &Program) -> Emu {
+        Emu::of(prog)
+    }
+}
+
+impl FromIterator<(Ob, Object)> for Emu {
+    fn from_iter<I: IntoIterator<Item = (Ob, Object)>>(iter: I) -> Self {
+        Emu::from(iter.into_iter().collect::<Program>())
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(
+            &self
+                .semantics
+                .iter()
+                .map(|v| format!("+semantics {}", v))
+                .chain(self.arith.iter().map(|a| format!("+arith {}", a)))
+                .chain(
+                    self.opts
+                        .iter()
+                        .filter(|opt| !matches!(opt, Opt::CrashDumps(_)))
+                        .map(|opt| format!("+opt {}", opt)),
+                )
+                .chain(
+                    self.inputs
+                        .iter()
+                        .map(|(name, ob)| format!("+input {} ν{}", name, ob)),
+                )
+                .chain(self.objects.iter().map(|(ob, obj)| {
+                    format!(
+                        "ν{}(𝜋) ↦ {}{}",
+                        ob,
+                        obj,
+                        obj.origin
+                            .as_ref()
+                            .map(|(ns, o)| format!(" # {}::ν{}", ns, o))
+                            .unwrap_or_default()
+                    )
+                }))
+                .collect::<Vec<String>>()
+                .join("\n"),
+        )
+    }
+}
+
+impl Program {
+    /// Build the program of a JSON value, for frontends that would rather
+    /// not print the text format. All keys but `objects` are optional,
+    /// objects have `delta` or `lambda` or neither, and an attribute is
+    /// either a locator or `{"locator": "ν2", "xi": true}`, which is
+    /// `ν2(ξ)` in the text:
+    ///
+    /// ```
+    /// use phie::program::Program;
+    /// use serde_json::json;
+    /// let prog = Program::from_json(json!({
+    ///     "semantics": 2,
+    ///     "arith": "checked",
+    ///     "opts": ["max-cycles 1000"],
+    ///     "inputs": {"x": 1},
+    ///     "objects": [
+    ///         {"ob": 0, "attrs": {"𝜑": "ν2"}},
+    ///         {"ob": 1, "delta": 42},
+    ///         {"ob": 2, "lambda": "int-neg", "attrs": {"ρ": {"locator": "ν1", "xi": false}}},
+    ///     ],
+    /// })).unwrap();
+    /// assert_eq!(-42, prog.dataize_pinned().unwrap().0);
+    /// assert_eq!(Program::from_json(prog.to_json()).unwrap().to_string(), prog.to_string());
+    /// ```
+    ///
+    /// An object may also be `"constant": true` (`!` in the text) and
+    /// `"memo": true` or `false` (`%` or `~`), and with the `rational`
+    /// feature, its Δ may have a `"denominator"`. The JSON is checked
+    /// against `schema::program_json()` first, and all problems found
+    /// are reported, one per line.
+    pub fn from_json(json: serde_json::Value) -> Result<Program, String> {
+        crate::schema::validate(&json).map_err(|errors| errors.join("\n"))?;
+        let mut prog = Program::new();
+        for (i, obj) in json["objects"]
+            .as_array()
+            .ok_or("There is no 'objects' array in the JSON")?
+            .iter()
+            .enumerate()
+        {
+            let ob = obj["ob"]
+                .as_u64()
+                .filter(|n| *n <= Ob::LIMIT as u64)
+                .map(|n| Ob::new(n as usize))
+                .ok_or_else(|| format!("There is no 'ob' in the object #{}", i))?;
+            let parsed = Program::object_of_json(obj).map_err(|e| format!("{} in ν{}", e, ob))?;
+            if prog.insert(ob, parsed).is_some() {
+                return Err(format!("Duplicate object ν{} in the JSON", ob));
+            }
+        }
+        if let Some(opts) = json.get("opts") {
+            for opt in opts.as_array().ok_or("The 'opts' must be an array")? {
+                prog.opts.push(Opt::from_str(
+                    opt.as_str()
+                        .ok_or("Every option in 'opts' must be a string")?,
+                )?);
+            }
+        }
+        if let Some(arith) = json.get("arith") {
+            let a = Arithmetic::from_str(arith.as_str().ok_or("The 'arith' must be a string")?)?;
+            if let Some(o) = prog
+                .opts
+                .iter()
+                .find(|o| matches!(o, Opt::Arithmetic(x) if *x != a))
+            {
+                return Err(format!(
+                    "The program declares arith '{}', but sets '{}'",
+                    a, o
+                ));
+            }
+            prog.arith = Some(a);
+        }
+        if let Some(v) = json.get("semantics") {
+            prog.semantics = Some(semantics::check(
+                v.as_u64().ok_or("The 'semantics' must be a number")? as Version,
+            )?);
+        }
+        if let Some(inputs) = json.get("inputs") {
+            for (name, ob) in inputs.as_object().ok_or("The 'inputs' must be an object")? {
+                let ob = ob
+                    .as_u64()
+                    .filter(|n| *n <= Ob::LIMIT as u64)
+                    .map(|n| Ob::new(n as usize))
+                    .ok_or_else(|| format!("The input '{}' must be a number of an object", name))?;
+                prog.inputs.insert(name.clone(), ob);
+            }
+        }
+        Ok(prog)
+    }
+
+    /// The object of one item of `objects`, see `from_json()`.
+    fn object_of_json(json: &serde_json::Value) -> Result<Object, String> {
+        let mut obj = match (json.get("delta"), json.get("lambda")) {
+            (Some(_), Some(_)) => return Err("Both 'delta' and 'lambda'".to_string()),
+            (Some(d), None) => Object::dataic(d.as_i64().ok_or("The 'delta' must be an integer")?),
+            (None, Some(l)) => {
+                let name = l.as_str().ok_or("The 'lambda' must be a string")?;
+                Object::atomic(
+                    crate::atom::id(name).ok_or_else(|| format!("Unknown lambda '{}'", name))?,
+                )
+            }
+            (None, None) => Object::open(),
+        };
+        #[cfg(feature = "rational")]
+        if let Some(d) = json.get("denominator") {
+            let d = d
+                .as_i64()
+                .filter(|d| *d != 0)
+                .ok_or("The 'denominator' must be non-zero")?;
+            obj = Object::rational(obj.delta.ok_or("A 'denominator' without 'delta'")?, d);
+        }
+        if let Some(c) = json.get("constant") {
+            obj.constant = c.as_bool().ok_or("The 'constant' must be a boolean")?;
+        }
+        if let Some(m) = json.get("memo") {
+            obj.memo = Some(m.as_bool().ok_or("The 'memo' must be a boolean")?);
+        }
+        if let Some(attrs) = json.get("attrs") {
+            for (name, attr) in attrs.as_object().ok_or("The 'attrs' must be an object")? {
+                let loc = Loc::from_str(name)?;
+                if matches!(loc, Loc::Delta) {
+                    return Err("The Δ must be a 'delta', not an attribute".to_string());
+                }
+                let (text, xi) = match attr {
+                    serde_json::Value::String(t) => (t.as_str(), false),
+                    _ => (
+                        attr["locator"]
+                            .as_str()
+                            .ok_or_else(|| format!("There is no 'locator' of '{}'", name))?,
+                        attr.get("xi").and_then(|x| x.as_bool()).unwrap_or(false),
+                    ),
+                };
+                let locator = Locator::from_str(text)
+                    .map_err(|e| format!("Can't parse locator '{}': {}", text, e))?;
+                obj.push(loc, locator, xi);
+            }
+        }
+        Ok(obj)
+    }
+
+    /// The JSON of the program, which `from_json()` takes back.
+    pub fn to_json(&self) -> serde_json::Value {
+        let objects: Vec<serde_json::Value> = self
+            .objects
+            .iter()
+            .map(|(ob, obj)| {
+                let mut json = serde_json::json!({"ob": ob});
+                if let Some(d) = obj.delta {
+                    json["delta"] = d.into();
+                }
+                #[cfg(feature = "rational")]
+                if let Some(d) = obj.denominator {
+                    json["denominator"] = d.into();
+                }
+                if let Some(m) = obj.atom() {
+                    json["lambda"] = m.name.into();
+                }
+                json["constant"] = obj.constant.into();
+                if let Some(m) = obj.memo {
+                    json["memo"] = m.into();
+                }
+                if !obj.attrs.is_empty() {
+                    json["attrs"] = obj
+                        .attrs
+                        .iter()
+                        .sorted_by(|a, b| a.0.cmp(b.0))
+                        .map(|(loc, (p, xi))| {
+                            (
+                                loc.to_string(),
+                                serde_json::json!({"locator": p.to_string(), "xi": xi}),
+                            )
+                        })
+                        .collect::<serde_json::Map<String, serde_json::Value>>()
+                        .into();
+                }
+                json
+            })
+            .collect();
+        let mut json = serde_json::json!({
+            "opts": self.opts.iter().map(|o| o.to_string()).collect::<Vec<String>>(),
+            "inputs": self.inputs,
+            "objects": objects,
+        });
+        if let Some(a) = self.arith {
+            json["arith"] = a.to_string().into();
+        }
+        if let Some(v) = self.semantics {
+            json["semantics"] = v.into();
+        }
+        json
+    }
+}
+
+impl Program {
+    /// Parse the text of a program, splitting it into lines and parsing
+    /// them on all cores at once. The result is the same as of `from_str`,
+    /// including the error reported, if there are many of them.
+    #[cfg(feature = "parallel")]
+    pub fn par_from_str(s: &str) -> Result<Program, String> {
+        use rayon::prelude::*;
+        let lines = Program::lines(s);
+        let parsed: Vec<Result<(Ob, Object), String>> = lines
+            .par_iter()
+            .map(|line| Program::parse_line(line))
+            .collect();
+        let mut prog = Program::assemble(&lines, parsed.into_iter())?;
+        prog.opts = Program::header(s)?;
+        prog.arith = Program::contract(s)?;
+        prog.semantics = Program::version(s)?;
+        prog.inputs = Program::named(s)?;
+        Ok(prog)
+    }
+
+    /// The lines of the text with objects, without blank lines,
+    /// comments and options.
+    fn lines(s: &str) -> Vec<&str> {
+        s.trim()
+            .split('\n')
+            .map(|t| t.trim())
+            .filter(|line| {
+                !line.is_empty()
+                    && !line.starts_with('#')
+                    && !line.starts_with("+opt")
+                    && !line.starts_with("+arith")
+                    && !line.starts_with("+semantics")
+                    && !line.starts_with("+input")
+            })
+            .collect()
+    }
+
+    /// The options of all `+opt` lines of the text.
+    fn header(s: &str) -> Result<Vec<Opt>, String> {
+        s.split('\n')
+            .filter_map(|t| t.trim().strip_prefix("+opt"))
+            .map(|opt| {
+                Opt::of_program(opt).map_err(|e| format!("Can't parse option '+opt{}': {}", opt, e))
+            })
+            .collect()
+    }
+
+    /// The arithmetic of the `+arith` line of the text, like `+arith checked`;
+    /// there may be only one, and it must agree with the `+opt` lines.
+    fn contract(s: &str) -> Result<Option<Arithmetic>, String> {
+        let mut found = None;
+        for rest in s
+            .split('\n')
+            .filter_map(|t| t.trim().strip_prefix("+arith"))
+        {
+            let a = Arithmetic::from_str(rest.trim())
+                .map_err(|e| format!("Can't parse '+arith{}': {}", rest, e))?;
+            match found {
+                Some(f) if f != a => {
+                    return Err(format!(
+                        "The program declares both '+arith {}' and '+arith {}'",
+                        f, a
+                    ))
+                }
+                _ => found = Some(a),
+            }
+        }
+        if let Some(f) = found {
+            let other = Program::header(s)?.into_iter().find_map(|o| match o {
+                Opt::Arithmetic(a) if a != f => Some(a),
+                _ => None,
+            });
+            if let Some(a) = other {
+                return Err(format!(
+                    "The program declares '+arith {}', but sets '+opt arithmetic {}'",
+                    f, a
+                ));
+            }
+        }
+        Ok(found)
+    }
+
+    /// The version of the `+semantics` line of the text, like `+semantics 1`,
+    /// if there is one and the emulator knows it.
+    fn version(s: &str) -> Result<Option<Version>, String> {
+        let mut found = None;
+        for rest in s
+            .split('\n')
+            .filter_map(|t| t.trim().strip_prefix("+semantics"))
+        {
+            let v: Version = rest
+                .trim()
+                .parse()
+                .map_err(|e| format!("Can't parse '+semantics{}': {}", rest, e))?;
+            if found.is_some_and(|f| f != v) {
+                return Err("There may be only one version of semantics".to_string());
+            }
+            found = Some(semantics::check(v)?);
+        }
+        Ok(found)
+    }
+
+    /// The names of all `+input` lines of the text, like `+input x ν1`.
+    fn named(s: &str) -> Result<BTreeMap<String, Ob>, String> {
+        s.split('\n')
+            .filter_map(|t| t.trim().strip_prefix("+input"))
+            .map(|rest| Program::parse_input(rest.trim()))
+            .collect()
+    }
+
+    /// Parse the rest of an `+input` line, like `x ν1`.
+    pub fn parse_input(s: &str) -> Result<(String, Ob), String> {
+        match s.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            [name, ob] => {
+                let digits = normalize(ob);
+                let digits = digits
+                    .strip_prefix('ν')
+                    .ok_or_else(|| format!("The input '{}' must be an object, like ν1", name))?;
+                Ok((name.to_string(), Ob::new(index(digits, Ob::LIMIT, "ν")?)))
+            }
+            _ => Err(format!("Can't parse input '{}', try '+input x ν1'", s)),
+        }
+    }
+
+    /// Put the parsed lines together, in their order, failing
+    /// on the first broken or duplicate one.
+    fn assemble(
+        lines: &[&str],
+        parsed: impl Iterator<Item = Result<(Ob, Object), String>>,
+    ) -> Result<Program, String> {
+        let mut prog = Program::new();
+        for (line, res) in lines.iter().zip(parsed) {
+            let (v, obj) = res?;
+            if prog.insert(v, obj).is_some() {
+                return Err(format!("Duplicate object ν{} in line '{}'", v, line));
+            }
+        }
+        Ok(prog)
+    }
+
+    /// Parse one line of the text format, like `ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧`.
+    pub fn parse_line(line: &str) -> Result<(Ob, Object), String> {
+        let line = normalize(line);
+        let line = line.as_str();
+        let re_line = Regex::new(syntax::LINE)
+            .map_err(|e| format!("Invalid emu line regex pattern: {}", e))?;
+        let caps = re_line
+            .captures(line)
+            .ok_or_else(|| format!("Can't parse emu line: '{}'", line))?;
+        let v_str = caps
+            .get(1)
+            .ok_or_else(|| format!("Missing object number in line: '{}'", line))?
+            .as_str();
+        let v = index(v_str, Ob::LIMIT, "ν")
+            .map(Ob::new)
+            .map_err(|e| format!("{} in line '{}'", e, line))?;
+        let obj_str = caps
+            .get(2)
+            .ok_or_else(|| format!("Missing object definition in line: '{}'", line))?
+            .as_str();
+        let mut obj = Object::from_str(obj_str)
+            .map_err(|e| format!("Can't parse object in line '{}': {}", line, e))?;
+        if let (Some(ns), Some(o)) = (caps.get(3), caps.get(4)) {
+            obj.origin = Some((
+                ns.as_str().to_string(),
+                Ob::new(index(o.as_str(), Ob::LIMIT, "ν")?),
+            ));
+        }
+        Ok((v, obj))
+    }
+}
+
+impl FromStr for Program {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = || {
+            let lines = Program::lines(s);
+            let mut prog =
+                Program::assemble(&lines, lines.iter().map(|line| Program::parse_line(line)))?;
+            prog.opts = Program::header(s)?;
+            prog.arith = Program::contract(s)?;
+            prog.semantics = Program::version(s)?;
+            prog.inputs = Program::named(s)?;
+            Ok(prog)
+        };
+        #[cfg(feature = "heap")]
+        let parse = || crate::heap::account("parsing", parse).0;
+        parse()
+    }
+}
+
+/// The numbers of a program, reported by `Program::stats()`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub dataic: usize,
+    pub atomic: BTreeMap<String, usize>,
+    pub composite: usize,
+    pub attrs: usize,
+    pub max_locator: usize,
+    pub depth: usize,
+}
+
+impl Stats {
+    /// The total number of objects of all kinds.
+    pub fn objects(&self) -> usize {
+        self.dataic + self.atomic.values().sum::<usize>() + self.composite
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = vec![
+            format!("Objects: {}", self.objects()),
+            format!("  dataic: {}", self.dataic),
+            format!("  atomic: {}", self.atomic.values().sum::<usize>()),
+        ];
+        for (name, count) in self.atomic.iter() {
+            lines.push(format!("    {}: {}", name, count));
+        }
+        lines.push(format!("  composite: {}", self.composite));
+        lines.push(format!("Attributes: {}", self.attrs));
+        lines.push(format!("Longest locator: {}", self.max_locator));
+        lines.push(format!("Depth: {}", self.depth));
+        f.write_str(lines.join("\n").as_str())
+    }
+}
+
+/// What a program file says about the outcome of its own run, in a
+/// trailer comment like `# expect: 84` or `# expect-error: Stuck`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expectation {
+    /// The program must be dataized to this value.
+    Value(Data),
+    /// The program must fail, with this text in the error message,
+    /// compared case-insensitively.
+    Error(String),
+}
+
+/// The way it's written after `# expect:` or `# expect-error:`,
+/// the latter with `error: ` in front.
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expectation::Value(d) => write!(f, "{}", d),
+            Expectation::Error(e) => write!(f, "error: {}", e),
+        }
+    }
+}
+
+impl Expectation {
+    /// Find the expectation in the text of a program; if there
+    /// are many of them, the last one wins.
+    pub fn find(text: &str) -> Result<Option<Expectation>, String> {
+        let mut found = None;
+        for line in text.split('\n').map(|t| t.trim()) {
+            if let Some(v) = line.strip_prefix("# expect:") {
+                let v = v.trim();
+                let d = v
+                    .parse::<Data>()
+                    .map_err(|e| format!("Can't parse expected value '{}': {}", v, e))?;
+                found = Some(Expectation::Value(d));
+            } else if let Some(e) = line.strip_prefix("# expect-error:") {
+                found = Some(Expectation::Error(e.trim().to_string()));
+            }
+        }
+        Ok(found)
+    }
+
+    /// Is the outcome of a run what's expected?
+    pub fn check(&self, outcome: &Result<Data, String>) -> Result<(), String> {
+        match (self, outcome) {
+            (Expectation::Value(v), Ok(d)) if v == d => Ok(()),
+            (Expectation::Value(v), Ok(d)) => {
+                Err(format!("Result {} does not match expected {}", d, v))
+            }
+            (Expectation::Value(v), Err(e)) => Err(format!("Expected {}, but failed: {}", v, e)),
+            (Expectation::Error(x), Err(e)) if e.to_lowercase().contains(&x.to_lowercase()) => {
+                Ok(())
+            }
+            (Expectation::Error(x), Err(e)) => {
+                Err(format!("Expected error '{}', but failed with: {}", x, e))
+            }
+            (Expectation::Error(x), Ok(d)) => {
+                Err(format!("Expected error '{}', but got result {}", x, d))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+use crate::ph;
+
+#[test]
+fn builds_emu_from_iterator() {
+    let mut emu: Emu = [
+        (ROOT_OB, Object::open().with(Loc::Phi, ph!("ν1"), false)),
+        (Ob::new(1), Object::dataic(42)),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(42, emu.dataize().0);
+}
+
+#[test]
+fn replaces_and_indexes_objects() {
+    let mut prog = Program::new();
+    assert!(prog.insert(Ob::new(3), Object::dataic(1)).is_none());
+    assert!(prog.insert(Ob::new(3), Object::dataic(2)).is_some());
+    assert_eq!(1, prog.len());
+    assert_eq!(Some(2), prog[Ob::new(3)].delta);
+    assert!(prog.get(Ob::new(4)).is_none());
+}
+
+#[test]
+#[should_panic(expected = "There is no ν7")]
+fn panics_on_absent_index() {
+    let _ = &Program::new()[Ob::new(7)];
+}
+
+#[test]
+fn prints_and_parses_itself() {
+    let txt = "ν0(𝜋) ↦ ⟦𝜑↦ν2(ξ)⟧\nν2(𝜋) ↦ ⟦! Δ↦0x002A⟧";
+    let prog = Program::from_str(txt).unwrap();
+    assert_eq!(txt, prog.to_string());
+}
+
+#[test]
+fn fails_on_duplicate_object() {
+    let result = Program::from_str("ν1(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧");
+    assert!(result.err().unwrap().contains("Duplicate object ν1"));
+}
+
+#[test]
+fn reads_file_with_includes() {
+    let dir = std::env::temp_dir().join("phie-program-includes");
+    fs::create_dir_all(dir.join("lib")).unwrap();
+    fs::write(
+        dir.join("main.phi"),
+        "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\n+include lib/data.phi",
+    )
+    .unwrap();
+    fs::write(dir.join("lib/data.phi"), "ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧").unwrap();
+    let prog = Program::from_file(&dir.join("main.phi")).unwrap();
+    assert_eq!(2, prog.len());
+    assert_eq!(Some(42), prog[Ob::new(1)].delta);
+}
+
+#[test]
+fn fails_on_circular_include() {
+    let dir = std::env::temp_dir().join("phie-program-cycle");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a.phi"), "+include b.phi").unwrap();
+    fs::write(dir.join("b.phi"), "+include a.phi").unwrap();
+    let result = Program::from_file(&dir.join("a.phi"));
+    assert!(matches!(result, Err(Error::Cycle(_))));
+}
+
+#[test]
+fn includes_shared_file_once() {
+    let dir = std::env::temp_dir().join("phie-program-diamond");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("main.phi"),
+        "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\n+include a.phi\n+include b.phi",
+    )
+    .unwrap();
+    fs::write(dir.join("a.phi"), "ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν5(𝜋) ⟧\n+include c.phi").unwrap();
+    fs::write(dir.join("b.phi"), "ν2(𝜋) ↦ ⟦ 𝜑 ↦ ν5(𝜋) ⟧\n+include c.phi").unwrap();
+    fs::write(dir.join("c.phi"), "ν5(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧").unwrap();
+    let prog = Program::from_file(&dir.join("main.phi")).unwrap();
+    assert_eq!(4, prog.len());
+    assert_eq!(Some(42), prog[Ob::new(5)].delta);
+}
+
+#[test]
+fn fails_on_absent_file() {
+    let result = Program::from_file(Path::new("/tmp/phie-absent-file.phi"));
+    assert!(matches!(result, Err(Error::Io(_, _))));
+}
+
+#[test]
+fn skips_comments_and_blank_lines() {
+    let prog = Program::from_str("ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧\n\n# expect: 42").unwrap();
+    assert_eq!(1, prog.len());
+}
+
+#[test]
+fn finds_expectations() {
+    assert_eq!(None, Expectation::find("ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧").unwrap());
+    assert_eq!(
+        Some(Expectation::Value(84)),
+        Expectation::find("# expect: 1\n# expect: 84").unwrap()
+    );
+    assert_eq!(
+        Some(Expectation::Error("Stuck".to_string())),
+        Expectation::find("# expect-error: Stuck").unwrap()
+    );
+    assert!(Expectation::find("# expect: many").is_err());
+}
+
+#[test]
+fn checks_outcomes() {
+    assert!(Expectation::Value(42).check(&Ok(42)).is_ok());
+    assert!(Expectation::Value(42).check(&Ok(7)).is_err());
+    let stuck = Expectation::Error("stuck".to_string());
+    assert!(stuck.check(&Err("We are Stuck".to_string())).is_ok());
+    assert!(stuck.check(&Err("Too many cycles".to_string())).is_err());
+    assert!(stuck.check(&Ok(1)).is_err());
+}
+
+#[test]
+fn fingerprints_objects_and_inputs() {
+    let text = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧";
+    let prog = Program::from_str(text).unwrap();
+    assert_eq!(prog.fingerprint(), Emu::from(&prog).fingerprint());
+    assert_eq!(
+        prog.fingerprint(),
+        Program::from_str(&format!("+opt memoize\n{}", text))
+            .unwrap()
+            .fingerprint()
+    );
+    assert_ne!(
+        prog.fingerprint(),
+        Program::from_str(&format!("+input x ν1\n{}", text))
+            .unwrap()
+            .fingerprint()
+    );
+    assert_ne!(
+        prog.fingerprint(),
+        Program::from_str(&text.replace("2A", "2B"))
+            .unwrap()
+            .fingerprint()
+    );
+}
+
+#[test]
+fn canonicalizes_unreachable_and_absent_objects() {
+    let mut prog = Program::from_str(
+        "
+        +input x ν9
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν5(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧
+        ν2(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧
+        ν5(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν4(𝜋) ⟧
+        ν8(𝜋) ↦ ⟦ 𝜑 ↦ ν0(𝜋) ⟧
+        ν9(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧
+        ",
+    )
+    .unwrap();
+    let fingerprint = prog.fingerprint();
+    prog.canonicalize();
+    assert_eq!(
+        "+input x ν4\n\
+        ν0(𝜋) ↦ ⟦𝜑↦ν1(𝜋), 𝛼0↦ν2(𝜋)⟧\n\
+        ν1(𝜋) ↦ ⟦λ↦int-add, ρ↦ν2(𝜋), 𝛼0↦ν5(𝜋)⟧\n\
+        ν2(𝜋) ↦ ⟦! Δ↦0x0001⟧\n\
+        ν3(𝜋) ↦ ⟦𝜑↦ν0(𝜋)⟧\n\
+        ν4(𝜋) ↦ ⟦! Δ↦0x0002⟧",
+        prog.to_string()
+    );
+    assert_eq!(fingerprint, prog.fingerprint());
+    let again = prog.to_string();
+    prog.canonicalize();
+    assert_eq!(again, prog.to_string());
+}
+
+#[test]
+fn counts_stats() {
+    let prog = Program::from_str(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν3(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+        ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ 𝜋.𝛼0, 𝛼0 ↦ 𝜋.𝛼1 ⟧
+        ν3(𝜋) ↦ ⟦ 𝜑 ↦ ν2(ξ), 𝛼0 ↦ ν1(𝜋), 𝛼1 ↦ ν3(𝜋) ⟧
+        ",
+    )
+    .unwrap();
+    let stats = prog.stats();
+    assert_eq!(4, stats.objects());
+    assert_eq!(1, stats.dataic);
+    assert_eq!(Some(&1), stats.atomic.get("int-add"));
+    assert_eq!(2, stats.composite);
+    assert_eq!(6, stats.attrs);
+    assert_eq!(2, stats.max_locator);
+    assert_eq!(3, stats.depth);
+    assert!(stats.to_string().contains("    int-add: 1"));
+}
+
+#[test]
+fn parses_program_copied_from_paper() {
+    let prog = Program::from_str("ν0(π) ↦ ⟦ φ ↦ 𝜈1(π) ⟧\n𝜈1(π) ↦ ⟦ ∆ ↦ 0x002A ⟧").unwrap();
+    let mut emu = Emu::from(prog);
+    assert_eq!(42, emu.dataize().0);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parses_in_parallel_like_sequentially() {
+    let text = (0..16)
+        .map(|i| format!("ν{}(𝜋) ↦ ⟦ Δ ↦ 0x{:04X} ⟧", i, i))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let seq = Program::from_str(&text).unwrap();
+    let par = Program::par_from_str(&text).unwrap();
+    assert_eq!(seq.to_string(), par.to_string());
+    let broken = format!("{}\nbroken\nν3(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧\nalso broken", text);
+    assert_eq!(
+        Program::from_str(&broken).err(),
+        Program::par_from_str(&broken).err()
+    );
+}
+
+#[test]
+fn shares_repeated_locators() {
+    let prog = Program::from_str(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋), 𝛼0 ↦ 𝜋.𝛼0 ⟧
+        ν1(𝜋) ↦ ⟦ 𝜑 ↦ 𝜋.𝛼0, 𝛼1 ↦ 𝜋.𝛼0 ⟧
+        ",
+    )
+    .unwrap();
+    assert_eq!(2, prog.locators().len());
+    assert!(prog.locators().id(&ph!("𝜋.𝛼0")).is_some());
+}
+
+#[test]
+fn takes_options_from_header() {
+    let text = "+opt max-cycles 3\n+opt schedule lifo\nν0(𝜋) ↦ ⟦𝜑↦ν0(𝜋)⟧";
+    let prog = Program::from_str(text).unwrap();
+    assert_eq!(
+        &[
+            Opt::MaxCycles(3),
+            Opt::Schedule(crate::scheduler::Schedule::Lifo)
+        ],
+        prog.opts()
+    );
+    assert_eq!(text, prog.to_string());
+    let err = Emu::from(prog).try_dataize().err().unwrap().to_string();
+    assert!(err.starts_with("Too many cycles (4)"), "{}", err);
+}
+
+#[test]
+fn declares_arithmetic_in_header() {
+    let text = "+arith checked\nν0(𝜋) ↦ ⟦! Δ↦0x0007⟧";
+    let prog = Program::from_str(text).unwrap();
+    assert_eq!(Some(Arithmetic::Checked), prog.arith());
+    assert_eq!(text, prog.to_string());
+    let mut emu = Emu::from(&prog);
+    assert_eq!(Arithmetic::Checked, emu.arithmetic());
+    let err = emu
+        .tune(&[Opt::Arithmetic(Arithmetic::Wrapping)])
+        .err()
+        .unwrap();
+    assert!(
+        err.contains("can't run with 'arithmetic wrapping'"),
+        "{}",
+        err
+    );
+    assert!(emu.tune(&[Opt::Arithmetic(Arithmetic::Checked)]).is_ok());
+    assert!(emu
+        .try_opt(Opt::Arithmetic(Arithmetic::Saturating))
+        .is_err());
+    emu.opt(Opt::Arithmetic(Arithmetic::Saturating));
+    assert_eq!(Arithmetic::Checked, emu.arithmetic());
+    let err = emu.try_dataize().err().unwrap().to_string();
+    assert!(
+        err.contains("can't run with 'arithmetic saturating'"),
+        "{}",
+        err
+    );
+    assert!(Program::from_str("+arith checked\n+opt arithmetic saturating").is_err());
+    assert!(Program::from_str("+arith checked\n+arith wrapping").is_err());
+    assert!(Program::from_str("+arith sometimes").is_err());
+}
+
+#[test]
+fn depends_on_version_of_semantics() {
+    let text = "+semantics 1\n+arith wrapping\nν0(𝜋) ↦ ⟦! Δ↦0x0007⟧";
+    let prog = Program::from_str(text).unwrap();
+    assert_eq!(Some(1), prog.semantics());
+    assert_eq!(text, prog.to_string());
+    assert_eq!(1, Emu::from(&prog).semantics());
+    assert_eq!(semantics::LATEST, Emu::from(Program::new()).semantics());
+    let err = Program::from_str("+semantics 9").err().unwrap();
+    assert!(err.contains("not supported"), "{}", err);
+    assert!(Program::from_str("+semantics 1\n+semantics 2").is_err());
+    assert!(Opt::from_str("semantics 0").is_err());
+    let mut emu = Emu::from(&prog);
+    assert!(emu.tune(&[Opt::Semantics(3)]).is_err());
+    let mut emu = Emu::from_str("+semantics 1\nν0(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧").unwrap();
+    let err = emu
+        .try_opt(Opt::Arithmetic(Arithmetic::Checked))
+        .err()
+        .unwrap();
+    assert!(err.contains("needs semantics 2"), "{}", err);
+    assert!(emu
+        .tune(&[Opt::Arithmetic(Arithmetic::Saturating), Opt::Semantics(2)])
+        .is_ok());
+    assert_eq!(Arithmetic::Saturating, emu.arithmetic());
+    assert!(emu.try_opt(Opt::Semantics(1)).is_err());
+}
+
+#[test]
+fn fails_on_unknown_option() {
+    let err = Program::from_str("+opt go-faster\nν0(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧")
+        .err()
+        .unwrap();
+    assert!(err.contains("Unknown option 'go-faster'"), "{}", err);
+}
+
+#[test]
+fn refuses_crash_dumps_in_program() {
+    let err = Program::from_str("+opt crash-dumps /tmp/phie-dumps\nν0(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧")
+        .err()
+        .unwrap();
+    assert!(err.contains("can't be set by a program"), "{}", err);
+    let mut prog = Program::from_str("ν0(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧").unwrap();
+    prog.opt(Opt::CrashDumps(PathBuf::from("/tmp/phie-dumps")));
+    assert!(Program::from_str(&prog.to_string()).is_ok());
+}
+
+#[test]
+fn links_library_under_namespace() {
+    let mut prog = Program::from_str("ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧").unwrap();
+    let lib = Program::from_str(
+        "
+        ν0(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ 𝜋.𝛼0, 𝛼0 ↦ ν1(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x0028 ⟧
+        ",
+    )
+    .unwrap();
+    let obs = prog.link(lib, "math");
+    assert_eq!(Some(&Ob::new(3)), obs.get(&Ob::new(1)));
+    let obj = prog.get(Ob::new(2)).unwrap();
+    assert_eq!("ν2 (math::ν0)", obj.label(Ob::new(2)));
+    assert_eq!("ν3", obj.attrs[&Loc::Attr(0)].0.to_string());
+    let again = Program::from_str(&prog.to_string()).unwrap();
+    assert_eq!(
+        Some(("math".to_string(), Ob::new(1))),
+        again.get(Ob::new(3)).unwrap().origin
+    );
+    assert_eq!(prog.to_string(), again.to_string());
+}
+
+#[test]
+fn parses_and_prints_inputs() {
+    let text = "+input x ν1\nν0(𝜋) ↦ ⟦𝜑↦ν1(𝜋)⟧\nν1(𝜋) ↦ ⟦! Δ↦0x0007⟧";
+    let prog = Program::from_str(text).unwrap();
+    assert_eq!(Some(&Ob::new(1)), prog.inputs().get("x"));
+    assert_eq!(text, prog.to_string());
+    assert!(Program::from_str("+input x 1\nν0(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧").is_err());
+}
+
+#[test]
+fn dataizes_tiny_programs_by_pinned_emu() {
+    let mut prog = Program::from_str(&(crate::examples::FIBONACCI.program)(6)).unwrap();
+    let (first, perf) = prog.dataize_pinned().unwrap();
+    let (again, same) = prog.dataize_pinned().unwrap();
+    assert_eq!(13, first);
+    assert_eq!(first, again);
+    assert_eq!(perf.cycles, same.cycles);
+    prog.insert(Ob::new(1), Object::dataic(7));
+    assert_eq!(21, prog.dataize_pinned().unwrap().0);
+    let big =
+        Program::from_str("+opt max-objects 64\nν0(𝜋) ↦ ⟦ 𝜑 ↦ ν40(𝜋) ⟧\nν40(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧")
+            .unwrap();
+    assert_eq!(42, big.dataize_pinned().unwrap().0);
+}
+
+#[test]
+fn builds_itself_from_json() {
+    let text = "+semantics 1\n+opt stop-when-stuck\n+input x ν1\nν0(𝜋) ↦ ⟦𝜑↦ν2(𝜋)⟧\n\
+        ν1(𝜋) ↦ ⟦! Δ↦0x0007⟧\nν2(𝜋) ↦ ⟦% λ↦int-add, ρ↦ν1(𝜋), 𝛼0↦ν1(ξ)⟧";
+    let prog = Program::from_str(text).unwrap();
+    let again = Program::from_json(prog.to_json()).unwrap();
+    assert_eq!(text, again.to_string());
+    assert_eq!(prog.fingerprint(), again.fingerprint());
+}
+
+#[test]
+fn refuses_broken_json() {
+    use serde_json::json;
+    let fails = |json: serde_json::Value, msg: &str| {
+        let err = Program::from_json(json).err().unwrap();
+        assert!(err.contains(msg), "{}", err);
+    };
+    fails(json!({}), "#: must have 'objects'");
+    fails(
+        json!({"objects": [{"delta": 1}]}),
+        "#/objects/0: must have 'ob'",
+    );
+    fails(
+        json!({"objects": [{"ob": 0}, {"ob": 0}]}),
+        "Duplicate object ν0",
+    );
+    fails(
+        json!({"objects": [{"ob": 0, "lambda": "int-nope"}]}),
+        "#/objects/0/lambda: must be one of",
+    );
+    fails(
+        json!({"objects": [{"ob": 3, "attrs": {"𝜑": {}}}]}),
+        "#/objects/0/attrs/𝜑: must have 'locator'",
+    );
+    fails(
+        json!({"objects": [{"ob": 3, "attrs": {"𝜑": "ν1.?"}}]}),
+        "in ν3",
+    );
+    fails(
+        json!({"objects": [{"ob": 0, "attrs": {"Δ": "ν1"}}]}),
+        "must be a 'delta'",
+    );
+    fails(
+        json!({"objects": [], "semantics": 99}),
+        "#/semantics: must be at most",
+    );
+    fails(
+        json!({"objects": [], "arith": "checked", "opts": ["arithmetic saturating"]}),
+        "but sets",
+    );
+}
@@ -0,0 +1,84 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Where the data came from, recorded when `Opt::TrackProvenance` is set.
+//!
+//! Every dataized value is either the Δ of an object or the result of
+//! an atom, which was calculated from the values the atom read. This
+//! makes a tree, which explains the result of dataization:
+//!
+//! ```
+//! use phie::emu::{Emu, Opt};
+//! let mut emu: Emu = "
+//!   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+//!   ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+//!   ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧
+//! ".parse().unwrap();
+//! emu.opt(Opt::TrackProvenance);
+//! emu.dataize();
+//! assert_eq!(
+//!     "84 ← ν2 int-add\n  42 ← ν1 Δ\n  42 ← ν1 Δ",
+//!     emu.provenance().unwrap().to_string()
+//! );
+//! ```
+
+use crate::atom::returns;
+use crate::data::{Data, Type};
+use crate::object::Ob;
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// Taken from the Δ of the object.
+    Delta,
+    /// Calculated by the atom of the object.
+    Atom(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    pub value: Data,
+    pub ob: Ob,
+    pub origin: Origin,
+    /// The values the atom read, in the order it read them.
+    pub inputs: Vec<Arc<Provenance>>,
+}
+
+impl Provenance {
+    /// The type of the value: a Δ is an integer, while the result of
+    /// an atom has the type it returns, see `atom::returns()`, or the
+    /// type of the value it read last, like the branch of `bool-if`.
+    pub fn kind(&self) -> Type {
+        match &self.origin {
+            Origin::Delta => Type::Int,
+            Origin::Atom(name) => returns(name)
+                .or_else(|| self.inputs.last().map(|i| i.kind()))
+                .unwrap_or(Type::Int),
+        }
+    }
+
+    fn lines(&self, depth: usize, lines: &mut Vec<String>) {
+        lines.push(format!(
+            "{}{} ← ν{} {}",
+            "  ".repeat(depth),
+            self.value,
+            self.ob,
+            match &self.origin {
+                Origin::Delta => "Δ",
+                Origin::Atom(name) => name,
+            }
+        ));
+        for i in self.inputs.iter() {
+            i.lines(depth + 1, lines);
+        }
+    }
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut lines = vec![];
+        self.lines(0, &mut lines);
+        f.write_str(&lines.join("\n"))
+    }
+}
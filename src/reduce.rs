@@ -0,0 +1,147 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! A smaller program cut out of a `crash::Dump`, which fails the same
+//! way: the program of the dump is replayed with `Opt::RecordEvents`,
+//! only the objects that the events touched are kept, and then the rest
+//! of them are taken out one by one, as long as the failure stays:
+//!
+//! ```
+//! use phie::crash::Dump;
+//! use phie::emu::{Emu, Opt};
+//! use phie::object::Ob;
+//! use phie::reduce::reduce;
+//! let mut emu: Emu = "
+//!   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧
+//!   ν1(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν1(ξ) ⟧
+//!   ν3(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+//! ".parse().unwrap();
+//! emu.opt(Opt::MaxCycles(10));
+//! let err = emu.try_dataize().err().unwrap().to_string();
+//! let prog = reduce(&Dump::of(&emu, &err, 11)).unwrap();
+//! assert!(prog.get(Ob::new(3)).is_none());
+//! ```
+
+use crate::crash::Dump;
+use crate::emu::{Emu, Opt, ROOT_OB};
+use crate::object::Ob;
+use crate::program::Program;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// The program of the dump, with as few objects as possible, failing
+/// with the same first line of the message, and with the options of
+/// the dump as its `+opt` lines, except `Opt::CrashDumps`.
+pub fn reduce(dump: &Dump) -> Result<Program, String> {
+    let mut prog = Program::from_str(&dump.program)?;
+    let opts = dump
+        .opts
+        .iter()
+        .map(|o| Opt::from_str(o))
+        .filter(|o| !matches!(o, Ok(Opt::CrashDumps(_))))
+        .collect::<Result<Vec<Opt>, String>>()?;
+    let failure = first(&dump.message);
+    let involved = match replay(&prog, &opts, dump.cycle) {
+        (Some(msg), obs) if first(&msg) == failure => obs,
+        (Some(msg), _) => {
+            return Err(format!(
+                "The crash dump fails differently when replayed: {}",
+                msg
+            ))
+        }
+        (None, _) => return Err("The crash dump doesn't fail when replayed".to_string()),
+    };
+    let fails = |p: &Program| {
+        replay(p, &opts, dump.cycle)
+            .0
+            .is_some_and(|m| first(&m) == failure)
+    };
+    let cut = without(&prog, |ob| !involved.contains(&ob));
+    if fails(&cut) {
+        prog = cut;
+    }
+    let obs: Vec<Ob> = prog
+        .iter()
+        .map(|(ob, _)| *ob)
+        .filter(|ob| *ob != ROOT_OB)
+        .collect();
+    for ob in obs.into_iter().rev() {
+        let cut = without(&prog, |o| o == ob);
+        if fails(&cut) {
+            prog = cut;
+        }
+    }
+    let mut canonical = without(&prog, |_| false);
+    canonical.canonicalize();
+    if fails(&canonical) {
+        prog = canonical;
+    }
+    for opt in opts {
+        prog.opt(opt);
+    }
+    Ok(prog)
+}
+
+/// The first line of the message, which tells what failed, while the
+/// rest of it is usually the snapshot of the baskets.
+fn first(message: &str) -> &str {
+    message.lines().next().unwrap_or_default()
+}
+
+/// The program without the objects that match, and without the inputs
+/// that named them.
+fn without(prog: &Program, drop: impl Fn(Ob) -> bool) -> Program {
+    let mut cut = Program::new();
+    for (name, ob) in prog.inputs().iter().filter(|(_, ob)| !drop(**ob)) {
+        cut.input(name, *ob);
+    }
+    for (ob, obj) in prog.iter().filter(|(ob, _)| !drop(**ob)) {
+        cut.insert(*ob, obj.clone());
+    }
+    cut
+}
+
+/// Dataize the program with the options, never longer than twice
+/// the cycles of the dump, unless the options say how long, and
+/// return the message of the failure, if any, with the objects
+/// that the transitions touched.
+fn replay(prog: &Program, opts: &[Opt], cycle: usize) -> (Option<String>, HashSet<Ob>) {
+    let mut emu: Emu = Emu::of(prog);
+    for opt in opts {
+        emu.opt(opt.clone());
+    }
+    if !opts.iter().any(|o| matches!(o, Opt::MaxCycles(_))) {
+        emu.opt(Opt::MaxCycles(cycle * 2 + 16));
+    }
+    emu.opt(Opt::RecordEvents);
+    let failure = emu.try_dataize().err().map(String::from);
+    let obs = emu
+        .events(crate::event::EventFilter::any())
+        .map(|e| e.ob)
+        .collect();
+    (failure, obs)
+}
+
+#[test]
+fn reduces_to_objects_involved() {
+    let mut emu: Emu = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧\n\
+        ν2(𝜋) ↦ ⟦ λ ↦ int-div, ρ ↦ ν5(𝜋), 𝛼0 ↦ ν4(𝜋) ⟧\nν3(𝜋) ↦ ⟦ Δ ↦ 0x0001 ⟧\n\
+        ν4(𝜋) ↦ ⟦ Δ ↦ 0x0000 ⟧\nν5(𝜋) ↦ ⟦ Δ ↦ 0x0009 ⟧"
+        .parse()
+        .unwrap();
+    let err = emu.try_dataize().err().unwrap().to_string();
+    let prog = reduce(&Dump::of(&emu, &err, 3)).unwrap();
+    assert_eq!(4, prog.len(), "{}", prog);
+    let mut again: Emu = Emu::from(prog);
+    assert_eq!(
+        first(&err),
+        first(&again.try_dataize().err().unwrap().to_string())
+    );
+}
+
+#[test]
+fn refuses_dump_that_does_not_fail() {
+    let emu: Emu = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+    let dump = Dump::of(&emu, "Nothing happened", 0);
+    assert!(reduce(&dump).err().unwrap().contains("doesn't fail"));
+}
@@ -0,0 +1,77 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Atoms an `Emu` is allowed to call, set by `Emu::sandbox()`.
+//!
+//! A profile lists the names of the atoms allowed, where a name ending
+//! with `*` allows all atoms starting with it, so that a whole class of
+//! them, like `io-*`, is allowed or not at once. An object with an atom
+//! outside of the profile makes dataization fail with
+//! `Error::AtomForbidden`:
+//!
+//! ```
+//! use phie::emu::Emu;
+//! use phie::error::Error;
+//! use phie::sandbox::Profile;
+//! let mut emu: Emu = "
+//!   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+//!   ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+//!   ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧
+//! ".parse().unwrap();
+//! emu.sandbox(Profile::none().allow("bool-*"));
+//! let err = emu.try_dataize().err().unwrap();
+//! assert!(matches!(err, Error::AtomForbidden(name, ob) if name == "int-add" && ob.index() == 2));
+//! ```
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    allowed: Vec<String>,
+}
+
+impl Profile {
+    /// No atoms at all.
+    pub fn none() -> Profile {
+        Profile::default()
+    }
+
+    /// The atoms that only calculate: integers, rationals, floats and booleans.
+    pub fn pure() -> Profile {
+        Profile::none()
+            .allow("int-*")
+            .allow("rat-*")
+            .allow("float-*")
+            .allow("bool-*")
+    }
+
+    /// Allow one more atom, or all atoms starting with the prefix
+    /// before a trailing `*`.
+    pub fn allow(mut self, name: &str) -> Profile {
+        self.allowed.push(name.to_string());
+        self
+    }
+
+    /// Is the atom with this name allowed?
+    pub fn allows(&self, name: &str) -> bool {
+        self.allowed.iter().any(|a| match a.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => a == name,
+        })
+    }
+}
+
+#[test]
+fn allows_by_prefix() {
+    let p = Profile::none().allow("int-*").allow("bool-if");
+    assert!(p.allows("int-add"));
+    assert!(p.allows("bool-if"));
+    assert!(!p.allows("bool-or"));
+    assert!(!p.allows("io-print"));
+}
+
+#[test]
+fn allows_only_pure_atoms() {
+    let p = Profile::pure();
+    assert!(p.allows("int-times"));
+    assert!(p.allows("rat-add"));
+    assert!(!p.allows("random-int"));
+}
@@ -0,0 +1,141 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Policies deciding in which order baskets are advanced in a cycle.
+//!
+//! The emulator visits every basket once per transition in a cycle.
+//! The order of these visits doesn't change the result, but it
+//! dramatically changes how many baskets are alive at the same time,
+//! especially for deep recursion. Pick a policy with `Opt::Schedule`:
+//!
+//! ```
+//! use phie::emu::{Emu, Opt};
+//! use phie::scheduler::Schedule;
+//! let mut emu: Emu = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+//! emu.opt(Opt::Schedule(Schedule::Deepest));
+//! assert_eq!(42, emu.dataize().0);
+//! ```
+
+use crate::basket::Bk;
+use crate::emu::ROOT_BK;
+use std::cmp::Reverse;
+
+/// The order in which baskets are advanced in one cycle.
+pub trait Scheduler {
+    /// All baskets of the `Emu`, given by their ξ as `Emu::psis()` returns
+    /// them, in the order they must be visited. Empty baskets, with
+    /// a negative ξ, must be included too, since they may be filled in
+    /// by the time they are visited.
+    fn order(&self, psis: &[Bk]) -> Vec<Bk>;
+}
+
+/// Scheduling policies available through `Opt::Schedule`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Schedule {
+    /// Oldest positions first, which is the default.
+    Fifo,
+    /// Newest positions first.
+    Lifo,
+    /// Baskets with the longest ξ-chain to the root first.
+    Deepest,
+}
+
+impl Schedule {
+    /// Make a scheduler implementing this policy.
+    pub fn scheduler(&self) -> Box<dyn Scheduler> {
+        match self {
+            Schedule::Fifo => Box::new(Fifo),
+            Schedule::Lifo => Box::new(Lifo),
+            Schedule::Deepest => Box::new(Deepest),
+        }
+    }
+}
+
+pub struct Fifo;
+
+impl Scheduler for Fifo {
+    fn order(&self, psis: &[Bk]) -> Vec<Bk> {
+        (0..psis.len()).map(Bk::new).collect()
+    }
+}
+
+pub struct Lifo;
+
+impl Scheduler for Lifo {
+    fn order(&self, psis: &[Bk]) -> Vec<Bk> {
+        (0..psis.len()).rev().map(Bk::new).collect()
+    }
+}
+
+pub struct Deepest;
+
+impl Deepest {
+    /// The number of ξ hops from the basket to the root one.
+    fn depth(psis: &[Bk], bk: Bk) -> usize {
+        let mut depth = 0;
+        let mut cur = bk;
+        while cur != ROOT_BK && depth < psis.len() {
+            let psi = psis[cur.index()];
+            if psi.is_none() {
+                break;
+            }
+            cur = psi;
+            depth += 1;
+        }
+        depth
+    }
+}
+
+impl Scheduler for Deepest {
+    fn order(&self, psis: &[Bk]) -> Vec<Bk> {
+        let mut all: Vec<Bk> = (0..psis.len()).map(Bk::new).collect();
+        all.sort_by_key(|bk| {
+            if psis[bk.index()].is_none() {
+                (1, Reverse(0))
+            } else {
+                (0, Reverse(Deepest::depth(psis, *bk)))
+            }
+        });
+        all
+    }
+}
+
+#[cfg(test)]
+use crate::basket::Basket;
+
+#[cfg(test)]
+use crate::emu::Emu;
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn fifo_keeps_positions() {
+    let emu = Emu::empty();
+    let order = Fifo.order(&emu.psis());
+    assert_eq!(emu.baskets.len(), order.len());
+    assert_eq!(ROOT_BK, order[0]);
+}
+
+#[test]
+fn lifo_reverses_positions() {
+    let emu = Emu::empty();
+    let order = Lifo.order(&emu.psis());
+    assert_eq!(ROOT_BK, *order.last().unwrap());
+}
+
+#[test]
+fn deepest_goes_first() {
+    let mut emu = Emu::empty();
+    emu.inject(Bk::new(1), Basket::from_str("[ν1, ξ:β0]").unwrap());
+    emu.inject(Bk::new(2), Basket::from_str("[ν1, ξ:β3]").unwrap());
+    emu.inject(Bk::new(3), Basket::from_str("[ν1, ξ:β1]").unwrap());
+    let order = Deepest.order(&emu.psis());
+    assert_eq!(
+        vec![2, 3, 1, 0],
+        order[0..4]
+            .iter()
+            .map(|bk| bk.index())
+            .collect::<Vec<usize>>()
+    );
+}
@@ -0,0 +1,281 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! The JSON Schema of programs taken by `Program::from_json()`, for
+//! generators of programs to check their output against, and the check
+//! itself, which reports every problem with the JSON pointer of the
+//! value, like `#/objects/2/attrs/𝜑`, before the program is built:
+//!
+//! ```
+//! use phie::schema::{program_json, validate};
+//! use serde_json::json;
+//! assert_eq!("object", program_json()["type"]);
+//! let errors = validate(&json!({"objects": [{"ob": 0, "delta": "42"}]})).unwrap_err();
+//! assert_eq!(vec!["#/objects/0/delta: must be an integer"], errors);
+//! ```
+//!
+//! Only the keywords the schema uses are understood by `validate()`:
+//! `type`, `enum`, `minimum`, `maximum`, `required`, `properties`,
+//! `additionalProperties`, `items`, `anyOf` and local `$ref`s.
+
+use crate::data::{Data, Datum};
+use crate::emu::ARITHMETICS;
+use crate::semantics::{FIRST, LATEST};
+use serde_json::{json, Map, Value};
+
+/// The schema of the JSON of a program, see `Program::from_json()`.
+pub fn program_json() -> Value {
+    #[allow(unused_mut)]
+    let mut object = json!({
+        "type": "object",
+        "required": ["ob"],
+        "additionalProperties": false,
+        "properties": {
+            "ob": {"type": "integer", "minimum": 0, "description": "The number of the object, like 7 in ν7"},
+            "delta": {"type": "integer", "description": "The data of the object, Δ"},
+            "lambda": {
+                "enum": Data::atoms().iter().map(|m| m.name).collect::<Vec<&str>>(),
+                "description": "The atom of the object, λ"
+            },
+            "constant": {"type": "boolean", "description": "! in the text"},
+            "memo": {"type": "boolean", "description": "% in the text if true, ~ if false"},
+            "attrs": {
+                "type": "object",
+                "additionalProperties": {"$ref": "#/$defs/attr"},
+                "description": "The attributes by their names, like 𝜑, ρ or 𝛼0"
+            }
+        }
+    });
+    #[cfg(feature = "rational")]
+    {
+        object["properties"]["denominator"] =
+            json!({"type": "integer", "description": "The Δ is a fraction with this denominator"});
+    }
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": "https://github.com/objectionary/phie/program.schema.json",
+        "title": "A program of phie",
+        "type": "object",
+        "required": ["objects"],
+        "additionalProperties": false,
+        "properties": {
+            "semantics": {"type": "integer", "minimum": FIRST, "maximum": LATEST},
+            "arith": {"enum": ARITHMETICS.iter().map(|(n, _)| *n).collect::<Vec<&str>>()},
+            "opts": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "Options, like in +opt lines"
+            },
+            "inputs": {
+                "type": "object",
+                "additionalProperties": {"type": "integer", "minimum": 0},
+                "description": "The numbers of objects by their names, like in +input lines"
+            },
+            "objects": {"type": "array", "items": {"$ref": "#/$defs/object"}}
+        },
+        "$defs": {
+            "object": object,
+            "attr": {
+                "anyOf": [
+                    {"type": "string", "description": "A locator, like ν2 or 𝜋.𝛼0"},
+                    {
+                        "type": "object",
+                        "required": ["locator"],
+                        "additionalProperties": false,
+                        "properties": {
+                            "locator": {"type": "string"},
+                            "xi": {"type": "boolean", "description": "(ξ) in the text"}
+                        }
+                    }
+                ]
+            }
+        }
+    })
+}
+
+/// Check the document against `program_json()`, returning all problems
+/// found, each with the pointer of the value it's about.
+pub fn validate(doc: &Value) -> Result<(), Vec<String>> {
+    let schema = program_json();
+    let mut errors = vec![];
+    check(&schema, &schema, doc, "#", &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Check the value against the part of the root schema.
+fn check(root: &Value, schema: &Value, value: &Value, ptr: &str, errors: &mut Vec<String>) {
+    if let Some(r) = schema["$ref"].as_str() {
+        let target = r
+            .strip_prefix('#')
+            .and_then(|p| root.pointer(p))
+            .unwrap_or(&Value::Null);
+        return check(root, target, value, ptr, errors);
+    }
+    if let Some(any) = schema["anyOf"].as_array() {
+        let tries: Vec<Vec<String>> = any
+            .iter()
+            .map(|s| {
+                let mut errs = vec![];
+                check(root, s, value, ptr, &mut errs);
+                errs
+            })
+            .collect();
+        if tries.iter().all(|errs| !errs.is_empty()) {
+            let typed: Vec<&Vec<String>> = any
+                .iter()
+                .zip(tries.iter())
+                .filter(|(s, _)| s["type"].as_str().is_some_and(|t| typed(value, t)))
+                .map(|(_, errs)| errs)
+                .collect();
+            match typed.as_slice() {
+                [errs] => errors.extend(errs.iter().cloned()),
+                _ => errors.push(format!(
+                    "{}: must be {}",
+                    ptr,
+                    any.iter()
+                        .filter_map(|s| s["type"].as_str().map(article))
+                        .collect::<Vec<String>>()
+                        .join(" or ")
+                )),
+            }
+        }
+        return;
+    }
+    if let Some(t) = schema["type"].as_str() {
+        if !typed(value, t) {
+            errors.push(format!("{}: must be {}", ptr, article(t)));
+            return;
+        }
+    }
+    if let Some(options) = schema["enum"].as_array() {
+        if !options.contains(value) {
+            errors.push(format!(
+                "{}: must be one of {}",
+                ptr,
+                options
+                    .iter()
+                    .map(|o| o.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
+    }
+    if let (Some(min), Some(n)) = (schema["minimum"].as_i64(), value.as_i64()) {
+        if n < min {
+            errors.push(format!("{}: must be at least {}", ptr, min));
+        }
+    }
+    if let (Some(max), Some(n)) = (schema["maximum"].as_i64(), value.as_i64()) {
+        if n > max {
+            errors.push(format!("{}: must be at most {}", ptr, max));
+        }
+    }
+    if let Some(items) = value.as_array() {
+        if let Some(each) = schema.get("items") {
+            for (i, item) in items.iter().enumerate() {
+                check(root, each, item, &format!("{}/{}", ptr, i), errors);
+            }
+        }
+    }
+    if let Some(map) = value.as_object() {
+        fields(root, schema, map, ptr, errors);
+    }
+}
+
+/// Check the keys of an object: the required ones, the known ones
+/// and the rest of them.
+fn fields(
+    root: &Value,
+    schema: &Value,
+    map: &Map<String, Value>,
+    ptr: &str,
+    errors: &mut Vec<String>,
+) {
+    for key in schema["required"].as_array().into_iter().flatten() {
+        let key = key.as_str().unwrap_or_default();
+        if !map.contains_key(key) {
+            errors.push(format!("{}: must have '{}'", ptr, key));
+        }
+    }
+    for (key, v) in map {
+        let here = format!("{}/{}", ptr, key.replace('~', "~0").replace('/', "~1"));
+        match (
+            schema["properties"].get(key),
+            schema.get("additionalProperties"),
+        ) {
+            (Some(s), _) => check(root, s, v, &here, errors),
+            (None, Some(Value::Bool(false))) => errors.push(format!("{}: is not expected", here)),
+            (None, Some(s)) if s.is_object() => check(root, s, v, &here, errors),
+            _ => {}
+        }
+    }
+}
+
+/// Is the value of the type of JSON Schema?
+fn typed(value: &Value, t: &str) -> bool {
+    match t {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
+
+/// The type with its article, like "an integer".
+fn article(t: &str) -> String {
+    if t.starts_with(['a', 'e', 'i', 'o', 'u']) {
+        format!("an {}", t)
+    } else {
+        format!("a {}", t)
+    }
+}
+
+#[test]
+fn points_at_every_problem() {
+    let errors = validate(&json!({
+        "semantics": 0,
+        "arith": "exact",
+        "extra": true,
+        "inputs": {"x/y": -1},
+        "objects": [
+            {"ob": 0, "attrs": {"𝜑": 5, "ρ": {"xi": true}}},
+            {"lambda": "int-nope"}
+        ]
+    }))
+    .unwrap_err();
+    assert_eq!(8, errors.len(), "{:?}", errors);
+    for expected in [
+        "#/arith: must be one of \"wrapping\", \"saturating\", \"checked\"",
+        "#/extra: is not expected",
+        "#/inputs/x~1y: must be at least 0",
+        "#/objects/0/attrs/ρ: must have 'locator'",
+        "#/objects/0/attrs/𝜑: must be a string or an object",
+        "#/objects/1: must have 'ob'",
+        "#/semantics: must be at least 1",
+    ] {
+        assert!(errors.contains(&expected.to_string()), "{:?}", errors);
+    }
+    assert!(
+        errors
+            .iter()
+            .any(|e| e.starts_with("#/objects/1/lambda: must be one of \"int-times\"")),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn accepts_program_it_describes() {
+    let prog: crate::program::Program = "+input x ν1\nν0(𝜋) ↦ ⟦𝜑↦ν1(ξ)⟧\nν1(𝜋) ↦ ⟦! Δ↦0x0007⟧"
+        .parse()
+        .unwrap();
+    assert_eq!(Ok(()), validate(&prog.to_json()));
+}
@@ -0,0 +1,203 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Scripts in Rhai, available with the `scripting` feature, which the
+//! debugger runs on every breakpoint, see `dap`, to look at the baskets,
+//! log what they find, and decide whether to stop or to go on, so that
+//! a recipe for a stuck program may be written once and run again.
+//!
+//! A script sees `cycle`, the `bk` and `ob` of the basket that stopped
+//! the debugger, the `loc` and `data` of its kid, if a condition held,
+//! or `()` if the basket is just made, see `breakpoint`, and `baskets`,
+//! an array of maps like `#{bk: 3, ob: 2, psi: 1, kids: #{"𝛼0": 42}}`,
+//! where dataized kids are integers and the others are strings. It may
+//! call `log(text)`, and stops the debugger unless it ends with `false`:
+//!
+//! ```
+//! use phie::emu::{Emu, ROOT_BK};
+//! use phie::script::{Script, Verdict};
+//! let emu: Emu = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+//! let mut script = Script::compile("
+//!   log(`cycle ${cycle}, ${baskets.len()} basket(s)`);
+//!   data != 7
+//! ").unwrap();
+//! let verdict = script.run(&emu, 3, ROOT_BK, None).unwrap();
+//! assert_eq!(Verdict::Stop, verdict);
+//! assert_eq!(vec!["cycle 3, 1 basket(s)"], script.logs());
+//! ```
+
+use crate::basket::{Bk, Kid};
+use crate::breakpoint::Break;
+use crate::emu::Emu;
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+/// How many operations a script may make on one breakpoint, so that
+/// a script that never ends can't hang the debugger.
+pub const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// How deep the functions of a script may call each other.
+pub const MAX_CALL_DEPTH: usize = 64;
+
+/// What the script decided to do with the breakpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Stop,
+    Continue,
+}
+
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    logs: Arc<Mutex<Vec<String>>>,
+}
+
+impl Script {
+    /// Compile the text of the script, once for all breakpoints; it may
+    /// make no more than `MAX_OPERATIONS` on each of them.
+    pub fn compile(text: &str) -> Result<Script, String> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(MAX_CALL_DEPTH);
+        let logs = Arc::new(Mutex::new(vec![]));
+        let sink = Arc::clone(&logs);
+        engine.register_fn("log", move |text: &str| {
+            sink.lock().unwrap().push(text.to_string());
+        });
+        let ast = engine
+            .compile(text)
+            .map_err(|e| format!("Can't compile the script: {}", e))?;
+        Ok(Script { engine, ast, logs })
+    }
+
+    /// Run it on the breakpoint hit in the cycle, which is either the
+    /// condition that held, or the new basket, if there is no `Break`.
+    pub fn run(
+        &mut self,
+        emu: &Emu,
+        cycle: usize,
+        bk: Bk,
+        hit: Option<&Break>,
+    ) -> Result<Verdict, String> {
+        let mut scope = Scope::new();
+        scope.push_constant("cycle", cycle as i64);
+        scope.push_constant("baskets", baskets(emu));
+        match hit {
+            Some(b) => {
+                scope.push_constant("bk", i64::from(b.bk));
+                scope.push_constant("ob", b.ob.index() as i64);
+                scope.push_constant("loc", b.loc.to_string());
+                scope.push_constant("data", b.data);
+            }
+            None => {
+                scope.push_constant("bk", i64::from(bk));
+                scope.push_constant("ob", emu.basket(bk).ob.index() as i64);
+                scope.push_constant("loc", Dynamic::UNIT);
+                scope.push_constant("data", Dynamic::UNIT);
+            }
+        }
+        let result: Dynamic = self
+            .engine
+            .eval_ast_with_scope(&mut scope, &self.ast)
+            .map_err(|e| format!("The script failed: {}", e))?;
+        Ok(match result.as_bool() {
+            Ok(false) => Verdict::Continue,
+            _ => Verdict::Stop,
+        })
+    }
+
+    /// Take the lines logged by the script so far.
+    pub fn logs(&mut self) -> Vec<String> {
+        mem::take(&mut *self.logs.lock().unwrap())
+    }
+}
+
+/// The live baskets, as the script sees them.
+fn baskets(emu: &Emu) -> Array {
+    emu.baskets
+        .iter()
+        .enumerate()
+        .filter(|(_, bsk)| !bsk.is_empty())
+        .map(|(bk, bsk)| {
+            let kids: Map = bsk
+                .kids
+                .iter()
+                .map(|(loc, kid)| {
+                    let value = match kid {
+                        Kid::Dtzd(d) => Dynamic::from(*d),
+                        other => Dynamic::from(other.to_string()),
+                    };
+                    (loc.to_string().into(), value)
+                })
+                .collect();
+            let mut map = Map::new();
+            map.insert("bk".into(), Dynamic::from(bk as i64));
+            map.insert("ob".into(), Dynamic::from(bsk.ob.index() as i64));
+            map.insert("psi".into(), Dynamic::from(i64::from(bsk.psi)));
+            map.insert("kids".into(), Dynamic::from(kids));
+            Dynamic::from(map)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+use crate::emu::ROOT_BK;
+#[cfg(test)]
+use crate::loc::Loc;
+#[cfg(test)]
+use crate::object::Ob;
+
+#[test]
+fn decides_by_data_of_breakpoint() {
+    let emu: Emu = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧"
+        .parse()
+        .unwrap();
+    let mut script = Script::compile("log(`${loc} of ν${ob}`); data > 10").unwrap();
+    let hit = Break {
+        condition: 0,
+        bk: ROOT_BK,
+        ob: Ob::new(1),
+        loc: Loc::Phi,
+        data: 7,
+    };
+    assert_eq!(
+        Verdict::Continue,
+        script.run(&emu, 1, ROOT_BK, Some(&hit)).unwrap()
+    );
+    assert_eq!(vec!["𝜑 of ν1"], script.logs());
+    assert!(script.logs().is_empty());
+}
+
+#[test]
+fn reads_kids_of_baskets() {
+    let emu: Emu = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧".parse().unwrap();
+    let mut script = Script::compile("ob == 0 && baskets[0].kids[\"𝜑\"] == \"→?\"").unwrap();
+    assert_eq!(Verdict::Stop, script.run(&emu, 0, ROOT_BK, None).unwrap());
+}
+
+#[test]
+fn reports_broken_scripts() {
+    assert!(Script::compile("let x = ;")
+        .err()
+        .unwrap()
+        .contains("compile"));
+    let emu: Emu = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧".parse().unwrap();
+    let mut script = Script::compile("undefined_thing()").unwrap();
+    assert!(script
+        .run(&emu, 0, ROOT_BK, None)
+        .unwrap_err()
+        .contains("failed"));
+}
+
+#[test]
+fn stops_endless_scripts() {
+    let emu: Emu = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧".parse().unwrap();
+    let mut script = Script::compile("loop {}").unwrap();
+    assert!(script
+        .run(&emu, 0, ROOT_BK, None)
+        .unwrap_err()
+        .contains("failed"));
+    let mut script = Script::compile("fn f(x) { f(x + 1) } f(0)").unwrap();
+    assert!(script.run(&emu, 0, ROOT_BK, None).is_err());
+}
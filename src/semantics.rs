@@ -0,0 +1,100 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Versions of the semantics of the emulator, selected by
+//! `Opt::Semantics` or a `+semantics 1` line of the program, for
+//! programs that depend on how it behaved before. Every change of
+//! behavior is listed in `CHANGES` with the version it came in, and
+//! the `Emu` refuses to do what the selected version didn't know of:
+//!
+//! ```
+//! use phie::emu::Emu;
+//! let text = "
+//!   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+//!   ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν3(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧
+//!   ν2(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+//!   ν3(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ Φ.𝜑.𝛼0.σ.𝛼0 ⟧
+//! ";
+//! let mut emu: Emu = text.parse().unwrap();
+//! assert_eq!(-7, emu.dataize().0);
+//! let mut old: Emu = format!("+semantics 1\n{}", text).parse().unwrap();
+//! let err = old.try_dataize().err().unwrap().to_string();
+//! assert!(err.contains("σ needs semantics 2"), "{}", err);
+//! ```
+
+use std::fmt;
+
+/// The version of the semantics, see `CHANGES`.
+pub type Version = u32;
+
+/// The first version, before any of the `CHANGES`.
+pub const FIRST: Version = 1;
+
+/// The version the `Emu` follows, unless told otherwise.
+pub const LATEST: Version = 2;
+
+/// A change of behavior of the emulator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Change {
+    /// Locators may go to the home of an object with `σ`.
+    Sigma,
+    /// Integer atoms may saturate or fail on overflow, see
+    /// `Opt::Arithmetic`, instead of always wrapping around.
+    Arithmetic,
+}
+
+/// All changes, with the version each of them came in.
+pub const CHANGES: [(Change, Version); 2] = [(Change::Sigma, 2), (Change::Arithmetic, 2)];
+
+/// The version the change came in.
+pub fn since(change: Change) -> Version {
+    CHANGES
+        .iter()
+        .find(|(c, _)| *c == change)
+        .map_or(FIRST, |(_, v)| *v)
+}
+
+/// Make sure the version is one the emulator knows.
+pub fn check(version: Version) -> Result<Version, String> {
+    if (FIRST..=LATEST).contains(&version) {
+        Ok(version)
+    } else {
+        Err(format!(
+            "The semantics {} is not supported, only {} to {} are",
+            version, FIRST, LATEST
+        ))
+    }
+}
+
+/// Make sure the change is there in the version.
+pub fn require(version: Version, change: Change) -> Result<(), String> {
+    if version >= since(change) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} needs semantics {}, while {} is selected",
+            change,
+            since(change),
+            version
+        ))
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Change::Sigma => "σ",
+            Change::Arithmetic => "Non-wrapping arithmetic",
+        })
+    }
+}
+
+#[test]
+fn knows_versions_of_changes() {
+    assert_eq!(Ok(1), check(1));
+    assert!(check(0).is_err());
+    assert!(check(LATEST + 1).unwrap_err().contains("not supported"));
+    assert!(require(1, Change::Sigma).is_err());
+    assert!(CHANGES.iter().all(|(c, v)| require(*v, *c).is_ok()));
+    assert!(CHANGES.iter().all(|(_, v)| check(*v).is_ok()));
+}
@@ -0,0 +1,255 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Live inspection of a running program over WebSocket, available with
+//! the `serve` feature, for debuggers in the browser.
+//!
+//! The `Inspector` runs the program with `Emu::step` and answers JSON
+//! requests, like `{"command": "step", "count": 5}`, with the state of
+//! the emulator:
+//!
+//! ```text
+//! {"cycle": 5, "running": false, "result": null, "error": null,
+//!  "baskets": [{"bk": 0, "basket": "[ν0, ξ:β0, 𝜑→?]"}],
+//!  "perf": {"cycles": 5, "peak": 3, "hits": 12, "ticks": 80, "atoms": 0}}
+//! ```
+//!
+//! The commands are `state`, `step` with an optional `count`, `run`,
+//! which makes cycles until the program is finished or `pause` comes,
+//! and `pause`. While running, the state is sent after every cycle.
+//! The `phie serve` command listens for one client after another:
+//!
+//! ```
+//! use phie::serve::Inspector;
+//! use serde_json::json;
+//! let mut inspector = Inspector::new("ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap());
+//! let state = inspector.handle(&json!({"command": "step"}));
+//! assert_eq!(1, state["cycle"]);
+//! assert_eq!(42, state["result"]);
+//! ```
+
+use crate::data::Data;
+use crate::emu::Emu;
+use crate::error::message;
+use crate::perf::Perf;
+use log::{error, warn};
+use serde_json::{json, Value};
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::panic;
+use std::time::Duration;
+use tungstenite::{Message, WebSocket};
+
+/// How long to wait for a request between cycles, while running.
+const PATIENCE: Duration = Duration::from_millis(10);
+
+/// The most cycles one `step` command makes, whatever its `count` is.
+const MAX_STEPS: u64 = 10_000;
+
+pub struct Inspector {
+    emu: Emu,
+    perf: Perf,
+    outcome: Option<Result<Data, String>>,
+    pub running: bool,
+}
+
+impl Inspector {
+    pub fn new(emu: Emu) -> Inspector {
+        Inspector {
+            emu,
+            perf: Perf::new(),
+            outcome: None,
+            running: false,
+        }
+    }
+
+    /// Handle one request and return the state after it.
+    pub fn handle(&mut self, msg: &Value) -> Value {
+        match msg["command"].as_str().unwrap_or("") {
+            "state" => {}
+            "step" => {
+                self.running = false;
+                for _ in 0..msg["count"].as_u64().unwrap_or(1).min(MAX_STEPS) {
+                    if self.outcome.is_some() {
+                        break;
+                    }
+                    self.step();
+                }
+            }
+            "run" => self.running = self.outcome.is_none(),
+            "pause" => self.running = false,
+            other => return json!({"error": format!("Unknown command '{}'", other)}),
+        }
+        self.state()
+    }
+
+    /// Make one cycle, if it's not finished yet.
+    pub fn step(&mut self) {
+        if self.outcome.is_some() {
+            return;
+        }
+        let (emu, perf) = (&mut self.emu, &mut self.perf);
+        match panic::catch_unwind(panic::AssertUnwindSafe(|| emu.step(perf))) {
+            Ok(Ok(Some(d))) => self.outcome = Some(Ok(d)),
+            Ok(Ok(None)) => {}
+            Ok(Err(e)) => self.outcome = Some(Err(e.to_string())),
+            Err(e) => self.outcome = Some(Err(message(e.as_ref()))),
+        }
+        if self.outcome.is_some() {
+            self.running = false;
+        }
+    }
+
+    /// The live baskets and the numbers of the performance so far.
+    pub fn state(&self) -> Value {
+        json!({
+            "cycle": self.perf.cycles,
+            "running": self.running,
+            "result": self.outcome.as_ref().and_then(|r| r.as_ref().ok()),
+            "error": self.outcome.as_ref().and_then(|r| r.as_ref().err()),
+            "baskets": self
+                .emu
+                .baskets
+                .iter()
+                .enumerate()
+                .filter(|(_, bsk)| !bsk.is_empty())
+                .map(|(bk, bsk)| json!({"bk": bk, "basket": bsk.to_string()}))
+                .collect::<Vec<Value>>(),
+            "perf": {
+                "cycles": self.perf.cycles,
+                "peak": self.perf.peak,
+                "hits": self.perf.total_hits(),
+                "ticks": self.perf.total_ticks(),
+                "atoms": self.perf.total_atoms(),
+            },
+        })
+    }
+}
+
+/// Accept clients one after another, each with a fresh emulator
+/// made by the function.
+pub fn serve(listener: TcpListener, emu: impl Fn() -> Emu) -> Result<(), String> {
+    for stream in listener.incoming() {
+        let stream = stream.map_err(|e| format!("Can't accept a client: {}", e))?;
+        let ws = match tungstenite::accept(stream) {
+            Ok(ws) => ws,
+            Err(e) => {
+                warn!("Can't make a WebSocket handshake: {}", e);
+                continue;
+            }
+        };
+        if let Err(e) = talk(ws, Inspector::new(emu())) {
+            error!("The client is gone: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Answer the requests of one client, making cycles between them,
+/// while running.
+fn talk(mut ws: WebSocket<TcpStream>, mut inspector: Inspector) -> Result<(), tungstenite::Error> {
+    loop {
+        ws.get_mut().set_read_timeout(if inspector.running {
+            Some(PATIENCE)
+        } else {
+            None
+        })?;
+        match ws.read() {
+            Ok(Message::Text(text)) => {
+                let reply = match serde_json::from_str::<Value>(text.as_str()) {
+                    Ok(msg) => inspector.handle(&msg),
+                    Err(e) => json!({"error": format!("Can't parse the request: {}", e)}),
+                };
+                ws.send(Message::text(reply.to_string()))?;
+            }
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                inspector.step();
+                ws.send(Message::text(inspector.state().to_string()))?;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[test]
+fn steps_and_pauses() {
+    let mut inspector = Inspector::new(
+        "
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν2(𝜋) ⟧
+        ν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧
+        ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν1(𝜋) ⟧
+        "
+        .parse()
+        .unwrap(),
+    );
+    let state = inspector.handle(&json!({"command": "step", "count": 2}));
+    assert_eq!(2, state["cycle"]);
+    assert_eq!(Value::Null, state["result"]);
+    assert!(!state["baskets"].as_array().unwrap().is_empty());
+    assert_eq!(
+        true,
+        inspector.handle(&json!({"command": "run"}))["running"]
+    );
+    assert_eq!(
+        false,
+        inspector.handle(&json!({"command": "pause"}))["running"]
+    );
+    while inspector.state()["result"].is_null() {
+        inspector.step();
+    }
+    assert_eq!(84, inspector.state()["result"]);
+    assert_eq!(
+        false,
+        inspector.handle(&json!({"command": "run"}))["running"]
+    );
+}
+
+#[test]
+fn reports_failure_and_unknown_commands() {
+    let mut inspector = Inspector::new(
+        "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ λ ↦ int-add ⟧"
+            .parse()
+            .unwrap(),
+    );
+    let state = inspector.handle(&json!({"command": "step", "count": 10}));
+    assert!(state["error"].is_string(), "{}", state);
+    assert!(inspector.handle(&json!({"command": "jump"}))["error"]
+        .as_str()
+        .unwrap()
+        .contains("Unknown command"));
+}
+
+#[test]
+fn caps_count_of_steps() {
+    let mut inspector = Inspector::new(
+        "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦! 𝜑 ↦ ν2(𝜋) ⟧\nν2(𝜋) ↦ ⟦! 𝜑 ↦ ν1(𝜋) ⟧"
+            .parse()
+            .unwrap(),
+    );
+    let state = inspector.handle(&json!({"command": "step", "count": u64::MAX}));
+    assert_eq!(MAX_STEPS, state["cycle"]);
+}
+
+#[test]
+fn talks_over_websocket() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        serve(listener, || "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap()).unwrap()
+    });
+    let (mut ws, _) = tungstenite::connect(format!("ws://{}", addr)).unwrap();
+    ws.send(Message::text(r#"{"command": "run"}"#)).unwrap();
+    let mut state = Value::Null;
+    while state["result"].is_null() {
+        state = serde_json::from_str(ws.read().unwrap().to_text().unwrap()).unwrap();
+    }
+    assert_eq!(42, state["result"]);
+    assert_eq!(false, state["running"]);
+}
@@ -0,0 +1,105 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Events of the `Emu` written as they happen, one JSON object per line,
+//! for visualizers that read them from a pipe, like `phie --events jsonl`.
+//!
+//! Every line has the `type` and the `cycle` it happened in, and one of
+//! these four schemas, with keys sorted:
+//!
+//! ```text
+//! {"cycle":3,"type":"cycle"}
+//! {"bk":2,"cycle":3,"kind":"CPY","loc":"𝛼0","ob":5,"type":"transition"}
+//! {"bk":2,"cycle":3,"name":"int-add","ob":5,"type":"atom","value":84}
+//! {"cycle":4,"type":"result","value":84}
+//! ```
+//!
+//! The `cycle` line starts a cycle, `transition` is a hit of one, where
+//! `kind` is the name of the `Transition` and `loc` is `null` if it
+//! doesn't touch an attribute, `atom` is a call of an atom that returned
+//! the value, and `result` is the dataized value of the first object:
+//!
+//! ```
+//! use phie::emu::Emu;
+//! use phie::stream::Stream;
+//! use std::sync::{Arc, Mutex};
+//! let out = Arc::new(Mutex::new(vec![]));
+//! let mut emu: Emu = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+//! emu.stream(Stream::new(Box::new(phie::stream::Shared(out.clone()))));
+//! emu.dataize();
+//! let text = String::from_utf8(out.lock().unwrap().clone()).unwrap();
+//! assert_eq!(Some(r#"{"cycle":1,"type":"result","value":42}"#), text.lines().last());
+//! ```
+
+use crate::basket::Bk;
+use crate::data::Data;
+use crate::event::Event;
+use crate::object::Ob;
+use serde_json::{json, Value};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+/// Where the lines go.
+pub struct Stream {
+    out: Box<dyn Write + Send>,
+}
+
+impl Stream {
+    pub fn new(out: Box<dyn Write + Send>) -> Stream {
+        Stream { out }
+    }
+
+    /// Lines go to the standard output.
+    pub fn stdout() -> Stream {
+        Stream::new(Box::new(io::stdout()))
+    }
+
+    pub fn cycle(&mut self, cycle: usize) {
+        self.line(json!({"type": "cycle", "cycle": cycle}));
+    }
+
+    pub fn transition(&mut self, event: &Event) {
+        self.line(json!({
+            "type": "transition",
+            "cycle": event.cycle,
+            "kind": event.kind.to_string(),
+            "bk": event.bk,
+            "ob": event.ob,
+            "loc": event.loc.as_ref().map(|l| l.to_string()),
+        }));
+    }
+
+    pub fn atom(&mut self, cycle: usize, bk: Bk, ob: Ob, name: &str, value: Data) {
+        self.line(json!({
+            "type": "atom",
+            "cycle": cycle,
+            "bk": bk,
+            "ob": ob,
+            "name": name,
+            "value": value,
+        }));
+    }
+
+    pub fn result(&mut self, cycle: usize, value: Data) {
+        self.line(json!({"type": "result", "cycle": cycle, "value": value}));
+    }
+
+    /// Write and flush right away, so that the reader sees it live. When
+    /// the reader is gone, the lines are lost, but dataization goes on.
+    fn line(&mut self, v: Value) {
+        let _ = writeln!(self.out, "{}", v).and_then(|_| self.out.flush());
+    }
+}
+
+/// A buffer shared with whoever reads the lines in the same process.
+pub struct Shared(pub Arc<Mutex<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,418 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! Symbolic execution of small programs: the Δ of each object named in
+//! a `+input` line is a variable, and the integer atoms build an
+//! expression of them instead of the data, which a bounded solver can
+//! then ask for the inputs that make the first object dataize to the
+//! given data, or which can be exported to SMT-LIB for a real solver:
+//!
+//! ```
+//! use phie::symexec::symbolize;
+//! let prog = "
+//!   +input x ν2
+//!   ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧
+//!   ν1(𝜋) ↦ ⟦ λ ↦ int-sub, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧
+//!   ν2(𝜋) ↦ ⟦ Δ ↦ 0x0000 ⟧
+//!   ν3(𝜋) ↦ ⟦ Δ ↦ 0x0007 ⟧
+//! ".parse().unwrap();
+//! let expr = symbolize(&prog).unwrap();
+//! assert_eq!("int-sub(x, 0x0007)", expr.to_string());
+//! assert_eq!(Some(vec![("x".to_string(), 7)]), expr.solve(0, -10..=10));
+//! ```
+//!
+//! Only the integer and boolean atoms are understood, while `σ` in
+//! locators is not. The branches of `bool-if` are both taken only if its
+//! condition depends on the inputs, which is why recursion over an input
+//! fails, once it gets deeper than `MAX_DEPTH`.
+
+use crate::data::{Calc, Data, Datum};
+use crate::emu::{Arithmetic, Bindings, ROOT_OB};
+use crate::loc::Loc;
+use crate::object::Ob;
+use crate::program::Program;
+use itertools::Itertools;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// How deep objects may be nested, while their expression is built.
+pub const MAX_DEPTH: usize = 256;
+
+/// How many objects may be visited, while the expression is built.
+const MAX_VISITS: usize = 100_000;
+
+/// The atoms that can be executed symbolically.
+const ATOMS: [&str; 9] = [
+    "int-add",
+    "int-sub",
+    "int-times",
+    "int-neg",
+    "int-div",
+    "int-less",
+    "int-to-bool",
+    "bool-to-int",
+    "bool-if",
+];
+
+/// What the first object dataizes to, in terms of the inputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Const(Data),
+    /// The input with this name, see `Program::inputs()`.
+    Var(String),
+    /// The atom with its arguments, in the order of its `Meta::args`.
+    Atom(&'static str, Vec<Expr>),
+}
+
+/// A basket of the symbolic execution: the object with its ψ.
+struct Frame {
+    ob: Ob,
+    psi: usize,
+}
+
+struct Builder<'a> {
+    prog: &'a Program,
+    arith: Arithmetic,
+    frames: Vec<Frame>,
+    visits: usize,
+}
+
+/// Build the expression of the first object of the program.
+pub fn symbolize(prog: &Program) -> Result<Expr, String> {
+    let mut b = Builder {
+        prog,
+        arith: prog.arith().unwrap_or(Arithmetic::Wrapping),
+        frames: vec![Frame {
+            ob: ROOT_OB,
+            psi: 0,
+        }],
+        visits: 0,
+    };
+    b.expr(0, 0)
+}
+
+impl Builder<'_> {
+    /// The expression of the 𝜑 of the frame.
+    fn expr(&mut self, f: usize, depth: usize) -> Result<Expr, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!(
+                "The objects are nested deeper than {}, at ν{}",
+                MAX_DEPTH, self.frames[f].ob
+            ));
+        }
+        self.visits += 1;
+        if self.visits > MAX_VISITS {
+            return Err(format!("More than {} objects are visited", MAX_VISITS));
+        }
+        let ob = self.frames[f].ob;
+        let obj = self
+            .prog
+            .get(ob)
+            .ok_or_else(|| format!("There is no object ν{}", ob))?;
+        if let Some(d) = obj.delta {
+            return Ok(match self.prog.inputs().iter().find(|(_, o)| **o == ob) {
+                Some((name, _)) => Expr::Var(name.clone()),
+                None => Expr::Const(d),
+            });
+        }
+        if let Some(m) = obj.atom() {
+            if !ATOMS.contains(&m.name) {
+                return Err(format!(
+                    "The atom '{}' of ν{} can't be executed symbolically",
+                    m.name, ob
+                ));
+            }
+            if m.name == "bool-if" {
+                let term = self.kid(f, &Loc::Rho, depth)?;
+                if let Expr::Const(c) = term {
+                    return self.kid(f, &Loc::Attr(if c == 1 { 0 } else { 1 }), depth);
+                }
+                let yes = self.kid(f, &Loc::Attr(0), depth)?;
+                let no = self.kid(f, &Loc::Attr(1), depth)?;
+                return Ok(Expr::Atom(m.name, vec![term, yes, no]));
+            }
+            let mut args = vec![];
+            for (loc, _) in m.args {
+                args.push(self.kid(f, loc, depth)?);
+            }
+            return Expr::fold(m.name, args, self.arith);
+        }
+        if obj.attrs.contains_key(&Loc::Phi) {
+            return self.kid(f, &Loc::Phi, depth);
+        }
+        Err(format!("Object ν{} has neither Δ, nor λ, nor 𝜑", ob))
+    }
+
+    /// The expression of the attribute of the frame, in a new frame.
+    fn kid(&mut self, f: usize, loc: &Loc, depth: usize) -> Result<Expr, String> {
+        let ob = self.frames[f].ob;
+        let (locator, xi) = self
+            .prog
+            .get(ob)
+            .and_then(|o| o.attrs.get(loc))
+            .ok_or_else(|| format!("The attribute {} is absent in ν{}", loc, ob))?;
+        let mut todo: Vec<Loc> = locator.to_vec().into_iter().rev().collect();
+        let mut at = f;
+        let mut psi = self.frames[f].psi;
+        let mut cur = ob;
+        let mut hops = 0;
+        while let Some(step) = todo.pop() {
+            hops += 1;
+            if hops > MAX_DEPTH {
+                return Err(format!(
+                    "The locator {} of ν{} makes too many hops",
+                    locator, ob
+                ));
+            }
+            cur = match step {
+                Loc::Obj(i) => i,
+                Loc::Root => ROOT_OB,
+                Loc::Pi => {
+                    if self.frames[at].psi == 0 {
+                        return Err(format!(
+                            "The locator {} of ν{} escaped the graph at ν{}",
+                            locator, ob, self.frames[at].ob
+                        ));
+                    }
+                    at = self.frames[at].psi;
+                    psi = at;
+                    self.frames[at].ob
+                }
+                Loc::Sigma => {
+                    return Err(format!(
+                        "The locator {} of ν{} has σ, which can't be executed symbolically",
+                        locator, ob
+                    ))
+                }
+                attr => {
+                    let obj = self
+                        .prog
+                        .get(cur)
+                        .ok_or_else(|| format!("There is no object ν{}", cur))?;
+                    match (obj.attrs.get(&attr), obj.attrs.get(&Loc::Phi)) {
+                        (Some((p, _)), _) => todo.extend(p.to_vec().into_iter().rev()),
+                        (None, Some((p, _))) => {
+                            todo.push(attr);
+                            todo.extend(p.to_vec().into_iter().rev());
+                        }
+                        (None, None) => {
+                            return Err(format!(
+                                "The attribute {} of ν{} is absent, while {} of ν{} is resolved",
+                                attr, cur, locator, ob
+                            ))
+                        }
+                    }
+                    cur
+                }
+            };
+        }
+        self.frames.push(Frame {
+            ob: cur,
+            psi: if *xi { f } else { psi },
+        });
+        let kid = self.frames.len() - 1;
+        let expr = self.expr(kid, depth + 1);
+        self.frames.truncate(kid);
+        expr
+    }
+}
+
+/// The result of the atom with the data of its arguments, unless it
+/// fails or it's not an integer or boolean one.
+fn eval(name: &str, args: &[Data], arith: Arithmetic) -> Option<Data> {
+    let calc = |op: Calc, a: Data, b: Data| a.calc(op, b, arith);
+    match (name, args) {
+        ("int-add", [a, b, ..]) => calc(Calc::Add, *a, *b),
+        ("int-sub", [a, b, ..]) => calc(Calc::Sub, *a, *b),
+        ("int-times", [a, b, ..]) => calc(Calc::Mul, *a, *b),
+        ("int-neg", [a, ..]) => calc(Calc::Sub, 0, *a),
+        ("int-div", [a, b, ..]) => a.checked_div(*b),
+        ("int-less", [a, b, ..]) => Some((a < b) as Data),
+        ("int-to-bool", [a, ..]) => Some((*a != 0) as Data),
+        ("bool-to-int", [a, ..]) => Some((*a == 1) as Data),
+        ("bool-if", [c, yes, no]) => Some(if *c == 1 { *yes } else { *no }),
+        _ => None,
+    }
+}
+
+impl Expr {
+    /// The atom with its arguments, or its data, if they are all known.
+    fn fold(name: &'static str, args: Vec<Expr>, arith: Arithmetic) -> Result<Expr, String> {
+        let known: Vec<Data> = args
+            .iter()
+            .filter_map(|a| match a {
+                Expr::Const(d) => Some(*d),
+                _ => None,
+            })
+            .collect();
+        if known.len() < args.len() {
+            return Ok(Expr::Atom(name, args));
+        }
+        eval(name, &known, arith).map(Expr::Const).ok_or_else(|| {
+            format!(
+                "The atom '{}' fails with {}",
+                name,
+                known.iter().map(|d| format!("0x{:04X}", d)).join(" and ")
+            )
+        })
+    }
+
+    /// The names of the inputs it depends on, sorted.
+    pub fn vars(&self) -> BTreeSet<String> {
+        match self {
+            Expr::Const(_) => BTreeSet::new(),
+            Expr::Var(v) => BTreeSet::from([v.clone()]),
+            Expr::Atom(_, args) => args.iter().flat_map(Expr::vars).collect(),
+        }
+    }
+
+    /// The data it dataizes to with the inputs bound, in the wrapping
+    /// arithmetic, unless an atom fails, like `int-div` by zero.
+    pub fn eval(&self, bindings: &Bindings) -> Result<Data, String> {
+        match self {
+            Expr::Const(d) => Ok(*d),
+            Expr::Var(v) => bindings
+                .iter()
+                .find(|(n, _)| n == v)
+                .map(|(_, d)| *d)
+                .ok_or_else(|| format!("The input '{}' is not bound", v)),
+            Expr::Atom("bool-if", args) => {
+                let c = args[0].eval(bindings)?;
+                args[if c == 1 { 1 } else { 2 }].eval(bindings)
+            }
+            Expr::Atom(name, args) => {
+                let mut data = vec![];
+                for a in args {
+                    data.push(a.eval(bindings)?);
+                }
+                Expr::fold(
+                    name,
+                    data.into_iter().map(Expr::Const).collect(),
+                    Arithmetic::Wrapping,
+                )
+                .map(|e| match e {
+                    Expr::Const(d) => d,
+                    _ => unreachable!("All arguments are known"),
+                })
+            }
+        }
+    }
+
+    /// The first binding of the inputs, each one taken from the range,
+    /// that makes it dataize to the target, trying them all one by one.
+    pub fn solve(&self, target: Data, range: RangeInclusive<Data>) -> Option<Bindings> {
+        let vars: Vec<String> = self.vars().into_iter().collect();
+        vars.iter()
+            .map(|_| range.clone())
+            .multi_cartesian_product()
+            .map(|ds| vars.iter().cloned().zip(ds).collect::<Bindings>())
+            .chain(vars.is_empty().then(Bindings::new))
+            .find(|b| self.eval(b) == Ok(target))
+    }
+
+    /// A script in SMT-LIB that asks for the inputs that make it dataize
+    /// to the target, in 64-bit vectors, which wrap around as `Data` do.
+    pub fn to_smtlib(&self, target: Data) -> String {
+        let mut lines: Vec<String> = vec!["(set-logic QF_BV)".to_string()];
+        for v in self.vars() {
+            lines.push(format!("(declare-const |{}| (_ BitVec 64))", v));
+        }
+        lines.push(format!("(assert (= {} {}))", self.smt(), bv(target)));
+        lines.push("(check-sat)".to_string());
+        lines.push("(get-model)".to_string());
+        lines.join("\n")
+    }
+
+    fn smt(&self) -> String {
+        let bool = |t: String| format!("(ite {} {} {})", t, bv(1), bv(0));
+        match self {
+            Expr::Const(d) => bv(*d),
+            Expr::Var(v) => format!("|{}|", v),
+            Expr::Atom(name, args) => {
+                let a: Vec<String> = args.iter().map(Expr::smt).collect();
+                match *name {
+                    "int-add" => format!("(bvadd {} {})", a[0], a[1]),
+                    "int-sub" => format!("(bvsub {} {})", a[0], a[1]),
+                    "int-times" => format!("(bvmul {} {})", a[0], a[1]),
+                    "int-div" => format!("(bvsdiv {} {})", a[0], a[1]),
+                    "int-neg" => format!("(bvneg {})", a[0]),
+                    "int-less" => bool(format!("(bvslt {} {})", a[0], a[1])),
+                    "int-to-bool" => bool(format!("(not (= {} {}))", a[0], bv(0))),
+                    "bool-to-int" => bool(format!("(= {} {})", a[0], bv(1))),
+                    _ => format!("(ite (= {} {}) {} {})", a[0], bv(1), a[1], a[2]),
+                }
+            }
+        }
+    }
+}
+
+/// The data as a 64-bit vector of SMT-LIB.
+fn bv(d: Data) -> String {
+    format!("#x{:016x}", d)
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Const(d) => write!(f, "0x{:04X}", d),
+            Expr::Var(v) => write!(f, "{}", v),
+            Expr::Atom(name, args) => write!(f, "{}({})", name, args.iter().join(", ")),
+        }
+    }
+}
+
+#[test]
+fn solves_branches_over_input() {
+    let prog: Program = "+input x ν5\n\
+        ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\n\
+        ν1(𝜋) ↦ ⟦ λ ↦ bool-if, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν3(𝜋), 𝛼1 ↦ ν4(𝜋) ⟧\n\
+        ν2(𝜋) ↦ ⟦ λ ↦ int-less, ρ ↦ ν5(𝜋), 𝛼0 ↦ ν6(𝜋) ⟧\n\
+        ν3(𝜋) ↦ ⟦ λ ↦ int-neg, ρ ↦ ν5(𝜋) ⟧\n\
+        ν4(𝜋) ↦ ⟦ λ ↦ int-times, ρ ↦ ν5(𝜋), 𝛼0 ↦ ν6(𝜋) ⟧\n\
+        ν5(𝜋) ↦ ⟦ Δ ↦ 0x0000 ⟧\nν6(𝜋) ↦ ⟦ Δ ↦ 0x0003 ⟧"
+        .parse()
+        .unwrap();
+    let expr = symbolize(&prog).unwrap();
+    assert_eq!(BTreeSet::from(["x".to_string()]), expr.vars());
+    assert_eq!(Ok(5), expr.eval(&vec![("x".to_string(), -5)]));
+    assert_eq!(Some(vec![("x".to_string(), -12)]), expr.solve(12, -20..=20));
+    assert_eq!(Some(vec![("x".to_string(), 4)]), expr.solve(12, 0..=20));
+    assert_eq!(None, expr.solve(13, 0..=20));
+    let smt = expr.to_smtlib(12);
+    assert!(smt.contains("(declare-const |x| (_ BitVec 64))"), "{}", smt);
+    assert!(smt.contains("(bvslt |x| #x0000000000000003)"), "{}", smt);
+    assert!(smt.ends_with("(check-sat)\n(get-model)"), "{}", smt);
+}
+
+#[test]
+fn folds_constants_and_follows_locators() {
+    let prog: Program = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\n\
+        ν1(𝜋) ↦ ⟦ 𝜑 ↦ ν2(ξ), 𝛼0 ↦ ν4(𝜋) ⟧\n\
+        ν2(𝜋) ↦ ⟦ λ ↦ int-add, ρ ↦ 𝜋.𝛼0, 𝛼0 ↦ ν3(𝜋) ⟧\n\
+        ν3(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧\nν4(𝜋) ↦ ⟦ Δ ↦ 0x0005 ⟧"
+        .parse()
+        .unwrap();
+    let expr = symbolize(&prog).unwrap();
+    assert_eq!(Expr::Const(7), expr);
+    assert_eq!(7, crate::emu::Emu::<Data>::from(&prog).dataize().0);
+    assert_eq!(Some(vec![]), expr.solve(7, 0..=0));
+}
+
+#[test]
+fn refuses_what_it_does_not_understand() {
+    let io: Program = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ λ ↦ io-read ⟧"
+        .parse()
+        .unwrap();
+    assert!(symbolize(&io).unwrap_err().contains("'io-read'"));
+    let zero: Program = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\n\
+        ν1(𝜋) ↦ ⟦ λ ↦ int-div, ρ ↦ ν2(𝜋), 𝛼0 ↦ ν3(𝜋) ⟧\n\
+        ν2(𝜋) ↦ ⟦ Δ ↦ 0x0002 ⟧\nν3(𝜋) ↦ ⟦ Δ ↦ 0x0000 ⟧"
+        .parse()
+        .unwrap();
+    assert!(symbolize(&zero).unwrap_err().contains("'int-div' fails"));
+    let endless: Program = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧"
+        .parse()
+        .unwrap();
+    assert!(symbolize(&endless).unwrap_err().contains("deeper"));
+}
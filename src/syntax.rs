@@ -0,0 +1,414 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! The grammar of the text format of programs and baskets, printed
+//! by `phie --help-syntax`. The patterns here are the ones the parsers
+//! match, and the names of atoms, locs and options are taken from the
+//! tables the parsers look them up in, so the grammar can't say one
+//! thing while the parsers do another:
+//!
+//! ```
+//! use phie::syntax::grammar;
+//! let text = grammar();
+//! assert!(text.contains("object-line ::= "));
+//! assert!(text.contains("\"int-add\""));
+//! ```
+
+use crate::atom::registry;
+use crate::data::Data;
+use crate::emu::{flags, ARITHMETICS, FLOAT_MODES, LEVELS, SCHEDULES};
+use crate::loc::{ALIASES, NAMES};
+
+/// The version of the text encodings of locs, kids, baskets and
+/// checkpoints, which crash dumps carry, so that a tool that reads them
+/// can tell whether it understands them. It goes up whenever one of the
+/// encodings changes, which the golden strings in the tests below catch.
+pub const FORMAT: u32 = 1;
+
+/// The pattern of a line with an object, see `Program::parse_line()`.
+pub const LINE: &str = "ν(\\d+)\\(𝜋\\) ↦ (⟦.*⟧)(?:\\s*#\\s*(\\S+)::ν(\\d+))?";
+
+/// The pattern of an object with its markers, see `Object::from_str()`.
+pub const OBJECT: &str = "⟦([!~%]*)(.*)⟧";
+
+/// The pattern of a basket, see `Basket::from_str()`.
+pub const BASKET: &str = "\\[(.*)]";
+
+/// The pattern of a kid of a basket, see `Basket::from_str()`.
+pub const KID: &str = "^(.*)(⇶0x|⇉β|→\\(ν|→∅|→\\?)(.*?)\\)?$";
+
+/// One rule of the grammar.
+pub struct Rule {
+    pub name: &'static str,
+    /// The right side of the rule, in EBNF.
+    pub grammar: String,
+    /// What it is, in one line.
+    pub doc: &'static str,
+    /// A text the parsers accept as this rule.
+    pub example: &'static str,
+}
+
+/// The alternatives, quoted and separated by bars.
+fn either<'a>(names: impl Iterator<Item = &'a str>) -> String {
+    names
+        .map(|n| format!("\"{}\"", n))
+        .collect::<Vec<String>>()
+        .join(" | ")
+}
+
+/// All rules, the program first.
+pub fn rules() -> Vec<Rule> {
+    let data = if cfg!(feature = "rational") {
+        "\"0x\" hex { hex } | number \"/\" number"
+    } else {
+        "\"0x\" hex { hex }"
+    };
+    vec![
+        Rule {
+            name: "program",
+            grammar: "{ [ line ] \"\\n\" }".to_string(),
+            doc: "Lines, blank or not, in any order",
+            example: "+opt memoize\nν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧",
+        },
+        Rule {
+            name: "line",
+            grammar: "object-line | option-line | arith-line | semantics-line | input-line \
+                 | include-line | expectation | comment"
+                .to_string(),
+            doc: "One line of a program, with spaces around it ignored",
+            example: "# the answer",
+        },
+        Rule {
+            name: "object-line",
+            grammar: "\"ν\" number \"(𝜋) ↦ \" object [ \"#\" namespace \"::ν\" number ]"
+                .to_string(),
+            doc: "An object by its number, maybe with the library it was linked from",
+            example: "ν7(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧ # lib::ν0",
+        },
+        Rule {
+            name: "object",
+            grammar: "\"⟦\" { \"!\" | \"%\" | \"~\" } [ pair { \",\" pair } ] \"⟧\"".to_string(),
+            doc: "Constant (!), memoized (%) or never memoized (~), with its attributes",
+            example: "⟦!% λ ↦ int-add, ρ ↦ ν1(𝜋), 𝛼0 ↦ ν2(𝜋) ⟧",
+        },
+        Rule {
+            name: "pair",
+            grammar: "\"λ ↦ \" atom | \"Δ ↦ \" data | loc \" ↦ \" locator [ \"(𝜋)\" | \"(ξ)\" ]"
+                .to_string(),
+            doc: "An atom, data, or an attribute found by its locator",
+            example: "𝜑 ↦ ν2(ξ)",
+        },
+        Rule {
+            name: "atom",
+            grammar: either(registry::<Data>().iter().map(|m| m.name)),
+            doc: "A name of an atom, see `atom::registry()`",
+            example: "int-add",
+        },
+        Rule {
+            name: "data",
+            grammar: data.to_string(),
+            doc: "A hexadecimal number, or a fraction with the rational feature",
+            example: "0x002A",
+        },
+        Rule {
+            name: "locator",
+            grammar: "loc { \".\" loc }".to_string(),
+            doc: "A path of locs; ν may only go alone, Φ only first, 𝛼 never first",
+            example: "𝜋.𝜋.𝛼0",
+        },
+        Rule {
+            name: "loc",
+            grammar: format!(
+                "{} | [ \"𝛼\" ] number | \"ν\" number",
+                NAMES
+                    .iter()
+                    .map(|(name, letter, _)| format!("\"{}\" | \"{}\"", name, letter))
+                    .collect::<Vec<String>>()
+                    .join(" | ")
+            ),
+            doc: "An attribute, maybe written with an ASCII letter, or an object",
+            example: "@",
+        },
+        Rule {
+            name: "option-line",
+            grammar: "\"+opt \" option".to_string(),
+            doc: "An option of the emulator, see `emu::Opt`",
+            example: "+opt max-cycles 1000",
+        },
+        Rule {
+            name: "option",
+            grammar: format!(
+                "{} | ( \"max-cycles\" | \"max-objects\" | \"checkpoint-every\" \
+                 | \"max-resolution-hops\" | \"progress\" | \"invariants-every\" \
+                 | \"semantics\" ) number \
+                 | \"schedule\" ( {} ) | \"log-level\" ( {} ) | \"float-mode\" ( {} ) \
+                 | \"arithmetic\" arithmetic | \"crash-dumps\" path | \"audit-locs\" loc {{ loc }}",
+                either(flags().iter().map(|(n, _)| *n)),
+                either(SCHEDULES.iter().map(|(n, _)| *n)),
+                either(LEVELS.iter().map(|(n, _)| *n)),
+                either(FLOAT_MODES.iter().map(|(n, _)| *n)),
+            ),
+            doc: "An option with its value, if it has one",
+            example: "schedule lifo",
+        },
+        Rule {
+            name: "arith-line",
+            grammar: "\"+arith \" arithmetic".to_string(),
+            doc: "The arithmetic the program is written for, which no option may change",
+            example: "+arith checked",
+        },
+        Rule {
+            name: "arithmetic",
+            grammar: either(ARITHMETICS.iter().map(|(n, _)| *n)),
+            doc: "What integer atoms do on overflow, see `emu::Arithmetic`",
+            example: "saturating",
+        },
+        Rule {
+            name: "semantics-line",
+            grammar: "\"+semantics \" number".to_string(),
+            doc: "The version of semantics the program depends on, see `semantics`",
+            example: "+semantics 1",
+        },
+        Rule {
+            name: "input-line",
+            grammar: "\"+input \" name \" ν\" number".to_string(),
+            doc: "A name of an object with Δ, see `Emu::bind()`",
+            example: "+input x ν1",
+        },
+        Rule {
+            name: "include-line",
+            grammar: "\"+include \" path".to_string(),
+            doc: "The lines of another file, relative to this one",
+            example: "+include lib.phi",
+        },
+        Rule {
+            name: "expectation",
+            grammar: "\"# expect: \" number | \"# expect-error: \" text".to_string(),
+            doc: "The result of the program, checked by `phie run-all`",
+            example: "# expect: 42",
+        },
+        Rule {
+            name: "comment",
+            grammar: "\"#\" text".to_string(),
+            doc: "Ignored",
+            example: "# the answer",
+        },
+        Rule {
+            name: "checkpoint",
+            grammar: "\"#\" number { \"\\n\" \"β\" number \" \" basket }".to_string(),
+            doc: "The cycle and the live baskets, see `checkpoint::Checkpoint`",
+            example: "#7\nβ1 [ν3, ξ:β0, 𝜑→?]",
+        },
+        Rule {
+            name: "basket",
+            grammar: "\"[ν\" number \", ξ:β\" number { \", \" kid } \"]\"".to_string(),
+            doc: "The object of a basket, its ψ, and its kids",
+            example: "[ν3, ξ:β0, 𝜑→(ν1;β0), ρ⇉β2.Δ, 𝛼0⇶0x002A, Δ→∅, σ→?]",
+        },
+        Rule {
+            name: "kid",
+            grammar: "loc ( \"→?\" | \"→∅\" | \"→(ν\" number \";β\" number \")\" \
+                      | \"⇉β\" number \".\" loc | \"⇶0x\" hex { hex } )"
+                .to_string(),
+            doc: "Requested, empty, needed, waiting or dataized",
+            example: "𝜑⇶0x002A",
+        },
+    ]
+}
+
+/// The grammar as text, one rule after another, each with its doc
+/// and example, followed by the characters that are read as others.
+pub fn grammar() -> String {
+    let mut lines = vec![];
+    for rule in rules() {
+        lines.push(format!("(* {} *)", rule.doc));
+        lines.push(format!("{} ::= {}", rule.name, rule.grammar));
+        for line in rule.example.lines() {
+            lines.push(format!("  e.g. {}", line));
+        }
+        lines.push(String::new());
+    }
+    lines.push("(* Look-alike characters are read as the ones they look like *)".to_string());
+    lines.push(
+        ALIASES
+            .iter()
+            .map(|(alias, real)| format!("{}→{}", alias, real))
+            .collect::<Vec<String>>()
+            .join(" "),
+    );
+    lines.join("\n")
+}
+
+#[cfg(test)]
+use crate::atom::meta;
+#[cfg(test)]
+use crate::basket::{Basket, Bk, Kid};
+#[cfg(test)]
+use crate::checkpoint::Checkpoint;
+#[cfg(test)]
+use crate::emu::{Arithmetic, Opt};
+#[cfg(test)]
+use crate::loc::Loc;
+#[cfg(test)]
+use crate::locator::Locator;
+#[cfg(test)]
+use crate::object::{Ob, Object};
+#[cfg(test)]
+use crate::program::{Expectation, Program};
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn parses_all_examples() {
+    for rule in rules() {
+        let e = rule.example;
+        let parsed = match rule.name {
+            "program" | "line" | "option-line" | "arith-line" | "semantics-line" | "comment" => {
+                Program::from_str(e).map(|_| ())
+            }
+            "arithmetic" => Arithmetic::from_str(e).map(|_| ()),
+            "object-line" => Program::parse_line(e).map(|_| ()),
+            "object" => Object::from_str(e).map(|_| ()),
+            "pair" => Object::from_str(&format!("⟦ {} ⟧", e)).map(|_| ()),
+            "data" => Object::from_str(&format!("⟦ Δ ↦ {} ⟧", e)).map(|_| ()),
+            "atom" => meta(e).map(|_| ()).ok_or_else(|| e.to_string()),
+            "locator" => Locator::from_str(e).map(|_| ()),
+            "loc" => Loc::from_str(e).map(|_| ()),
+            "option" => Opt::from_str(e).map(|_| ()),
+            "input-line" => Program::parse_input(&e["+input ".len()..]).map(|_| ()),
+            "include-line" => e
+                .strip_prefix("+include ")
+                .map(|_| ())
+                .ok_or_else(|| e.to_string()),
+            "expectation" => Expectation::find(e).map(|_| ()),
+            "checkpoint" => Checkpoint::from_str(e).map(|_| ()),
+            "basket" => <Basket>::from_str(e).map(|_| ()),
+            "kid" => <Basket>::from_str(&format!("[ν1, ξ:β0, {}]", e)).map(|_| ()),
+            name => panic!("There is no parser for the rule '{}'", name),
+        };
+        assert!(parsed.is_ok(), "{}: {:?}", rule.name, parsed);
+    }
+}
+
+#[test]
+fn parses_all_locs_and_options_of_grammar() {
+    let quoted = |name: &str| -> Vec<String> {
+        let rule = rules().into_iter().find(|r| r.name == name).unwrap();
+        rule.grammar
+            .split('"')
+            .skip(1)
+            .step_by(2)
+            .map(|s| s.to_string())
+            .collect()
+    };
+    for loc in quoted("loc")
+        .iter()
+        .filter(|l| !["𝛼", "ν"].contains(&l.as_str()))
+    {
+        assert!(Loc::from_str(loc).is_ok(), "{}", loc);
+    }
+    let valued = ["schedule", "log-level", "float-mode", "arithmetic"];
+    for opt in quoted("option") {
+        let texts = [opt.clone(), format!("{} 1", opt)]
+            .into_iter()
+            .chain(valued.iter().map(|v| format!("{} {}", v, opt)));
+        assert!(
+            valued.contains(&opt.as_str()) || texts.into_iter().any(|t| Opt::from_str(&t).is_ok()),
+            "{}",
+            opt
+        );
+    }
+}
+
+#[test]
+fn prints_every_rule_once() {
+    let text = grammar();
+    for rule in rules() {
+        let head = format!("{} ::= ", rule.name);
+        assert_eq!(
+            1,
+            text.lines().filter(|l| l.starts_with(&head)).count(),
+            "{}",
+            text
+        );
+    }
+}
+
+/// The golden encodings of locs, one for every variant; a new variant
+/// breaks the build here, until it gets its own.
+#[cfg(test)]
+fn golden_locs() -> Vec<(Loc, &'static str)> {
+    let all = vec![
+        (Loc::Root, "Φ"),
+        (Loc::Rho, "ρ"),
+        (Loc::Phi, "𝜑"),
+        (Loc::Pi, "𝜋"),
+        (Loc::Delta, "Δ"),
+        (Loc::Sigma, "σ"),
+        (Loc::Attr(0), "𝛼0"),
+        (Loc::Attr(127), "𝛼127"),
+        (Loc::Obj(Ob::new(0)), "ν0"),
+        (Loc::Obj(Ob::new(4096)), "ν4096"),
+    ];
+    for (loc, _) in &all {
+        match loc {
+            Loc::Root | Loc::Rho | Loc::Phi | Loc::Pi | Loc::Delta | Loc::Sigma => {}
+            Loc::Attr(_) | Loc::Obj(_) => {}
+        }
+    }
+    all
+}
+
+/// The golden encodings of kids, one for every variant, like
+/// `golden_locs()`.
+#[cfg(test)]
+fn golden_kids() -> Vec<(Kid, &'static str)> {
+    let all = vec![
+        (Kid::Empt, "→∅"),
+        (Kid::Rqtd, "→?"),
+        (Kid::Need(Ob::new(7), Bk::new(12)), "→(ν7;β12)"),
+        (Kid::Need(Ob::new(0), Bk::NONE), "→(ν0;β-1)"),
+        (Kid::Wait(Bk::new(3), Loc::Phi), "⇉β3.𝜑"),
+        (Kid::Wait(Bk::new(0), Loc::Attr(2)), "⇉β0.𝛼2"),
+        (Kid::Dtzd(42), "⇶0x002A"),
+        (Kid::Dtzd(0), "⇶0x0000"),
+        (Kid::Dtzd(0x12345), "⇶0x12345"),
+        (Kid::Dtzd(-1), "⇶0xFFFFFFFFFFFFFFFF"),
+    ];
+    for (kid, _) in &all {
+        match kid {
+            Kid::Empt | Kid::Rqtd | Kid::Need(..) | Kid::Wait(..) | Kid::Dtzd(_) => {}
+        }
+    }
+    all
+}
+
+#[test]
+fn keeps_encodings_of_locs() {
+    for (loc, text) in golden_locs() {
+        assert_eq!(text, loc.to_string(), "format {}", FORMAT);
+        assert_eq!(loc, Loc::from_str(text).unwrap(), "format {}", FORMAT);
+    }
+}
+
+#[test]
+fn keeps_encodings_of_kids() {
+    for (kid, text) in golden_kids() {
+        assert_eq!(text, kid.to_string(), "format {}", FORMAT);
+        let bsk = <Basket>::from_str(&format!("[ν1, ξ:β0, 𝛼0{}]", text)).unwrap();
+        assert_eq!(Some(&kid), bsk.kids.get(&Loc::Attr(0)), "format {}", FORMAT);
+    }
+}
+
+#[test]
+fn keeps_encodings_of_baskets() {
+    let mut bsk = <Basket>::start(Ob::new(5), Bk::new(7));
+    for (i, (kid, _)) in golden_kids().into_iter().enumerate() {
+        bsk.put(Loc::Attr(i as i8), kid);
+    }
+    let text = "[ν5, ξ:β7, 𝛼0→∅, 𝛼1→?, 𝛼2→(ν7;β12), 𝛼3→(ν0;β-1), 𝛼4⇉β3.𝜑, \
+        𝛼5⇉β0.𝛼2, 𝛼6⇶0x002A, 𝛼7⇶0x0000, 𝛼8⇶0x12345, 𝛼9⇶0xFFFFFFFFFFFFFFFF]";
+    assert_eq!(text, bsk.to_string(), "format {}", FORMAT);
+    assert_eq!(text, <Basket>::from_str(text).unwrap().to_string());
+    assert_eq!("[ν0, ξ:β-1]", <Basket>::empty().to_string());
+}
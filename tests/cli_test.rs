@@ -173,3 +173,28 @@ fn preserves_absolute_path() {
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), "/absolute/path/test.phie");
 }
+
+#[test]
+fn streams_events_as_json_lines() {
+    let out = assert_cmd::Command::cargo_bin("phie")
+        .unwrap()
+        .arg("tests/resources/written_sum_test")
+        .arg("--events")
+        .arg("jsonl")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let text = String::from_utf8(out).unwrap();
+    let lines: Vec<serde_json::Value> = text
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!("cycle", lines[0]["type"]);
+    assert!(lines.iter().any(|v| v["type"] == "transition"));
+    assert!(lines.iter().any(|v| v["type"] == "atom"));
+    let result = &lines[lines.len() - 2];
+    assert_eq!("result", result["type"]);
+    assert_eq!(result["value"], lines[lines.len() - 1]);
+}
@@ -54,7 +54,7 @@ fn fails_with_expected_value_mismatch() {
     cmd.arg("tests/resources/written_test_example")
         .arg("100")
         .assert()
-        .failure()
+        .code(2)
         .stderr(predicates::str::contains("does not match expected"));
 }
 
@@ -76,3 +76,51 @@ fn fails_with_invalid_expected_value() {
         .failure()
         .stderr(predicates::str::contains("Invalid expected value argument"));
 }
+
+#[test]
+fn exits_with_code_of_failure() {
+    let dir = std::env::temp_dir();
+    let broken = dir.join("phie-exit-broken.phi");
+    std::fs::write(&broken, "broken").unwrap();
+    Command::cargo_bin("custom_executor")
+        .unwrap()
+        .arg(&broken)
+        .assert()
+        .code(3);
+    let stuck = dir.join("phie-exit-stuck.phi");
+    std::fs::write(
+        &stuck,
+        "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦! 𝜑 ↦ ν2(𝜋) ⟧\nν2(𝜋) ↦ ⟦! 𝜑 ↦ ν1(𝜋) ⟧",
+    )
+    .unwrap();
+    Command::cargo_bin("custom_executor")
+        .unwrap()
+        .arg(&stuck)
+        .assert()
+        .code(4)
+        .stderr(predicates::str::starts_with("We are stuck"));
+}
+
+#[test]
+fn prints_nothing_when_quiet() {
+    let mut cmd = Command::cargo_bin("custom_executor").unwrap();
+    cmd.arg("--quiet")
+        .arg("tests/resources/written_test_example")
+        .arg("100")
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr("");
+}
+
+#[test]
+fn prints_result_as_json_with_type() {
+    let mut cmd = Command::cargo_bin("custom_executor").unwrap();
+    cmd.arg("--json")
+        .arg("tests/resources/written_sum_test")
+        .arg("--expect")
+        .arg("int:84")
+        .assert()
+        .success()
+        .stdout("{\"type\":\"int\",\"value\":84}\n");
+}
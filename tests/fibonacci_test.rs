@@ -39,7 +39,7 @@ fn fails_with_invalid_cycles() {
         .arg("invalid")
         .assert()
         .failure()
-        .stderr(predicates::str::contains("Invalid cycles argument"));
+        .stderr(predicates::str::contains("Invalid repetitions argument"));
 }
 
 #[test]
@@ -0,0 +1,54 @@
+// SPDX-FileCopyrightText: Copyright (c) 2022 Yegor Bugayenko
+// SPDX-License-Identifier: MIT
+
+//! The tests of `isolate()`, without the harness of `cargo test`, which
+//! runs tests in threads, while `isolate()` may only fork a process of
+//! one thread: here they all run one after another in the main thread.
+
+use phie::emu::Opt;
+use phie::isolate::{isolate, Limits};
+
+fn reports_failure_of_child() {
+    let prog = "ν0(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧\nν1(𝜋) ↦ ⟦ 𝜑 ↦ ν1(𝜋) ⟧"
+        .parse()
+        .unwrap();
+    // SAFETY: the tests run in the main thread, the only one.
+    let err = unsafe { isolate(&prog, Limits::default(), |emu| emu.opt(Opt::StopWhenStuck)) }
+        .unwrap_err();
+    assert!(err.to_string().contains("No more empty baskets"), "{}", err);
+}
+
+fn kills_child_out_of_memory() {
+    let prog = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+    let limits = Limits {
+        cpu: 10,
+        memory: 1 << 32,
+    };
+    // SAFETY: the tests run in the main thread, the only one.
+    let err = unsafe {
+        isolate(&prog, limits, |_| {
+            std::hint::black_box(vec![0u8; 1 << 33]);
+        })
+    }
+    .unwrap_err();
+    assert!(err.to_string().contains("killed by signal"), "{}", err);
+}
+
+fn reports_panic_of_setup() {
+    let prog = "ν0(𝜋) ↦ ⟦ Δ ↦ 0x002A ⟧".parse().unwrap();
+    // SAFETY: the tests run in the main thread, the only one.
+    let err = unsafe { isolate(&prog, Limits::default(), |_| panic!("The setup is broken")) }
+        .unwrap_err();
+    assert!(err.to_string().contains("The setup is broken"), "{}", err);
+}
+
+fn main() {
+    for (name, test) in [
+        ("reports_failure_of_child", reports_failure_of_child as fn()),
+        ("kills_child_out_of_memory", kills_child_out_of_memory),
+        ("reports_panic_of_setup", reports_panic_of_setup),
+    ] {
+        test();
+        println!("test {} ... ok", name);
+    }
+}